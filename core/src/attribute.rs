@@ -0,0 +1,1116 @@
+//! The `#[arithmetic_mode(...)]` attribute, which applies a mode to all the
+//! arithmetic operations in an item, or to the operations of each operator
+//! given its own mode, like `#[arithmetic_mode(add = "wrapping")]`.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    Attribute, BinOp, Block, Expr, ExprArray, ExprAssign, ExprBinary, ExprBlock, ExprCall,
+    ExprConst, ExprForLoop, ExprGroup, ExprIf, ExprLit, ExprLoop, ExprMacro, ExprMatch,
+    ExprMethodCall, ExprParen, ExprReturn, ExprTuple, ExprUnary, ExprUnsafe, ExprWhile, Ident,
+    ImplItemConst, ImplItemFn, Item, ItemConst, ItemFn, ItemImpl, ItemMod, ItemStatic, ItemTrait,
+    Lit, LitStr, Local, Macro, Meta, MetaNameValue, ReturnType, Stmt, Token, TraitItemConst,
+    TraitItemFn, Type, TypeArray, UnOp,
+};
+
+use crate::config::{self, Policy};
+use crate::default_mode::{self, default_attrs};
+use crate::diagnostic::{self, check_ambiguous_literals, Limitation};
+use crate::dot::mac_function;
+use crate::literal_type::propagate_literal_type;
+use crate::mixed;
+use crate::modes::{self, TypeModes};
+use crate::passthrough::passthrough_expr;
+use crate::{
+    checked_expr, checked_impl, deref_operand, panicking_expr, panicking_impl, saturating_expr,
+    saturating_impl, try_checked_expr, try_checked_impl, verified_expr, verified_impl,
+    wrapping_expr, wrapping_impl, Options,
+};
+
+/// One of the arithmetic modes provided by this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Panics if an operation overflows, like `panicking!`.
+    Panicking,
+    /// Wraps around at the boundary of the type, like `wrapping!`.
+    Wrapping,
+    /// Saturates at the numeric bounds of the type, like `saturating!`.
+    Saturating,
+    /// Evaluates to `None` if an operation overflows, like `checked!`.
+    Checked,
+    /// Evaluates to `Err(ArithmeticError)` if an operation overflows, like
+    /// `try_checked!`.
+    TryChecked,
+    /// Panics like `panicking!`, and asserts the absence of overflow for Kani,
+    /// like `verified!`.
+    Verified,
+}
+
+impl Mode {
+    pub(crate) fn from_ident(ident: &Ident) -> syn::Result<Mode> {
+        Ok(match ident.to_string().as_str() {
+            "panicking" => Mode::Panicking,
+            "wrapping" => Mode::Wrapping,
+            "saturating" => Mode::Saturating,
+            "checked" => Mode::Checked,
+            "try_checked" => Mode::TryChecked,
+            "verified" => Mode::Verified,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    format!("Unknown arithmetic mode `{ident}`"),
+                ))
+            }
+        })
+    }
+
+    /// The name of the mode in the attribute and the macros.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Mode::Panicking => "panicking",
+            Mode::Wrapping => "wrapping",
+            Mode::Saturating => "saturating",
+            Mode::Checked => "checked",
+            Mode::TryChecked => "try_checked",
+            Mode::Verified => "verified",
+        }
+    }
+
+    /// Transforms the arithmetic operations in `expr` into this mode.
+    pub(crate) fn transform(self, expr: Expr, options: Options) -> syn::Result<TokenStream> {
+        if options.passthrough {
+            return passthrough_expr(expr, self);
+        }
+        match self {
+            Mode::Panicking => panicking_expr(expr, None, options),
+            Mode::Wrapping => wrapping_expr(expr, options),
+            Mode::Saturating => saturating_expr(expr, options),
+            Mode::Checked => checked_expr(expr, options),
+            Mode::TryChecked => try_checked_expr(expr, options),
+            Mode::Verified => verified_expr(expr, None, options),
+        }
+    }
+
+    /// Expands the macro of this mode, like `checked! { item }`.
+    pub(crate) fn expand(self, item: TokenStream) -> syn::Result<TokenStream> {
+        match self {
+            Mode::Panicking => panicking_impl(item),
+            Mode::Wrapping => wrapping_impl(item),
+            Mode::Saturating => saturating_impl(item),
+            Mode::Checked => checked_impl(item),
+            Mode::TryChecked => try_checked_impl(item),
+            Mode::Verified => verified_impl(item),
+        }
+    }
+
+    /// Whether the expressions in this mode evaluate to an `Option` or a
+    /// `Result` instead of the integer itself.
+    pub(crate) fn is_fallible(self) -> bool {
+        matches!(self, Mode::Checked | Mode::TryChecked)
+    }
+
+    /// The constructor of successful values in the fallible modes.
+    pub(crate) fn success(self) -> TokenStream {
+        match self {
+            Mode::TryChecked => quote! { ::core::result::Result::Ok },
+            _ => quote! { ::core::option::Option::Some },
+        }
+    }
+}
+
+/// Expands `#[arithmetic_mode(attr)] item`.
+pub fn arithmetic_mode_impl(attr: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    transform_item(attr, item, Options::default())
+}
+
+/// Transforms the arithmetic of `item` into the mode given in `attr`, with
+/// `options`.
+pub(crate) fn transform_item(
+    attr: TokenStream,
+    item: TokenStream,
+    options: Options,
+) -> syn::Result<TokenStream> {
+    if let Some(attrs) = default_attrs(&attr)? {
+        let items = attrs
+            .into_iter()
+            .map(|attr| transform_item(attr, item.clone(), options))
+            .collect::<syn::Result<Vec<_>>>()?;
+        return Ok(default_mode::select(items));
+    }
+    // Only meaningful inside another `#[arithmetic_mode]` item, which leaves
+    // this item unchanged.
+    let Some(mut transformer) = transformer(attr, options)? else {
+        return Ok(item);
+    };
+    let mut item: Item = syn::parse2(item)?;
+    match &mut item {
+        Item::Fn(item_fn) => {
+            transformer.visit_item_fn_mut(item_fn);
+            warn_stacked_attributes("arithmetic_mode", item_fn);
+        }
+        Item::Impl(item_impl) => transformer.visit_item_impl_mut(item_impl),
+        Item::Mod(item_mod) if item_mod.content.is_none() => {
+            return Err(syn::Error::new_spanned(
+                item_mod,
+                "#[arithmetic_mode] is only supported on modules with inline content",
+            ))
+        }
+        Item::Mod(item_mod) => transformer.visit_item_mod_mut(item_mod),
+        Item::Trait(item_trait) => transformer.visit_item_trait_mut(item_trait),
+        Item::Const(item_const) => transformer.visit_item_const_mut(item_const),
+        Item::Static(item_static) => transformer.visit_item_static_mut(item_static),
+        item => {
+            return Err(syn::Error::new_spanned(
+                item,
+                "#[arithmetic_mode] is only supported on functions, impl blocks, modules, traits, \
+                consts and statics",
+            ))
+        }
+    }
+    match transformer.error {
+        Some(e) => Err(e),
+        None => Ok(add_tracked(item, transformer.tracked)),
+    }
+}
+
+/// Adds the `tracked` item including the config file to the transformed
+/// `item`. The functions and consts may be among the items of an impl block,
+/// where an unnamed const is not allowed, so it is added to their body
+/// instead, like to the content of the modules.
+fn add_tracked(mut item: Item, tracked: TokenStream) -> TokenStream {
+    if tracked.is_empty() {
+        return item.into_token_stream();
+    }
+    match &mut item {
+        Item::Fn(item_fn) => item_fn
+            .block
+            .stmts
+            .insert(0, syn::parse_quote! { #tracked }),
+        Item::Mod(ItemMod {
+            content: Some((_, items)),
+            ..
+        }) => items.insert(0, syn::parse_quote! { #tracked }),
+        Item::Const(ItemConst { expr, .. }) | Item::Static(ItemStatic { expr, .. }) => {
+            **expr = syn::parse_quote! { { #tracked #expr } };
+        }
+        // The impl blocks and traits are never among the items of an impl block
+        _ => return quote! { #item #tracked },
+    }
+    item.into_token_stream()
+}
+
+/// Expands `closure!(item)`.
+pub fn closure_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    transform_closure(item, Options::default())
+}
+
+/// Transforms the arithmetic of the closure at the end of `item` into the
+/// mode given before it, like the arguments of the attribute, with `options`.
+pub(crate) fn transform_closure(item: TokenStream, options: Options) -> syn::Result<TokenStream> {
+    let mut args: Vec<Expr> = Punctuated::<Expr, Token![,]>::parse_terminated
+        .parse2(item)?
+        .into_iter()
+        .collect();
+    let (Some(Expr::Closure(mut closure)), false) = (args.pop(), args.is_empty()) else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "closure! takes the mode and a closure, like `closure!(wrapping, |x| x * 2 + 1)`",
+        ));
+    };
+    let Some(mut transformer) = transformer(quote! { #(#args),* }, options)? else {
+        return Ok(closure.into_token_stream());
+    };
+    transformer.visit_expr_closure_mut(&mut closure);
+    if let Some(e) = transformer.error {
+        return Err(e);
+    }
+    // The warnings of a body without a block are not added to one
+    let Transformer {
+        warnings, tracked, ..
+    } = transformer;
+    Ok(quote! { { #tracked #(#warnings;)* #closure } })
+}
+
+/// The visitor transforming the arithmetic into the mode given in the
+/// arguments `attr` of the attribute, with `options`, or `None` for the skip
+/// marker.
+fn transformer(attr: TokenStream, options: Options) -> syn::Result<Option<Transformer>> {
+    let args = Punctuated::<Meta, Token![,]>::parse_separated_nonempty.parse2(attr)?;
+    // The mode or skip marker given first, if any
+    let mut mode_name = None;
+    let mut operators = Vec::new();
+    let mut audit = false;
+    let mut policy = None;
+    for (i, arg) in args.iter().enumerate() {
+        match arg {
+            Meta::Path(path) if i == 0 => mode_name = Some(path.require_ident()?),
+            Meta::Path(path) if path.is_ident("audit") => audit = true,
+            Meta::NameValue(MetaNameValue {
+                path,
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(name),
+                        ..
+                    }),
+                ..
+            }) if path.is_ident("policy") => policy = Some((config::load(name)?, name)),
+            Meta::NameValue(operator) => {
+                let (name, mode) = operator_mode(operator)?;
+                if operators.iter().any(|(other, _)| *other == name) {
+                    return Err(syn::Error::new_spanned(
+                        &operator.path,
+                        format!("The mode of `{name}` is given more than once"),
+                    ));
+                }
+                operators.push((name, mode));
+            }
+            flag => {
+                return Err(syn::Error::new_spanned(
+                    flag,
+                    format!("Unknown flag `{}`", flag.to_token_stream()),
+                ))
+            }
+        }
+    }
+    let mode = match mode_name {
+        Some(ident) if ident == "skip" => return Ok(None),
+        Some(ident) => Mode::from_ident(ident)?,
+        // The operators without a mode of their own panic by default
+        None => policy
+            .as_ref()
+            .and_then(|(policy, _)| policy.mode)
+            .unwrap_or(Mode::Panicking),
+    };
+    let mut transformer = Transformer {
+        mode,
+        operators,
+        options,
+        propagate: false,
+        audit,
+        warnings: Vec::new(),
+        error: None,
+        types: Vec::new(),
+        modules: Vec::new(),
+        module_path: Vec::new(),
+        tracked: TokenStream::new(),
+    };
+    if let Some((policy, name)) = policy {
+        transformer.apply_policy(policy, name)?;
+    }
+    Ok(Some(transformer))
+}
+
+/// Implementation of `#[checked_fn]` and `#[try_fn]`, which transforms the
+/// function into the fallible `mode`, and changes it to return the `Option` or
+/// `Result` with failures propagated using `?`.
+pub fn fallible_fn_impl(
+    mode: Mode,
+    attr: TokenStream,
+    item: TokenStream,
+) -> syn::Result<TokenStream> {
+    fallible_fn_item(mode, attr, item, Options::default())
+}
+
+/// Transforms the function `item` like [`fallible_fn_impl`], with `options`.
+pub(crate) fn fallible_fn_item(
+    mode: Mode,
+    attr: TokenStream,
+    item: TokenStream,
+    options: Options,
+) -> syn::Result<TokenStream> {
+    let mut item_fn: ItemFn = syn::parse2(item)?;
+    let mut transformer = Transformer {
+        mode,
+        operators: Vec::new(),
+        options,
+        propagate: true,
+        audit: false,
+        warnings: Vec::new(),
+        error: None,
+        types: Vec::new(),
+        modules: Vec::new(),
+        module_path: Vec::new(),
+        tracked: TokenStream::new(),
+    };
+    transformer.visit_block_mut(&mut item_fn.block);
+    if let Some(e) = transformer.error {
+        return Err(e);
+    }
+    let name = if mode == Mode::Checked {
+        "checked_fn"
+    } else {
+        "try_fn"
+    };
+    warn_stacked_attributes(name, &mut item_fn);
+    let ty = match &item_fn.sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) => quote! { #ty },
+    };
+    let return_type = match mode {
+        Mode::Checked if attr.is_empty() => quote! { ::core::option::Option<#ty> },
+        Mode::Checked => {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "#[checked_fn] does not take any arguments",
+            ))
+        }
+        Mode::TryChecked => {
+            let error: Type = if attr.is_empty() {
+                syn::parse_quote! { ::arithmetic_mode::ArithmeticError }
+            } else {
+                syn::parse2(attr)?
+            };
+            quote! { ::core::result::Result<#ty, #error> }
+        }
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                format!("{mode:?} mode cannot be used for fallible functions"),
+            ))
+        }
+    };
+    item_fn.sig.output = syn::parse2(quote! { -> #return_type })?;
+    let success = mode.success();
+    let block = &item_fn.block;
+    item_fn.block = syn::parse2(quote! { { #success(#block) } })?;
+    Ok(item_fn.into_token_stream())
+}
+
+/// The operators that can be given their own mode in the attribute, like
+/// `add = "wrapping"`.
+pub(crate) const OPERATORS: [&str; 7] = ["add", "sub", "mul", "div", "rem", "shl", "shr"];
+
+/// The name of the arithmetic operator `op` in [`OPERATORS`].
+fn operator_name(op: &BinOp) -> Option<&'static str> {
+    Some(match op {
+        BinOp::Add(_) => "add",
+        BinOp::Sub(_) => "sub",
+        BinOp::Mul(_) => "mul",
+        BinOp::Div(_) => "div",
+        BinOp::Rem(_) => "rem",
+        BinOp::Shl(_) => "shl",
+        BinOp::Shr(_) => "shr",
+        _ => return None,
+    })
+}
+
+/// The operator and its mode given like `add = "wrapping"`.
+fn operator_mode(operator: &MetaNameValue) -> syn::Result<(&'static str, Mode)> {
+    let name = OPERATORS
+        .into_iter()
+        .find(|name| operator.path.is_ident(name))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                &operator.path,
+                format!(
+                    "Unknown operator `{}`, expected one of {}",
+                    operator.path.to_token_stream(),
+                    OPERATORS.join(", ")
+                ),
+            )
+        })?;
+    let Expr::Lit(ExprLit {
+        lit: Lit::Str(mode),
+        ..
+    }) = &operator.value
+    else {
+        return Err(syn::Error::new_spanned(
+            &operator.value,
+            format!("Expected the mode in quotes, like `{name} = \"wrapping\"`"),
+        ));
+    };
+    Ok((name, Mode::from_ident(&mode.parse()?)?))
+}
+
+/// Visitor that transforms every arithmetic expression it finds. Since the
+/// visitor cannot return errors, they are recorded instead.
+struct Transformer {
+    /// The mode of the operators that are not in `operators`.
+    mode: Mode,
+    /// The operators given their own mode, by their name in [`OPERATORS`].
+    operators: Vec<(&'static str, Mode)>,
+    options: Options,
+    /// Whether failures of the fallible modes are propagated out of the
+    /// function with `?`, so that the transformed expressions keep their type.
+    propagate: bool,
+    /// Whether to report every operation with a warning, see [`audit_note`].
+    audit: bool,
+    /// The warnings about conflicting modes in the current block, which are
+    /// added to its start.
+    warnings: Vec<TokenStream>,
+    error: Option<syn::Error>,
+    /// The integer types given their own mode by a policy, whose operations
+    /// are transformed like in `modes!`, with `mode` for the other types.
+    types: Vec<(Ident, Mode)>,
+    /// The modules given their own `mode` by a policy, by the last segments
+    /// of their path.
+    modules: Vec<(Vec<String>, Mode)>,
+    /// The path of the current module inside the item.
+    module_path: Vec<String>,
+    /// The item tracking the config file of the policy, if any, see
+    /// [`Policy::tracked`].
+    tracked: TokenStream,
+}
+
+impl Transformer {
+    /// Applies the `policy` named `name` of the config file, whose modes
+    /// are used unless given in the attribute.
+    fn apply_policy(&mut self, policy: Policy, name: &LitStr) -> syn::Result<()> {
+        self.tracked = policy.tracked();
+        for (operator, mode) in policy.operators {
+            if !self.operators.iter().any(|(other, _)| *other == operator) {
+                self.operators.push((operator, mode));
+            }
+        }
+        self.audit |= policy.audit;
+        if !policy.types.is_empty() {
+            if !self.operators.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "The modes of the types of the policy cannot be combined with the modes of \
+                    the operators",
+                ));
+            }
+            if !matches!(
+                self.mode,
+                Mode::Panicking | Mode::Wrapping | Mode::Saturating
+            ) {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    format!(
+                        "The {} mode cannot be used with the modes of the types of the policy, \
+                        which only support panicking, wrapping and saturating mode",
+                        self.mode.name()
+                    ),
+                ));
+            }
+        }
+        self.types = policy.types;
+        self.modules = policy.modules;
+        Ok(())
+    }
+
+    /// The mode of the operation `op`.
+    fn mode_of(&self, op: &BinOp) -> Mode {
+        operator_name(op).map_or(self.mode, |name| self.mode_of_operator(name))
+    }
+
+    /// The mode of the operator `name` in [`OPERATORS`].
+    fn mode_of_operator(&self, name: &str) -> Mode {
+        self.operators
+            .iter()
+            .find(|(operator, _)| *operator == name)
+            .map_or(self.mode, |(_, mode)| *mode)
+    }
+
+    fn transform(&mut self, mut expr: Expr) -> Expr {
+        propagate_literal_type(&mut expr);
+        let result = check_ambiguous_literals(&expr).and_then(|()| {
+            if !self.types.is_empty() {
+                let modes = TypeModes {
+                    types: self.types.clone(),
+                    other: self.mode,
+                };
+                return modes::per_type_expr(&modes, &expr, self.options);
+            }
+            if self.operators.is_empty() {
+                return self.mode.transform(expr, self.options);
+            }
+            // Each operation is in the mode of its operator, like in `mixed!`
+            mixed::transform_by(expr, self.mode, self.options, &|binary| {
+                Ok(self.mode_of(&binary.op))
+            })
+        });
+        match result {
+            Ok(tokens) if self.propagate => Expr::Verbatim(quote! { (#tokens)? }),
+            Ok(tokens) => Expr::Verbatim(tokens),
+            Err(e) => {
+                self.record(e);
+                Expr::Verbatim(TokenStream::new())
+            }
+        }
+    }
+
+    /// The audit notes of the arithmetic operations in `expr` that are
+    /// transformed together, i.e. excluding the ones in its operands, in an
+    /// operation in `mode`.
+    fn audit_chain(&self, expr: &Expr, mode: Mode) -> TokenStream {
+        match expr {
+            _ if !self.audit => TokenStream::new(),
+            Expr::Binary(ExprBinary {
+                left, op, right, ..
+            }) => {
+                let mode = if is_arithmetic(op) {
+                    self.mode_of(op)
+                } else {
+                    mode
+                };
+                let mut notes = self.audit_chain(left, mode);
+                if is_arithmetic(op) {
+                    notes.extend(self.audit_transformed(expr, op));
+                }
+                notes.extend(self.audit_chain(right, mode));
+                notes
+            }
+            Expr::Group(ExprGroup { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) => {
+                self.audit_chain(expr, mode)
+            }
+            Expr::Unary(ExprUnary { op, expr, .. })
+                if mode.is_fallible() && !matches!(op, UnOp::Deref(_)) =>
+            {
+                self.audit_chain(expr, mode)
+            }
+            _ => TokenStream::new(),
+        }
+    }
+
+    /// The audit note of the operation `op` in `expr`, transformed into the
+    /// mode.
+    fn audit_transformed(&self, expr: &impl ToTokens, op: &BinOp) -> TokenStream {
+        let mode = self.mode_of(op).name();
+        let message = if self.types.is_empty() {
+            format!("`{}` uses {mode} arithmetic", expr.to_token_stream())
+        } else {
+            format!(
+                "`{}` uses the arithmetic of the mode of its type, or {mode} arithmetic",
+                expr.to_token_stream()
+            )
+        };
+        audit_note(op.span(), &message)
+    }
+
+    /// Records the error `e`, in addition to the previous ones.
+    fn record(&mut self, e: syn::Error) {
+        match &mut self.error {
+            Some(error) => error.combine(e),
+            None => self.error = Some(e),
+        }
+    }
+
+    /// Warns about the nested mode macro `mac` if its mode differs from this
+    /// one, since it is not transformed and keeps its own mode.
+    fn check_nested_macro(&mut self, mac: &Macro) {
+        let Some(segment) = mac.path.segments.last() else {
+            return;
+        };
+        let name = segment.ident.to_string();
+        // `const_checked!` panics like `panicking!`, at compile time, and
+        // `control_flow!` fails like `try_checked!`
+        let nested = match name.as_str() {
+            "const_checked" => "panicking",
+            "control_flow" => "try_checked",
+            name if MODE_MACROS.contains(&name) => name,
+            _ => return,
+        };
+        if nested != self.mode.name() {
+            let message = format!(
+                "`{name}!` overrides the {} mode of the enclosing attribute",
+                self.mode.name()
+            );
+            self.warn(segment.ident.span(), &message);
+        }
+    }
+
+    /// Warns about the explicit integer method `method`, like `wrapping_add`,
+    /// if its mode differs from this one, since it is not transformed.
+    fn check_explicit_method(&mut self, method: &Ident) {
+        let name = method.to_string();
+        let Some((prefix, operation)) = name.split_once('_') else {
+            return;
+        };
+        let explicit = matches!(
+            prefix,
+            "checked" | "wrapping" | "saturating" | "overflowing"
+        ) && matches!(
+            operation,
+            "add" | "sub" | "mul" | "div" | "rem" | "shl" | "shr" | "neg" | "pow"
+        );
+        let mode = self.mode_of_operator(operation);
+        let same = prefix == mode.name() || (prefix == "checked" && mode == Mode::TryChecked);
+        if explicit && !same {
+            let message = format!(
+                "`{name}` keeps its explicit arithmetic instead of the {} mode of the enclosing \
+                attribute",
+                mode.name()
+            );
+            self.warn(method.span(), &message);
+        }
+    }
+
+    /// Adds a warning about conflicting modes at `span` to the current block.
+    fn warn(&mut self, span: Span, message: &str) {
+        let message = format!("arithmetic_mode: {message}");
+        self.warnings.push(diagnostic::warning(
+            "ARITHMETIC_MODE_CONFLICT",
+            span,
+            &message,
+        ));
+    }
+
+    /// Calls `f` on `item`, in a const context if `is_const` is true.
+    fn with_const<T>(&mut self, is_const: bool, item: &mut T, f: fn(&mut Self, &mut T)) {
+        let options = self.options;
+        self.options.is_const |= is_const;
+        f(self, item);
+        self.options = options;
+    }
+
+    /// The operands `a` and `b` of the multiply-accumulate `acc += a * b`,
+    /// whose product is added to `acc` exactly, like `mac!` does. Const
+    /// contexts keep the separate operations, since the functions of `mac!`
+    /// are not const.
+    fn multiply_accumulate<'a>(&self, op: &BinOp, right: &'a Expr) -> Option<(&'a Expr, &'a Expr)> {
+        match right {
+            // The types of a policy may give the operators different modes
+            _ if self.options.is_const || !self.types.is_empty() => None,
+            _ if !matches!(op, BinOp::Add(_)) => None,
+            Expr::Group(ExprGroup { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) => {
+                self.multiply_accumulate(op, expr)
+            }
+            // Both operators have to be in the same mode
+            Expr::Binary(ExprBinary {
+                attrs,
+                left,
+                op: mul @ BinOp::Mul(_),
+                right,
+            }) if attrs.is_empty() && self.mode_of(mul) == self.mode_of(op) => Some((left, right)),
+            _ => None,
+        }
+    }
+
+    /// Transforms `left += a * b` like `mac!` does in `mode`, after the
+    /// `notes`.
+    fn transform_mac(
+        &mut self,
+        left: &mut Expr,
+        mut a: Expr,
+        mut b: Expr,
+        mode: Mode,
+        notes: TokenStream,
+    ) -> Expr {
+        let function = mac_function(mode);
+        let deref_a = deref_operand(
+            self.options.may_be_reference(&a),
+            quote! { __arithmetic_mode_a },
+        );
+        let deref_b = deref_operand(
+            self.options.may_be_reference(&b),
+            quote! { __arithmetic_mode_b },
+        );
+        self.visit_expr_mut(left);
+        self.visit_expr_mut(&mut a);
+        self.visit_expr_mut(&mut b);
+        let mut new_expr = quote! {
+            ::arithmetic_mode::__private::#function(
+                *__arithmetic_mode_place,
+                #deref_a,
+                #deref_b,
+            )
+        };
+        if self.propagate {
+            new_expr = quote! { (#new_expr)? };
+        }
+        // Like the other compound assignments, the operands are evaluated
+        // before the place being assigned to.
+        Expr::Verbatim(quote! {
+            {
+                #notes
+                let __arithmetic_mode_a = #a;
+                let __arithmetic_mode_b = #b;
+                let __arithmetic_mode_place = &mut #left;
+                *__arithmetic_mode_place = #new_expr;
+            }
+        })
+    }
+
+    /// Transforms the operands of the arithmetic expression `expr` that are not
+    /// themselves part of the arithmetic (e.g. function call arguments) on their
+    /// own, and turns the ones that the modes don't support into opaque
+    /// `Expr::Verbatim` operands.
+    fn isolate_operands(&mut self, expr: &mut Expr, mode: Mode) {
+        match expr {
+            Expr::Binary(ExprBinary {
+                left, op, right, ..
+            }) => {
+                let mode = if is_arithmetic(op) {
+                    self.mode_of(op)
+                } else {
+                    mode
+                };
+                self.isolate_operands(left, mode);
+                self.isolate_operands(right, mode);
+            }
+            Expr::Group(ExprGroup { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) => {
+                self.isolate_operands(expr, mode)
+            }
+            // Only the fallible modes transform the operand of unary operators
+            Expr::Unary(ExprUnary { op, expr, .. })
+                if mode.is_fallible() && !matches!(op, UnOp::Deref(_)) =>
+            {
+                self.isolate_operands(expr, mode)
+            }
+            Expr::Lit(_) | Expr::Path(_) => {}
+            Expr::Cast(_) | Expr::Index(_) | Expr::Reference(_) | Expr::Unary(_) => {
+                visit_mut::visit_expr_mut(self, expr)
+            }
+            _ => {
+                self.visit_expr_mut(expr);
+                *expr = Expr::Verbatim(expr.to_token_stream());
+            }
+        }
+    }
+}
+
+impl VisitMut for Transformer {
+    // Items with their own `#[arithmetic_mode]` attribute are transformed by
+    // that attribute instead.
+    fn visit_item_fn_mut(&mut self, item_fn: &mut ItemFn) {
+        if !has_mode_attribute(&item_fn.attrs) {
+            let is_const = item_fn.sig.constness.is_some();
+            self.with_const(is_const, item_fn, visit_mut::visit_item_fn_mut);
+        }
+    }
+
+    fn visit_item_impl_mut(&mut self, item_impl: &mut ItemImpl) {
+        if !has_mode_attribute(&item_impl.attrs) {
+            visit_mut::visit_item_impl_mut(self, item_impl);
+        }
+    }
+
+    fn visit_item_mod_mut(&mut self, item_mod: &mut ItemMod) {
+        if !has_mode_attribute(&item_mod.attrs) {
+            self.module_path.push(item_mod.ident.to_string());
+            let mode = self.mode;
+            // The most specific module of the policy, if any
+            let module = self
+                .modules
+                .iter()
+                .filter(|(path, _)| self.module_path.ends_with(path))
+                .max_by_key(|(path, _)| path.len());
+            if let Some((_, module_mode)) = module {
+                self.mode = *module_mode;
+            }
+            visit_mut::visit_item_mod_mut(self, item_mod);
+            self.mode = mode;
+            self.module_path.pop();
+        }
+    }
+
+    fn visit_item_trait_mut(&mut self, item_trait: &mut ItemTrait) {
+        if !has_mode_attribute(&item_trait.attrs) {
+            visit_mut::visit_item_trait_mut(self, item_trait);
+        }
+    }
+
+    fn visit_trait_item_fn_mut(&mut self, trait_item_fn: &mut TraitItemFn) {
+        if !has_mode_attribute(&trait_item_fn.attrs) {
+            let is_const = trait_item_fn.sig.constness.is_some();
+            self.with_const(is_const, trait_item_fn, visit_mut::visit_trait_item_fn_mut);
+        }
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, impl_item_fn: &mut ImplItemFn) {
+        if !has_mode_attribute(&impl_item_fn.attrs) {
+            let is_const = impl_item_fn.sig.constness.is_some();
+            self.with_const(is_const, impl_item_fn, visit_mut::visit_impl_item_fn_mut);
+        }
+    }
+
+    fn visit_item_const_mut(&mut self, item_const: &mut ItemConst) {
+        if !has_mode_attribute(&item_const.attrs) {
+            self.with_const(true, item_const, visit_mut::visit_item_const_mut);
+        }
+    }
+
+    fn visit_item_static_mut(&mut self, item_static: &mut ItemStatic) {
+        if !has_mode_attribute(&item_static.attrs) {
+            self.with_const(true, item_static, visit_mut::visit_item_static_mut);
+        }
+    }
+
+    fn visit_impl_item_const_mut(&mut self, impl_item_const: &mut ImplItemConst) {
+        if !has_mode_attribute(&impl_item_const.attrs) {
+            self.with_const(true, impl_item_const, visit_mut::visit_impl_item_const_mut);
+        }
+    }
+
+    fn visit_trait_item_const_mut(&mut self, trait_item_const: &mut TraitItemConst) {
+        if !has_mode_attribute(&trait_item_const.attrs) {
+            self.with_const(
+                true,
+                trait_item_const,
+                visit_mut::visit_trait_item_const_mut,
+            );
+        }
+    }
+
+    fn visit_expr_const_mut(&mut self, expr_const: &mut ExprConst) {
+        self.with_const(true, expr_const, visit_mut::visit_expr_const_mut);
+    }
+
+    fn visit_type_array_mut(&mut self, type_array: &mut TypeArray) {
+        self.with_const(true, type_array, visit_mut::visit_type_array_mut);
+    }
+
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        // `?` in nested items would not propagate out of the function
+        if !self.propagate {
+            visit_mut::visit_item_mut(self, item);
+        }
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        let warnings = std::mem::take(&mut self.warnings);
+        visit_mut::visit_block_mut(self, block);
+        let nested = std::mem::replace(&mut self.warnings, warnings);
+        let stmts = nested
+            .into_iter()
+            .map(|warning| Stmt::Expr(Expr::Verbatim(warning), Some(Default::default())));
+        block.stmts.splice(0..0, stmts);
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut Macro) {
+        self.check_nested_macro(mac);
+    }
+
+    fn visit_expr_method_call_mut(&mut self, call: &mut ExprMethodCall) {
+        self.check_explicit_method(&call.method);
+        visit_mut::visit_expr_method_call_mut(self, call);
+    }
+
+    fn visit_local_mut(&mut self, local: &mut Local) {
+        if !take_skip_marker(&mut local.attrs) {
+            visit_mut::visit_local_mut(self, local);
+        } else if let Some(init) = local.init.as_mut().filter(|_| self.audit) {
+            audit_skipped(&mut init.expr);
+        }
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if expr_attrs_mut(expr).is_some_and(take_skip_marker) {
+            if self.audit {
+                audit_skipped(expr);
+            }
+            return;
+        }
+        match expr {
+            // `?` in closures would not propagate out of the function
+            Expr::Async(_) | Expr::Closure(_) if self.propagate => {}
+            Expr::Return(ExprReturn { expr: value, .. }) if self.propagate => {
+                if let Some(value) = value {
+                    self.visit_expr_mut(value);
+                }
+                let value = value
+                    .take()
+                    .map_or_else(|| quote! { () }, |v| quote! { #v });
+                let success = self.mode.success();
+                *expr = Expr::Verbatim(quote! { return #success(#value) });
+            }
+            Expr::Binary(ExprBinary { op, .. }) if is_arithmetic(op) => {
+                let notes = self.audit_chain(expr, self.mode);
+                self.isolate_operands(expr, self.mode);
+                *expr = self.transform(expr.clone());
+                if !notes.is_empty() {
+                    *expr = Expr::Verbatim(quote! { { #notes #expr } });
+                }
+            }
+            Expr::Binary(ExprBinary {
+                left,
+                op: compound,
+                right,
+                ..
+            }) => {
+                let Some(op) = compound_op(compound) else {
+                    return visit_mut::visit_expr_mut(self, expr);
+                };
+                let mode = self.mode_of(&op);
+                if mode.is_fallible() && !self.propagate {
+                    let limitation = Limitation::FallibleCompoundAssignment(mode);
+                    self.record(limitation.error(compound));
+                    return;
+                }
+                let notes = if self.audit {
+                    self.audit_transformed(&quote! { #left #compound #right }, &op)
+                } else {
+                    TokenStream::new()
+                };
+                if let Some((a, b)) = self.multiply_accumulate(&op, right) {
+                    let (a, b) = (a.clone(), b.clone());
+                    *expr = self.transform_mac(left, a, b, mode, notes);
+                    return;
+                }
+                self.visit_expr_mut(left);
+                self.visit_expr_mut(right);
+                // Evaluate the right hand side first, so that it can refer to
+                // the place being assigned to.
+                let new_expr = self.transform(Expr::Binary(ExprBinary {
+                    attrs: Vec::new(),
+                    left: Box::new(Expr::Verbatim(quote! { *__arithmetic_mode_place })),
+                    op,
+                    right: Box::new(Expr::Verbatim(quote! { __arithmetic_mode_rhs })),
+                }));
+                *expr = Expr::Verbatim(quote! {
+                    {
+                        #notes
+                        let __arithmetic_mode_rhs = #right;
+                        let __arithmetic_mode_place = &mut #left;
+                        *__arithmetic_mode_place = #new_expr;
+                    }
+                });
+            }
+            _ => visit_mut::visit_expr_mut(self, expr),
+        }
+    }
+}
+
+/// The mode macros provided by this crate.
+pub(crate) const MODE_MACROS: &[&str] = &[
+    "panicking",
+    "wrapping",
+    "saturating",
+    "checked",
+    "try_checked",
+    "verified",
+];
+
+/// The attributes of this crate that transform the arithmetic of an item.
+const MODE_ATTRIBUTES: &[&str] = &["arithmetic_mode", "checked_fn", "try_fn"];
+
+/// The name of `attr` if it is one of the [`MODE_ATTRIBUTES`].
+fn mode_attribute(attr: &Attribute) -> Option<&Ident> {
+    let segment = attr.path().segments.last()?;
+    MODE_ATTRIBUTES
+        .iter()
+        .any(|name| segment.ident == name)
+        .then_some(&segment.ident)
+}
+
+fn has_mode_attribute(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| mode_attribute(attr).is_some())
+}
+
+/// Warns about the attributes of `item_fn` that transform its arithmetic again
+/// after the attribute `name` did.
+fn warn_stacked_attributes(name: &str, item_fn: &mut ItemFn) {
+    let warnings = item_fn
+        .attrs
+        .iter()
+        .filter(|attr| !is_skip_marker(attr))
+        .filter_map(mode_attribute)
+        .map(|other| {
+            let message = format!(
+                "arithmetic_mode: the arithmetic is transformed twice, by `#[{name}]` and \
+                again by `#[{other}]`"
+            );
+            let warning = diagnostic::warning("ARITHMETIC_MODE_CONFLICT", other.span(), &message);
+            Stmt::Expr(Expr::Verbatim(warning), Some(Default::default()))
+        })
+        .collect::<Vec<_>>();
+    item_fn.block.stmts.splice(0..0, warnings);
+}
+
+/// Whether `attr` is the `#[arithmetic_mode(skip)]` marker, which leaves the
+/// marked item, statement or expression unchanged.
+pub(crate) fn is_skip_marker(attr: &Attribute) -> bool {
+    mode_attribute(attr).is_some_and(|ident| ident == "arithmetic_mode")
+        && attr
+            .parse_args::<Ident>()
+            .is_ok_and(|ident| ident == "skip")
+}
+
+/// Removes the skip marker from `attrs`, returning whether it was present.
+fn take_skip_marker(attrs: &mut Vec<Attribute>) -> bool {
+    let len = attrs.len();
+    attrs.retain(|attr| !is_skip_marker(attr));
+    attrs.len() != len
+}
+
+/// Returns the attributes of the expressions that can have attributes in
+/// statement position.
+pub(crate) fn expr_attrs_mut(expr: &mut Expr) -> Option<&mut Vec<Attribute>> {
+    match expr {
+        Expr::Array(ExprArray { attrs, .. })
+        | Expr::Assign(ExprAssign { attrs, .. })
+        | Expr::Binary(ExprBinary { attrs, .. })
+        | Expr::Block(ExprBlock { attrs, .. })
+        | Expr::Call(ExprCall { attrs, .. })
+        | Expr::ForLoop(ExprForLoop { attrs, .. })
+        | Expr::If(ExprIf { attrs, .. })
+        | Expr::Loop(ExprLoop { attrs, .. })
+        | Expr::Macro(ExprMacro { attrs, .. })
+        | Expr::Match(ExprMatch { attrs, .. })
+        | Expr::MethodCall(ExprMethodCall { attrs, .. })
+        | Expr::Paren(ExprParen { attrs, .. })
+        | Expr::Return(ExprReturn { attrs, .. })
+        | Expr::Tuple(ExprTuple { attrs, .. })
+        | Expr::Unsafe(ExprUnsafe { attrs, .. })
+        | Expr::While(ExprWhile { attrs, .. }) => Some(attrs),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_arithmetic(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Add(_)
+            | BinOp::Sub(_)
+            | BinOp::Mul(_)
+            | BinOp::Div(_)
+            | BinOp::Rem(_)
+            | BinOp::Shl(_)
+            | BinOp::Shr(_)
+    )
+}
+
+/// Returns the arithmetic operator of a compound assignment, e.g. `+` for `+=`.
+pub(crate) fn compound_op(op: &BinOp) -> Option<BinOp> {
+    Some(match op {
+        BinOp::AddAssign(t) => BinOp::Add(syn::Token![+](t.spans[0])),
+        BinOp::SubAssign(t) => BinOp::Sub(syn::Token![-](t.spans[0])),
+        BinOp::MulAssign(t) => BinOp::Mul(syn::Token![*](t.spans[0])),
+        BinOp::DivAssign(t) => BinOp::Div(syn::Token![/](t.spans[0])),
+        BinOp::RemAssign(t) => BinOp::Rem(syn::Token![%](t.spans[0])),
+        BinOp::ShlAssign(t) => BinOp::Shl(syn::Token![<<](t.spans[0])),
+        BinOp::ShrAssign(t) => BinOp::Shr(syn::Token![>>](t.spans[0])),
+        _ => return None,
+    })
+}
+
+/// A note reported as a warning at `span`, see [`diagnostic::warning`].
+fn audit_note(span: Span, message: &str) -> TokenStream {
+    let message = format!("arithmetic_mode audit: {message}");
+    diagnostic::warning("ARITHMETIC_MODE_AUDIT", span, &message)
+}
+
+/// Adds audit notes for the arithmetic operations left unchanged in the
+/// skipped `expr`.
+fn audit_skipped(expr: &mut Expr) {
+    struct Skipped(TokenStream);
+
+    impl VisitMut for Skipped {
+        fn visit_expr_binary_mut(&mut self, binary: &mut ExprBinary) {
+            if is_arithmetic(&binary.op) || compound_op(&binary.op).is_some() {
+                let message = format!(
+                    "`{}` is skipped and uses the default arithmetic",
+                    binary.to_token_stream()
+                );
+                self.0.extend(audit_note(binary.op.span(), &message));
+            }
+            visit_mut::visit_expr_binary_mut(self, binary);
+        }
+
+        // Nested items are reported by their own attribute
+        fn visit_item_mut(&mut self, _: &mut Item) {}
+    }
+
+    let mut skipped = Skipped(TokenStream::new());
+    skipped.visit_expr_mut(expr);
+    let notes = skipped.0;
+    if !notes.is_empty() {
+        *expr = Expr::Verbatim(quote! { { #notes #expr } });
+    }
+}
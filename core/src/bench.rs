@@ -0,0 +1,86 @@
+//! The `bench!` macro, which transforms an expression like the mode given in
+//! brackets, like `bench! { [checked] a * b + c }`, with its operands passed
+//! through `core::hint::black_box`. The optimizer then cannot constant-fold
+//! the expression or hoist it out of a benchmark loop, so that the modes can be
+//! compared by their cost at runtime.
+
+use proc_macro2::TokenStream;
+use quote::quote_spanned;
+use syn::spanned::Spanned;
+use syn::{Expr, ExprBinary, ExprGroup, ExprParen, ExprUnary, UnOp};
+
+use crate::passthrough::passthrough_expr;
+use crate::{input, mixed};
+
+/// Expands `bench! { item }`.
+pub fn bench_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (mode, item) = mixed::split_default_mode(item)?;
+    let (expr, options) = input::parse_expr(item)?;
+    mode.transform(black_box_operands(expr), options)
+}
+
+/// The original operators of `bench! { item }`, with the operands still
+/// passed through `black_box`, for `--cfg arithmetic_mode_passthrough`.
+pub(crate) fn passthrough_bench(item: TokenStream) -> syn::Result<TokenStream> {
+    let (mode, item) = mixed::split_default_mode(item)?;
+    let (expr, _) = input::parse_expr(item)?;
+    passthrough_expr(black_box_operands(expr), mode)
+}
+
+/// Passes the operands of the arithmetic in `expr` through `black_box`. The
+/// operands that the modes splice in, like `opt(x)` in `checked!` and `x?` in
+/// `try_checked!`, are already wrapped, so they are left unchanged.
+fn black_box_operands(expr: Expr) -> Expr {
+    match expr {
+        Expr::Binary(ExprBinary {
+            attrs,
+            left,
+            op,
+            right,
+        }) => Expr::Binary(ExprBinary {
+            attrs,
+            left: Box::new(black_box_operands(*left)),
+            op,
+            right: Box::new(black_box_operands(*right)),
+        }),
+        Expr::Group(ExprGroup {
+            attrs,
+            group_token,
+            expr,
+        }) => Expr::Group(ExprGroup {
+            attrs,
+            group_token,
+            expr: Box::new(black_box_operands(*expr)),
+        }),
+        Expr::Paren(ExprParen {
+            attrs,
+            paren_token,
+            expr,
+        }) => Expr::Paren(ExprParen {
+            attrs,
+            paren_token,
+            expr: Box::new(black_box_operands(*expr)),
+        }),
+        // Dereferencing is part of the operand
+        Expr::Unary(ExprUnary { attrs, op, expr }) if !matches!(op, UnOp::Deref(_)) => {
+            Expr::Unary(ExprUnary {
+                attrs,
+                op,
+                expr: Box::new(black_box_operands(*expr)),
+            })
+        }
+        // Type hints, like `(x: u8)`, are blocks
+        Expr::Block(_)
+        | Expr::Cast(_)
+        | Expr::Index(_)
+        | Expr::Lit(_)
+        | Expr::Path(_)
+        | Expr::Reference(_)
+        | Expr::Unary(_) => {
+            // Isolated like the operands of the attribute, so that the modes
+            // keep it as an operand
+            Expr::Verbatim(quote_spanned! { expr.span()=> ::core::hint::black_box(#expr) })
+        }
+        expr => expr,
+    }
+}
@@ -0,0 +1,58 @@
+//! The `blend_add!` and `scale_u8!` macros for the 8-bit channels of image
+//! compositing, like `blend_add! { dst, glow }` and
+//! `scale_u8! { color, alpha, 255 }`, which saturate at `u8::MAX` instead of
+//! hand-rolling the widening and the rounding.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{Expr, Token};
+
+use crate::mul_div;
+
+/// Expands `blend_add! { item }`.
+pub fn blend_add_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let channels = Punctuated::<Expr, Token![,]>::parse_terminated.parse2(item)?;
+    let mut channels = channels.iter();
+    let (Some(first), Some(_)) = (channels.next(), channels.clone().next()) else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "blend_add! takes two or more channels, like `blend_add! { a, b }`",
+        ));
+    };
+    Ok(channels.fold(quote! { #first }, |sum, channel| {
+        quote! { ::arithmetic_mode::__private::Channel::blend_add(#sum, #channel) }
+    }))
+}
+
+/// Expands `scale_u8! { item }`.
+pub fn scale_u8_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let mut operands: Vec<Expr> = Punctuated::<Expr, Token![,]>::parse_terminated
+        .parse2(item)?
+        .into_iter()
+        .collect();
+    // Rounded to the nearest value by default, like the colors of images are
+    let rounding = match operands.last() {
+        Some(Expr::Assign(assign)) => {
+            let rounding = mul_div::rounding(assign)?;
+            operands.pop();
+            rounding
+        }
+        _ => quote! { Nearest },
+    };
+    let [Some(x), Some(num), Some(den), None] = [0, 1, 2, 3].map(|i| operands.get(i)) else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "scale_u8! takes three operands, like `scale_u8! { x, num, den }` for `x * num / den`",
+        ));
+    };
+    Ok(quote! {
+        ::arithmetic_mode::__private::Channel::scale(
+            #x,
+            #num,
+            #den,
+            ::arithmetic_mode::__private::Rounding::#rounding,
+        )
+    })
+}
@@ -0,0 +1,343 @@
+//! The named policies of the config file `arithmetic-mode.toml`, which
+//! `#[arithmetic_mode(policy = "payments")]` applies, so that a team can keep
+//! its overflow rules in one place. The file is looked up in the directory of
+//! the crate being compiled and its parent directories, like the workspace
+//! root, and declares each policy in its own table:
+//!
+//! ```toml
+//! [policy.payments]
+//! mode = "checked"
+//! mul = "checked"
+//! audit = true
+//!
+//! [policy.payments.modules]
+//! fees = "saturating"
+//! ```
+//!
+//! Only the subset of TOML used by the policies is supported: tables, and
+//! string or boolean values.
+
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, LitStr};
+
+use crate::attribute::{Mode, OPERATORS};
+use crate::literal_type::is_integer_type;
+
+/// The name of the config file.
+const FILE: &str = "arithmetic-mode.toml";
+
+/// A policy of the config file.
+#[derive(Debug, Default)]
+pub(crate) struct Policy {
+    /// The mode of the operators without a mode of their own, if given.
+    pub(crate) mode: Option<Mode>,
+    /// The operators given their own mode, like in the attribute.
+    pub(crate) operators: Vec<(&'static str, Mode)>,
+    /// Whether every operation is reported, like the `audit` flag.
+    pub(crate) audit: bool,
+    /// The integer types given their own mode, like in `modes!`.
+    pub(crate) types: Vec<(Ident, Mode)>,
+    /// The modules inside the item given their own default mode, by the last
+    /// segments of their path, like `fees` or `billing::fees`.
+    pub(crate) modules: Vec<(Vec<String>, Mode)>,
+    /// The config file declaring the policy.
+    pub(crate) path: PathBuf,
+}
+
+impl Policy {
+    /// All the modes used by the policy.
+    pub(crate) fn modes(&self) -> impl Iterator<Item = Mode> + '_ {
+        self.mode
+            .into_iter()
+            .chain(self.operators.iter().map(|(_, mode)| *mode))
+            .chain(self.types.iter().map(|(_, mode)| *mode))
+            .chain(self.modules.iter().map(|(_, mode)| *mode))
+    }
+
+    /// An item including the config file with `include_bytes!`, so that the
+    /// crate is compiled again when it changes, since the file is only read
+    /// during the expansion. Creating a config file closer to the crate does
+    /// not recompile it though.
+    pub(crate) fn tracked(&self) -> TokenStream {
+        let Some(path) = self.path.to_str() else {
+            return TokenStream::new();
+        };
+        quote! { const _: &[u8] = ::core::include_bytes!(#path); }
+    }
+}
+
+/// Loads the policy `name` from the config file.
+pub(crate) fn load(name: &LitStr) -> syn::Result<Policy> {
+    let error = |message: String| syn::Error::new_spanned(name, message);
+    let Some(path) = find() else {
+        return Err(error(format!(
+            "No {FILE} declaring the policy `{}` in the directory of the crate or its \
+            parent directories",
+            name.value()
+        )));
+    };
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| error(format!("Cannot read {}: {e}", path.display())))?;
+    let policy = parse_policy(&text, &name.value())
+        .map_err(|e| error(format!("{}: {e}", path.display())))?;
+    Ok(Policy { path, ..policy })
+}
+
+/// The config file of the crate being compiled, if any.
+fn find() -> Option<PathBuf> {
+    let dir = std::env::var_os("CARGO_MANIFEST_DIR")?;
+    Path::new(&dir)
+        .ancestors()
+        .map(|dir| dir.join(FILE))
+        .find(|path| path.is_file())
+}
+
+/// A value of the config file.
+#[derive(Debug, PartialEq, Eq)]
+enum Value {
+    String(String),
+    Bool(bool),
+}
+
+/// A line of the config file, other than the blank lines and comments.
+#[derive(Debug, PartialEq, Eq)]
+enum Line {
+    /// The header of a table, like `[policy.payments]`.
+    Table(Vec<String>),
+    /// A key and its value, like `mode = "checked"`.
+    Value(String, Value),
+}
+
+/// The policy `name` declared in the config `text`.
+pub(crate) fn parse_policy(text: &str, name: &str) -> Result<Policy, String> {
+    let mut policy: Option<Policy> = None;
+    // The section of the policy the values are in, like `modules`, if in it
+    let mut section: Option<Option<String>> = None;
+    let mut keys: Vec<(Option<String>, String)> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let at = |message: String| format!("line {}: {message}", number + 1);
+        match parse_line(line).map_err(at)? {
+            None => {}
+            Some(Line::Table(table)) => {
+                section = match table.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                    ["policy", policy] if policy == name => Some(None),
+                    ["policy", policy, section] if policy == name => {
+                        if !matches!(section, "types" | "modules") {
+                            return Err(at(format!(
+                                "Unknown table `{}`, expected `types` or `modules`",
+                                table.join(".")
+                            )));
+                        }
+                        Some(Some(section.to_string()))
+                    }
+                    ["policy", _] | ["policy", _, _] => None,
+                    _ => {
+                        return Err(at(format!(
+                            "Unknown table `{}`, expected a policy like `[policy.payments]`",
+                            table.join(".")
+                        )))
+                    }
+                };
+                if section.is_some() {
+                    policy.get_or_insert_with(Policy::default);
+                }
+            }
+            Some(Line::Value(key, value)) => {
+                let (Some(section), Some(policy)) = (&section, &mut policy) else {
+                    continue;
+                };
+                if keys.contains(&(section.clone(), key.clone())) {
+                    return Err(at(format!("`{key}` is given more than once")));
+                }
+                keys.push((section.clone(), key.clone()));
+                add_value(policy, section.as_deref(), key, value).map_err(at)?;
+            }
+        }
+    }
+    let policy = policy.ok_or_else(|| format!("The policy `{name}` is not declared"))?;
+    check_types(&policy)?;
+    Ok(policy)
+}
+
+/// Adds the `key` and its `value` in the `section` to `policy`.
+fn add_value(
+    policy: &mut Policy,
+    section: Option<&str>,
+    key: String,
+    value: Value,
+) -> Result<(), String> {
+    match (section, key.as_str()) {
+        (None, "mode") => policy.mode = Some(mode(&key, value)?),
+        (None, "audit") => match value {
+            Value::Bool(audit) => policy.audit = audit,
+            Value::String(_) => return Err("Expected `audit = true` or `audit = false`".into()),
+        },
+        (None, name) => {
+            let Some(name) = OPERATORS.into_iter().find(|operator| *operator == name) else {
+                return Err(format!(
+                    "Unknown key `{name}`, expected `mode`, `audit` or one of {}",
+                    OPERATORS.join(", ")
+                ));
+            };
+            let mode = mode(&key, value)?;
+            policy.operators.push((name, mode));
+        }
+        (Some("types"), ty) => {
+            let ty = syn::parse_str::<Ident>(ty)
+                .ok()
+                .filter(is_integer_type)
+                .ok_or_else(|| format!("Unknown integer type `{ty}`"))?;
+            let mode = mode(&key, value)?;
+            policy.types.push((ty, mode));
+        }
+        (Some(_), module) => {
+            let path = module
+                .split("::")
+                .map(|name| {
+                    syn::parse_str::<Ident>(name)
+                        .map(|name| name.to_string())
+                        .map_err(|_| format!("Expected the path of a module, not `{module}`"))
+                })
+                .collect::<Result<_, _>>()?;
+            let mode = mode(&key, value)?;
+            policy.modules.push((path, mode));
+        }
+    }
+    Ok(())
+}
+
+/// The mode `value` of `key`.
+fn mode(key: &str, value: Value) -> Result<Mode, String> {
+    let Value::String(value) = value else {
+        return Err(format!(
+            "Expected the mode in quotes, like `{key} = \"wrapping\"`"
+        ));
+    };
+    syn::parse_str::<Ident>(&value)
+        .and_then(|ident| Mode::from_ident(&ident))
+        .map_err(|_| format!("Unknown arithmetic mode `{value}`"))
+}
+
+/// Checks that the modes of the types of `policy`, which are transformed like
+/// in `modes!`, can be used in an item, where the expressions keep their type.
+fn check_types(policy: &Policy) -> Result<(), String> {
+    if policy.types.is_empty() {
+        return Ok(());
+    }
+    if !policy.operators.is_empty() {
+        return Err(
+            "The modes of the types cannot be combined with the modes of the operators".into(),
+        );
+    }
+    match policy
+        .modes()
+        .find(|mode| !matches!(mode, Mode::Panicking | Mode::Wrapping | Mode::Saturating))
+    {
+        Some(mode) => Err(format!(
+            "The {} mode cannot be used with the modes of the types, which only support \
+            panicking, wrapping and saturating mode",
+            mode.name()
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Parses a `line` of the config file, which is `None` if blank or a comment.
+fn parse_line(line: &str) -> Result<Option<Line>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    if let Some(rest) = line.strip_prefix('[') {
+        let (table, rest) = parse_key(rest)?;
+        let rest = rest
+            .strip_prefix(']')
+            .ok_or("Expected `]` after the table")?;
+        end_of_line(rest)?;
+        return Ok(Some(Line::Table(table)));
+    }
+    let (mut key, rest) = parse_key(line)?;
+    let rest = rest.strip_prefix('=').ok_or("Expected `=` after the key")?;
+    if key.len() != 1 {
+        return Err("Dotted keys are not supported, use a table instead".into());
+    }
+    let (value, rest) = parse_value(rest.trim_start())?;
+    end_of_line(rest)?;
+    Ok(Some(Line::Value(key.remove(0), value)))
+}
+
+/// Parses the dotted key at the start of `input`, like `policy.payments`, and
+/// returns it with the rest of `input`.
+fn parse_key(input: &str) -> Result<(Vec<String>, &str), String> {
+    let mut key = Vec::new();
+    let mut rest = input.trim_start();
+    loop {
+        let (part, after) = if rest.starts_with(['"', '\'']) {
+            parse_string(rest)?
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return Err("Expected a key".into());
+            }
+            (rest[..end].to_string(), &rest[end..])
+        };
+        key.push(part);
+        rest = after.trim_start();
+        match rest.strip_prefix('.') {
+            Some(after) => rest = after.trim_start(),
+            None => return Ok((key, rest)),
+        }
+    }
+}
+
+/// Parses the value at the start of `input`, and returns it with the rest of
+/// `input`.
+fn parse_value(input: &str) -> Result<(Value, &str), String> {
+    if input.starts_with(['"', '\'']) {
+        let (value, rest) = parse_string(input)?;
+        return Ok((Value::String(value), rest));
+    }
+    for (name, value) in [("true", true), ("false", false)] {
+        if let Some(rest) = input.strip_prefix(name) {
+            return Ok((Value::Bool(value), rest));
+        }
+    }
+    Err("Expected a string, `true` or `false`".into())
+}
+
+/// Parses the basic or literal string at the start of `input`, and returns it
+/// with the rest of `input`.
+fn parse_string(input: &str) -> Result<(String, &str), String> {
+    let mut chars = input.char_indices();
+    let quote = chars.next().map(|(_, quote)| quote);
+    let mut value = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            _ if Some(c) == quote => return Ok((value, &input[i + 1..])),
+            '\\' if quote == Some('"') => match chars.next().map(|(_, c)| c) {
+                Some('"') => value.push('"'),
+                Some('\\') => value.push('\\'),
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                _ => return Err("Unsupported escape in the string".into()),
+            },
+            c => value.push(c),
+        }
+    }
+    Err("Unterminated string".into())
+}
+
+/// Checks that only a comment follows the value or table, in `rest`.
+fn end_of_line(rest: &str) -> Result<(), String> {
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err(format!("Unexpected `{rest}`"))
+    }
+}
@@ -0,0 +1,137 @@
+//! The `ct_checked!` macro, which is like `checked!` for constant-time code,
+//! with the `subtle` feature. Each operation computes its result and whether
+//! it overflowed without branching, and the flags are combined into a
+//! `subtle::Choice`, so that the expression evaluates to a `CtOption` instead
+//! of taking a different path when it overflows.
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Expr, ExprBinary, UnOp};
+
+use crate::engine::{self, Strategy};
+use crate::{fold, input, operand_names, Limitation, Mode};
+
+/// Expands `ct_checked! { item }`.
+pub fn ct_checked_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (expr, options) = input::parse_expr(item)?;
+    if options.is_const {
+        return Err(syn::Error::new_spanned(
+            &expr,
+            "ct_checked! cannot be used in const contexts, since the operations of `Choice` are \
+            not const",
+        ));
+    }
+    let expr = fold::fold_constants(expr, Mode::Checked, options.division)?;
+    let result = engine::transform_expr(&expr, &ConstantTime)?;
+    Ok(quote! {
+        {
+            let (v, valid) = #result;
+            ::arithmetic_mode::__private::subtle::CtOption::new(v, valid)
+        }
+    })
+}
+
+/// The operations of `ct_checked!`, which evaluate to the wrapped result and
+/// the `Choice` of whether no operation overflowed.
+struct ConstantTime;
+
+impl Strategy for ConstantTime {
+    fn is_fallible(&self) -> bool {
+        true
+    }
+
+    fn operand(&self, expr: &Expr) -> TokenStream {
+        quote! { (#expr, ::arithmetic_mode::__private::subtle::Choice::from(1_u8)) }
+    }
+
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        new_left: TokenStream,
+        new_right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        let op = binary.op;
+        let span = op.span();
+        let (l, r) = operand_names(span);
+        let subtle = quote_spanned! { span=> ::arithmetic_mode::__private::subtle };
+        let no_overflow = |func: TokenStream, divisor: TokenStream| {
+            quote_spanned! { span=>
+                let (v, overflow) = #l.#func(#divisor);
+                (v, valid & #subtle::Choice::from(u8::from(!overflow)))
+            }
+        };
+        let value = match op {
+            syn::BinOp::Add(_) => no_overflow(quote! { overflowing_add }, quote! { #r }),
+            syn::BinOp::Sub(_) => no_overflow(quote! { overflowing_sub }, quote! { #r }),
+            syn::BinOp::Mul(_) => no_overflow(quote! { overflowing_mul }, quote! { #r }),
+            syn::BinOp::Shl(_) => no_overflow(quote! { overflowing_shl }, quote! { #r }),
+            syn::BinOp::Shr(_) => no_overflow(quote! { overflowing_shr }, quote! { #r }),
+            // The divisor is replaced with 1 when it is zero, which is then
+            // invalid, instead of branching to avoid the panic
+            syn::BinOp::Div(_) | syn::BinOp::Rem(_) => {
+                let func = match op {
+                    syn::BinOp::Div(_) => quote! { overflowing_div },
+                    _ => quote! { overflowing_rem },
+                };
+                let divided = no_overflow(func, quote! { divisor });
+                quote_spanned! { span=>
+                    let zero = #subtle::ConstantTimeEq::ct_eq(&#r, &0);
+                    let divisor = #subtle::ConditionallySelectable::conditional_select(&#r, &1, zero);
+                    let valid = valid & !zero;
+                    #divided
+                }
+            }
+            syn::BinOp::And(_)
+            | syn::BinOp::Or(_)
+            | syn::BinOp::BitXor(_)
+            | syn::BinOp::BitAnd(_)
+            | syn::BinOp::BitOr(_)
+            | syn::BinOp::Eq(_)
+            | syn::BinOp::Lt(_)
+            | syn::BinOp::Le(_)
+            | syn::BinOp::Ne(_)
+            | syn::BinOp::Ge(_)
+            | syn::BinOp::Gt(_) => quote_spanned! { span=> (#l #op #r, valid) },
+            syn::BinOp::AddAssign(_)
+            | syn::BinOp::SubAssign(_)
+            | syn::BinOp::MulAssign(_)
+            | syn::BinOp::DivAssign(_)
+            | syn::BinOp::RemAssign(_)
+            | syn::BinOp::BitXorAssign(_)
+            | syn::BinOp::BitAndAssign(_)
+            | syn::BinOp::BitOrAssign(_)
+            | syn::BinOp::ShlAssign(_)
+            | syn::BinOp::ShrAssign(_) => return Err(Limitation::CompoundAssignment.error(op)),
+            _ => return Err(syn::Error::new_spanned(op, "Unknown operation")),
+        };
+        // Both operands are evaluated, so that the timing does not depend on
+        // which of them overflowed
+        Ok(quote_spanned! { span=>
+            {
+                let (#l, l_valid) = #new_left;
+                let (#r, r_valid) = #new_right;
+                let valid = l_valid & r_valid;
+                #value
+            }
+        })
+    }
+
+    fn unary(&self, op: &UnOp, operand: TokenStream) -> TokenStream {
+        match op {
+            UnOp::Neg(_) => quote_spanned! { op.span()=>
+                {
+                    let (v, valid) = #operand;
+                    let (v, overflow) = v.overflowing_neg();
+                    (v, valid & ::arithmetic_mode::__private::subtle::Choice::from(u8::from(!overflow)))
+                }
+            },
+            _ => quote_spanned! { op.span()=>
+                {
+                    let (v, valid) = #operand;
+                    (#op v, valid)
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! The default mode of a crate, which `default! { expr }` and
+//! `#[arithmetic_mode(default)]` use, so that the policy of a whole workspace
+//! can be changed for an experiment without editing every call site. It is
+//! selected with `--cfg arithmetic_mode_default="saturating"` for
+//! `arithmetic-mode`, through the `__default!` macro of its cfgs, or with the
+//! `ARITHMETIC_MODE_DEFAULT` environment variable read during the expansion,
+//! which takes precedence. Without either, the default mode is `panicking`.
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::Ident;
+
+use crate::attribute::Mode;
+
+/// The environment variable selecting the default mode.
+const VARIABLE: &str = "ARITHMETIC_MODE_DEFAULT";
+
+/// The modes that can be the default, in the order of the arguments of
+/// `__default!`. They evaluate to the same type, so that the code compiles
+/// in all of them.
+const MODES: [Mode; 3] = [Mode::Panicking, Mode::Wrapping, Mode::Saturating];
+
+/// Expands `default! { item }`.
+pub fn default_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let values = modes()?
+        .into_iter()
+        .map(|mode| mode.expand(item.clone()))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let tracked = tracked();
+    let value = select(values);
+    Ok(quote! { { #tracked #value } })
+}
+
+/// The arguments of the attribute, starting with `default`, like
+/// `#[arithmetic_mode(default, audit)]`, with `default` replaced by each of
+/// the modes the default can be, or `None` for the other arguments.
+pub(crate) fn default_attrs(attr: &TokenStream) -> syn::Result<Option<Vec<TokenStream>>> {
+    let mut tokens = attr.clone().into_iter();
+    let span = match tokens.next() {
+        Some(TokenTree::Ident(ident)) if ident == "default" => ident.span(),
+        _ => return Ok(None),
+    };
+    let rest: TokenStream = tokens.collect();
+    let attrs = modes()?.into_iter().map(|mode| {
+        let name = Ident::new(mode.name(), span);
+        quote! { #name #rest }
+    });
+    Ok(Some(attrs.collect()))
+}
+
+/// The modes the default can be, which is only the mode of the environment
+/// variable if set, and otherwise selected by `__default!`.
+fn modes() -> syn::Result<Vec<Mode>> {
+    let Some(value) = std::env::var_os(VARIABLE) else {
+        return Ok(MODES.to_vec());
+    };
+    let mode = value
+        .to_str()
+        .and_then(|value| syn::parse_str::<Ident>(value).ok())
+        .and_then(|ident| Mode::from_ident(&ident).ok())
+        .filter(|mode| MODES.contains(mode));
+    match mode {
+        Some(mode) => Ok(vec![mode]),
+        None => Err(syn::Error::new(
+            Span::call_site(),
+            format!(
+                "{VARIABLE} is {value:?}, expected one of {}",
+                MODES.map(Mode::name).join(", ")
+            ),
+        )),
+    }
+}
+
+/// Selects the expansion of the default mode among the `expansions` of
+/// [`modes`], like the items transformed by each of the modes of
+/// [`default_attrs`].
+pub(crate) fn select(mut expansions: Vec<TokenStream>) -> TokenStream {
+    if expansions.len() == 1 {
+        return expansions.remove(0);
+    }
+    quote! { ::arithmetic_mode::__default! { #({ #expansions })* } }
+}
+
+/// Reads the environment variable with `option_env!`, so that the crate is
+/// compiled again when it changes. The attribute cannot, since a `const` item
+/// is not allowed among the items of an `impl` block it can be applied to.
+fn tracked() -> TokenStream {
+    quote! {
+        const _: ::core::option::Option<&str> = ::core::option_env!("ARITHMETIC_MODE_DEFAULT");
+    }
+}
@@ -0,0 +1,171 @@
+//! The errors for the known limitations of the macros, which suggest how to
+//! work around them.
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{BinOp, Expr, ExprBinary, ExprGroup, ExprLit, ExprParen, ExprUnary, Lit};
+
+use crate::attribute::{is_arithmetic, Mode};
+use crate::type_hint::TypeHint;
+
+/// A known limitation of the macros.
+pub(crate) enum Limitation {
+    /// An operation on an unsuffixed `literal`, whose type cannot be inferred
+    /// for calling the integer methods.
+    AmbiguousLiteral { literal: String },
+    /// A compound assignment like `+=` in the function-like macros.
+    CompoundAssignment,
+    /// A compound assignment in a fallible mode of the attribute, whose failure
+    /// cannot be assigned.
+    FallibleCompoundAssignment(Mode),
+    /// A saturating bit shift, which the integer types don't provide.
+    SaturatingShift,
+    /// A `?` operand in a const context, where the error cannot be converted.
+    ConstTry,
+    /// A division flavor like `div = floor` in a const context, where its
+    /// trait methods cannot be called.
+    ConstDivision,
+    /// `modes!` in a const context, where the modes of the types cannot be
+    /// dispatched to.
+    ConstModes,
+}
+
+impl Limitation {
+    fn message(&self) -> String {
+        match self {
+            Limitation::AmbiguousLiteral { literal } => {
+                format!("Cannot infer the integer type of `{literal}`")
+            }
+            Limitation::CompoundAssignment => {
+                "Compound assignments like `+=` are not supported in the macros".to_string()
+            }
+            Limitation::FallibleCompoundAssignment(mode) => {
+                format!("Compound assignment is not supported in {mode:?} mode")
+            }
+            Limitation::SaturatingShift => "Saturating bit shifts are not supported \
+                (https://github.com/rust-lang/libs-team/issues/230)"
+                .to_string(),
+            Limitation::ConstTry => "`?` operands are not supported in const contexts, since \
+                they convert the error with `Into`"
+                .to_string(),
+            Limitation::ConstDivision => {
+                "Division flavors are not supported in const contexts".to_string()
+            }
+            Limitation::ConstModes => "modes! is not supported in const contexts".to_string(),
+        }
+    }
+
+    fn help(&self) -> String {
+        match self {
+            Limitation::AmbiguousLiteral { literal } => format!(
+                "add a type suffix like `{literal}_i32`, or state the type of the unsuffixed \
+                literals before the expression, like `i32: {literal} + 1`"
+            ),
+            Limitation::CompoundAssignment => "assign the result instead, like \
+                `x = wrapping! { x + y }`, or use the `#[arithmetic_mode]` attribute, which \
+                transforms compound assignments"
+                .to_string(),
+            Limitation::FallibleCompoundAssignment(mode) => format!(
+                "use `#[{}]` on the function instead, which propagates the failure out of \
+                the function",
+                if *mode == Mode::Checked {
+                    "checked_fn"
+                } else {
+                    "try_fn"
+                }
+            ),
+            Limitation::SaturatingShift => {
+                "use `checked!` or `wrapping!` for the shift instead".to_string()
+            }
+            Limitation::ConstTry => {
+                "handle the error before the expression, like with `match`".to_string()
+            }
+            Limitation::ConstDivision => "call the methods of the integer types instead, like \
+                `checked_div_euclid`"
+                .to_string(),
+            Limitation::ConstModes => "use a mode macro for the operations of each type \
+                instead, like `saturating!` and `checked!`"
+                .to_string(),
+        }
+    }
+
+    /// The error for this limitation, located at `tokens`.
+    pub(crate) fn error(&self, tokens: impl ToTokens) -> syn::Error {
+        let message = format!("{}\nhelp: {}", self.message(), self.help());
+        syn::Error::new_spanned(tokens, message)
+    }
+}
+
+/// Finds the operations in `expr` whose left operand only consists of
+/// unsuffixed literals, like `1 + x`, since calling the integer methods on it
+/// fails to compile, and returns the error for the first one.
+pub(crate) fn check_ambiguous_literals(expr: &Expr) -> syn::Result<()> {
+    untyped_literal(expr).1
+}
+
+/// Returns a literal of `expr` if it only consists of unsuffixed literals, and
+/// the error for the first operation in it with such a left operand. Both are
+/// found in one pass, so that deep expressions are only traversed once.
+fn untyped_literal(expr: &Expr) -> (Option<TokenStream>, syn::Result<()>) {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) if lit.suffix().is_empty() => (Some(lit.to_token_stream()), Ok(())),
+        Expr::Binary(ExprBinary {
+            left, op, right, ..
+        }) => {
+            let (literal, left_result) = untyped_literal(left);
+            let (right_literal, right_result) = untyped_literal(right);
+            let result = match &literal {
+                Some(literal) if is_arithmetic(op) => {
+                    let literal = literal.to_string();
+                    Err(Limitation::AmbiguousLiteral { literal }.error(left))
+                }
+                _ => left_result.and(right_result),
+            };
+            let literal = match op {
+                // The right-hand side of shifts can be of a different type
+                BinOp::Shl(_) | BinOp::Shr(_) => literal,
+                _ => right_literal.and(literal),
+            };
+            (literal, result)
+        }
+        Expr::Group(ExprGroup { expr, .. })
+        | Expr::Paren(ExprParen { expr, .. })
+        | Expr::Unary(ExprUnary { expr, .. }) => untyped_literal(expr),
+        _ => (None, Ok(())),
+    }
+}
+
+/// A warning with `note` reported at `span`, by referring to the deprecated
+/// constant `name`, since proc macros cannot emit warnings on stable. It can
+/// be silenced with `#[allow(deprecated)]` or turned into an error with
+/// `#[deny(deprecated)]`. The statement evaluates to `()`, also in const
+/// contexts.
+pub(crate) fn warning(name: &str, span: Span, note: &str) -> TokenStream {
+    let constant = Ident::new(name, span);
+    quote! {
+        {
+            #[deprecated(note = #note)]
+            const #constant: () = ();
+            let () = #constant;
+        }
+    }
+}
+
+/// Whether `expr` has an arithmetic operation that the macros transform, as
+/// opposed to e.g. `flags | mask`, which is left unchanged.
+pub(crate) fn has_arithmetic(expr: &Expr) -> bool {
+    match expr {
+        Expr::Binary(ExprBinary {
+            left, op, right, ..
+        }) => is_arithmetic(op) || has_arithmetic(left) || has_arithmetic(right),
+        Expr::Block(block) => {
+            TypeHint::from_block(block.clone()).is_ok_and(|hint| has_arithmetic(&hint.expr))
+        }
+        Expr::Group(ExprGroup { expr, .. })
+        | Expr::Paren(ExprParen { expr, .. })
+        | Expr::Unary(ExprUnary { expr, .. }) => has_arithmetic(expr),
+        _ => false,
+    }
+}
@@ -0,0 +1,207 @@
+//! The `differential_test!` macro, which generates tests comparing the modes
+//! against the exact result computed with `i128`.
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{braced, parenthesized, BinOp, Expr, ExprBinary, ExprUnary, Ident, Token, Type, UnOp};
+
+/// A test like `fn mul_add(a: u8 in 0..=255, b: u8 in 0..=16) -> u8 { a * b + 7 }`.
+struct DifferentialTest {
+    name: Ident,
+    params: Punctuated<Param, Token![,]>,
+    ty: Type,
+    expr: Expr,
+}
+
+/// A parameter of the expression, and the range of its values to test.
+struct Param {
+    name: Ident,
+    ty: Type,
+    range: Expr,
+}
+
+impl Parse for DifferentialTest {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![fn]>()?;
+        let name = input.parse()?;
+        let params;
+        parenthesized!(params in input);
+        let params = params.parse_terminated(Param::parse, Token![,])?;
+        input.parse::<Token![->]>()?;
+        let ty = input.parse()?;
+        let body;
+        braced!(body in input);
+        Ok(DifferentialTest {
+            name,
+            params,
+            ty,
+            expr: body.parse()?,
+        })
+    }
+}
+
+impl Parse for Param {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        input.parse::<Token![in]>()?;
+        Ok(Param {
+            name,
+            ty,
+            range: input.parse()?,
+        })
+    }
+}
+
+/// Finds the operators that the comparison depends on.
+pub(crate) struct Operators {
+    /// Whether all the operations are `+`, `-` or `*`, whose wrapping result is
+    /// the exact result truncated to the type.
+    pub ring: bool,
+    /// The first shift, which the comparison does not support.
+    pub shift: Option<BinOp>,
+    pub remainder: bool,
+    /// The number of operations that can overflow.
+    pub count: usize,
+}
+
+impl Operators {
+    /// Finds the operators in `expr`, failing if the comparison does not
+    /// support them.
+    pub fn of(expr: &Expr) -> syn::Result<Self> {
+        let mut operators = Operators {
+            ring: true,
+            shift: None,
+            remainder: false,
+            count: 0,
+        };
+        operators.visit_expr_mut(&mut expr.clone());
+        if let Some(shift) = operators.shift {
+            return Err(syn::Error::new_spanned(
+                shift,
+                "Shifts are not supported, since checked shifts don't detect the bits shifted out",
+            ));
+        }
+        Ok(operators)
+    }
+}
+
+impl VisitMut for Operators {
+    fn visit_expr_binary_mut(&mut self, binary: &mut ExprBinary) {
+        self.count += 1;
+        match binary.op {
+            BinOp::Add(_) | BinOp::Sub(_) | BinOp::Mul(_) => {}
+            BinOp::Shl(_) | BinOp::Shr(_) => {
+                self.shift.get_or_insert(binary.op);
+                self.ring = false;
+            }
+            BinOp::Rem(_) => {
+                self.remainder = true;
+                self.ring = false;
+            }
+            _ => self.ring = false,
+        }
+        visit_mut::visit_expr_binary_mut(self, binary);
+    }
+
+    fn visit_expr_unary_mut(&mut self, unary: &mut ExprUnary) {
+        if let UnOp::Neg(_) = unary.op {
+            self.count += 1;
+        }
+        visit_mut::visit_expr_unary_mut(self, unary);
+    }
+}
+
+/// Expands `differential_test! { item }`.
+pub fn differential_test_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let DifferentialTest {
+        name,
+        params,
+        ty,
+        expr,
+    } = syn::parse2(item)?;
+    let operators = Operators::of(&expr)?;
+    let names: Vec<_> = params.iter().map(|param| &param.name).collect();
+    // Describes the failed case, followed by the inputs
+    let case = format!(
+        "`{}` with {} =",
+        expr.to_token_stream(),
+        quote! { (#(#names),*) }
+    );
+    let wrapping = if operators.ring {
+        quote! {
+            if let ::core::option::Option::Some(exact) = exact {
+                ::core::assert_eq!(
+                    exact as #ty,
+                    ::arithmetic_mode::wrapping! { #expr },
+                    "wrapping! differs from the truncated exact result of {} {:?}",
+                    #case,
+                    inputs,
+                );
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+    let exact = exact_result(&names, &expr);
+    let mut body = quote! {
+        let inputs = (#(#names),*);
+        let exact = #exact;
+        let checked: ::core::option::Option<#ty> = ::arithmetic_mode::checked! { #expr };
+        // `checked!` may also fail when only an intermediate result overflows
+        if let ::core::option::Option::Some(value) = checked {
+            ::core::assert_eq!(
+                exact,
+                ::core::option::Option::Some(value as i128),
+                "checked! differs from the exact result of {} {:?}",
+                #case,
+                inputs,
+            );
+            ::core::assert_eq!(
+                value,
+                ::arithmetic_mode::wrapping! { #expr },
+                "wrapping! differs from checked! for {} {:?}",
+                #case,
+                inputs,
+            );
+            ::core::assert_eq!(
+                value,
+                ::arithmetic_mode::saturating! { #expr },
+                "saturating! differs from checked! for {} {:?}",
+                #case,
+                inputs,
+            );
+        }
+        #wrapping
+    };
+    for Param { name, ty, range } in params.iter().rev() {
+        body = quote! {
+            for #name in #range {
+                let #name: #ty = #name;
+                #body
+            }
+        };
+    }
+    Ok(quote! {
+        #[test]
+        fn #name() {
+            #body
+        }
+    })
+}
+
+/// The exact result of `expr` with the parameters `names`, as an
+/// `Option<i128>` that is `None` if it overflows `i128` or divides by zero.
+pub(crate) fn exact_result(names: &[&Ident], expr: &Expr) -> TokenStream {
+    quote! {
+        {
+            #(let #names = #names as i128;)*
+            let exact: ::core::option::Option<i128> = ::arithmetic_mode::checked! { #expr };
+            exact
+        }
+    }
+}
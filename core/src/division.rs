@@ -0,0 +1,116 @@
+//! The flavors of the division of the mode macros, stated before the rest of
+//! the input like `div = floor; a / b`, which round the quotient of `/` toward
+//! negative infinity, toward positive infinity with `ceil`, to the Euclidean
+//! quotient with `euclid`, or to the nearest integer with the halves rounded
+//! to even with `half_even`, instead of truncating it. `%` is the
+//! matching remainder, so that `b * (a / b) + a % b == a` still holds.
+
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::parse::{ParseStream, Parser};
+use syn::spanned::Spanned;
+use syn::{Ident, Token};
+
+use crate::diagnostic::Limitation;
+use crate::Options;
+
+/// How the modes round the quotient of `/`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Division {
+    /// Toward zero, like `/`.
+    #[default]
+    Truncate,
+    /// Toward negative infinity.
+    Floor,
+    /// Toward positive infinity.
+    Ceil,
+    /// To the quotient whose remainder is never negative, like `div_euclid`.
+    Euclid,
+    /// To the nearest integer, and to the even one when halfway, like the
+    /// banker's rounding of financial code.
+    HalfEven,
+}
+
+/// The names of the flavors after `div =`.
+const FLAVORS: [(&str, Division); 5] = [
+    ("trunc", Division::Truncate),
+    ("floor", Division::Floor),
+    ("ceil", Division::Ceil),
+    ("euclid", Division::Euclid),
+    ("half_even", Division::HalfEven),
+];
+
+impl Division {
+    /// The method of this flavor replacing the integer method `func`, like
+    /// `checked_div_floor` for `checked_div`, if `func` divides, and whether
+    /// it is a method of the integer types instead of the `Division` trait,
+    /// like the Euclidean divisions other than the saturating one.
+    pub(crate) fn method(self, func: &TokenStream) -> Option<(Ident, bool)> {
+        let suffix = match self {
+            Division::Truncate => return None,
+            Division::Floor => "floor",
+            Division::Ceil => "ceil",
+            Division::Euclid => "euclid",
+            Division::HalfEven => "half_even",
+        };
+        let name = func.to_string();
+        let divides = matches!(
+            name.as_str(),
+            "checked_div" | "wrapping_div" | "saturating_div" | "checked_rem" | "wrapping_rem"
+        );
+        let inherent = self == Division::Euclid && name != "saturating_div";
+        divides.then(|| {
+            (
+                Ident::new(&format!("{name}_{suffix}"), func.span()),
+                inherent,
+            )
+        })
+    }
+}
+
+/// Splits the flavor of the division stated before the rest of the input,
+/// like `div = floor; a / b`, from the rest of `item`.
+pub(crate) fn split_division(item: TokenStream) -> syn::Result<(Option<Ident>, TokenStream)> {
+    let mut tokens = item.clone().into_iter();
+    let has_division = matches!(
+        (tokens.next(), tokens.next()),
+        (Some(TokenTree::Ident(name)), Some(TokenTree::Punct(eq)))
+            if name == "div" && eq.as_char() == '=' && eq.spacing() == Spacing::Alone
+    );
+    if !has_division {
+        return Ok((None, item));
+    }
+    (|input: ParseStream| {
+        input.parse::<Ident>()?;
+        input.parse::<Token![=]>()?;
+        let flavor: Ident = input.parse()?;
+        input.parse::<Token![;]>()?;
+        Ok((Some(flavor), input.parse()?))
+    })
+    .parse2(item)
+}
+
+/// `options` with the division `flavor` split by [`split_division`], if any,
+/// which fails in const contexts, since the flavors are implemented by trait
+/// methods.
+pub(crate) fn with_division(options: Options, flavor: Option<Ident>) -> syn::Result<Options> {
+    let Some(flavor) = flavor else {
+        return Ok(options);
+    };
+    let Some((_, division)) = FLAVORS.iter().find(|(name, _)| flavor == name) else {
+        return Err(syn::Error::new_spanned(
+            &flavor,
+            format!(
+                "Unknown division, expected one of {}",
+                FLAVORS.map(|(name, _)| name).join(", ")
+            ),
+        ));
+    };
+    if options.is_const && *division != Division::Truncate {
+        return Err(Limitation::ConstDivision.error(flavor.to_token_stream()));
+    }
+    Ok(Options {
+        division: *division,
+        ..options
+    })
+}
@@ -0,0 +1,69 @@
+//! The `dot_product!` macro, which computes the dot product of two vectors in
+//! the mode given in brackets, like `dot_product! { [saturating] a, b }`.
+//!
+//! The products are accumulated in a wider integer type by the functions of
+//! `arithmetic-mode`, and only the final sum is narrowed in the mode, so that
+//! the intermediate sums cannot overflow like in a loop over `a[i] * b[i]`.
+//!
+//! The `mac!` macro is a single step of it, `acc + a * b` with the exact
+//! product, like `mac! { [saturating] acc, a, b }`.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{Expr, Token};
+
+use crate::attribute::Mode;
+use crate::mixed;
+
+/// Expands `dot_product! { item }`.
+pub fn dot_product_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (mode, item) = mixed::split_default_mode(item)?;
+    let vectors = Punctuated::<Expr, Token![,]>::parse_terminated.parse2(item)?;
+    let [a, b] = [0, 1].map(|i| vectors.get(i));
+    let (Some(a), Some(b), 2) = (a, b, vectors.len()) else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "dot_product! takes two vectors, like `dot_product! { a, b }`",
+        ));
+    };
+    let function = match mode {
+        Mode::Panicking | Mode::Verified => quote! { dot_panicking },
+        Mode::Wrapping => quote! { dot_wrapping },
+        Mode::Saturating => quote! { dot_saturating },
+        Mode::Checked => quote! { dot_checked },
+        Mode::TryChecked => quote! { dot_try_checked },
+    };
+    Ok(quote! {
+        ::arithmetic_mode::__private::#function(&(#a)[..], &(#b)[..])
+    })
+}
+
+/// Expands `mac! { item }`.
+pub fn mac_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (mode, item) = mixed::split_default_mode(item)?;
+    let operands = Punctuated::<Expr, Token![,]>::parse_terminated.parse2(item)?;
+    let [acc, a, b] = [0, 1, 2].map(|i| operands.get(i));
+    let (Some(acc), Some(a), Some(b), 3) = (acc, a, b, operands.len()) else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "mac! takes three operands, like `mac! { acc, a, b }`",
+        ));
+    };
+    let function = mac_function(mode);
+    Ok(quote! {
+        ::arithmetic_mode::__private::#function(#acc, #a, #b)
+    })
+}
+
+/// The function of `arithmetic-mode` that computes `acc + a * b` in `mode`.
+pub(crate) fn mac_function(mode: Mode) -> TokenStream {
+    match mode {
+        Mode::Panicking | Mode::Verified => quote! { mac_panicking },
+        Mode::Wrapping => quote! { mac_wrapping },
+        Mode::Saturating => quote! { mac_saturating },
+        Mode::Checked => quote! { mac_checked },
+        Mode::TryChecked => quote! { mac_try_checked },
+    }
+}
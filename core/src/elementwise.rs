@@ -0,0 +1,100 @@
+//! The `elementwise!` macro, which transforms an expression of arrays like the
+//! mode given in brackets, like `elementwise! { [saturating] a + b }`, for
+//! each index of the arrays, e.g. to mix pixel or audio buffers.
+//!
+//! Each operand is passed through `arithmetic_mode::__private::lane`, which
+//! takes the element of an array at the index, and leaves the other integers
+//! unchanged, so that they apply to every element. The length of the arrays is
+//! inferred from their types, so they must have the same length.
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Expr, ExprBinary, ExprCast, ExprGroup, ExprParen, ExprUnary, UnOp};
+
+use crate::{input, mixed};
+
+/// Expands `elementwise! { item }`.
+pub fn elementwise_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (mode, item) = mixed::split_default_mode(item)?;
+    let (expr, options) = input::parse_expr(item)?;
+    if options.is_const {
+        return Err(syn::Error::new_spanned(
+            &expr,
+            "elementwise! cannot be used in const contexts, since its closure is not const",
+        ));
+    }
+    let index = Ident::new("index", Span::mixed_site());
+    let lanes = Ident::new("lanes", Span::mixed_site());
+    let lane = mode.transform(lane_operands(expr, &index, &lanes), options)?;
+    Ok(quote! {
+        {
+            // The length of the arrays, inferred from their types
+            let #lanes = ::core::marker::PhantomData;
+            ::arithmetic_mode::__private::lanes(#lanes, |#index| #lane)
+        }
+    })
+}
+
+/// Replaces the operands of the arithmetic in `expr` with their element at
+/// `index`. Literals are left unchanged, since their type is only inferred
+/// from the other operands.
+fn lane_operands(expr: Expr, index: &Ident, lanes: &Ident) -> Expr {
+    match expr {
+        Expr::Binary(ExprBinary {
+            attrs,
+            left,
+            op,
+            right,
+        }) => Expr::Binary(ExprBinary {
+            attrs,
+            left: Box::new(lane_operands(*left, index, lanes)),
+            op,
+            right: Box::new(lane_operands(*right, index, lanes)),
+        }),
+        Expr::Cast(ExprCast {
+            attrs,
+            expr,
+            as_token,
+            ty,
+        }) => Expr::Cast(ExprCast {
+            attrs,
+            expr: Box::new(lane_operands(*expr, index, lanes)),
+            as_token,
+            ty,
+        }),
+        Expr::Group(ExprGroup {
+            attrs,
+            group_token,
+            expr,
+        }) => Expr::Group(ExprGroup {
+            attrs,
+            group_token,
+            expr: Box::new(lane_operands(*expr, index, lanes)),
+        }),
+        Expr::Paren(ExprParen {
+            attrs,
+            paren_token,
+            expr,
+        }) => Expr::Paren(ExprParen {
+            attrs,
+            paren_token,
+            expr: Box::new(lane_operands(*expr, index, lanes)),
+        }),
+        Expr::Unary(ExprUnary { attrs, op, expr }) if !matches!(op, UnOp::Deref(_)) => {
+            Expr::Unary(ExprUnary {
+                attrs,
+                op,
+                expr: Box::new(lane_operands(*expr, index, lanes)),
+            })
+        }
+        Expr::Field(_) | Expr::Index(_) | Expr::Path(_) | Expr::Reference(_) | Expr::Unary(_) => {
+            // Isolated like the operands of the attribute, so that the modes
+            // keep it as an operand
+            Expr::Verbatim(quote_spanned! { expr.span()=>
+                ::arithmetic_mode::__private::lane(&#expr, #index, #lanes)
+            })
+        }
+        expr => expr,
+    }
+}
@@ -0,0 +1,286 @@
+//! The `#[deny_unchecked_arithmetic]` and `#[enforce(...)]` attributes, which
+//! reject the arithmetic operations in an item that don't use one of the modes
+//! explicitly.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    Attribute, Expr, ExprBinary, ExprLit, Ident, ImplItem, ImplItemConst, ImplItemFn, Item,
+    ItemConst, ItemFn, ItemImpl, ItemMod, ItemStatic, ItemTrait, Lit, LitStr, Local, Macro, Meta,
+    MetaNameValue, Token, TraitItem, TraitItemConst, TraitItemFn,
+};
+
+use crate::attribute::{
+    compound_op, expr_attrs_mut, is_arithmetic, is_skip_marker, Mode, MODE_MACROS,
+};
+use crate::config;
+
+/// Expands `#[deny_unchecked_arithmetic] item`.
+pub fn deny_unchecked_arithmetic_impl(
+    attr: TokenStream,
+    item: TokenStream,
+) -> syn::Result<TokenStream> {
+    if !attr.is_empty() {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "#[deny_unchecked_arithmetic] does not take any arguments",
+        ));
+    }
+    check(None, item)
+}
+
+/// Expands `#[enforce(attr)] item`.
+pub fn enforce_impl(attr: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    check(Some(Policy::parse(attr)?), item)
+}
+
+fn check(policy: Option<Policy>, item: TokenStream) -> syn::Result<TokenStream> {
+    let mut item: Item = syn::parse2(item)?;
+    let mut checker = Checker {
+        policy,
+        errors: Vec::new(),
+    };
+    checker.visit_item_mut(&mut item);
+    let errors = checker.errors.iter().map(syn::Error::to_compile_error);
+    Ok(quote! {
+        #item
+        #(#errors)*
+    })
+}
+
+/// The modes allowed by `#[enforce(default = "checked", allow = [...])]`.
+struct Policy {
+    default: String,
+    allow: Vec<String>,
+}
+
+impl Policy {
+    fn parse(attr: TokenStream) -> syn::Result<Policy> {
+        let mut default = None;
+        let mut allow = Vec::new();
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("default") {
+                default = Some(parse_mode(meta.value()?.parse()?, false)?);
+            } else if meta.path.is_ident("allow") {
+                let value = meta.value()?;
+                let content;
+                syn::bracketed!(content in value);
+                for mode in Punctuated::<LitStr, Token![,]>::parse_terminated(&content)? {
+                    allow.push(parse_mode(mode, true)?);
+                }
+            } else {
+                return Err(meta.error("Expected `default` or `allow`"));
+            }
+            Ok(())
+        });
+        parser.parse2(attr)?;
+        let Some(default) = default else {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "#[enforce] requires a default mode, like `default = \"checked\"`",
+            ));
+        };
+        Ok(Policy { default, allow })
+    }
+
+    fn allows(&self, mode: &str) -> bool {
+        self.default == mode || self.allow.iter().any(|allowed| allowed == mode)
+    }
+}
+
+/// Parses the name of a mode, or of the skip marker if `allow_skip` is true.
+fn parse_mode(lit: LitStr, allow_skip: bool) -> syn::Result<String> {
+    let ident: Ident = lit.parse()?;
+    if !(allow_skip && ident == "skip") {
+        Mode::from_ident(&ident)?;
+    }
+    Ok(ident.to_string())
+}
+
+/// The modes of the `#[arithmetic_mode(...)]` attribute `attr`, including the
+/// ones of its operators like `add = "wrapping"` and of its policy, and
+/// `panicking` for the operators without a mode if it has none.
+fn attribute_modes(attr: &Attribute) -> Option<Vec<String>> {
+    let args = attr
+        .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+        .ok()?;
+    let mut modes = Vec::new();
+    let mut panicking = !matches!(args.first(), Some(Meta::Path(_)));
+    for (i, arg) in args.iter().enumerate() {
+        match arg {
+            Meta::Path(path) if i == 0 => modes.push(path.get_ident()?.to_string()),
+            // The modes of the policy, whose errors the attribute reports. The
+            // attribute is left in the item, and its expansion tracks the
+            // config file, so it is not tracked here
+            Meta::NameValue(MetaNameValue {
+                path,
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(name),
+                        ..
+                    }),
+                ..
+            }) if path.is_ident("policy") => {
+                let policy = config::load(name).ok()?;
+                panicking &= policy.mode.is_none();
+                modes.extend(policy.modes().map(|mode| mode.name().to_string()));
+            }
+            Meta::NameValue(MetaNameValue {
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(mode),
+                        ..
+                    }),
+                ..
+            }) => modes.push(mode.value()),
+            _ => {}
+        }
+    }
+    if panicking {
+        modes.insert(0, "panicking".to_string());
+    }
+    Some(modes)
+}
+
+/// Visitor that records an error for every arithmetic operator outside of the
+/// mode macros, and for every mode not allowed by the policy. The operators
+/// inside macro invocations are never visited.
+struct Checker {
+    policy: Option<Policy>,
+    errors: Vec<syn::Error>,
+}
+
+impl Checker {
+    fn check_mode(&mut self, mode: &str, tokens: impl ToTokens) {
+        let Some(policy) = &self.policy else {
+            return;
+        };
+        if !policy.allows(mode) {
+            let allowed = core::iter::once(&policy.default)
+                .chain(&policy.allow)
+                .map(|mode| format!("`{mode}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let message = format!("The `{mode}` mode is not allowed here. Allowed: {allowed}");
+            self.errors.push(syn::Error::new_spanned(tokens, message));
+        }
+    }
+
+    /// Checks the modes of this crate's attributes in `attrs`, and returns
+    /// whether the marked code is exempt from the check. Skip markers are
+    /// removed, since they are not expanded on statements and expressions.
+    fn check_attributes(&mut self, attrs: &mut Vec<Attribute>) -> bool {
+        let mut exempt = false;
+        for attr in attrs.iter() {
+            let Some(segment) = attr.path().segments.last() else {
+                continue;
+            };
+            let modes = match segment.ident.to_string().as_str() {
+                "arithmetic_mode" => match attribute_modes(attr) {
+                    Some(modes) => modes,
+                    None => continue,
+                },
+                "checked_fn" => vec!["checked".to_string()],
+                "try_fn" => vec!["try_checked".to_string()],
+                // Nested checks are done by the attributes themselves
+                "deny_unchecked_arithmetic" | "enforce" => {
+                    exempt = true;
+                    continue;
+                }
+                _ => continue,
+            };
+            for mode in modes {
+                self.check_mode(&mode, attr);
+            }
+            exempt = true;
+        }
+        attrs.retain(|attr| !is_skip_marker(attr));
+        exempt
+    }
+}
+
+impl VisitMut for Checker {
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        let attrs = match item {
+            Item::Const(ItemConst { attrs, .. })
+            | Item::Fn(ItemFn { attrs, .. })
+            | Item::Impl(ItemImpl { attrs, .. })
+            | Item::Mod(ItemMod { attrs, .. })
+            | Item::Static(ItemStatic { attrs, .. })
+            | Item::Trait(ItemTrait { attrs, .. }) => attrs,
+            _ => return visit_mut::visit_item_mut(self, item),
+        };
+        if !self.check_attributes(attrs) {
+            visit_mut::visit_item_mut(self, item);
+        }
+    }
+
+    fn visit_impl_item_mut(&mut self, impl_item: &mut ImplItem) {
+        let attrs = match impl_item {
+            ImplItem::Const(ImplItemConst { attrs, .. })
+            | ImplItem::Fn(ImplItemFn { attrs, .. }) => attrs,
+            _ => return visit_mut::visit_impl_item_mut(self, impl_item),
+        };
+        if !self.check_attributes(attrs) {
+            visit_mut::visit_impl_item_mut(self, impl_item);
+        }
+    }
+
+    fn visit_trait_item_mut(&mut self, trait_item: &mut TraitItem) {
+        let attrs = match trait_item {
+            TraitItem::Const(TraitItemConst { attrs, .. })
+            | TraitItem::Fn(TraitItemFn { attrs, .. }) => attrs,
+            _ => return visit_mut::visit_trait_item_mut(self, trait_item),
+        };
+        if !self.check_attributes(attrs) {
+            visit_mut::visit_trait_item_mut(self, trait_item);
+        }
+    }
+
+    fn visit_local_mut(&mut self, local: &mut Local) {
+        if !self.check_attributes(&mut local.attrs) {
+            visit_mut::visit_local_mut(self, local);
+        }
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut Macro) {
+        if let Some(segment) = mac.path.segments.last() {
+            if MODE_MACROS.iter().any(|name| segment.ident == name) {
+                self.check_mode(&segment.ident.to_string(), &mac.path);
+            } else if segment.ident == "control_flow" {
+                // It fails like `try_checked!`, with a `ControlFlow`
+                self.check_mode("try_checked", &mac.path);
+            }
+        }
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Some(attrs) = expr_attrs_mut(expr) {
+            if self.check_attributes(attrs) {
+                return;
+            }
+        }
+        if let Expr::Binary(ExprBinary { op, .. }) = expr {
+            if is_arithmetic(op) || compound_op(op).is_some() {
+                let message = match &self.policy {
+                    Some(policy) => format!(
+                        "Unchecked arithmetic operator `{}`. Use the `{}` mode or one of the \
+                        allowed modes.",
+                        op.to_token_stream(),
+                        policy.default
+                    ),
+                    None => format!(
+                        "Unchecked arithmetic operator `{}`. Use one of the arithmetic mode \
+                        macros, or `#[arithmetic_mode(skip)]` to allow it.",
+                        op.to_token_stream()
+                    ),
+                };
+                self.errors.push(syn::Error::new_spanned(op, message));
+            }
+        }
+        visit_mut::visit_expr_mut(self, expr);
+    }
+}
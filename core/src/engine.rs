@@ -0,0 +1,141 @@
+//! The traversal shared by the modes, which walks the arithmetic of an
+//! expression and leaves the code of each part to a [`Strategy`].
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{Expr, ExprBinary, ExprGroup, ExprParen, ExprUnary, Type, UnOp};
+
+use crate::type_hint::TypeHint;
+use crate::{both, literal_type, unsupported_expression};
+
+/// The code that a mode generates for the parts of an arithmetic expression.
+/// Only [`binary`](Strategy::binary) is required; the other parts are left
+/// unchanged by default, like in `wrapping!`.
+///
+/// The operands are transformed before the operation they are part of, so a
+/// mode only has to combine the code of its operands.
+pub trait Strategy {
+    /// Whether the transformed expressions evaluate to a wrapper of the
+    /// integer, like an `Option`, instead of the integer itself. The unary
+    /// operators are then applied inside the wrapper with
+    /// [`unary`](Strategy::unary), instead of being left unchanged.
+    fn is_fallible(&self) -> bool {
+        false
+    }
+
+    /// The code of an operand without arithmetic, like a variable, a literal,
+    /// a cast or an index expression.
+    fn operand(&self, expr: &Expr) -> TokenStream {
+        expr.to_token_stream()
+    }
+
+    /// The code of the operation `binary`, whose operands have been
+    /// transformed into `left` and `right`. This includes the operators
+    /// without overflow, like `==` and `&`.
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        left: TokenStream,
+        right: TokenStream,
+    ) -> syn::Result<TokenStream>;
+
+    /// The code of the unary operator `op` applied to the transformed
+    /// `operand`, in the fallible modes.
+    fn unary(&self, op: &UnOp, operand: TokenStream) -> TokenStream {
+        quote! { #op #operand }
+    }
+
+    /// The code of the transformed `operand` with the inline type hint `ty`,
+    /// like `(x: u8)`.
+    fn type_hint(&self, operand: TokenStream, ty: &Type) -> TokenStream {
+        quote! { { let __arithmetic_mode_hint: #ty = #operand; __arithmetic_mode_hint } }
+    }
+
+    /// The code of the other expressions, like `opt(x)` in `checked!`, which
+    /// are not supported by default.
+    fn other(&self, expr: &Expr) -> syn::Result<TokenStream> {
+        Err(unsupported_expression(expr))
+    }
+}
+
+/// Changes the arithmetic operations in `expr` with a user-defined
+/// `strategy`, like [`transform`](crate::transform) does for the modes of the
+/// macros.
+///
+/// ```rust
+/// use arithmetic_mode_core::{transform_with, Strategy};
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+///
+/// /// Discards the overflow flag of `overflowing_*`, like `wrapping!`.
+/// struct Overflowing;
+///
+/// impl Strategy for Overflowing {
+///     fn binary(
+///         &self,
+///         binary: &syn::ExprBinary,
+///         left: TokenStream,
+///         right: TokenStream,
+///     ) -> syn::Result<TokenStream> {
+///         let op = &binary.op;
+///         Ok(match op {
+///             syn::BinOp::Add(_) => quote! { (#left).overflowing_add(#right).0 },
+///             _ => quote! { #left #op #right },
+///         })
+///     }
+/// }
+///
+/// let expr = syn::parse_quote! { a + 1_u8 };
+/// let tokens = transform_with(expr, &Overflowing).unwrap();
+/// assert!(tokens.to_string().contains("overflowing_add"));
+/// ```
+pub fn transform_with(mut expr: Expr, strategy: &impl Strategy) -> syn::Result<TokenStream> {
+    literal_type::propagate_literal_type(&mut expr);
+    transform_expr(&expr, strategy)
+}
+
+/// Transforms the arithmetic of `expr` with `strategy`.
+pub(crate) fn transform_expr(expr: &Expr, strategy: &impl Strategy) -> syn::Result<TokenStream> {
+    Ok(match expr {
+        Expr::Binary(binary) => {
+            let (left, right) = both(
+                transform_expr(&binary.left, strategy),
+                transform_expr(&binary.right, strategy),
+            )?;
+            strategy.binary(binary, left, right)?
+        }
+        Expr::Block(block) => {
+            let TypeHint { expr, ty } = TypeHint::from_block(block.clone())?;
+            let operand = transform_expr(&expr, strategy)?;
+            strategy.type_hint(operand, &ty)
+        }
+        Expr::Cast(_) => strategy.operand(expr),
+        // Recurse inside the following items
+        Expr::Index(_) => strategy.operand(expr),
+        Expr::Lit(_) => strategy.operand(expr),
+        Expr::Paren(ExprParen {
+            expr, paren_token, ..
+        }) => {
+            let new_expr = transform_expr(expr, strategy)?;
+            // Located at the original parentheses, like the operands, but
+            // still part of the expansion, since `unused_parens` would
+            // otherwise warn about the ones around e.g. a `match` scrutinee
+            let span = Span::call_site().located_at(paren_token.span.join());
+            quote_spanned! { span=> ( #new_expr ) }
+        }
+        Expr::Path(_) => strategy.operand(expr),
+        // Operands isolated by the `arithmetic_mode` attribute
+        Expr::Verbatim(_) => strategy.operand(expr),
+        Expr::Reference(_) => strategy.operand(expr),
+        // Dereferencing cannot overflow, and may not be moved into the wrapper
+        Expr::Unary(ExprUnary { op, expr, .. })
+            if strategy.is_fallible() && !matches!(op, UnOp::Deref(_)) =>
+        {
+            let new_expr = transform_expr(expr, strategy)?;
+            strategy.unary(op, new_expr)
+        }
+        Expr::Unary(_) => strategy.operand(expr),
+        Expr::Group(ExprGroup { expr, .. }) => transform_expr(expr, strategy)?,
+        _ => strategy.other(expr)?,
+    })
+}
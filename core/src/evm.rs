@@ -0,0 +1,239 @@
+//! The `evm!` macro, which computes an expression with the integer semantics
+//! of the Ethereum Virtual Machine, like `evm! { balance * price / 10 ** 18 }`.
+//!
+//! The operands are converted to the `U256` of `arithmetic-mode`, and all the
+//! operations wrap modulo 2^256. Dividing by zero and shifting by 256 bits or
+//! more give zero, and `**` is the exponentiation, which binds tighter than
+//! the other operators and groups to the right, like in Solidity.
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+use syn::{
+    parse_quote, Attribute, Expr, ExprBinary, ExprGroup, ExprLit, ExprParen, ExprUnary, Lit,
+    LitInt, UnOp,
+};
+
+use crate::engine::{self, Strategy};
+use crate::{operand_names, Limitation};
+
+/// The attribute that marks the multiplications that were written as `**`.
+const POWER: &str = "__arithmetic_mode_power";
+
+/// Expands `evm! { item }`.
+pub fn evm_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    // The operands are converted to U256, so their literals need no type
+    let expr: Expr = syn::parse2(item)?;
+    engine::transform_expr(&powers(expr), &Evm)
+}
+
+/// Marks the exponentiations in `expr`. Rust parses `a ** b` as `a * *b`,
+/// whose dereference binds tighter than `*`, so `a * b ** c` is parsed as
+/// `(a * b) * *c` and is regrouped into `a * (b ** c)`.
+fn powers(expr: Expr) -> Expr {
+    match expr {
+        Expr::Binary(ExprBinary {
+            attrs,
+            left,
+            op: op @ syn::BinOp::Mul(_),
+            right,
+        }) => match *right {
+            Expr::Unary(ExprUnary {
+                op: UnOp::Deref(_),
+                expr: exponent,
+                ..
+            }) => power(powers(*left), op, powers(*exponent)),
+            right => Expr::Binary(ExprBinary {
+                attrs,
+                left: Box::new(powers(*left)),
+                op,
+                right: Box::new(powers(right)),
+            }),
+        },
+        Expr::Binary(ExprBinary {
+            attrs,
+            left,
+            op,
+            right,
+        }) => Expr::Binary(ExprBinary {
+            attrs,
+            left: Box::new(powers(*left)),
+            op,
+            right: Box::new(powers(*right)),
+        }),
+        Expr::Group(ExprGroup {
+            attrs,
+            group_token,
+            expr,
+        }) => Expr::Group(ExprGroup {
+            attrs,
+            group_token,
+            expr: Box::new(powers(*expr)),
+        }),
+        Expr::Paren(ExprParen {
+            attrs,
+            paren_token,
+            expr,
+        }) => Expr::Paren(ExprParen {
+            attrs,
+            paren_token,
+            expr: Box::new(powers(*expr)),
+        }),
+        Expr::Unary(ExprUnary { attrs, op, expr }) if !matches!(op, UnOp::Deref(_)) => {
+            Expr::Unary(ExprUnary {
+                attrs,
+                op,
+                expr: Box::new(powers(*expr)),
+            })
+        }
+        expr => expr,
+    }
+}
+
+/// The exponentiation of `base` by `exponent`, whose operator `op` is the
+/// first `*` of `**`. The operations and unary operators that end `base`
+/// apply to the exponentiation of their last operand instead.
+fn power(base: Expr, op: syn::BinOp, exponent: Expr) -> Expr {
+    match base {
+        Expr::Binary(ExprBinary {
+            attrs,
+            left,
+            op: base_op,
+            right,
+        }) => Expr::Binary(ExprBinary {
+            attrs,
+            left,
+            op: base_op,
+            right: Box::new(power(*right, op, exponent)),
+        }),
+        Expr::Unary(ExprUnary {
+            attrs,
+            op: unary_op,
+            expr,
+        }) if !matches!(unary_op, UnOp::Deref(_)) => Expr::Unary(ExprUnary {
+            attrs,
+            op: unary_op,
+            expr: Box::new(power(*expr, op, exponent)),
+        }),
+        base => {
+            let marker = Ident::new(POWER, Span::call_site());
+            let marker: Attribute = parse_quote! { #[#marker] };
+            Expr::Binary(ExprBinary {
+                attrs: vec![marker],
+                left: Box::new(base),
+                op,
+                right: Box::new(exponent),
+            })
+        }
+    }
+}
+
+/// The operations of `evm!`.
+struct Evm;
+
+impl Strategy for Evm {
+    // The unary operators apply to the converted operands, like in the
+    // fallible modes, since `-x` wraps around in U256
+    fn is_fallible(&self) -> bool {
+        true
+    }
+
+    fn operand(&self, expr: &Expr) -> TokenStream {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(lit), ..
+            }) => literal(lit).unwrap_or_else(syn::Error::into_compile_error),
+            _ => quote_spanned! { expr.span()=> ::arithmetic_mode::evm::U256::from(#expr) },
+        }
+    }
+
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        left: TokenStream,
+        right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        let op = binary.op;
+        let span = op.span();
+        let (l, r) = operand_names(span);
+        let call = match op {
+            syn::BinOp::Mul(_) if binary.attrs.iter().any(|attr| attr.path().is_ident(POWER)) => {
+                quote_spanned! { span=> #l.wrapping_pow(#r) }
+            }
+            syn::BinOp::Add(_) => quote_spanned! { span=> #l.wrapping_add(#r) },
+            syn::BinOp::Sub(_) => quote_spanned! { span=> #l.wrapping_sub(#r) },
+            syn::BinOp::Mul(_) => quote_spanned! { span=> #l.wrapping_mul(#r) },
+            syn::BinOp::Div(_) => {
+                quote_spanned! { span=> ::arithmetic_mode::__private::evm_div(#l, #r) }
+            }
+            syn::BinOp::Rem(_) => {
+                quote_spanned! { span=> ::arithmetic_mode::__private::evm_rem(#l, #r) }
+            }
+            syn::BinOp::Shl(_) => {
+                quote_spanned! { span=> ::arithmetic_mode::__private::evm_shl(#l, #r) }
+            }
+            syn::BinOp::Shr(_) => {
+                quote_spanned! { span=> ::arithmetic_mode::__private::evm_shr(#l, #r) }
+            }
+            syn::BinOp::And(_)
+            | syn::BinOp::Or(_)
+            | syn::BinOp::BitXor(_)
+            | syn::BinOp::BitAnd(_)
+            | syn::BinOp::BitOr(_)
+            | syn::BinOp::Eq(_)
+            | syn::BinOp::Lt(_)
+            | syn::BinOp::Le(_)
+            | syn::BinOp::Ne(_)
+            | syn::BinOp::Ge(_)
+            | syn::BinOp::Gt(_) => return Ok(quote! { #left #op #right }),
+            syn::BinOp::AddAssign(_)
+            | syn::BinOp::SubAssign(_)
+            | syn::BinOp::MulAssign(_)
+            | syn::BinOp::DivAssign(_)
+            | syn::BinOp::RemAssign(_)
+            | syn::BinOp::BitXorAssign(_)
+            | syn::BinOp::BitAndAssign(_)
+            | syn::BinOp::BitOrAssign(_)
+            | syn::BinOp::ShlAssign(_)
+            | syn::BinOp::ShrAssign(_) => return Err(Limitation::CompoundAssignment.error(op)),
+            _ => return Err(syn::Error::new_spanned(op, "Unknown operation")),
+        };
+        Ok(quote_spanned! { span=>
+            {
+                let #l = #left;
+                let #r = #right;
+                #call
+            }
+        })
+    }
+
+    fn unary(&self, op: &UnOp, operand: TokenStream) -> TokenStream {
+        match op {
+            UnOp::Neg(_) => quote_spanned! { op.span()=> (#operand).wrapping_neg() },
+            _ => quote! { #op #operand },
+        }
+    }
+}
+
+/// The U256 of the integer literal `lit`, whatever its suffix, from its limbs,
+/// so that it can be larger than the primitive integers.
+fn literal(lit: &LitInt) -> syn::Result<TokenStream> {
+    let mut limbs = [0_u64; 4];
+    for digit in lit.base10_digits().bytes() {
+        let mut carry = u128::from(digit - b'0');
+        for limb in &mut limbs {
+            let value = u128::from(*limb) * 10 + carry;
+            *limb = value as u64;
+            carry = value >> 64;
+        }
+        if carry != 0 {
+            return Err(syn::Error::new_spanned(
+                lit.to_token_stream(),
+                "The literal does not fit in 256 bits",
+            ));
+        }
+    }
+    Ok(quote_spanned! { lit.span()=>
+        ::arithmetic_mode::evm::U256::from_limbs([#(#limbs),*])
+    })
+}
@@ -0,0 +1,30 @@
+//! The `explain!` macro, which expands an expression like the mode given
+//! before it, like `explain!(checked, a + b * c)`, and shows the expansion in
+//! a compiler note, to see what the mode does without `cargo expand`.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{ParseStream, Parser};
+use syn::{Ident, Token};
+
+use crate::attribute::Mode;
+use crate::diagnostic;
+
+/// Expands `explain!(mode, item)`.
+pub fn explain_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (mode, item) = (|input: ParseStream| {
+        let ident: Ident = input.parse()?;
+        input.parse::<Token![,]>()?;
+        Ok((ident, input.parse::<TokenStream>()?))
+    })
+    .parse2(item)?;
+    let span = mode.span();
+    let mode = Mode::from_ident(&mode)?;
+    let expansion = mode.expand(item.clone())?;
+    let note = format!(
+        "arithmetic_mode: `{}! {{ {item} }}` expands to `{expansion}`",
+        mode.name()
+    );
+    let warning = diagnostic::warning("EXPLAIN", span, &note);
+    Ok(quote! { { #warning #expansion } })
+}
@@ -0,0 +1,156 @@
+//! The `fast_checked!` macro, which evaluates a chain of additions,
+//! subtractions and multiplications of a narrow integer type in a wider type,
+//! and checks for overflow once at the end instead of branching after every
+//! operation.
+//!
+//! Every intermediate result is still compared with the bounds of the type,
+//! but the comparisons are combined into a flag without branching, so the
+//! result is the same as with `checked!`: `None` if any operation overflows.
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{BinOp, Expr, ExprBinary, ExprGroup, ExprParen, ExprUnary, UnOp};
+
+use crate::{cold_branch, input, Options};
+
+/// Expands `fast_checked! { item }`.
+pub fn fast_checked_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (ty, expr, options) = input::parse_typed_expr(item)?;
+    let Some(ty) = ty else {
+        return Err(syn::Error::new_spanned(
+            &expr,
+            "fast_checked! needs the type of the operands before the expression, like \
+            `fast_checked! { u8: a + b * c }`",
+        ));
+    };
+    let wide = wide_type(&ty)?;
+    let mut steps = Steps {
+        ty,
+        wide,
+        options,
+        tokens: TokenStream::new(),
+        count: 0,
+    };
+    let result = steps.evaluate(expr)?;
+    let Steps { ty, tokens, .. } = steps;
+    let none = cold_branch(quote! { ::core::option::Option::None });
+    Ok(quote! {
+        {
+            let mut __arithmetic_mode_overflow = false;
+            #tokens
+            if __arithmetic_mode_overflow {
+                #none
+            } else {
+                ::core::option::Option::Some(#result as #ty)
+            }
+        }
+    })
+}
+
+/// The signed type that holds the result of any operation on two values of
+/// the integer type `ty`, including the product of its bounds.
+fn wide_type(ty: &Ident) -> syn::Result<Ident> {
+    let wide = match ty.to_string().as_str() {
+        "i8" | "u8" | "i16" => "i32",
+        "u16" | "i32" => "i64",
+        "u32" | "i64" | "isize" => "i128",
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ty,
+                format!(
+                    "fast_checked! does not support `{ty}`, since there is no wider type for the \
+                    products of its values. Use `checked!` instead"
+                ),
+            ))
+        }
+    };
+    Ok(Ident::new(wide, ty.span()))
+}
+
+/// The statements that evaluate the operations one after the other in the
+/// wide type, and flag the results outside of the bounds of `ty`.
+struct Steps {
+    ty: Ident,
+    wide: Ident,
+    options: Options,
+    tokens: TokenStream,
+    count: usize,
+}
+
+impl Steps {
+    /// Adds the statements evaluating `expr`, and returns the variable
+    /// holding its value in the wide type.
+    fn evaluate(&mut self, expr: Expr) -> syn::Result<Ident> {
+        match expr {
+            Expr::Binary(ExprBinary {
+                left, op, right, ..
+            }) => {
+                let func = match op {
+                    BinOp::Add(_) => quote! { wrapping_add },
+                    BinOp::Sub(_) => quote! { wrapping_sub },
+                    BinOp::Mul(_) => quote! { wrapping_mul },
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            op,
+                            "fast_checked! only supports `+`, `-` and `*`. Use `checked!` for \
+                            the other operators",
+                        ))
+                    }
+                };
+                let l = self.evaluate(*left)?;
+                let r = self.evaluate(*right)?;
+                Ok(self.step(op.span(), quote! { #l.#func(#r) }))
+            }
+            Expr::Group(ExprGroup { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) => {
+                self.evaluate(*expr)
+            }
+            Expr::Unary(ExprUnary {
+                op: UnOp::Neg(minus),
+                expr,
+                ..
+            }) => {
+                let value = self.evaluate(*expr)?;
+                let wide = &self.wide;
+                Ok(self.step(minus.span, quote! { #wide::wrapping_sub(0, #value) }))
+            }
+            operand => Ok(self.operand(operand)),
+        }
+    }
+
+    /// Adds the statement widening `operand`, which is within the bounds.
+    fn operand(&mut self, operand: Expr) -> Ident {
+        let name = self.next_name(operand.span());
+        let (ty, wide) = (&self.ty, &self.wide);
+        let value = if self.options.is_const {
+            quote! { (#operand) as #wide }
+        } else {
+            // Also accepts references, like `Borrow` in the other modes
+            quote! {
+                <#wide as ::core::convert::From<#ty>>::from(
+                    *::core::borrow::Borrow::<#ty>::borrow(&#operand)
+                )
+            }
+        };
+        self.tokens.extend(quote! { let #name: #wide = #value; });
+        name
+    }
+
+    /// Adds the statement evaluating an operation, flagging its result if it
+    /// is out of the bounds.
+    fn step(&mut self, span: Span, value: TokenStream) -> Ident {
+        let name = self.next_name(span);
+        let (ty, wide) = (&self.ty, &self.wide);
+        self.tokens.extend(quote_spanned! { span=>
+            let #name: #wide = #value;
+            __arithmetic_mode_overflow |=
+                #name < #ty::MIN as #wide || #name > #ty::MAX as #wide;
+        });
+        name
+    }
+
+    fn next_name(&mut self, span: Span) -> Ident {
+        self.count += 1;
+        format_ident!("__arithmetic_mode_v{}", self.count, span = span)
+    }
+}
@@ -0,0 +1,273 @@
+//! Folding of the operations whose operands are all suffixed literals or the
+//! bounds of an integer type, like `60_u32 * 60_u32` or `u8::MAX - 1_u8`, which
+//! are evaluated during the expansion instead of being checked at runtime. An operation that always fails is a compile error in the
+//! modes that would panic or return an error, and is folded into the wrapped or
+//! saturated value in the other modes.
+
+use proc_macro2::{Ident, Span};
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::{
+    parse_quote, token, BinOp, Expr, ExprBinary, ExprGroup, ExprLit, ExprParen, ExprPath,
+    ExprUnary, Lit, LitInt, UnOp,
+};
+
+use crate::attribute::Mode;
+use crate::division::Division;
+use crate::literal_type::is_integer_type;
+use crate::{both, panic_message};
+
+/// Folds the operations of `expr` whose operands are constants, as they
+/// evaluate in `mode`. The divisions of the flavors other than `/` are left to
+/// the mode at runtime.
+pub(crate) fn fold_constants(expr: Expr, mode: Mode, division: Division) -> syn::Result<Expr> {
+    Ok(match expr {
+        Expr::Binary(ExprBinary {
+            attrs,
+            left,
+            op,
+            right,
+        }) => {
+            let (left, right) = both(
+                fold_constants(*left, mode, division),
+                fold_constants(*right, mode, division),
+            )?;
+            let binary = ExprBinary {
+                attrs,
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            };
+            match evaluate(&binary, mode, division) {
+                Some(Ok((ty, value))) => literal(&ty, &value, op.span()),
+                Some(Err(division_by_zero)) => {
+                    // The operands are already folded, so the expression is short
+                    let expression = binary.to_token_stream().to_string();
+                    return Err(syn::Error::new(
+                        op.span(),
+                        format!(
+                            "This operation always fails: {}",
+                            panic_message(None, &expression, op, division_by_zero)
+                        ),
+                    ));
+                }
+                None => Expr::Binary(binary),
+            }
+        }
+        Expr::Group(ExprGroup {
+            attrs,
+            group_token,
+            expr,
+        }) => Expr::Group(ExprGroup {
+            attrs,
+            group_token,
+            expr: Box::new(fold_constants(*expr, mode, division)?),
+        }),
+        Expr::Paren(ExprParen {
+            attrs,
+            paren_token,
+            expr,
+        }) => Expr::Paren(ExprParen {
+            attrs,
+            paren_token,
+            expr: Box::new(fold_constants(*expr, mode, division)?),
+        }),
+        Expr::Unary(ExprUnary { attrs, op, expr }) => Expr::Unary(ExprUnary {
+            attrs,
+            op,
+            expr: Box::new(fold_constants(*expr, mode, division)?),
+        }),
+        expr => expr,
+    })
+}
+
+/// Evaluates `binary` in `mode` if its operands are constants of the same
+/// type, returning the type and the value, or whether the failure is a
+/// division by zero if it always fails.
+fn evaluate(
+    binary: &ExprBinary,
+    mode: Mode,
+    division: Division,
+) -> Option<Result<(String, String), bool>> {
+    let (ty, left) = constant(&binary.left)?;
+    let (right_ty, right) = constant(&binary.right)?;
+    let ty = ty?;
+    let op = match binary.op {
+        BinOp::Add(_) => Operation::Add,
+        BinOp::Sub(_) => Operation::Sub,
+        BinOp::Mul(_) => Operation::Mul,
+        BinOp::Div(_) | BinOp::Rem(_) if division != Division::Truncate => return None,
+        BinOp::Div(_) => Operation::Div,
+        BinOp::Rem(_) => Operation::Rem,
+        BinOp::Shl(_) => Operation::Shl,
+        BinOp::Shr(_) => Operation::Shr,
+        _ => return None,
+    };
+    // The amount of a shift can be of any type
+    if !matches!(op, Operation::Shl | Operation::Shr) && right_ty.as_ref() != Some(&ty) {
+        return None;
+    }
+    let value = evaluate_in(&ty, op, &left, &right, mode)?;
+    Some(value.map(|value| (ty, value)))
+}
+
+/// The type, if the literal is suffixed, and the value of a constant operand.
+fn constant(expr: &Expr) -> Option<(Option<String>, String)> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => {
+            let ty = Some(lit.suffix().to_string()).filter(|ty| !ty.is_empty());
+            Some((ty, lit.base10_digits().to_string()))
+        }
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => {
+            let (ty, value) = constant(expr)?;
+            let value = match value.strip_prefix('-') {
+                Some(positive) => positive.to_string(),
+                None => format!("-{value}"),
+            };
+            Some((ty, value))
+        }
+        Expr::Path(ExprPath {
+            qself: None, path, ..
+        }) if path.segments.len() == 2 => {
+            let ty = &path.segments[0].ident;
+            let bound = &path.segments[1].ident;
+            if !is_integer_type(ty) || !path.segments.iter().all(|s| s.arguments.is_none()) {
+                return None;
+            }
+            let value = bound_value(&ty.to_string(), &bound.to_string())?;
+            Some((Some(ty.to_string()), value))
+        }
+        Expr::Group(ExprGroup { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) => constant(expr),
+        _ => None,
+    }
+}
+
+/// The literal of `value` in the integer type `ty`. The minimum of a signed
+/// type is `MIN`, since the literal of its absolute value is out of range.
+fn literal(ty: &str, value: &str, span: Span) -> Expr {
+    if value.starts_with('-') && bound_value(ty, "MIN").as_deref() == Some(value) {
+        let ty = Ident::new(ty, span);
+        let min = Ident::new("MIN", span);
+        return parse_quote!(#ty::#min);
+    }
+    let positive = |value| {
+        Expr::Lit(ExprLit {
+            attrs: Vec::new(),
+            lit: Lit::Int(LitInt::new(&format!("{value}_{ty}"), span)),
+        })
+    };
+    match value.strip_prefix('-') {
+        Some(value) => Expr::Unary(ExprUnary {
+            attrs: Vec::new(),
+            op: UnOp::Neg(token::Minus(span)),
+            expr: Box::new(positive(value)),
+        }),
+        None => positive(value),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operation {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Shl,
+    Shr,
+}
+
+macro_rules! evaluate_in {
+    ($($ty:ident)*) => {
+        /// The value of the bound `MIN` or `MAX` of the integer type `ty`.
+        fn bound_value(ty: &str, bound: &str) -> Option<String> {
+            match (ty, bound) {
+                $(
+                    (stringify!($ty), "MIN") => Some($ty::MIN.to_string()),
+                    (stringify!($ty), "MAX") => Some($ty::MAX.to_string()),
+                )*
+                _ => None,
+            }
+        }
+
+        /// Evaluates `left op right` in the integer type `ty` with the overflow
+        /// behavior of `mode`. Returns `None` if the operands are not values of
+        /// `ty`, or if the operation is left to the mode at runtime.
+        fn evaluate_in(
+            ty: &str,
+            op: Operation,
+            left: &str,
+            right: &str,
+            mode: Mode,
+        ) -> Option<Result<String, bool>> {
+            match ty {
+                $(stringify!($ty) => {
+                    let left: $ty = left.parse().ok()?;
+                    let (checked, wrapped, saturated) = match op {
+                        Operation::Shl | Operation::Shr => {
+                            let right: u32 = right.parse().ok()?;
+                            let (checked, wrapped) = if op == Operation::Shl {
+                                (left.checked_shl(right), left.wrapping_shl(right))
+                            } else {
+                                (left.checked_shr(right), left.wrapping_shr(right))
+                            };
+                            // Saturating shifts are not supported
+                            (checked, Some(wrapped), None)
+                        }
+                        _ => {
+                            let right: $ty = right.parse().ok()?;
+                            if right == 0 && matches!(op, Operation::Div | Operation::Rem) {
+                                // Dividing by zero panics in all the modes
+                                return (!matches!(mode, Mode::Wrapping | Mode::Saturating))
+                                    .then_some(Err(true));
+                            }
+                            match op {
+                                Operation::Add => (
+                                    left.checked_add(right),
+                                    Some(left.wrapping_add(right)),
+                                    Some(left.saturating_add(right)),
+                                ),
+                                Operation::Sub => (
+                                    left.checked_sub(right),
+                                    Some(left.wrapping_sub(right)),
+                                    Some(left.saturating_sub(right)),
+                                ),
+                                Operation::Mul => (
+                                    left.checked_mul(right),
+                                    Some(left.wrapping_mul(right)),
+                                    Some(left.saturating_mul(right)),
+                                ),
+                                Operation::Div => (
+                                    left.checked_div(right),
+                                    Some(left.wrapping_div(right)),
+                                    Some(left.saturating_div(right)),
+                                ),
+                                // The only overflow, `MIN % -1`, is 0 when saturating too
+                                _ => (
+                                    left.checked_rem(right),
+                                    Some(left.wrapping_rem(right)),
+                                    Some(left.wrapping_rem(right)),
+                                ),
+                            }
+                        }
+                    };
+                    match mode {
+                        Mode::Wrapping => wrapped.map(|value| Ok(value.to_string())),
+                        Mode::Saturating => saturated.map(|value| Ok(value.to_string())),
+                        _ => Some(checked.map(|value| value.to_string()).ok_or(false)),
+                    }
+                })*
+                _ => None,
+            }
+        }
+    };
+}
+
+// The size of `isize` and `usize` on the target is unknown during the expansion
+evaluate_in!(i8 i16 i32 i64 i128 u8 u16 u32 u64 u128);
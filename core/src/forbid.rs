@@ -0,0 +1,91 @@
+//! The `forbid!` macro, which rejects some operators in an expression, like
+//! the lossy `/` and `%` in fixed-point code, and transforms the others like
+//! `mixed!`.
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{BinOp, Ident, Token};
+
+use crate::attribute::compound_op;
+use crate::{mixed, operation_names};
+
+/// The names of the operators that can be forbidden, as in `Operation`.
+const OPERATORS: &[&str] = &["add", "sub", "mul", "div", "rem", "shl", "shr"];
+
+/// Input of `forbid!`: the forbidden operators, followed by the input of
+/// `mixed!`, like `div, rem; [checked] price * quantity`.
+struct ForbidInput {
+    forbidden: Vec<Ident>,
+    item: TokenStream,
+}
+
+impl Parse for ForbidInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let forbidden = Punctuated::<Ident, Token![,]>::parse_separated_nonempty(input)?;
+        input.parse::<Token![;]>()?;
+        for name in &forbidden {
+            if !OPERATORS.iter().any(|operator| name == operator) {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    format!(
+                        "Unknown operator `{name}`, expected one of {}",
+                        OPERATORS.join(", ")
+                    ),
+                ));
+            }
+        }
+        Ok(ForbidInput {
+            forbidden: forbidden.into_iter().collect(),
+            item: input.parse()?,
+        })
+    }
+}
+
+/// Expands `forbid! { item }`.
+pub fn forbid_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let ForbidInput { forbidden, item } = syn::parse2(item)?;
+    let (default, mut expr, options) = mixed::parse(item)?;
+    let mut checker = Checker {
+        forbidden,
+        error: None,
+    };
+    checker.visit_expr_mut(&mut expr);
+    match checker.error {
+        Some(e) => Err(e),
+        None => mixed::transform(default, expr, options),
+    }
+}
+
+/// Visitor that records an error for every forbidden operator, including the
+/// ones in nested expressions that are not transformed.
+struct Checker {
+    forbidden: Vec<Ident>,
+    error: Option<syn::Error>,
+}
+
+impl VisitMut for Checker {
+    fn visit_bin_op_mut(&mut self, op: &mut BinOp) {
+        let base = compound_op(op).unwrap_or(*op);
+        let Some((name, _)) = operation_names(base) else {
+            return;
+        };
+        let name = name.to_lowercase();
+        if self.forbidden.iter().any(|forbidden| *forbidden == name) {
+            let e = syn::Error::new_spanned(
+                &op,
+                format!(
+                    "The `{name}` operator `{}` is forbidden in this expression",
+                    op.to_token_stream()
+                ),
+            );
+            match &mut self.error {
+                Some(error) => error.combine(e),
+                None => self.error = Some(e),
+            }
+        }
+        visit_mut::visit_bin_op_mut(self, op);
+    }
+}
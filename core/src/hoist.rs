@@ -0,0 +1,109 @@
+//! The hoisting of the repeated operations of `checked!` with the `hoist`
+//! feature, like `a * b` in `(a * b) + (a * b) / c`, which are then checked
+//! and computed once into a temporary that the expression reuses.
+//!
+//! Only the operations without side effects are hoisted, whose operands are
+//! variables, literals, casts and other such operations. Any failure makes the
+//! whole `checked!` expression `None`, so evaluating them once and before the
+//! rest of the expression gives the same result.
+
+use std::collections::HashMap;
+use std::mem;
+
+use proc_macro2::{Ident, Span};
+use quote::ToTokens;
+use syn::{parse_quote, Expr, ExprBinary, ExprCast, ExprGroup, ExprParen, ExprUnary};
+
+use crate::checked_op;
+
+/// Replaces the operations that are repeated in `expr` with temporaries,
+/// returning the temporaries with the operations they hold, in the order they
+/// have to be evaluated, and the expression using them. The innermost
+/// operations are hoisted first, so that the temporaries of the outer ones
+/// reuse them too.
+pub(crate) fn hoist(mut expr: Expr) -> (Vec<(Ident, Expr)>, Expr) {
+    let mut temporaries = Vec::new();
+    while let Some(key) = innermost_repeated(&expr) {
+        let name = Ident::new(
+            &format!("__arithmetic_mode_h{}", temporaries.len()),
+            Span::mixed_site(),
+        );
+        let mut hoisted = None;
+        replace(&mut expr, &key, &name, &mut hoisted);
+        temporaries.extend(hoisted.map(|hoisted| (name, hoisted)));
+    }
+    (temporaries, expr)
+}
+
+/// The source of the first operation of `expr` that is repeated, after its
+/// operands, so that none of its operands is repeated.
+fn innermost_repeated(expr: &Expr) -> Option<String> {
+    let mut keys = Vec::new();
+    collect(expr, &mut keys);
+    let mut counts = HashMap::new();
+    for key in &keys {
+        *counts.entry(key.as_str()).or_insert(0) += 1;
+    }
+    keys.iter().find(|key| counts[key.as_str()] > 1).cloned()
+}
+
+/// Adds the source of each operation of `expr` that can be hoisted to `keys`,
+/// after the ones of its operands.
+fn collect(expr: &Expr, keys: &mut Vec<String>) {
+    match expr {
+        Expr::Binary(binary) => {
+            collect(&binary.left, keys);
+            collect(&binary.right, keys);
+            if can_hoist(binary) {
+                keys.push(binary.to_token_stream().to_string());
+            }
+        }
+        Expr::Cast(ExprCast { expr, .. })
+        | Expr::Group(ExprGroup { expr, .. })
+        | Expr::Paren(ExprParen { expr, .. })
+        | Expr::Unary(ExprUnary { expr, .. }) => collect(expr, keys),
+        _ => {}
+    }
+}
+
+/// Replaces the operations of `expr` whose source is `key` with the temporary
+/// `name`, storing the replaced operation in `hoisted`.
+fn replace(expr: &mut Expr, key: &str, name: &Ident, hoisted: &mut Option<Expr>) {
+    match expr {
+        Expr::Binary(binary)
+            if can_hoist(binary) && binary.to_token_stream().to_string() == key =>
+        {
+            *hoisted = Some(mem::replace(expr, parse_quote! { #name }));
+        }
+        Expr::Binary(binary) => {
+            replace(&mut binary.left, key, name, hoisted);
+            replace(&mut binary.right, key, name, hoisted);
+        }
+        Expr::Cast(ExprCast { expr, .. })
+        | Expr::Group(ExprGroup { expr, .. })
+        | Expr::Paren(ExprParen { expr, .. })
+        | Expr::Unary(ExprUnary { expr, .. }) => replace(expr, key, name, hoisted),
+        _ => {}
+    }
+}
+
+/// Whether `binary` is checked and has no side effects, so that it is worth
+/// hoisting and can be hoisted.
+fn can_hoist(binary: &ExprBinary) -> bool {
+    matches!(checked_op(binary.op), Ok(Some(_))) && is_pure(&binary.left) && is_pure(&binary.right)
+}
+
+/// Whether evaluating `expr` has no side effects, apart from failing.
+fn is_pure(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(_) | Expr::Path(_) => true,
+        Expr::Binary(binary) => {
+            checked_op(binary.op).is_ok() && is_pure(&binary.left) && is_pure(&binary.right)
+        }
+        Expr::Cast(ExprCast { expr, .. })
+        | Expr::Group(ExprGroup { expr, .. })
+        | Expr::Paren(ExprParen { expr, .. })
+        | Expr::Unary(ExprUnary { expr, .. }) => is_pure(expr),
+        _ => false,
+    }
+}
@@ -0,0 +1,211 @@
+//! Parsing of the input of the mode macros.
+
+use proc_macro2::{Spacing, TokenTree};
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::{token, Expr, Ident, LitStr, Token};
+
+use crate::diagnostic::check_ambiguous_literals;
+use crate::division::split_division;
+use crate::literal_type::{
+    apply_default_type, apply_type, is_integer_type, propagate_literal_type,
+};
+use crate::type_hint::rewrite_type_hints;
+use crate::Options;
+
+/// Input of the mode macros: an expression, optionally preceded by the integer
+/// type to use for its unsuffixed literals, like `u64: a * 2 + 1`. Both can be
+/// preceded by `const` to expand to code that can be used in const contexts,
+/// like `const u64: a * 2 + 1`.
+pub(crate) struct MacroInput {
+    pub is_const: bool,
+    pub literal_type: Option<Ident>,
+    pub expr: Expr,
+}
+
+impl Parse for MacroInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // `const { .. }` is a const block expression instead
+        let is_const = input.peek(Token![const]) && !input.peek2(token::Brace);
+        if is_const {
+            input.parse::<Token![const]>()?;
+        }
+        let literal_type =
+            if input.peek(Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+                let ty: Ident = input.parse()?;
+                if !is_integer_type(&ty) {
+                    return Err(syn::Error::new(
+                        ty.span(),
+                        format!("Expected an integer type, found `{ty}`"),
+                    ));
+                }
+                input.parse::<Token![:]>()?;
+                Some(ty)
+            } else {
+                None
+            };
+        Ok(MacroInput {
+            is_const,
+            literal_type,
+            expr: input.parse()?,
+        })
+    }
+}
+
+/// Input of `panicking!`: a [`MacroInput`], optionally preceded by a message to
+/// include in the panic, like `"frame budget": elapsed + cost`.
+struct MessageInput {
+    message: Option<LitStr>,
+    input: MacroInput,
+}
+
+impl Parse for MessageInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let message = if input.peek(LitStr) && input.peek2(Token![:]) {
+            let message = input.parse()?;
+            input.parse::<Token![:]>()?;
+            Some(message)
+        } else {
+            None
+        };
+        Ok(MessageInput {
+            message,
+            input: input.parse()?,
+        })
+    }
+}
+
+/// The expression of `name! { item }` if it cannot be parsed, like `foo.`
+/// while it is being typed, after the prefixes of the input, like the literal
+/// type and the division flavor. Emitting it with the error lets IDEs still complete and resolve the
+/// incomplete expression. The inputs of the other macros are left out, since
+/// their syntax is not Rust syntax.
+pub fn incomplete_expression(
+    name: &str,
+    item: proc_macro2::TokenStream,
+) -> Option<proc_macro2::TokenStream> {
+    if !matches!(
+        name,
+        "panicking"
+            | "verified"
+            | "wrapping"
+            | "hashing"
+            | "saturating"
+            | "checked"
+            | "try_checked"
+            | "control_flow"
+            | "const_checked"
+            | "assert_no_overflow"
+            | "fast_checked"
+    ) {
+        return None;
+    }
+    let (_, item) = split_division(item).ok()?;
+    let expr = (|input: ParseStream| {
+        if input.peek(LitStr) && input.peek2(Token![:]) {
+            input.parse::<LitStr>()?;
+            input.parse::<Token![:]>()?;
+        }
+        if input.peek(Token![const]) && !input.peek2(token::Brace) {
+            input.parse::<Token![const]>()?;
+        }
+        if input.peek(Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+            input.parse::<Ident>()?;
+            input.parse::<Token![:]>()?;
+        }
+        input.parse::<proc_macro2::TokenStream>()
+    })
+    .parse2(item)
+    .ok()?;
+    syn::parse2::<Expr>(rewrite_type_hints(expr.clone()))
+        .is_err()
+        .then_some(expr)
+}
+
+/// Splits the error that `try_checked!` returns instead of an
+/// `ArithmeticError`, stated before the rest of the input like
+/// `err = MyError::MathOverflow; a * b`.
+pub(crate) fn split_error(
+    item: proc_macro2::TokenStream,
+) -> syn::Result<(Option<Expr>, proc_macro2::TokenStream)> {
+    let mut tokens = item.clone().into_iter();
+    // `err => ..` is not an error, but neither is it an expression
+    let has_error = matches!(
+        (tokens.next(), tokens.next()),
+        (Some(TokenTree::Ident(name)), Some(TokenTree::Punct(eq)))
+            if name == "err" && eq.as_char() == '=' && eq.spacing() == Spacing::Alone
+    );
+    if !has_error {
+        return Ok((None, item));
+    }
+    (|input: ParseStream| {
+        input.parse::<Ident>()?;
+        input.parse::<Token![=]>()?;
+        let error = input.parse()?;
+        input.parse::<Token![;]>()?;
+        Ok((Some(error), input.parse()?))
+    })
+    .parse2(item)
+}
+
+/// Parses the macro input into the expression to transform, with the type of
+/// its unsuffixed literals resolved, and the options to transform it with.
+pub(crate) fn parse_expr(item: proc_macro2::TokenStream) -> syn::Result<(Expr, Options)> {
+    resolve(syn::parse2(rewrite_type_hints(item))?)
+}
+
+/// Like [`parse_expr`], but also returns the integer type stated before the
+/// expression, if any.
+pub(crate) fn parse_typed_expr(
+    item: proc_macro2::TokenStream,
+) -> syn::Result<(Option<Ident>, Expr, Options)> {
+    let input: MacroInput = syn::parse2(rewrite_type_hints(item))?;
+    let ty = input.literal_type.clone();
+    let (expr, options) = resolve(input)?;
+    Ok((ty, expr, options))
+}
+
+/// Like [`parse_expr`], but the literals whose type would be ambiguous, like
+/// in `5381 * 33`, are of the `default` type instead of an error, unless the
+/// input states the type of its literals.
+pub(crate) fn parse_default_expr(
+    item: proc_macro2::TokenStream,
+    default: &str,
+) -> syn::Result<(Expr, Options)> {
+    let mut input: MacroInput = syn::parse2(rewrite_type_hints(item))?;
+    if input.literal_type.is_none() {
+        propagate_literal_type(&mut input.expr);
+        apply_default_type(&mut input.expr, default);
+    }
+    resolve(input)
+}
+
+/// Like [`parse_expr`], but also accepts a message before the expression.
+pub(crate) fn parse_message_expr(
+    item: proc_macro2::TokenStream,
+) -> syn::Result<(Option<LitStr>, Expr, Options)> {
+    let MessageInput { message, input } = syn::parse2(rewrite_type_hints(item))?;
+    let (expr, options) = resolve(input)?;
+    Ok((message, expr, options))
+}
+
+/// Resolves the type of the unsuffixed literals in the expression, failing if
+/// the type of an operation remains ambiguous.
+fn resolve(input: MacroInput) -> syn::Result<(Expr, Options)> {
+    let MacroInput {
+        is_const,
+        literal_type,
+        mut expr,
+    } = input;
+    match literal_type {
+        Some(ty) => apply_type(&mut expr, &ty.to_string()),
+        None => propagate_literal_type(&mut expr),
+    }
+    check_ambiguous_literals(&expr)?;
+    Ok((
+        expr,
+        Options {
+            is_const,
+            ..Options::default()
+        },
+    ))
+}
@@ -0,0 +1,1135 @@
+//! The transformations of the [`arithmetic-mode`](https://docs.rs/arithmetic-mode)
+//! macros, for tools like lints, code rewriters and other procedural macros
+//! that want to reuse them outside of a proc-macro crate.
+//!
+//! [`transform`] changes the arithmetic operations of an expression into one
+//! of the [`Mode`]s, and the `*_impl` functions expand the input of the
+//! corresponding macros. [`transform_with`] does the same for a user-defined
+//! mode, given as a [`Strategy`]. The generated code refers to items of
+//! `arithmetic-mode`, so the crate using it has to depend on that crate.
+//!
+//! With the `pretty` feature, `expand_pretty` formats the expansion of a macro
+//! input, for snapshot tests of how the expressions of a crate expand.
+
+#![warn(missing_docs)]
+
+mod attribute;
+mod bench;
+mod channel;
+mod config;
+mod constant_time;
+mod default_mode;
+mod diagnostic;
+mod differential;
+mod division;
+mod dot;
+mod elementwise;
+mod enforce;
+mod engine;
+mod evm;
+mod explain;
+mod fast;
+mod fold;
+mod forbid;
+mod hoist;
+mod input;
+mod literal_type;
+mod matrix;
+mod mixed;
+mod modes;
+mod modfield;
+mod mul_div;
+mod passthrough;
+#[cfg(feature = "pretty")]
+mod pretty;
+mod property;
+mod qformat;
+mod record;
+mod statement;
+mod trace;
+mod type_hint;
+mod variadic;
+
+use diagnostic::Limitation;
+use division::{split_division, with_division, Division};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+use syn::{Expr, ExprBinary, ExprCall, ExprGroup, ExprParen, ExprPath, ExprTry, Type, UnOp};
+
+pub use attribute::{arithmetic_mode_impl, closure_impl, fallible_fn_impl, Mode};
+pub use bench::bench_impl;
+pub use channel::{blend_add_impl, scale_u8_impl};
+pub use constant_time::ct_checked_impl;
+pub use default_mode::default_impl;
+pub use differential::differential_test_impl;
+pub use dot::{dot_product_impl, mac_impl};
+pub use elementwise::elementwise_impl;
+pub use enforce::{deny_unchecked_arithmetic_impl, enforce_impl};
+pub use engine::{transform_with, Strategy};
+pub use evm::evm_impl;
+pub use explain::explain_impl;
+pub use fast::fast_checked_impl;
+pub use forbid::forbid_impl;
+pub use input::incomplete_expression;
+pub use matrix::overflow_matrix_impl;
+pub use mixed::mixed_impl;
+pub use modes::modes_impl;
+pub use modfield::modfield_impl;
+pub use mul_div::{bps_of_impl, lerp_impl, mul_div_impl, percent_of_impl};
+pub use passthrough::{passthrough, passthrough_attribute};
+#[cfg(feature = "pretty")]
+pub use pretty::expand_pretty;
+pub use property::proptest_modes_impl;
+pub use qformat::qformat_impl;
+pub use record::record_impl;
+pub use statement::stmt_impl;
+pub use trace::trace_impl;
+pub use variadic::{checked_product_impl, checked_sum_impl};
+
+/// Changes the arithmetic operations in `expr` into `mode`, like the
+/// corresponding macro. The type of the unsuffixed literals is propagated from
+/// the other operands like in the macros.
+///
+/// ```rust
+/// use arithmetic_mode_core::{transform, Mode};
+///
+/// let expr = syn::parse_quote! { a + 1_u8 };
+/// let tokens = transform(expr, Mode::Wrapping).unwrap();
+/// assert!(tokens.to_string().contains("wrapping_add"));
+/// ```
+pub fn transform(mut expr: Expr, mode: Mode) -> syn::Result<TokenStream> {
+    literal_type::propagate_literal_type(&mut expr);
+    mode.transform(expr, Options::default())
+}
+
+/// The stack that the expansions are run on with the `deep-stack` feature,
+/// since parsing and transforming an expression recurses once per level of
+/// nesting, which machine-generated expressions with thousands of terms have.
+/// It is only reserved, so the pages that are not used are not allocated.
+#[cfg(feature = "deep-stack")]
+const DEEP_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Runs the expansion `f` on a larger stack, unless the current one has enough
+/// left, with the `deep-stack` feature. Otherwise, `f` runs on the stack of the
+/// compiler, which `RUST_MIN_STACK` can increase.
+pub fn with_deep_stack<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "deep-stack")]
+    return stacker::maybe_grow(DEEP_STACK_SIZE, DEEP_STACK_SIZE, f);
+    #[cfg(not(feature = "deep-stack"))]
+    f()
+}
+
+/// Expands `panicking! { item }`.
+pub fn panicking_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (division, item) = split_division(item)?;
+    let (message, expr, options) = input::parse_message_expr(item)?;
+    let options = with_division(options, division)?;
+    panicking_expr(expr, message.map(|m| m.value()).as_deref(), options)
+}
+
+/// Transforms `expr` into panicking operations. `message`, if any, is included
+/// in the panic message before the description of the failed operation.
+fn panicking_expr(expr: Expr, message: Option<&str>, options: Options) -> syn::Result<TokenStream> {
+    let expr = fold::fold_constants(expr, Mode::Panicking, options.division)?;
+    engine::transform_expr(&expr, &Panicking { message, options })
+}
+
+/// The operations of `panicking!`, which are also used by `verified!` and
+/// `const_checked!`.
+struct Panicking<'a> {
+    message: Option<&'a str>,
+    options: Options,
+}
+
+impl Strategy for Panicking<'_> {
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        new_left: TokenStream,
+        new_right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        let Panicking { message, options } = *self;
+        let op = binary.op;
+        let span = op.span();
+        let (l, r) = operand_names(span);
+        let panic = cold_branch(profiled(
+            binary,
+            options,
+            operation_panic(binary, quote! { #l }, quote! { #r }, message),
+        ));
+        let expression = binary.to_token_stream().to_string();
+        let new_right = deref_operand(options.may_be_reference(&binary.right), new_right);
+        // Kani's functions cannot be called in const contexts
+        let proof = match checked_op(op)? {
+            Some(func) if options.verified && !options.is_const => {
+                verification(&func, &l, &r, message, &expression, op)
+            }
+            _ => TokenStream::new(),
+        };
+        Ok(match checked_op(op)? {
+            // Formatting the operands is not possible in const contexts
+            Some(func) if options.is_const => {
+                let overflow = panic_message(message, &expression, op, false);
+                let panic = match op {
+                    syn::BinOp::Div(_) | syn::BinOp::Rem(_) => {
+                        let division_by_zero = panic_message(message, &expression, op, true);
+                        quote_spanned! { span=>
+                            if #r == 0 {
+                                ::core::panic!("{}", #division_by_zero)
+                            } else {
+                                ::core::panic!("{}", #overflow)
+                            }
+                        }
+                    }
+                    _ => quote_spanned! { span=> ::core::panic!("{}", #overflow) },
+                };
+                let panic = cold_branch(panic);
+                quote_spanned! { span=>
+                    {
+                        let #l = #new_left;
+                        let #r = #new_right;
+                        #proof
+                        match #l.#func(#r) {
+                            ::core::option::Option::Some(v) => v,
+                            ::core::option::Option::None => #panic,
+                        }
+                    }
+                }
+            }
+            Some(func) => {
+                let call = method_call(&func, &l, &r, options);
+                quote_spanned! { span=>
+                    {
+                        let #l = #new_left;
+                        let #r = #new_right;
+                        #proof
+                        match #call {
+                            ::core::option::Option::Some(v) => v,
+                            ::core::option::Option::None => #panic,
+                        }
+                    }
+                }
+            }
+            None => quote! { #new_left #op #new_right },
+        })
+    }
+}
+
+/// The `kani::assert` statements proving that the operation `func` of the
+/// operands `l` and `r` does not fail, which are only compiled when verifying
+/// with Kani.
+fn verification(
+    func: &TokenStream,
+    l: &Ident,
+    r: &Ident,
+    message: Option<&str>,
+    expression: &str,
+    op: syn::BinOp,
+) -> TokenStream {
+    let span = op.span();
+    let overflow = panic_message(message, expression, op, false);
+    let division_by_zero = match op {
+        syn::BinOp::Div(_) | syn::BinOp::Rem(_) => {
+            let division_by_zero = panic_message(message, expression, op, true);
+            quote_spanned! { span=>
+                #[cfg(kani)]
+                ::kani::assert(#r != 0, #division_by_zero);
+            }
+        }
+        _ => TokenStream::new(),
+    };
+    quote_spanned! { span=>
+        #division_by_zero
+        #[cfg(kani)]
+        ::kani::assert(#l.#func(#r).is_some(), #overflow);
+    }
+}
+
+/// Expands `verified! { item }`.
+pub fn verified_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (division, item) = split_division(item)?;
+    let (message, expr, options) = input::parse_message_expr(item)?;
+    let options = with_division(options, division)?;
+    verified_expr(expr, message.map(|m| m.value()).as_deref(), options)
+}
+
+fn verified_expr(expr: Expr, message: Option<&str>, options: Options) -> syn::Result<TokenStream> {
+    let options = Options {
+        verified: true,
+        ..options
+    };
+    panicking_expr(expr, message, options)
+}
+
+/// Expands `wrapping! { item }`.
+pub fn wrapping_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (division, item) = split_division(item)?;
+    let (expr, options) = input::parse_expr(item)?;
+    let options = with_division(options, division)?;
+    wrapping_expr(expr, options)
+}
+
+/// The type of the literals of `hashing!` that have no other type, since hash
+/// functions mostly mix `u64` values.
+pub(crate) const HASHING_TYPE: &str = "u64";
+
+/// Expands `hashing! { item }`, which is `wrapping!` with the literals that
+/// are only combined with each other of type [`HASHING_TYPE`].
+pub fn hashing_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (division, item) = split_division(item)?;
+    let (expr, options) = input::parse_default_expr(item, HASHING_TYPE)?;
+    let options = with_division(options, division)?;
+    wrapping_expr(expr, options)
+}
+
+fn wrapping_expr(expr: Expr, options: Options) -> syn::Result<TokenStream> {
+    let expr = fold::fold_constants(expr, Mode::Wrapping, options.division)?;
+    engine::transform_expr(&expr, &Wrapping { options })
+}
+
+/// The operations of `wrapping!`.
+struct Wrapping {
+    options: Options,
+}
+
+impl Strategy for Wrapping {
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        new_left: TokenStream,
+        new_right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        let op = binary.op;
+        let span = op.span();
+        let (l, r) = operand_names(span);
+        let new_right = deref_operand(self.options.may_be_reference(&binary.right), new_right);
+        let Some(func) = wrapping_op(op)? else {
+            return Ok(quote! { #new_left #op #new_right });
+        };
+        let call = profiled_call(binary, &func, &l, &r, self.options)?;
+        Ok(quote_spanned! { span=>
+            {
+                let #l = #new_left;
+                let #r = #new_right;
+                #call
+            }
+        })
+    }
+}
+
+/// Expands `saturating! { item }`.
+pub fn saturating_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (division, item) = split_division(item)?;
+    let (expr, options) = input::parse_expr(item)?;
+    let options = with_division(options, division)?;
+    saturating_expr(expr, options)
+}
+
+fn saturating_expr(expr: Expr, options: Options) -> syn::Result<TokenStream> {
+    let expr = fold::fold_constants(expr, Mode::Saturating, options.division)?;
+    engine::transform_expr(&expr, &Saturating { options })
+}
+
+/// The operations of `saturating!`.
+struct Saturating {
+    options: Options,
+}
+
+impl Strategy for Saturating {
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        new_left: TokenStream,
+        new_right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        let op = binary.op;
+        let span = op.span();
+        let (l, r) = operand_names(span);
+        let new_right = deref_operand(self.options.may_be_reference(&binary.right), new_right);
+        let func = match op {
+            syn::BinOp::Add(_) => quote_spanned! { span=> saturating_add },
+            syn::BinOp::Sub(_) => quote_spanned! { span=> saturating_sub },
+            syn::BinOp::Mul(_) => quote_spanned! { span=> saturating_mul },
+            syn::BinOp::Div(_) => quote_spanned! { span=> saturating_div },
+            // The only overflow, `MIN % -1`, is 0, so wrapping is already saturating
+            syn::BinOp::Rem(_) => quote_spanned! { span=> wrapping_rem },
+            syn::BinOp::Shl(_) | syn::BinOp::Shr(_) => {
+                return Err(Limitation::SaturatingShift.error(op))
+            }
+            syn::BinOp::And(_)
+            | syn::BinOp::Or(_)
+            | syn::BinOp::BitXor(_)
+            | syn::BinOp::BitAnd(_)
+            | syn::BinOp::BitOr(_)
+            | syn::BinOp::Eq(_)
+            | syn::BinOp::Lt(_)
+            | syn::BinOp::Le(_)
+            | syn::BinOp::Ne(_)
+            | syn::BinOp::Ge(_)
+            | syn::BinOp::Gt(_) => return Ok(quote! { #new_left #op #new_right }),
+            syn::BinOp::AddAssign(_)
+            | syn::BinOp::SubAssign(_)
+            | syn::BinOp::MulAssign(_)
+            | syn::BinOp::DivAssign(_)
+            | syn::BinOp::RemAssign(_)
+            | syn::BinOp::BitXorAssign(_)
+            | syn::BinOp::BitAndAssign(_)
+            | syn::BinOp::BitOrAssign(_)
+            | syn::BinOp::ShlAssign(_)
+            | syn::BinOp::ShrAssign(_) => return Err(Limitation::CompoundAssignment.error(op)),
+            _ => return Err(syn::Error::new_spanned(op, "Unknown operation")),
+        };
+        let call = profiled_call(binary, &func, &l, &r, self.options)?;
+        Ok(quote_spanned! { span=>
+            {
+                let #l = #new_left;
+                let #r = #new_right;
+                #call
+            }
+        })
+    }
+}
+
+/// Expands `checked! { item }`.
+pub fn checked_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (division, item) = split_division(item)?;
+    let (expr, options) = input::parse_expr(item)?;
+    let options = with_division(options, division)?;
+    checked_expr(expr, options)
+}
+
+fn checked_expr(expr: Expr, options: Options) -> syn::Result<TokenStream> {
+    let expr = fold::fold_constants(expr, Mode::Checked, options.division)?;
+    if cfg!(feature = "hoist") {
+        return hoisted_checked_expr(expr, options);
+    }
+    engine::transform_expr(&expr, &Checked { options })
+}
+
+/// Like [`checked_expr`], with the repeated operations of `expr` checked and
+/// computed once, before the rest of the expression.
+fn hoisted_checked_expr(expr: Expr, options: Options) -> syn::Result<TokenStream> {
+    let strategy = Checked { options };
+    let (temporaries, expr) = hoist::hoist(expr);
+    let mut tokens = engine::transform_expr(&expr, &strategy)?;
+    for (name, value) in temporaries.iter().rev() {
+        let value = engine::transform_expr(value, &strategy)?;
+        tokens = quote! {
+            match #value {
+                Some(#name) => #tokens,
+                None => None,
+            }
+        };
+    }
+    Ok(tokens)
+}
+
+/// The operations of `checked!`, which evaluate to an `Option`.
+struct Checked {
+    options: Options,
+}
+
+impl Strategy for Checked {
+    fn is_fallible(&self) -> bool {
+        true
+    }
+
+    fn operand(&self, expr: &Expr) -> TokenStream {
+        quote! { Some(#expr) }
+    }
+
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        new_left: TokenStream,
+        new_right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        let op = binary.op;
+        let span = op.span();
+        let (l, r) = operand_names(span);
+        let deref_r = deref_operand(self.options.may_be_reference(&binary.right), quote! { #r });
+        let value = match checked_op(op)? {
+            Some(func) if cfg!(feature = "nightly") || cfg!(feature = "profile") => {
+                let call = method_call(&func, &l, &deref_r, self.options);
+                let none = cold_branch(profiled(binary, self.options, quote! { None }));
+                quote_spanned! { span=>
+                    match #call {
+                        Some(v) => Some(v),
+                        None => #none,
+                    }
+                }
+            }
+            Some(func) => method_call(&func, &l, &deref_r, self.options),
+            None => quote_spanned! { span=> Some(#l #op #r) },
+        };
+        // The right operand is only evaluated if the left one succeeded.
+        // Matching instead of using closures keeps it usable in const
+        // contexts.
+        Ok(quote_spanned! { span=>
+            match #new_left {
+                Some(#l) => match #new_right {
+                    Some(#r) => #value,
+                    None => None,
+                },
+                None => None,
+            }
+        })
+    }
+
+    fn unary(&self, op: &UnOp, operand: TokenStream) -> TokenStream {
+        quote! {
+            match #operand {
+                Some(v) => Some(#op v),
+                None => None,
+            }
+        }
+    }
+
+    fn type_hint(&self, operand: TokenStream, ty: &Type) -> TokenStream {
+        quote! {
+            match #operand {
+                Some(v) => Some::<#ty>(v),
+                None => None,
+            }
+        }
+    }
+
+    fn other(&self, expr: &Expr) -> syn::Result<TokenStream> {
+        match expr {
+            Expr::Call(ExprCall { func, args, .. }) if is_opt_marker(func) && args.len() == 1 => {
+                // `opt(x)` splices an operand that is already an `Option`
+                let arg = &args[0];
+                Ok(quote! { #arg })
+            }
+            _ => Err(unsupported_expression(expr)),
+        }
+    }
+}
+
+/// Expands `try_checked! { item }`.
+pub fn try_checked_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (error, item) = input::split_error(item)?;
+    let (division, item) = split_division(item)?;
+    let (expr, options) = input::parse_expr(item)?;
+    let options = with_division(options, division)?;
+    Ok(with_error(try_checked_expr(expr, options)?, error))
+}
+
+/// Replaces the `ArithmeticError` of the `Result` of `expansion` with the
+/// caller's `error`, if any, which is only evaluated on failure.
+pub(crate) fn with_error(expansion: TokenStream, error: Option<Expr>) -> TokenStream {
+    match error {
+        Some(error) => quote! {
+            match #expansion {
+                ::core::result::Result::Ok(v) => ::core::result::Result::Ok(v),
+                ::core::result::Result::Err(_) => ::core::result::Result::Err(#error),
+            }
+        },
+        None => expansion,
+    }
+}
+
+/// Expands `control_flow! { item }`, which evaluates like `try_checked!` to a
+/// `ControlFlow` breaking with the `ArithmeticError`.
+pub fn control_flow_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (division, item) = split_division(item)?;
+    let (expr, options) = input::parse_expr(item)?;
+    let options = with_division(options, division)?;
+    Ok(control_flow(try_checked_expr(expr, options)?))
+}
+
+/// Converts the `Result` of `expansion` into a `ControlFlow`.
+pub(crate) fn control_flow(expansion: TokenStream) -> TokenStream {
+    quote! {
+        match #expansion {
+            ::core::result::Result::Ok(v) => ::core::ops::ControlFlow::Continue(v),
+            ::core::result::Result::Err(e) => ::core::ops::ControlFlow::Break(e),
+        }
+    }
+}
+
+fn try_checked_expr(expr: Expr, options: Options) -> syn::Result<TokenStream> {
+    let expr = fold::fold_constants(expr, Mode::TryChecked, options.division)?;
+    engine::transform_expr(&expr, &TryChecked { options })
+}
+
+/// The operations of `try_checked!`, which evaluate to a `Result` with an
+/// `ArithmeticError`.
+struct TryChecked {
+    options: Options,
+}
+
+impl Strategy for TryChecked {
+    fn is_fallible(&self) -> bool {
+        true
+    }
+
+    fn operand(&self, expr: &Expr) -> TokenStream {
+        quote! { Ok::<_, ::arithmetic_mode::ArithmeticError>(#expr) }
+    }
+
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        new_left: TokenStream,
+        new_right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        let op = binary.op;
+        let span = op.span();
+        let (l, r) = operand_names(span);
+        let error = operation_error(binary, quote! { #l }, quote! { #r }, self.options);
+        let may_be_reference = self.options.may_be_reference(&binary.right);
+        let value = match checked_op(op)? {
+            Some(func) => {
+                let error = cold_branch(profiled(binary, self.options, quote! { Err(#error) }));
+                let call = method_call(&func, &l, &r, self.options);
+                let value = quote_spanned! { span=>
+                    match #call {
+                        Some(v) => Ok(v),
+                        None => #error,
+                    }
+                };
+                // The error describes the dereferenced operand
+                if may_be_reference {
+                    let deref_r = deref_operand(true, quote! { #r });
+                    quote_spanned! { span=> { let #r = #deref_r; #value } }
+                } else {
+                    value
+                }
+            }
+            None => quote_spanned! { span=> Ok(#l #op #r) },
+        };
+        // Like `checked!`, matching instead of using closures keeps it usable
+        // in const contexts.
+        Ok(quote_spanned! { span=>
+            match #new_left {
+                Ok(#l) => match #new_right {
+                    Ok(#r) => #value,
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            }
+        })
+    }
+
+    fn unary(&self, op: &UnOp, operand: TokenStream) -> TokenStream {
+        quote! {
+            match #operand {
+                Ok(v) => Ok(#op v),
+                Err(e) => Err(e),
+            }
+        }
+    }
+
+    fn type_hint(&self, operand: TokenStream, ty: &Type) -> TokenStream {
+        quote! {
+            match #operand {
+                Ok(v) => Ok::<#ty, ::arithmetic_mode::ArithmeticError>(v),
+                Err(e) => Err(e),
+            }
+        }
+    }
+
+    fn other(&self, expr: &Expr) -> syn::Result<TokenStream> {
+        match expr {
+            // `x?` splices an operand that is already a `Result`
+            Expr::Try(ExprTry {
+                expr,
+                question_token,
+                ..
+            }) => {
+                if self.options.is_const {
+                    return Err(Limitation::ConstTry.error(question_token));
+                }
+                Ok(quote! {
+                    (#expr).map_err(::core::convert::Into::<::arithmetic_mode::ArithmeticError>::into)
+                })
+            }
+            _ => Err(unsupported_expression(expr)),
+        }
+    }
+}
+
+/// Expands `assert_no_overflow! { item }`.
+pub fn assert_no_overflow_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (message, expr, options) = input::parse_message_expr(item)?;
+    let assertion = format!(
+        "assertion failed: no overflow in `{}`",
+        expr.to_token_stream()
+    );
+    let assertion = match message {
+        Some(message) => format!("{}: {assertion}", message.value()),
+        None => assertion,
+    };
+    let expr = try_checked_expr(expr, options)?;
+    Ok(quote! {
+        match #expr {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err(e) => ::core::panic!("{}: {}", #assertion, e),
+        }
+    })
+}
+
+/// Expands `const_checked! { item }`.
+pub fn const_checked_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (message, expr, _) = input::parse_message_expr(item)?;
+    let options = Options {
+        is_const: true,
+        ..Options::default()
+    };
+    let expr = panicking_expr(expr, message.map(|m| m.value()).as_deref(), options)?;
+    Ok(const_block(expr))
+}
+
+/// Adds a warning to the `expansion` of `name! { item }` if the expression has
+/// no arithmetic operations to transform, like `wrapping! { flags | mask }`,
+/// since the macro then misleadingly suggests that overflows are handled.
+pub fn warn_without_arithmetic(
+    name: &str,
+    item: TokenStream,
+    expansion: TokenStream,
+) -> TokenStream {
+    let Ok((_, expr, _)) = input::parse_message_expr(item) else {
+        return expansion;
+    };
+    if diagnostic::has_arithmetic(&expr) {
+        return expansion;
+    }
+    let note = format!(
+        "arithmetic_mode: `{}` has no arithmetic operations for `{name}!` to transform",
+        expr.to_token_stream()
+    );
+    let warning = diagnostic::warning("NO_ARITHMETIC_TO_TRANSFORM", expr.span(), &note);
+    quote! { { #warning #expansion } }
+}
+
+/// The error for an expression that the modes don't support, located at the
+/// expression.
+fn unsupported_expression(expr: &Expr) -> syn::Error {
+    let message = format!("Unsupported expression `{}`", expr.to_token_stream());
+    syn::Error::new_spanned(expr, message)
+}
+
+/// Combines the results of transforming the two operands of an operation, so
+/// that the errors in both of them are reported at once.
+fn both<L, R>(left: syn::Result<L>, right: syn::Result<R>) -> syn::Result<(L, R)> {
+    match (left, right) {
+        (Ok(left), Ok(right)) => Ok((left, right)),
+        (Err(mut left), Err(right)) => {
+            left.combine(right);
+            Err(left)
+        }
+        (Err(e), Ok(_)) | (Ok(_), Err(e)) => Err(e),
+    }
+}
+
+/// Options for transforming an expression.
+#[derive(Clone, Copy, Debug, Default)]
+struct Options {
+    /// Whether the expression is evaluated in a const context, where operands
+    /// cannot be dereferenced through `Borrow`.
+    is_const: bool,
+    /// Whether the panicking operations also assert the absence of overflow
+    /// with `kani::assert`, for the Kani model checker.
+    verified: bool,
+    /// Whether the operators are left unchanged, for the expansion used with
+    /// `--cfg arithmetic_mode_passthrough`.
+    passthrough: bool,
+    /// How `/` rounds its quotient, and `%` its remainder to match.
+    division: Division,
+}
+
+impl Options {
+    fn may_be_reference(self, expr: &Expr) -> bool {
+        !self.is_const && may_be_reference(expr)
+    }
+}
+
+/// Whether `func` is the `opt` marker used in `checked!` to denote operands
+/// that are already an `Option`.
+fn is_opt_marker(func: &Expr) -> bool {
+    matches!(func, Expr::Path(ExprPath { qself: None, path, .. }) if path.is_ident("opt"))
+}
+
+/// Whether the given operand may evaluate to a reference to an integer, like
+/// the `&u32` items commonly seen in iterator closures. Literals and the
+/// results of operators are always values.
+fn may_be_reference(expr: &Expr) -> bool {
+    match expr {
+        Expr::Binary(_) | Expr::Cast(_) | Expr::Lit(_) | Expr::Unary(_) => false,
+        Expr::Group(ExprGroup { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) => {
+            may_be_reference(expr)
+        }
+        _ => true,
+    }
+}
+
+/// Hints that the overflow `branch` is unlikely to be taken, so that the
+/// compiler optimizes for the operations that succeed. The hint needs a
+/// nightly compiler, so `branch` is unchanged without the `nightly` feature.
+fn cold_branch(branch: TokenStream) -> TokenStream {
+    if cfg!(feature = "nightly") {
+        quote! {
+            {
+                ::arithmetic_mode::__private::cold_path();
+                #branch
+            }
+        }
+    } else {
+        branch
+    }
+}
+
+/// Evaluates `expr` at compile time, in a `const` block. The blocks need Rust
+/// 1.79, so with the `msrv` feature, `expr` is a plain block instead, which is
+/// only evaluated at compile time in const contexts.
+pub(crate) fn const_block(expr: TokenStream) -> TokenStream {
+    if cfg!(feature = "msrv") {
+        quote! { { #expr } }
+    } else {
+        quote! { const { #expr } }
+    }
+}
+
+/// A `&'static` reference to the `Location` constructed by `location`, which
+/// is promoted in a `const` block, or with the `msrv` feature, stored in a
+/// `static`.
+pub(crate) fn static_location(location: TokenStream) -> TokenStream {
+    if cfg!(feature = "msrv") {
+        quote! {
+            {
+                static LOCATION: ::arithmetic_mode::Location = #location;
+                &LOCATION
+            }
+        }
+    } else {
+        quote! { const { &#location } }
+    }
+}
+
+/// The call of the integer method `func` on the operand `l` with the argument
+/// `r`. With the `qualified` feature, it is called through a trait that is only
+/// implemented for the primitive integers, instead of a method call that
+/// would prefer a method with the same name of another type. Trait methods
+/// cannot be called in const contexts though. The divisions of the flavors
+/// other than `/` are the methods of the `Division` trait.
+fn method_call(func: &TokenStream, l: &Ident, r: &impl ToTokens, options: Options) -> TokenStream {
+    match options.division.method(func) {
+        Some((method, true)) => return quote_spanned! { func.span()=> #l.#method(#r) },
+        Some((method, false)) => {
+            return quote_spanned! { func.span()=>
+                {
+                    use ::arithmetic_mode::__private::Division as _;
+                    #l.#method(#r)
+                }
+            }
+        }
+        None => {}
+    }
+    if cfg!(feature = "qualified") && !options.is_const {
+        quote_spanned! { func.span()=>
+            ::arithmetic_mode::__private::Integer::#func(
+                ::arithmetic_mode::__private::Operand::integer(#l),
+                #r,
+            )
+        }
+    } else {
+        quote_spanned! { func.span()=> #l.#func(#r) }
+    }
+}
+
+/// Counts the overflows of `binary` in `arithmetic_mode::profile` before the
+/// overflow `branch`, with the `profile` feature. The statics counting them
+/// cannot be used in const contexts, so `branch` is unchanged there.
+fn profiled(binary: &ExprBinary, options: Options, branch: TokenStream) -> TokenStream {
+    if !cfg!(feature = "profile") || options.is_const {
+        return branch;
+    }
+    let Some((_, expression, location)) = operation_details(binary) else {
+        return branch;
+    };
+    quote_spanned! { binary.op.span()=>
+        {
+            static SITE: ::arithmetic_mode::profile::Site =
+                ::arithmetic_mode::profile::Site::__new(#expression, #location);
+            SITE.__record();
+            #branch
+        }
+    }
+}
+
+/// Like [`method_call`], for the modes that do not fail, like `wrapping_add`.
+/// With the `profile` feature, the operation is checked first, so that its
+/// overflows are counted like in [`profiled`].
+fn profiled_call(
+    binary: &ExprBinary,
+    func: &TokenStream,
+    l: &Ident,
+    r: &Ident,
+    options: Options,
+) -> syn::Result<TokenStream> {
+    let call = method_call(func, l, r, options);
+    if !cfg!(feature = "profile") || options.is_const {
+        return Ok(call);
+    }
+    let Some(checked) = checked_op(binary.op)? else {
+        return Ok(call);
+    };
+    let checked = method_call(&checked, l, r, options);
+    let overflow = profiled(binary, options, call);
+    Ok(quote_spanned! { binary.op.span()=>
+        match #checked {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => #overflow,
+        }
+    })
+}
+
+/// Dereferences the operand `tokens` if it may be a reference, so that it can
+/// be passed as the argument of the integer methods, which take `Self` by
+/// value. `Borrow` is used since it is implemented for both `T` and `&T`.
+fn deref_operand(may_be_reference: bool, tokens: TokenStream) -> TokenStream {
+    if may_be_reference {
+        quote! { *::core::borrow::Borrow::borrow(&#tokens) }
+    } else {
+        tokens
+    }
+}
+
+/// The names of the bindings of the evaluated left and right operands, located
+/// at the operator `span`. They are hygienic, so that the right operand can
+/// refer to variables of the same name.
+fn operand_names(span: Span) -> (Ident, Ident) {
+    let span = Span::mixed_site().located_at(span);
+    (Ident::new("l", span), Ident::new("r", span))
+}
+
+/// The `Operation` variant and the verb used in the error message for an
+/// operation that can fail.
+fn operation_names(op: syn::BinOp) -> Option<(&'static str, &'static str)> {
+    Some(match op {
+        syn::BinOp::Add(_) => ("Add", "add"),
+        syn::BinOp::Sub(_) => ("Sub", "subtract"),
+        syn::BinOp::Mul(_) => ("Mul", "multiply"),
+        syn::BinOp::Div(_) => ("Div", "divide"),
+        syn::BinOp::Rem(_) => ("Rem", "calculate the remainder"),
+        syn::BinOp::Shl(_) => ("Shl", "shift left"),
+        syn::BinOp::Shr(_) => ("Shr", "shift right"),
+        _ => return None,
+    })
+}
+
+/// The message of the panic in const contexts, matching the `Display` of
+/// `ArithmeticError` without the operands, after the user's `message` if any.
+fn panic_message(
+    message: Option<&str>,
+    expression: &str,
+    op: syn::BinOp,
+    division_by_zero: bool,
+) -> String {
+    let description = match op {
+        syn::BinOp::Rem(_) if division_by_zero => {
+            "attempt to calculate the remainder with a divisor of zero".to_string()
+        }
+        _ if division_by_zero => "attempt to divide by zero".to_string(),
+        _ => {
+            let (_, verb) = operation_names(op).unwrap_or_default();
+            format!("attempt to {verb} with overflow")
+        }
+    };
+    let description = format!("{description}: `{expression}`");
+    match message {
+        Some(message) => format!("{message}: {description}"),
+        None => description,
+    }
+}
+
+/// Constructs the `ArithmeticError` describing the failure of `binary`, whose
+/// operands have been evaluated into `left` and `right`. The location is the
+/// one of the operator.
+/// Division and remainder also check whether the divisor `right` is zero. In
+/// const contexts, where the operands cannot be formatted, the error only has
+/// the operation.
+fn operation_error(
+    binary: &ExprBinary,
+    left: TokenStream,
+    right: TokenStream,
+    options: Options,
+) -> TokenStream {
+    // Only used for the operations that can fail
+    let Some((operation, expression, location)) = operation_details(binary) else {
+        return TokenStream::new();
+    };
+    let location = static_location(location);
+    let error = if options.is_const {
+        quote! { ::arithmetic_mode::ArithmeticError::new(#operation) }
+    } else {
+        quote! {
+            ::arithmetic_mode::ArithmeticError::__operation(
+                #operation,
+                #expression,
+                #location,
+                &#left,
+                &#right,
+            )
+        }
+    };
+    match binary.op {
+        syn::BinOp::Div(_) | syn::BinOp::Rem(_) => {
+            let kind = error_kind(binary, &right);
+            quote! { #error.with_kind(#kind) }
+        }
+        _ => error,
+    }
+}
+
+/// Panics with the `ArithmeticError` of [`operation_error`], after `message`
+/// if any. The error is constructed and formatted in a single call to a cold
+/// function shared by all the operations, so that only the comparison and the
+/// call are inlined into the hot path. With the `abort` feature, the program
+/// is aborted instead, without formatting the error, and with the `defmt`
+/// feature, it panics with `defmt::panic!`.
+fn operation_panic(
+    binary: &ExprBinary,
+    left: TokenStream,
+    right: TokenStream,
+    message: Option<&str>,
+) -> TokenStream {
+    let Some((operation, expression, location)) = operation_details(binary) else {
+        return TokenStream::new();
+    };
+    let location = static_location(location);
+    if cfg!(feature = "abort") {
+        return quote_spanned! { binary.op.span()=>
+            ::arithmetic_mode::ArithmeticError::__abort()
+        };
+    }
+    let kind = error_kind(binary, &right);
+    let message = match message {
+        Some(message) => quote! { ::core::option::Option::Some(#message) },
+        None => quote! { ::core::option::Option::None },
+    };
+    // The location of the panic is the one of the function name
+    let span = binary.op.span();
+    let panic_operation = if cfg!(feature = "defmt") {
+        quote_spanned! { span=> __defmt_panic_operation }
+    } else {
+        quote_spanned! { span=> __panic_operation }
+    };
+    quote_spanned! { span=>
+        ::arithmetic_mode::ArithmeticError::#panic_operation(
+            #operation,
+            #expression,
+            #location,
+            &#left,
+            &#right,
+            #kind,
+            #message,
+        )
+    }
+}
+
+/// The `Operation` of `binary`, its source code, and the location of its
+/// operator, if it is an operation that can fail.
+fn operation_details(binary: &ExprBinary) -> Option<(TokenStream, String, TokenStream)> {
+    let (operation, _) = operation_names(binary.op)?;
+    let operation = Ident::new(operation, Span::call_site());
+    let expression = binary.to_token_stream().to_string();
+    let location = quote_spanned! { binary.op.span()=>
+        ::arithmetic_mode::Location::__new(::core::file!(), ::core::line!(), ::core::column!())
+    };
+    Some((
+        quote! { ::arithmetic_mode::Operation::#operation },
+        expression,
+        location,
+    ))
+}
+
+/// The `ErrorKind` of the failure of `binary`, which is a division by zero if
+/// it is a division or remainder whose divisor `right` is zero.
+fn error_kind(binary: &ExprBinary, right: &TokenStream) -> TokenStream {
+    match binary.op {
+        syn::BinOp::Div(_) | syn::BinOp::Rem(_) => quote! {
+            if #right == 0 {
+                ::arithmetic_mode::ErrorKind::DivisionByZero
+            } else {
+                ::arithmetic_mode::ErrorKind::Overflow
+            }
+        },
+        _ => quote! { ::arithmetic_mode::ErrorKind::Overflow },
+    }
+}
+
+fn checked_op(op: syn::BinOp) -> syn::Result<Option<TokenStream>> {
+    let span = op.span();
+    Ok(match op {
+        syn::BinOp::Add(_) => Some(quote_spanned! { span=> checked_add }),
+        syn::BinOp::Sub(_) => Some(quote_spanned! { span=> checked_sub }),
+        syn::BinOp::Mul(_) => Some(quote_spanned! { span=> checked_mul }),
+        syn::BinOp::Div(_) => Some(quote_spanned! { span=> checked_div }),
+        syn::BinOp::Rem(_) => Some(quote_spanned! { span=> checked_rem }),
+        syn::BinOp::Shl(_) => Some(quote_spanned! { span=> checked_shl }),
+        syn::BinOp::Shr(_) => Some(quote_spanned! { span=> checked_shr }),
+        syn::BinOp::And(_)
+        | syn::BinOp::Or(_)
+        | syn::BinOp::BitXor(_)
+        | syn::BinOp::BitAnd(_)
+        | syn::BinOp::BitOr(_)
+        | syn::BinOp::Eq(_)
+        | syn::BinOp::Lt(_)
+        | syn::BinOp::Le(_)
+        | syn::BinOp::Ne(_)
+        | syn::BinOp::Ge(_)
+        | syn::BinOp::Gt(_) => None,
+        syn::BinOp::AddAssign(_)
+        | syn::BinOp::SubAssign(_)
+        | syn::BinOp::MulAssign(_)
+        | syn::BinOp::DivAssign(_)
+        | syn::BinOp::RemAssign(_)
+        | syn::BinOp::BitXorAssign(_)
+        | syn::BinOp::BitAndAssign(_)
+        | syn::BinOp::BitOrAssign(_)
+        | syn::BinOp::ShlAssign(_)
+        | syn::BinOp::ShrAssign(_) => return Err(Limitation::CompoundAssignment.error(op)),
+        _ => return Err(syn::Error::new_spanned(op, "Unknown operation")),
+    })
+}
+
+fn wrapping_op(op: syn::BinOp) -> syn::Result<Option<TokenStream>> {
+    let span = op.span();
+    Ok(match op {
+        syn::BinOp::Add(_) => Some(quote_spanned! { span=> wrapping_add }),
+        syn::BinOp::Sub(_) => Some(quote_spanned! { span=> wrapping_sub }),
+        syn::BinOp::Mul(_) => Some(quote_spanned! { span=> wrapping_mul }),
+        syn::BinOp::Div(_) => Some(quote_spanned! { span=> wrapping_div }),
+        syn::BinOp::Rem(_) => Some(quote_spanned! { span=> wrapping_rem }),
+        syn::BinOp::Shl(_) => Some(quote_spanned! { span=> wrapping_shl }),
+        syn::BinOp::Shr(_) => Some(quote_spanned! { span=> wrapping_shr }),
+        syn::BinOp::And(_)
+        | syn::BinOp::Or(_)
+        | syn::BinOp::BitXor(_)
+        | syn::BinOp::BitAnd(_)
+        | syn::BinOp::BitOr(_)
+        | syn::BinOp::Eq(_)
+        | syn::BinOp::Lt(_)
+        | syn::BinOp::Le(_)
+        | syn::BinOp::Ne(_)
+        | syn::BinOp::Ge(_)
+        | syn::BinOp::Gt(_) => None,
+        syn::BinOp::AddAssign(_)
+        | syn::BinOp::SubAssign(_)
+        | syn::BinOp::MulAssign(_)
+        | syn::BinOp::DivAssign(_)
+        | syn::BinOp::RemAssign(_)
+        | syn::BinOp::BitXorAssign(_)
+        | syn::BinOp::BitAndAssign(_)
+        | syn::BinOp::BitOrAssign(_)
+        | syn::BinOp::ShlAssign(_)
+        | syn::BinOp::ShrAssign(_) => return Err(Limitation::CompoundAssignment.error(op)),
+        _ => return Err(syn::Error::new_spanned(op, "Unknown operation")),
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,136 @@
+//! Propagation of integer types to unsuffixed literals, to work around the
+//! inference issue in <https://github.com/rust-lang/rust/issues/24124>, where
+//! calling e.g. `(2).wrapping_add(x)` fails because the type of the receiver is
+//! ambiguous.
+
+use crate::type_hint;
+use syn::{
+    BinOp, Expr, ExprBinary, ExprBlock, ExprCast, ExprGroup, ExprLit, ExprParen, ExprUnary, Ident,
+    Lit, LitInt, Type, TypePath,
+};
+
+pub(crate) const INTEGER_TYPES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+];
+
+/// Whether `ident` names one of the primitive integer types.
+pub(crate) fn is_integer_type(ident: &Ident) -> bool {
+    INTEGER_TYPES.iter().any(|ty| ident == ty)
+}
+
+/// Returns the name of `ty` if it is one of the primitive integer types.
+fn integer_type(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path
+            .get_ident()
+            .filter(|ident| is_integer_type(ident))
+            .map(ToString::to_string),
+        _ => None,
+    }
+}
+
+/// Finds the integer type of the expression from its suffixed literals (e.g.
+/// `2_i32`) and casts (e.g. `x as i32`), and applies it as the suffix to all of
+/// its unsuffixed literals. Nothing is changed if the expression has no such
+/// operands, or if they disagree on the type.
+pub(crate) fn propagate_literal_type(expr: &mut Expr) {
+    let mut types = Vec::new();
+    collect_types(expr, &mut types);
+    match &types[..] {
+        [ty, rest @ ..] if rest.iter().all(|t| t == ty) => apply_type(expr, ty),
+        _ => propagate_type_hints(expr),
+    }
+}
+
+/// Calls `f` on the operands of `expr` that share its type. The right-hand side
+/// of bit shifts is excluded since it can be of a different type, and the
+/// operand of casts is excluded since it is converted to the target type.
+fn for_each_operand(expr: &mut Expr, f: &mut impl FnMut(&mut Expr)) {
+    match expr {
+        Expr::Binary(ExprBinary {
+            left, op, right, ..
+        }) => {
+            f(left);
+            if !matches!(op, BinOp::Shl(_) | BinOp::Shr(_)) {
+                f(right);
+            }
+        }
+        Expr::Group(ExprGroup { expr, .. })
+        | Expr::Paren(ExprParen { expr, .. })
+        | Expr::Unary(ExprUnary { expr, .. }) => f(expr),
+        _ => {}
+    }
+}
+
+fn collect_types(expr: &mut Expr, types: &mut Vec<String>) {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) if !lit.suffix().is_empty() => types.push(lit.suffix().to_string()),
+        Expr::Cast(ExprCast { ty, .. }) => types.extend(integer_type(ty)),
+        Expr::Block(block) => {
+            types.extend(type_hint::parts_mut(block).and_then(|(_, ty)| integer_type(ty)));
+        }
+        _ => for_each_operand(expr, &mut |operand| collect_types(operand, types)),
+    }
+}
+
+/// Applies `ty` as the suffix of all unsuffixed literals in `expr`.
+pub(crate) fn apply_type(expr: &mut Expr, ty: &str) {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) if lit.suffix().is_empty() => *lit = LitInt::new(&format!("{lit}{ty}"), lit.span()),
+        Expr::Block(block) => propagate_type_hint(block),
+        _ => for_each_operand(expr, &mut |operand| apply_type(operand, ty)),
+    }
+}
+
+/// Applies `ty` to the operations in `expr` whose operands are all unsuffixed
+/// literals, like `5381 * 33` in `h * 33 + 5381 * 33`, whose type would
+/// otherwise be ambiguous. The other literals keep being inferred from the
+/// operands they are combined with.
+pub(crate) fn apply_default_type(expr: &mut Expr, ty: &str) {
+    match expr {
+        Expr::Binary(_) if is_untyped(expr) => apply_type(expr, ty),
+        _ => for_each_operand(expr, &mut |operand| apply_default_type(operand, ty)),
+    }
+}
+
+/// Whether `expr` only consists of unsuffixed literals, except for the
+/// right-hand side of bit shifts, which has its own type.
+fn is_untyped(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.suffix().is_empty(),
+        Expr::Binary(ExprBinary {
+            left,
+            op: BinOp::Shl(_) | BinOp::Shr(_),
+            ..
+        }) => is_untyped(left),
+        Expr::Binary(ExprBinary { left, right, .. }) => is_untyped(left) && is_untyped(right),
+        Expr::Group(ExprGroup { expr, .. })
+        | Expr::Paren(ExprParen { expr, .. })
+        | Expr::Unary(ExprUnary { expr, .. }) => is_untyped(expr),
+        _ => false,
+    }
+}
+
+fn propagate_type_hints(expr: &mut Expr) {
+    match expr {
+        Expr::Block(block) => propagate_type_hint(block),
+        _ => for_each_operand(expr, &mut propagate_type_hints),
+    }
+}
+
+/// Propagates the type inside a type hint, where the hinted type takes
+/// precedence over the type of the surrounding expression.
+fn propagate_type_hint(block: &mut ExprBlock) {
+    if let Some((expr, hint)) = type_hint::parts_mut(block) {
+        match integer_type(hint) {
+            Some(hint) => apply_type(expr, &hint),
+            None => propagate_literal_type(expr),
+        }
+    }
+}
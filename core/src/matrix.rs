@@ -0,0 +1,149 @@
+//! The `overflow_matrix!` macro, which generates exhaustive tests of the modes
+//! for all the pairs of operands of the small integer types, against the exact
+//! results computed with `i32`.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::{Expr, Ident, Token};
+
+/// The types whose results all fit in the `i32` of the exact results.
+const TYPES: [&str; 4] = ["u8", "i8", "u16", "i16"];
+
+/// A type to test, like `u8`, with the range of its operands, like
+/// `i16 in -300..=300`, which is all of its values by default.
+struct MatrixType {
+    ty: Ident,
+    range: Option<Expr>,
+}
+
+impl Parse for MatrixType {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ty: Ident = input.parse()?;
+        if !TYPES.iter().any(|name| ty == name) {
+            return Err(syn::Error::new_spanned(
+                &ty,
+                format!(
+                    "Unsupported type `{ty}`, expected one of {}",
+                    TYPES.join(", ")
+                ),
+            ));
+        }
+        let range = if input.parse::<Option<Token![in]>>()?.is_some() {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(MatrixType { ty, range })
+    }
+}
+
+/// Expands `overflow_matrix! { item }`.
+pub fn overflow_matrix_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let types = Punctuated::<MatrixType, Token![,]>::parse_terminated.parse2(item)?;
+    Ok(types.iter().map(matrix_test).collect())
+}
+
+/// The test of all the operations of the modes for all the pairs of operands
+/// of `ty` in its range.
+fn matrix_test(MatrixType { ty, range }: &MatrixType) -> TokenStream {
+    let name = format_ident!("overflow_matrix_{}", ty);
+    let range = match range {
+        Some(range) => quote! { #range },
+        None => quote! { #ty::MIN..=#ty::MAX },
+    };
+    let operations = [
+        (quote! { a + b }, quote! { checked_add }),
+        (quote! { a - b }, quote! { checked_sub }),
+        (quote! { a * b }, quote! { checked_mul }),
+        (quote! { a / b }, quote! { checked_div }),
+        (quote! { a % b }, quote! { checked_rem }),
+    ];
+    let checks = operations.iter().map(|(expr, exact)| {
+        let case = format!("`{expr}` with (a, b) =");
+        let quotient_fits = if exact.to_string() == "checked_rem" {
+            // Like in the integer methods, `MIN % -1` overflows since `MIN / -1`
+            // does, although the remainder is 0
+            quote! {
+                let fits = fits.filter(|_| {
+                    (a as i32)
+                        .checked_div(b as i32)
+                        .is_some_and(|quotient| #ty::try_from(quotient).is_ok())
+                });
+            }
+        } else {
+            TokenStream::new()
+        };
+        quote! {
+            // Not computed with the macros, which are what is tested
+            let exact: ::core::option::Option<i32> = (a as i32).#exact(b as i32);
+            let fits = exact.and_then(|exact| #ty::try_from(exact).ok());
+            #quotient_fits
+            ::core::assert_eq!(
+                fits,
+                ::arithmetic_mode::checked! { #expr },
+                "checked! differs from the exact result of {} {:?}",
+                #case,
+                (a, b),
+            );
+            match ::arithmetic_mode::try_checked! { #expr } {
+                ::core::result::Result::Ok(value) => ::core::assert_eq!(
+                    fits,
+                    ::core::option::Option::Some(value),
+                    "try_checked! differs from the exact result of {} {:?}",
+                    #case,
+                    (a, b),
+                ),
+                ::core::result::Result::Err(error) => ::core::assert_eq!(
+                    (fits, b == 0),
+                    (
+                        ::core::option::Option::None,
+                        error.kind() == ::arithmetic_mode::ErrorKind::DivisionByZero,
+                    ),
+                    "try_checked! fails with {} for {} {:?}",
+                    error,
+                    #case,
+                    (a, b),
+                ),
+            }
+            // The other modes panic when dividing by zero
+            if let ::core::option::Option::Some(exact) = exact {
+                ::core::assert_eq!(
+                    exact as #ty,
+                    ::arithmetic_mode::wrapping! { #expr },
+                    "wrapping! differs from the truncated exact result of {} {:?}",
+                    #case,
+                    (a, b),
+                );
+                ::core::assert_eq!(
+                    exact.clamp(#ty::MIN as i32, #ty::MAX as i32) as #ty,
+                    ::arithmetic_mode::saturating! { #expr },
+                    "saturating! differs from the clamped exact result of {} {:?}",
+                    #case,
+                    (a, b),
+                );
+            }
+            if let ::core::option::Option::Some(value) = fits {
+                ::core::assert_eq!(
+                    value,
+                    ::arithmetic_mode::panicking! { #expr },
+                    "panicking! differs from the exact result of {} {:?}",
+                    #case,
+                    (a, b),
+                );
+            }
+        }
+    });
+    quote! {
+        #[test]
+        fn #name() {
+            for a in #range {
+                for b in #range {
+                    let (a, b): (#ty, #ty) = (a, b);
+                    #(#checks)*
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! The `mixed!` macro, which mixes the modes in one expression, either with
+//! Zig-style operators, where `+%` wraps and `+|` saturates, or with the mode
+//! in brackets after the operator, like `a +[checked] b`. The other operators
+//! use the mode given in brackets at the start of the input, like
+//! `mixed! { [wrapping] a * b +[saturating] c }`, or panic by default.
+//!
+//! Since neither is valid Rust syntax, they are rewritten before parsing into
+//! the plain operator followed by a marker attribute, like
+//! `a + #[__arithmetic_mode(wrapping)] b`. Rust parses the attribute onto the
+//! first operand after the operator, i.e. the leftmost operand of the right
+//! side of the operation, where [`take_marker`] finds it.
+
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, TokenStream, TokenTree};
+use quote::{quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Expr, ExprBinary, ExprCast, ExprField, ExprGroup, ExprIndex, ExprLit,
+    ExprMethodCall, ExprParen, ExprPath, ExprReference, ExprTry, ExprUnary,
+};
+
+use crate::attribute::{expr_attrs_mut, is_arithmetic, Mode};
+use crate::{input, Options};
+
+const MARKER: &str = "__arithmetic_mode";
+
+/// Expands `mixed! { item }`.
+pub fn mixed_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (default, expr, options) = parse(item)?;
+    transform(default, expr, options)
+}
+
+/// Parses the input of `mixed!` into the mode of the operators without
+/// annotation, and the expression with the markers of the other operators.
+pub(crate) fn parse(item: TokenStream) -> syn::Result<(Mode, Expr, Options)> {
+    let (default, item) = split_default_mode(item)?;
+    let (expr, options) = input::parse_expr(mark_operators(item))?;
+    Ok((default, expr, options))
+}
+
+/// Transforms each operation of the parsed `expr` into its own mode.
+pub(crate) fn transform(default: Mode, expr: Expr, options: Options) -> syn::Result<TokenStream> {
+    transform_by(expr, default, options, &|binary| {
+        Ok(take_marker(&mut binary.right)?.unwrap_or(default))
+    })
+}
+
+/// The mode of an arithmetic operation, which may remove the marker that
+/// determines it.
+pub(crate) type ModeOf<'a> = &'a dyn Fn(&mut ExprBinary) -> syn::Result<Mode>;
+
+/// Transforms each arithmetic operation of `expr` into the mode that
+/// `mode_of` returns for it, like the operators of `#[arithmetic_mode]` given
+/// like `add = "wrapping"`. An expression without arithmetic at its root uses
+/// the `default` mode.
+pub(crate) fn transform_by(
+    expr: Expr,
+    default: Mode,
+    options: Options,
+    mode_of: ModeOf,
+) -> syn::Result<TokenStream> {
+    let mode = root_mode(&expr, default, mode_of)?;
+    let expr = isolate(expr, mode, mode_of, options)?;
+    mode.transform(expr, options)
+}
+
+/// Splits the mode of the operators without annotation, like `[wrapping]`,
+/// from the start of `item`.
+pub(crate) fn split_default_mode(item: TokenStream) -> syn::Result<(Mode, TokenStream)> {
+    let (mode, item) = split_mode(item)?;
+    Ok((mode.map_or(Mode::Panicking, |(mode, _)| mode), item))
+}
+
+/// Like [`split_default_mode`], without a default, also returning the
+/// annotation to report errors at.
+pub(crate) fn split_mode(item: TokenStream) -> syn::Result<(Option<(Mode, Ident)>, TokenStream)> {
+    let mut tokens = item.clone().into_iter();
+    if let Some(TokenTree::Group(group)) = tokens.next() {
+        if let Some(ident) = mode_annotation(&group) {
+            return Ok((Some((Mode::from_ident(&ident)?, ident)), tokens.collect()));
+        }
+    }
+    Ok((None, item))
+}
+
+/// The mode of the bracketed annotation `group`, like `[checked]`.
+fn mode_annotation(group: &Group) -> Option<Ident> {
+    if group.delimiter() != Delimiter::Bracket {
+        return None;
+    }
+    let mut tokens = group.stream().into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(ident)), None) if Mode::from_ident(&ident).is_ok() => Some(ident),
+        _ => None,
+    }
+}
+
+/// Rewrites the extended operators `+%`, `-%`, `*%`, `+|`, `-|` and `*|`, and
+/// the operators followed by a mode in brackets, into the plain operator
+/// followed by a marker attribute for its mode.
+fn mark_operators(tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut marked = TokenStream::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            TokenTree::Group(group)
+                if matches!(group.delimiter(), Delimiter::Parenthesis | Delimiter::None) =>
+            {
+                let mut new_group = Group::new(group.delimiter(), mark_operators(group.stream()));
+                new_group.set_span(group.span());
+                marked.extend([TokenTree::Group(new_group)]);
+            }
+            TokenTree::Punct(op)
+                if matches!(op.as_char(), '+' | '-' | '*' | '/' | '%' | '<' | '>') =>
+            {
+                let zig_mode = match tokens.get(i + 1) {
+                    Some(TokenTree::Punct(suffix))
+                        if matches!(op.as_char(), '+' | '-' | '*')
+                            && op.spacing() == Spacing::Joint =>
+                    {
+                        match suffix.as_char() {
+                            '%' => Some(Ident::new("wrapping", suffix.span())),
+                            '|' => Some(Ident::new("saturating", suffix.span())),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                // An annotation is followed by the operand, unlike an array
+                let annotation = match tokens.get(i + 1) {
+                    Some(TokenTree::Group(group)) if i + 2 < tokens.len() => mode_annotation(group),
+                    _ => None,
+                };
+                match zig_mode.or(annotation) {
+                    Some(mode) => {
+                        let mut plain = Punct::new(op.as_char(), Spacing::Alone);
+                        plain.set_span(op.span());
+                        let marker = Ident::new(MARKER, mode.span());
+                        marked.extend(quote_spanned! { mode.span()=> #plain #[#marker(#mode)] });
+                        i += 1;
+                    }
+                    None => marked.extend([TokenTree::Punct(op.clone())]),
+                }
+            }
+            token => marked.extend([token.clone()]),
+        }
+        i += 1;
+    }
+    marked
+}
+
+/// The mode of the outermost operation of `expr`.
+fn root_mode(expr: &Expr, default: Mode, mode_of: ModeOf) -> syn::Result<Mode> {
+    match expr {
+        Expr::Binary(binary) if is_arithmetic(&binary.op) => mode_of(&mut binary.clone()),
+        Expr::Group(ExprGroup { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) => {
+            root_mode(expr, default, mode_of)
+        }
+        _ => Ok(default),
+    }
+}
+
+/// Replaces the operations in `expr` whose mode differs from the `mode` of the
+/// enclosing operation with the opaque result of transforming them into their
+/// own mode, so that `expr` can be transformed into `mode` as a whole. The
+/// mode of each operation is the one that `mode_of` returns.
+fn isolate(expr: Expr, mode: Mode, mode_of: ModeOf, options: Options) -> syn::Result<Expr> {
+    Ok(match expr {
+        Expr::Binary(mut binary) => {
+            let own = if is_arithmetic(&binary.op) {
+                mode_of(&mut binary)?
+            } else {
+                mode
+            };
+            // The operands in the other modes have to be integers
+            if own != mode && own.is_fallible() {
+                return Err(syn::Error::new(
+                    binary.op.span(),
+                    format!(
+                        "A {} operation cannot be an operand of a {} operation, since it \
+                        evaluates to an `Option` or a `Result`",
+                        own.name(),
+                        mode.name()
+                    ),
+                ));
+            }
+            let ExprBinary {
+                attrs,
+                left,
+                op,
+                right,
+            } = binary;
+            let binary = Expr::Binary(ExprBinary {
+                attrs,
+                left: Box::new(isolate(*left, own, mode_of, options)?),
+                op,
+                right: Box::new(isolate(*right, own, mode_of, options)?),
+            });
+            if own == mode {
+                binary
+            } else {
+                Expr::Verbatim(own.transform(binary, options)?)
+            }
+        }
+        Expr::Group(ExprGroup {
+            attrs,
+            group_token,
+            expr,
+        }) => Expr::Group(ExprGroup {
+            attrs,
+            group_token,
+            expr: Box::new(isolate(*expr, mode, mode_of, options)?),
+        }),
+        Expr::Paren(ExprParen {
+            attrs,
+            paren_token,
+            expr,
+        }) => Expr::Paren(ExprParen {
+            attrs,
+            paren_token,
+            expr: Box::new(isolate(*expr, mode, mode_of, options)?),
+        }),
+        Expr::Unary(ExprUnary { attrs, op, expr }) => Expr::Unary(ExprUnary {
+            attrs,
+            op,
+            expr: Box::new(isolate(*expr, mode, mode_of, options)?),
+        }),
+        expr if has_marker(expr.to_token_stream()) => {
+            return Err(syn::Error::new_spanned(
+                expr,
+                "Operators like `+%` and `+[checked]` are only supported in the arithmetic of \
+                `mixed!`, not inside other expressions",
+            ))
+        }
+        expr => expr,
+    })
+}
+
+/// Removes the marker of an extended or annotated operator from the leftmost
+/// operand of `expr`, returning the mode of the operator.
+fn take_marker(expr: &mut Expr) -> syn::Result<Option<Mode>> {
+    if let Some(attrs) = attrs_mut(expr) {
+        if let Some(position) = attrs.iter().position(|attr| attr.path().is_ident(MARKER)) {
+            let mode: Ident = attrs.remove(position).parse_args()?;
+            return Mode::from_ident(&mode).map(Some);
+        }
+    }
+    match expr {
+        Expr::Binary(ExprBinary { left: expr, .. })
+        | Expr::Cast(ExprCast { expr, .. })
+        | Expr::Field(ExprField { base: expr, .. })
+        | Expr::Index(ExprIndex { expr, .. })
+        | Expr::MethodCall(ExprMethodCall { receiver: expr, .. })
+        | Expr::Try(ExprTry { expr, .. }) => take_marker(expr),
+        _ => Ok(None),
+    }
+}
+
+/// Returns the attributes of the expressions that the markers can be parsed
+/// onto.
+fn attrs_mut(expr: &mut Expr) -> Option<&mut Vec<Attribute>> {
+    match expr {
+        Expr::Cast(ExprCast { attrs, .. })
+        | Expr::Field(ExprField { attrs, .. })
+        | Expr::Group(ExprGroup { attrs, .. })
+        | Expr::Index(ExprIndex { attrs, .. })
+        | Expr::Lit(ExprLit { attrs, .. })
+        | Expr::Path(ExprPath { attrs, .. })
+        | Expr::Reference(ExprReference { attrs, .. })
+        | Expr::Try(ExprTry { attrs, .. })
+        | Expr::Unary(ExprUnary { attrs, .. }) => Some(attrs),
+        _ => expr_attrs_mut(expr),
+    }
+}
+
+/// Whether `tokens` contain a marker that was not removed, because its
+/// operator is not part of the transformed arithmetic.
+fn has_marker(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == MARKER,
+        TokenTree::Group(group) => has_marker(group.stream()),
+        _ => false,
+    })
+}
@@ -0,0 +1,296 @@
+//! The `modes!` macro, which gives each integer type its own mode in one
+//! expression, like `modes! { u8 = saturating, usize = checked; expr }` for
+//! pixel math next to buffer offsets. The types of the operands are not known
+//! to the macro, so it declares the mode of each type for the trait
+//! `ModeOf`, and each operation calls a function that is dispatched to the
+//! mode of its type when the expression is type checked.
+//!
+//! The operations evaluate to a `Result` like in `try_checked!`, whose error
+//! only occurs in the fallible modes, and the whole expression is then
+//! converted into what the fallible mode given, if any, evaluates to.
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::parse::{ParseStream, Parser};
+use syn::spanned::Spanned;
+use syn::{Expr, ExprBinary, ExprCast, Token, Type, UnOp};
+
+use crate::attribute::Mode;
+use crate::diagnostic::Limitation;
+use crate::engine::{self, Strategy};
+use crate::literal_type::{is_integer_type, INTEGER_TYPES};
+use crate::{
+    cold_branch, deref_operand, input, operand_names, operation_error, operation_panic, Options,
+    TryChecked,
+};
+
+/// The name of the type implementing `ModeOf` for the types of the
+/// expression.
+const MODES: &str = "__ArithmeticModes";
+
+/// Expands `modes! { item }`.
+pub fn modes_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (modes, item) = split_modes(item)?;
+    let (expr, options) = input::parse_expr(item)?;
+    per_type_expr(&modes, &expr, options)
+}
+
+/// Transforms `expr` into the modes of its types, with `options`.
+pub(crate) fn per_type_expr(
+    modes: &TypeModes,
+    expr: &Expr,
+    options: Options,
+) -> syn::Result<TokenStream> {
+    if options.is_const {
+        return Err(Limitation::ConstModes.error(expr));
+    }
+    let fallible = modes.fallible()?;
+    let name = Ident::new(MODES, Span::call_site());
+    let impls = INTEGER_TYPES.iter().map(|ty| {
+        let mode = modes.mode(ty);
+        let ty = Ident::new(ty, Span::call_site());
+        let mode = Ident::new(
+            match mode {
+                Mode::Wrapping => "Wrapping",
+                Mode::Saturating => "Saturating",
+                Mode::Checked | Mode::TryChecked => "Fallible",
+                Mode::Panicking | Mode::Verified => "Panicking",
+            },
+            Span::call_site(),
+        );
+        quote! {
+            impl ::arithmetic_mode::__private::modes::ModeOf<#ty> for #name {
+                type Mode = ::arithmetic_mode::__private::modes::#mode;
+            }
+        }
+    });
+    let value = engine::transform_expr(
+        expr,
+        &PerType {
+            try_checked: TryChecked { options },
+        },
+    )?;
+    let value = match fallible {
+        Some(Mode::TryChecked) => value,
+        Some(_) => quote! {
+            match #value {
+                ::core::result::Result::Ok(v) => ::core::option::Option::Some(v),
+                ::core::result::Result::Err(_) => ::core::option::Option::None,
+            }
+        },
+        // None of the operations fail
+        None => quote! {
+            match #value {
+                ::core::result::Result::Ok(v) => v,
+                ::core::result::Result::Err(_) => ::core::unreachable!(),
+            }
+        },
+    };
+    Ok(quote! {
+        {
+            enum #name {}
+            #(#impls)*
+            #value
+        }
+    })
+}
+
+/// The modes of the integer types given to `modes!`.
+pub(crate) struct TypeModes {
+    /// The types given with their mode.
+    pub(crate) types: Vec<(Ident, Mode)>,
+    /// The mode of the other types, given as `_ = mode`, which panic by
+    /// default.
+    pub(crate) other: Mode,
+}
+
+impl TypeModes {
+    /// The mode of the integer type `ty`.
+    fn mode(&self, ty: &str) -> Mode {
+        self.types
+            .iter()
+            .find(|(name, _)| name == ty)
+            .map_or(self.other, |(_, mode)| *mode)
+    }
+
+    /// The fallible mode among the modes, which determines what the
+    /// expression evaluates to, if any. `checked` and `try_checked` cannot
+    /// both be given, since the expression would evaluate to both an `Option`
+    /// and a `Result`.
+    fn fallible(&self) -> syn::Result<Option<Mode>> {
+        let mut fallible: Option<Mode> = None;
+        let all = self
+            .types
+            .iter()
+            .map(|(ty, mode)| (*mode, ty.span()))
+            .chain([(self.other, Span::call_site())]);
+        for (mode, span) in all.filter(|(mode, _)| mode.is_fallible()) {
+            match fallible {
+                Some(other) if other != mode => {
+                    return Err(syn::Error::new(
+                        span,
+                        "checked and try_checked modes cannot both be given, since the \
+                        expression evaluates to either an `Option` or a `Result`",
+                    ))
+                }
+                _ => fallible = Some(mode),
+            }
+        }
+        Ok(fallible)
+    }
+}
+
+/// Splits the modes of the types stated before the rest of the input, like
+/// `u8 = saturating, _ = checked;`, from the rest of `item`.
+fn split_modes(item: TokenStream) -> syn::Result<(TypeModes, TokenStream)> {
+    (|input: ParseStream| {
+        let mut modes: Vec<(Ident, Mode)> = Vec::new();
+        let mut default = None;
+        loop {
+            // `_` is the mode of the other types
+            let ty = if input.peek(Token![_]) {
+                input.parse::<Token![_]>()?;
+                None
+            } else {
+                let ty = input.fork().parse::<Ident>().ok().filter(is_integer_type);
+                if ty.is_none() {
+                    return Err(input.error(
+                        "modes! starts with the modes of the integer types, like \
+                        `modes! { u8 = saturating, usize = checked; expr }`, and `_` for the \
+                        other types",
+                    ));
+                }
+                input.parse::<Ident>()?;
+                ty
+            };
+            input.parse::<Token![=]>()?;
+            let name: Ident = input.parse()?;
+            let mode = Mode::from_ident(&name)?;
+            if mode == Mode::Verified {
+                return Err(syn::Error::new_spanned(
+                    &name,
+                    "verified mode is not supported in modes!",
+                ));
+            }
+            match ty {
+                Some(ty) if modes.iter().any(|(other, _)| *other == ty) => {
+                    return Err(syn::Error::new_spanned(
+                        &ty,
+                        format!("The mode of `{ty}` is given more than once"),
+                    ))
+                }
+                Some(ty) => modes.push((ty, mode)),
+                None if default.is_some() => {
+                    return Err(syn::Error::new_spanned(
+                        &name,
+                        "The mode of the other types is given more than once",
+                    ))
+                }
+                None => default = Some(mode),
+            }
+            if input.parse::<Option<Token![;]>>()?.is_some() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+            if input.parse::<Option<Token![;]>>()?.is_some() {
+                break;
+            }
+        }
+        let modes = TypeModes {
+            types: modes,
+            other: default.unwrap_or(Mode::Panicking),
+        };
+        Ok((modes, input.parse()?))
+    })
+    .parse2(item)
+}
+
+/// The operations of `modes!`, which evaluate to a `Result` like in
+/// `try_checked!`.
+struct PerType {
+    try_checked: TryChecked,
+}
+
+impl Strategy for PerType {
+    fn is_fallible(&self) -> bool {
+        true
+    }
+
+    // The operations in casts are transformed too, since the casts are what
+    // mixes the types, like `offset + (pixel * gain) as usize`
+    fn operand(&self, expr: &Expr) -> TokenStream {
+        match expr {
+            Expr::Cast(ExprCast { expr, ty, .. }) => match engine::transform_expr(expr, self) {
+                Ok(operand) => quote! {
+                    match #operand {
+                        ::core::result::Result::Ok(v) => ::core::result::Result::Ok(v as #ty),
+                        ::core::result::Result::Err(e) => ::core::result::Result::Err(e),
+                    }
+                },
+                Err(e) => e.into_compile_error(),
+            },
+            _ => self.try_checked.operand(expr),
+        }
+    }
+
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        new_left: TokenStream,
+        new_right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        let op = binary.op;
+        let span = op.span();
+        let func = match op {
+            syn::BinOp::Add(_) => quote_spanned! { span=> add },
+            syn::BinOp::Sub(_) => quote_spanned! { span=> sub },
+            syn::BinOp::Mul(_) => quote_spanned! { span=> mul },
+            syn::BinOp::Div(_) => quote_spanned! { span=> div },
+            syn::BinOp::Rem(_) => quote_spanned! { span=> rem },
+            syn::BinOp::Shl(_) => quote_spanned! { span=> shl },
+            syn::BinOp::Shr(_) => quote_spanned! { span=> shr },
+            // The other operations cannot fail, and are checked like the
+            // compound assignments in `try_checked!`
+            _ => return self.try_checked.binary(binary, new_left, new_right),
+        };
+        let (l, r) = operand_names(span);
+        let name = Ident::new(MODES, Span::call_site());
+        let options = self.try_checked.options;
+        let error = operation_error(binary, quote! { #l }, quote! { #r }, options);
+        let error = cold_branch(quote! { ::core::result::Result::Err(#error) });
+        let panic = cold_branch(operation_panic(binary, quote! { #l }, quote! { #r }, None));
+        let deref_r = deref_operand(options.may_be_reference(&binary.right), quote! { #r });
+        Ok(quote_spanned! { span=>
+            match #new_left {
+                ::core::result::Result::Ok(#l) => match #new_right {
+                    ::core::result::Result::Ok(#r) => {
+                        let #r = #deref_r;
+                        match ::arithmetic_mode::__private::modes::#func::<#name, _>(#l, #r) {
+                            ::core::result::Result::Ok(v) => ::core::result::Result::Ok(v),
+                            ::core::result::Result::Err(
+                                ::arithmetic_mode::__private::modes::Failure::Fails,
+                            ) => #error,
+                            ::core::result::Result::Err(
+                                ::arithmetic_mode::__private::modes::Failure::Panics,
+                            ) => #panic,
+                        }
+                    }
+                    ::core::result::Result::Err(e) => ::core::result::Result::Err(e),
+                },
+                ::core::result::Result::Err(e) => ::core::result::Result::Err(e),
+            }
+        })
+    }
+
+    fn unary(&self, op: &UnOp, operand: TokenStream) -> TokenStream {
+        self.try_checked.unary(op, operand)
+    }
+
+    fn type_hint(&self, operand: TokenStream, ty: &Type) -> TokenStream {
+        self.try_checked.type_hint(operand, ty)
+    }
+
+    fn other(&self, expr: &Expr) -> syn::Result<TokenStream> {
+        self.try_checked.other(expr)
+    }
+}
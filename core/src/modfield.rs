@@ -0,0 +1,113 @@
+//! The `modfield!` macro, which computes an expression modulo a constant, like
+//! `modfield!(P; a * b + c)` for field arithmetic. The modulus is reduced with
+//! its Barrett factor, which is computed at compile time, instead of dividing
+//! after each operation like `%` does.
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::parse::{ParseStream, Parser};
+use syn::spanned::Spanned;
+use syn::{Expr, ExprBinary, ExprLit, Lit, LitInt, Token, UnOp};
+
+use crate::engine::{self, Strategy};
+use crate::{const_block, operand_names};
+
+/// Expands `modfield! { item }`.
+pub fn modfield_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (modulus, expr) = (|input: ParseStream| {
+        let modulus: Expr = input.parse()?;
+        input.parse::<Token![;]>()?;
+        let expr: Expr = input.parse()?;
+        Ok((modulus, expr))
+    })
+    .parse2(item)?;
+    // The result has the type of the modulus, which is `u64` if unsuffixed
+    let modulus = match modulus {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) if lit.suffix().is_empty() => {
+            let lit = LitInt::new(&format!("{}_u64", lit.base10_digits()), lit.span());
+            quote! { #lit }
+        }
+        modulus => quote! { #modulus },
+    };
+    let name = Ident::new("modulus", Span::mixed_site());
+    let new = const_block(quote! {
+        ::arithmetic_mode::__private::Modulus::new(#modulus as u64)
+    });
+    let residue = engine::transform_expr(&expr, &Modfield { modulus: &name })?;
+    Ok(quote! {
+        {
+            let #name = #new;
+            ::arithmetic_mode::__private::Modulus::narrow(#modulus, #residue)
+        }
+    })
+}
+
+/// The operations of `modfield!` on the residues, with the `Modulus` bound to
+/// `modulus`.
+struct Modfield<'a> {
+    modulus: &'a Ident,
+}
+
+impl Strategy for Modfield<'_> {
+    // The negation is applied to the residue, like in the fallible modes
+    fn is_fallible(&self) -> bool {
+        true
+    }
+
+    fn operand(&self, expr: &Expr) -> TokenStream {
+        let modulus = self.modulus;
+        let expr = match expr {
+            // The operands can be as wide as `u128`
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(lit), ..
+            }) if lit.suffix().is_empty() => {
+                let lit = LitInt::new(&format!("{}_u128", lit.base10_digits()), lit.span());
+                quote! { #lit }
+            }
+            expr => quote! { #expr },
+        };
+        quote! { #modulus.reduce(#expr) }
+    }
+
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        left: TokenStream,
+        right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        let modulus = self.modulus;
+        let op = binary.op;
+        let span = op.span();
+        let (l, r) = operand_names(span);
+        let func = match op {
+            syn::BinOp::Add(_) => quote_spanned! { span=> add },
+            syn::BinOp::Sub(_) => quote_spanned! { span=> sub },
+            syn::BinOp::Mul(_) => quote_spanned! { span=> mul },
+            syn::BinOp::Div(_) => quote_spanned! { span=> div },
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    op,
+                    "modfield! only supports `+`, `-`, `*` and `/`",
+                ))
+            }
+        };
+        Ok(quote_spanned! { span=>
+            {
+                let #l = #left;
+                let #r = #right;
+                #modulus.#func(#l, #r)
+            }
+        })
+    }
+
+    fn unary(&self, op: &UnOp, operand: TokenStream) -> TokenStream {
+        let modulus = self.modulus;
+        match op {
+            UnOp::Neg(_) => quote_spanned! { op.span()=> #modulus.neg(#operand) },
+            _ => syn::Error::new_spanned(op, "modfield! only supports the negation `-`")
+                .into_compile_error(),
+        }
+    }
+}
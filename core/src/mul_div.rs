@@ -0,0 +1,154 @@
+//! The `mul_div!` macro, which computes `a * b / c` in the mode given in
+//! brackets, like `mul_div! { [checked] amount, fee_bps, 10_000 }`, without
+//! overflowing on the product, and the `percent_of!` and `bps_of!` macros,
+//! which compute percentages and basis points of a value the same way, and the
+//! `lerp!` macro, which interpolates `a + (b - a) * t / t_max` like them.
+//!
+//! The product and the quotient are computed in a wider integer type by the
+//! functions of `arithmetic-mode`, and only the quotient is narrowed in the
+//! mode.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{Expr, ExprAssign, Token};
+
+use crate::attribute::Mode;
+use crate::mixed;
+
+/// The names of the roundings, which are the variants of
+/// `arithmetic_mode::__private::Rounding`.
+const ROUNDINGS: [(&str, &str); 5] = [
+    ("down", "Down"),
+    ("up", "Up"),
+    ("floor", "Floor"),
+    ("ceil", "Ceil"),
+    ("nearest", "Nearest"),
+];
+
+/// Expands `mul_div! { item }`.
+pub fn mul_div_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (mode, operands, rounding) = parse(item)?;
+    let [Some(a), Some(b), Some(c), None] = [0, 1, 2, 3].map(|i| operands.get(i)) else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "mul_div! takes three operands, like `mul_div! { a, b, c }` for `a * b / c`",
+        ));
+    };
+    Ok(mul_div(mode, a, b, quote! { #c }, rounding))
+}
+
+/// Expands `percent_of! { item }`.
+pub fn percent_of_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    fraction_of("percent_of", item, quote! { 100 })
+}
+
+/// Expands `bps_of! { item }`.
+pub fn bps_of_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    fraction_of("bps_of", item, quote! { 10_000 })
+}
+
+/// Expands `name! { item }`, which computes the fraction of a value with the
+/// given number of parts of `whole`, like `x * p / 100`.
+fn fraction_of(name: &str, item: TokenStream, whole: TokenStream) -> syn::Result<TokenStream> {
+    let (mode, operands, rounding) = parse(item)?;
+    let [Some(x), Some(parts), None] = [0, 1, 2].map(|i| operands.get(i)) else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            format!("{name}! takes two operands, like `{name}! {{ amount, rate }}`"),
+        ));
+    };
+    Ok(mul_div(mode, x, parts, whole, rounding))
+}
+
+/// Expands `lerp! { item }`.
+pub fn lerp_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (mode, operands, rounding) = parse(item)?;
+    let [Some(a), Some(b), Some(t), Some(t_max), None] = [0, 1, 2, 3, 4].map(|i| operands.get(i))
+    else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "lerp! takes four operands, like `lerp! { a, b, t, t_max }` for \
+             `a + (b - a) * t / t_max`",
+        ));
+    };
+    let function = match mode {
+        Mode::Panicking | Mode::Verified => quote! { lerp_panicking },
+        Mode::Wrapping => quote! { lerp_wrapping },
+        Mode::Saturating => quote! { lerp_saturating },
+        Mode::Checked => quote! { lerp_checked },
+        Mode::TryChecked => quote! { lerp_try_checked },
+    };
+    Ok(quote! {
+        ::arithmetic_mode::__private::#function(
+            #a,
+            #b,
+            #t,
+            #t_max,
+            ::arithmetic_mode::__private::Rounding::#rounding,
+        )
+    })
+}
+
+/// Parses the mode, the operands and the rounding of `item`, like
+/// `[checked] a, b, c, rounding = ceil`.
+fn parse(item: TokenStream) -> syn::Result<(Mode, Vec<Expr>, TokenStream)> {
+    let (mode, item) = mixed::split_default_mode(item)?;
+    let mut operands: Vec<Expr> = Punctuated::<Expr, Token![,]>::parse_terminated
+        .parse2(item)?
+        .into_iter()
+        .collect();
+    let rounding = match operands.last() {
+        Some(Expr::Assign(assign)) => {
+            let rounding = rounding(assign)?;
+            operands.pop();
+            rounding
+        }
+        _ => quote! { Down },
+    };
+    Ok((mode, operands, rounding))
+}
+
+/// The call of the function of `mode` computing `a * b / c`.
+fn mul_div(mode: Mode, a: &Expr, b: &Expr, c: TokenStream, rounding: TokenStream) -> TokenStream {
+    let function = match mode {
+        Mode::Panicking | Mode::Verified => quote! { mul_div_panicking },
+        Mode::Wrapping => quote! { mul_div_wrapping },
+        Mode::Saturating => quote! { mul_div_saturating },
+        Mode::Checked => quote! { mul_div_checked },
+        Mode::TryChecked => quote! { mul_div_try_checked },
+    };
+    quote! {
+        ::arithmetic_mode::__private::#function(
+            #a,
+            #b,
+            #c,
+            ::arithmetic_mode::__private::Rounding::#rounding,
+        )
+    }
+}
+
+/// The variant of the rounding of `rounding = name`.
+pub(crate) fn rounding(assign: &ExprAssign) -> syn::Result<TokenStream> {
+    let (Expr::Path(left), Expr::Path(right)) = (&*assign.left, &*assign.right) else {
+        return Err(syn::Error::new_spanned(assign, "Expected `rounding = ...`"));
+    };
+    if !left.path.is_ident("rounding") {
+        return Err(syn::Error::new_spanned(left, "Expected `rounding = ...`"));
+    }
+    let variant = ROUNDINGS
+        .iter()
+        .find(|(name, _)| right.path.is_ident(name))
+        .map(|(_, variant)| variant);
+    match variant {
+        Some(variant) => Ok(format_ident!("{}", variant).into_token_stream()),
+        None => Err(syn::Error::new_spanned(
+            right,
+            format!(
+                "Unknown rounding, expected one of {}",
+                ROUNDINGS.map(|(name, _)| name).join(", ")
+            ),
+        )),
+    }
+}
@@ -0,0 +1,138 @@
+//! The identity expansions of the mode macros, which leave the operators
+//! unchanged when the crate is compiled with
+//! `--cfg arithmetic_mode_passthrough`, e.g. to measure the runtime cost of a
+//! mode across a whole crate.
+//!
+//! Procedural macros cannot see the cfgs of the crate they are used in, so the
+//! macros expand to both the original operators and the transformed ones, and
+//! `arithmetic-mode`, which is compiled with the same cfgs, picks one of them.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Expr, ExprBinary};
+
+use crate::attribute::{fallible_fn_item, transform_closure, transform_item, Mode};
+use crate::bench::passthrough_bench;
+use crate::division::Division;
+use crate::engine::{self, Strategy};
+use crate::statement::transform_stmt;
+use crate::{const_block, control_flow, input, mixed, with_error, Options, HASHING_TYPE};
+
+/// The options of the transformations that leave the operators unchanged.
+const PASSTHROUGH: Options = Options {
+    is_const: false,
+    verified: false,
+    passthrough: true,
+    division: Division::Truncate,
+};
+
+/// Adds the original operators of `name! { item }` to its `expansion`, to be
+/// used instead when compiled with `--cfg arithmetic_mode_passthrough`. The
+/// expansion is unchanged for the macros that are not modes, and for the
+/// inputs that only the mode supports, like `opt(x)` in `checked!`.
+pub fn passthrough(name: &str, item: TokenStream, expansion: TokenStream) -> TokenStream {
+    let mode = match name {
+        "panicking" | "verified" | "const_checked" => Mode::Panicking,
+        "wrapping" | "hashing" => Mode::Wrapping,
+        "saturating" => Mode::Saturating,
+        "checked" | "fast_checked" => Mode::Checked,
+        "try_checked" | "control_flow" => Mode::TryChecked,
+        // The modes of `default!` evaluate to the same type
+        "default" => Mode::Wrapping,
+        "bench" => return select(passthrough_bench(item), expansion),
+        "closure" => return select(transform_closure(item, PASSTHROUGH), expansion),
+        "stmt" => {
+            return select(
+                transform_stmt(item, |mode, expr| passthrough_expr(expr, mode)),
+                expansion,
+            )
+        }
+        "mixed" => {
+            let original = mixed::parse(item)
+                .and_then(|(default, expr, _)| mixed::transform(default, expr, PASSTHROUGH));
+            return select(original, expansion);
+        }
+        _ => return expansion,
+    };
+    // The error of `try_checked!` is replaced in the original operators too
+    let (error, item) = match name {
+        "try_checked" => match input::split_error(item) {
+            Ok(split) => split,
+            Err(_) => return expansion,
+        },
+        _ => (None, item),
+    };
+    let expr = match mode {
+        Mode::Panicking => input::parse_message_expr(item).map(|(_, expr, _)| expr),
+        _ if name == "hashing" => {
+            input::parse_default_expr(item, HASHING_TYPE).map(|(expr, _)| expr)
+        }
+        _ => input::parse_expr(item).map(|(expr, _)| expr),
+    };
+    let original = expr.and_then(|expr| passthrough_expr(expr, mode));
+    let original = match name {
+        "const_checked" => original.map(const_block),
+        "control_flow" => original.map(control_flow),
+        _ => original,
+    };
+    select(
+        original.map(|original| with_error(original, error)),
+        expansion,
+    )
+}
+
+/// Like [`passthrough`], for the attribute `#[name(attr)] item`, whose items
+/// are duplicated with the original operators.
+pub fn passthrough_attribute(
+    name: &str,
+    attr: TokenStream,
+    item: TokenStream,
+    expansion: TokenStream,
+) -> TokenStream {
+    let original = match name {
+        "arithmetic_mode" => transform_item(attr, item, PASSTHROUGH),
+        "checked_fn" => fallible_fn_item(Mode::Checked, attr, item, PASSTHROUGH),
+        "try_fn" => fallible_fn_item(Mode::TryChecked, attr, item, PASSTHROUGH),
+        _ => return expansion,
+    };
+    select(original, expansion)
+}
+
+/// Selects the `original` operators or the `expansion`, with the cfgs of
+/// `arithmetic-mode`.
+fn select(original: syn::Result<TokenStream>, expansion: TokenStream) -> TokenStream {
+    match original {
+        Ok(original) => quote! {
+            ::arithmetic_mode::__passthrough! { { #original } { #expansion } }
+        },
+        Err(_) => expansion,
+    }
+}
+
+/// The original operators of `expr`, which evaluate to the same type as in
+/// `mode`.
+pub(crate) fn passthrough_expr(expr: Expr, mode: Mode) -> syn::Result<TokenStream> {
+    let expr = engine::transform_expr(&expr, &Passthrough)?;
+    Ok(match mode {
+        Mode::Checked => quote! { ::core::option::Option::Some(#expr) },
+        Mode::TryChecked => quote! {
+            ::core::result::Result::Ok::<_, ::arithmetic_mode::ArithmeticError>(#expr)
+        },
+        _ => expr,
+    })
+}
+
+/// The operations of the passthrough expansions, which are left unchanged.
+struct Passthrough;
+
+impl Strategy for Passthrough {
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        left: TokenStream,
+        right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        let op = &binary.op;
+        Ok(quote! { #left #op #right })
+    }
+}
@@ -0,0 +1,41 @@
+//! The pretty-printed expansions of the mode macros, for snapshot tests of how
+//! the expressions of a crate expand, e.g. with `insta`, which catch the
+//! changes of the expansions across upgrades of `arithmetic-mode`.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attribute::Mode;
+
+/// The expansion of the macro of `mode` for the `source` of its input,
+/// formatted with `prettyplease`. The input is parsed like in the macro, so it
+/// can start with the type of the unsuffixed literals, like
+/// `u8: a * 2 + b`. Needs the `pretty` feature.
+///
+/// ```rust
+/// use arithmetic_mode_core::{expand_pretty, Mode};
+///
+/// # // The expansion is the one without the features changing the operations
+/// # if cfg!(any(feature = "nightly", feature = "qualified", feature = "profile")) {
+/// #     return;
+/// # }
+/// let expansion = expand_pretty(Mode::Wrapping, "a * 2_u8").unwrap();
+/// assert_eq!(
+///     expansion,
+///     "{\n    let l = a;\n    let r = 2_u8;\n    l.wrapping_mul(r)\n}\n"
+/// );
+/// ```
+pub fn expand_pretty(mode: Mode, source: &str) -> syn::Result<String> {
+    let item: TokenStream = syn::parse_str(source)?;
+    let expansion = mode.expand(item)?;
+    // `prettyplease` formats whole files, so the expansion is formatted as the
+    // body of a function, which is removed afterwards
+    let file = syn::parse2(quote! { fn expansion() { #expansion } })?;
+    let formatted = prettyplease::unparse(&file);
+    let lines: Vec<&str> = formatted.lines().collect();
+    let body = &lines[1..lines.len() - 1];
+    Ok(body
+        .iter()
+        .map(|line| format!("{}\n", line.strip_prefix("    ").unwrap_or(line)))
+        .collect())
+}
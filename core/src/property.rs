@@ -0,0 +1,141 @@
+//! The `proptest_modes!` macro, which checks the relationships between the
+//! modes with property tests.
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, Ident, Token, Type};
+
+use crate::differential::{exact_result, Operators};
+
+/// A closure like `|a: u8, b: u8| a * b + 7`.
+struct Property {
+    params: Vec<(Ident, Type)>,
+    expr: Expr,
+}
+
+impl Parse for Property {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![|]>()?;
+        let mut params = Vec::new();
+        while !input.peek(Token![|]) {
+            let name = input.parse()?;
+            input.parse::<Token![:]>()?;
+            params.push((name, input.parse()?));
+            if input.peek(Token![|]) {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        input.parse::<Token![|]>()?;
+        Ok(Property {
+            params,
+            expr: input.parse()?,
+        })
+    }
+}
+
+/// Expands `proptest_modes!(item)`.
+pub fn proptest_modes_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let Property { params, expr } = syn::parse2(item)?;
+    let operators = Operators::of(&expr)?;
+    let names: Vec<_> = params.iter().map(|(name, _)| name).collect();
+    let types = params.iter().map(|(_, ty)| ty);
+    let exact = exact_result(&names, &expr);
+    let case = format!("`{}`", expr.to_token_stream());
+    let ring = if operators.ring {
+        quote! {
+            if let ::core::option::Option::Some(exact) = exact {
+                let wrapping = ::arithmetic_mode::wrapping! { #expr };
+                let modulus = 1_i128 << (::core::mem::size_of_val(&wrapping) * 8);
+                ::core::assert_eq!(
+                    0,
+                    (exact - wrapping as i128).rem_euclid(modulus),
+                    "wrapping! is not the truncated exact result of {}",
+                    #case,
+                );
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+    // With several operations, the intermediate results can overflow instead,
+    // and `MIN % -1` fails even though the exact result fits
+    let single = if operators.count == 1 && !operators.remainder {
+        quote! {
+            if let ::core::option::Option::Some(exact) = exact {
+                let wrapping = ::arithmetic_mode::wrapping! { #expr };
+                ::core::assert_eq!(
+                    checked.is_none(),
+                    wrapping as i128 != exact,
+                    "checked! fails unless wrapping! is the exact result of {}",
+                    #case,
+                );
+                if checked.is_none() {
+                    let saturating = ::arithmetic_mode::saturating! { #expr };
+                    let bound = if exact > saturating as i128 {
+                        saturating.checked_add(1)
+                    } else {
+                        saturating.checked_sub(1)
+                    };
+                    ::core::assert!(
+                        bound.is_none(),
+                        "saturating! does not clamp {} to the bounds of the type",
+                        #case,
+                    );
+                }
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+    Ok(quote! {
+        {
+            use ::arithmetic_mode::__private::proptest;
+            let mut runner = proptest::test_runner::TestRunner::default();
+            let result = runner.run(
+                &(#(proptest::arbitrary::any::<#types>(),)*),
+                |(#(#names,)*)| {
+                    let exact = #exact;
+                    let checked = ::arithmetic_mode::checked! { #expr };
+                    let panicked = ::arithmetic_mode::__private::catch_unwind(|| {
+                        ::arithmetic_mode::panicking! { #expr }
+                    })
+                    .is_err();
+                    ::core::assert_eq!(
+                        checked.is_none(),
+                        panicked,
+                        "panicking! panics unless checked! succeeds for {}",
+                        #case,
+                    );
+                    if let ::core::option::Option::Some(value) = checked {
+                        ::core::assert_eq!(
+                            exact,
+                            ::core::option::Option::Some(value as i128),
+                            "checked! differs from the exact result of {}",
+                            #case,
+                        );
+                        ::core::assert_eq!(
+                            value,
+                            ::arithmetic_mode::wrapping! { #expr },
+                            "wrapping! differs from checked! for {}",
+                            #case,
+                        );
+                        ::core::assert_eq!(
+                            value,
+                            ::arithmetic_mode::saturating! { #expr },
+                            "saturating! differs from checked! for {}",
+                            #case,
+                        );
+                    }
+                    #ring
+                    #single
+                    ::core::result::Result::Ok(())
+                },
+            );
+            if let ::core::result::Result::Err(e) = result {
+                ::core::panic!("{}\n{}", e, runner);
+            }
+        }
+    })
+}
@@ -0,0 +1,138 @@
+//! The `qformat!` macro, which computes an expression of fixed-point numbers
+//! in a Q format, like `qformat!(Q15; gain * sample + offset)`, over the plain
+//! signed integers that hold them. Additions and subtractions saturate, and
+//! multiplications and divisions are rounded and shifted back into the format
+//! before saturating, like DSP code does by hand with
+//! `(a as i32 * b as i32 + (1 << 14)) >> 15`.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::parse::{ParseStream, Parser};
+use syn::spanned::Spanned;
+use syn::{Expr, ExprBinary, ExprLit, Ident, Lit, LitFloat, LitInt, Token, UnOp};
+
+use crate::engine::{self, Strategy};
+use crate::operand_names;
+
+/// Expands `qformat! { item }`.
+pub fn qformat_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (format, expr) = (|input: ParseStream| {
+        let format: Ident = input.parse()?;
+        input.parse::<Token![;]>()?;
+        let expr: Expr = input.parse()?;
+        Ok((format, expr))
+    })
+    .parse2(item)?;
+    let fraction = format
+        .to_string()
+        .strip_prefix('Q')
+        .and_then(|bits| bits.parse::<u32>().ok())
+        .filter(|&bits| (1..128).contains(&bits))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                &format,
+                "Expected a Q format with its number of fractional bits, like `Q15`",
+            )
+        })?;
+    engine::transform_expr(&expr, &QFormat { fraction })
+}
+
+/// The operations of `qformat!` with `fraction` fractional bits.
+struct QFormat {
+    fraction: u32,
+}
+
+impl Strategy for QFormat {
+    // The negation saturates too, which the unary operators only get in the
+    // fallible modes
+    fn is_fallible(&self) -> bool {
+        true
+    }
+
+    fn operand(&self, expr: &Expr) -> TokenStream {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Float(lit),
+                ..
+            }) => self.raw(lit).unwrap_or_else(syn::Error::into_compile_error),
+            _ => quote! { #expr },
+        }
+    }
+
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        left: TokenStream,
+        right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        let op = binary.op;
+        let span = op.span();
+        let (l, r) = operand_names(span);
+        let fraction = LitInt::new(&self.fraction.to_string(), span);
+        let call = match op {
+            syn::BinOp::Add(_) => quote_spanned! { span=>
+                ::arithmetic_mode::__private::Fixed::q_add(#l, #r)
+            },
+            syn::BinOp::Sub(_) => quote_spanned! { span=>
+                ::arithmetic_mode::__private::Fixed::q_sub(#l, #r)
+            },
+            syn::BinOp::Mul(_) => quote_spanned! { span=>
+                ::arithmetic_mode::__private::Fixed::q_mul::<#fraction>(#l, #r)
+            },
+            syn::BinOp::Div(_) => quote_spanned! { span=>
+                ::arithmetic_mode::__private::Fixed::q_div::<#fraction>(#l, #r)
+            },
+            syn::BinOp::Eq(_)
+            | syn::BinOp::Lt(_)
+            | syn::BinOp::Le(_)
+            | syn::BinOp::Ne(_)
+            | syn::BinOp::Ge(_)
+            | syn::BinOp::Gt(_)
+            | syn::BinOp::And(_)
+            | syn::BinOp::Or(_) => return Ok(quote! { #left #op #right }),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    op,
+                    "qformat! only supports `+`, `-`, `*`, `/` and comparisons",
+                ))
+            }
+        };
+        Ok(quote_spanned! { span=>
+            {
+                let #l = #left;
+                let #r = #right;
+                #call
+            }
+        })
+    }
+
+    fn unary(&self, op: &UnOp, operand: TokenStream) -> TokenStream {
+        match op {
+            UnOp::Neg(_) => quote_spanned! { op.span()=>
+                ::arithmetic_mode::__private::Fixed::q_neg(#operand)
+            },
+            _ => quote! { #op #operand },
+        }
+    }
+}
+
+impl QFormat {
+    /// The raw integer of the real number `lit`, which is saturated at the
+    /// bounds of the type of the other operands.
+    fn raw(&self, lit: &LitFloat) -> syn::Result<TokenStream> {
+        let value: f64 = lit.base10_parse()?;
+        // Rounded to the nearest raw value, with the halves away from zero
+        let raw = (value * 2_f64.powi(self.fraction as i32)).round();
+        if raw >= i128::MAX as f64 {
+            return Err(syn::Error::new_spanned(
+                lit,
+                "The literal is too large for the Q format",
+            ));
+        }
+        // Located at the literal within the expansion, since lints like
+        // `zero_prefixed_literal` would otherwise check the source of `lit`
+        let span = Span::mixed_site().located_at(lit.span());
+        let raw = LitInt::new(&format!("{}_i128", raw as i128), span);
+        Ok(quote_spanned! { lit.span()=> ::arithmetic_mode::__private::Fixed::saturate(#raw) })
+    }
+}
@@ -0,0 +1,74 @@
+//! The `record!` macro, which transforms an expression like the mode given in
+//! brackets, like `record! { &mut log; [checked] a * b + c }`, and records
+//! each operation with its operands and result into the recorder before it.
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{Expr, Ident, Token};
+
+use crate::trace::traced;
+use crate::{
+    checked_op, deref_operand, input, method_call, mixed, operation_details, static_location,
+};
+
+/// The input of `record!`, the recorder and the expression.
+struct RecordInput {
+    recorder: Expr,
+    item: TokenStream,
+}
+
+impl Parse for RecordInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let recorder = input.parse()?;
+        input.parse::<Token![;]>()?;
+        Ok(RecordInput {
+            recorder,
+            item: input.parse()?,
+        })
+    }
+}
+
+/// Expands `record! { item }`.
+pub fn record_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let RecordInput { recorder, item } = syn::parse2(item)?;
+    let (mode, item) = mixed::split_default_mode(item)?;
+    let (expr, options) = input::parse_expr(item)?;
+    if options.is_const {
+        return Err(syn::Error::new_spanned(
+            &expr,
+            "record! cannot be used in const contexts, where nothing can be recorded",
+        ));
+    }
+    let name = Ident::new("recorder", proc_macro2::Span::mixed_site());
+    let expansion = traced(expr, mode, options, &|binary, [lhs, rhs, result]| {
+        let (Some(func), Some((operation, expression, location))) =
+            (checked_op(binary.op)?, operation_details(binary))
+        else {
+            return Ok(TokenStream::new());
+        };
+        let location = static_location(location);
+        let checked = method_call(&func, lhs, &deref_operand(true, quote! { #rhs }), options);
+        Ok(quote_spanned! { binary.op.span()=>
+            ::arithmetic_mode::record::Recorder::record(
+                &mut *#name,
+                ::arithmetic_mode::record::Step::__new(
+                    #operation,
+                    #expression,
+                    #location,
+                    &#lhs,
+                    &#rhs,
+                    &#result,
+                    #checked.is_none(),
+                ),
+            );
+        })
+    })?;
+    Ok(quote! {
+        {
+            let #name = #recorder;
+            #expansion
+        }
+    })
+}
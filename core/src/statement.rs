@@ -0,0 +1,77 @@
+//! The `stmt!` macro, which applies a mode to a single statement of an
+//! otherwise unchanged function, like `stmt!(checked: let total = a + b * c;)`,
+//! since attributes on statements are unstable. The bound value is what the
+//! mode evaluates to, like an `Option` in `checked` mode.
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Expr, ExprAssign, Ident, Local, LocalInit, Stmt, Token};
+
+use crate::attribute::Mode;
+
+/// Input of `stmt!`: the mode, followed by the statement, like
+/// `checked: let total = a + b * c;`.
+struct StmtInput {
+    mode: Mode,
+    stmt: Stmt,
+}
+
+impl Parse for StmtInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mode = Mode::from_ident(&input.parse::<Ident>()?)?;
+        input.parse::<Token![:]>()?;
+        let stmt = input.parse()?;
+        if !input.is_empty() {
+            return Err(input.error("stmt! takes a single statement"));
+        }
+        Ok(StmtInput { mode, stmt })
+    }
+}
+
+/// Expands `stmt!(item)`.
+pub fn stmt_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    transform_stmt(item, |mode, expr| mode.expand(expr.into_token_stream()))
+}
+
+/// Transforms the value of the statement in `item` into its mode with
+/// `transform`, which is the initializer of a `let` statement, or the right
+/// side of an assignment.
+pub(crate) fn transform_stmt(
+    item: TokenStream,
+    transform: impl FnOnce(Mode, Expr) -> syn::Result<TokenStream>,
+) -> syn::Result<TokenStream> {
+    let StmtInput { mode, stmt } = syn::parse2(item)?;
+    match stmt {
+        Stmt::Local(Local {
+            attrs,
+            pat,
+            init: Some(LocalInit { expr, diverge, .. }),
+            ..
+        }) => {
+            let value = transform(mode, *expr)?;
+            Ok(match diverge {
+                // The value can end with a block, which cannot be followed by
+                // `else`
+                Some((_, diverge)) => quote! { #(#attrs)* let #pat = (#value) else #diverge; },
+                None => quote! { #(#attrs)* let #pat = #value; },
+            })
+        }
+        Stmt::Expr(
+            Expr::Assign(ExprAssign {
+                attrs,
+                left,
+                eq_token,
+                right,
+            }),
+            semi,
+        ) => {
+            let value = transform(mode, *right)?;
+            Ok(quote! { #(#attrs)* #left #eq_token #value #semi })
+        }
+        stmt => Err(syn::Error::new_spanned(
+            stmt,
+            "stmt! only supports `let` statements with a value and assignments",
+        )),
+    }
+}
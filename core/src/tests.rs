@@ -0,0 +1,2314 @@
+// Most snapshot tests expect the expansion without the `profile` feature, so
+// their helpers and imports are unused with it
+#![cfg_attr(feature = "profile", allow(dead_code, unused_imports))]
+
+use crate::attribute::{arithmetic_mode_impl, closure_impl, fallible_fn_impl, Mode};
+use crate::bench::bench_impl;
+use crate::channel::{blend_add_impl, scale_u8_impl};
+use crate::config::parse_policy;
+use crate::constant_time::ct_checked_impl;
+use crate::default_mode::default_impl;
+use crate::differential::differential_test_impl;
+use crate::dot::{dot_product_impl, mac_impl};
+use crate::elementwise::elementwise_impl;
+use crate::enforce::{deny_unchecked_arithmetic_impl, enforce_impl};
+use crate::evm::evm_impl;
+use crate::explain::explain_impl;
+use crate::fast::fast_checked_impl;
+use crate::forbid::forbid_impl;
+use crate::hoist::hoist;
+use crate::matrix::overflow_matrix_impl;
+use crate::mixed::mixed_impl;
+use crate::modes::modes_impl;
+use crate::modfield::modfield_impl;
+use crate::mul_div::{bps_of_impl, lerp_impl, mul_div_impl, percent_of_impl};
+use crate::passthrough::{passthrough, passthrough_attribute};
+use crate::property::proptest_modes_impl;
+use crate::qformat::qformat_impl;
+use crate::record::record_impl;
+use crate::statement::stmt_impl;
+use crate::trace::trace_impl;
+use crate::type_hint::rewrite_type_hints;
+use crate::variadic::{checked_product_impl, checked_sum_impl};
+use crate::{
+    checked_impl, const_block, const_checked_impl, control_flow_impl, hashing_impl,
+    incomplete_expression, panicking_impl, saturating_impl, static_location, try_checked_impl,
+    verified_impl, warn_without_arithmetic, with_deep_stack, wrapping_impl,
+};
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use proc_macro_utils::assert_expansion;
+use quote::{format_ident, quote, ToTokens};
+
+/// The expected reference to the location of the failed operation.
+fn location() -> TokenStream {
+    static_location(quote! {
+        ::arithmetic_mode::Location::__new(::core::file!(), ::core::line!(), ::core::column!())
+    })
+}
+
+/// The expected `ArithmeticError` of the failed `operation` in `expression`.
+fn operation_error(operation: &str, expression: &str) -> TokenStream {
+    let operation = format_ident!("{operation}");
+    let location = location();
+    quote! {
+        ::arithmetic_mode::ArithmeticError::__operation(
+            ::arithmetic_mode::Operation::#operation,
+            #expression,
+            #location,
+            &l,
+            &r,
+        )
+    }
+}
+
+/// The expected panic of the failed `operation` in `expression`, after
+/// `message` if any, which is an abort with the `abort` feature and
+/// `defmt::panic!` with the `defmt` feature.
+fn operation_panic(operation: &str, expression: &str, message: Option<&str>) -> TokenStream {
+    if cfg!(feature = "abort") {
+        return quote! { ::arithmetic_mode::ArithmeticError::__abort() };
+    }
+    let operation = format_ident!("{operation}");
+    let message = match message {
+        Some(message) => quote! { ::core::option::Option::Some(#message) },
+        None => quote! { ::core::option::Option::None },
+    };
+    let panic_operation = if cfg!(feature = "defmt") {
+        format_ident!("__defmt_panic_operation")
+    } else {
+        format_ident!("__panic_operation")
+    };
+    let location = location();
+    quote! {
+        ::arithmetic_mode::ArithmeticError::#panic_operation(
+            ::arithmetic_mode::Operation::#operation,
+            #expression,
+            #location,
+            &l,
+            &r,
+            ::arithmetic_mode::ErrorKind::Overflow,
+            #message,
+        )
+    }
+}
+
+/// The expected expansion of a panicking operation.
+fn panicking_op(
+    left: TokenStream,
+    func: &str,
+    right: TokenStream,
+    operation: &str,
+    expression: &str,
+) -> TokenStream {
+    let call = method(func, quote! { r });
+    let panic = cold(operation_panic(operation, expression, None));
+    quote! {
+        {
+            let l = #left;
+            let r = #right;
+            match #call {
+                ::core::option::Option::Some(v) => v,
+                ::core::option::Option::None => #panic,
+            }
+        }
+    }
+}
+
+/// The expected overflow `branch`, which is hinted as unlikely with the
+/// `nightly` feature.
+fn cold(branch: TokenStream) -> TokenStream {
+    if cfg!(feature = "nightly") {
+        quote! {
+            {
+                ::arithmetic_mode::__private::cold_path();
+                #branch
+            }
+        }
+    } else {
+        branch
+    }
+}
+
+/// The expected result of the `func` checked method in `checked!`, with the
+/// right operand `right`.
+fn checked_value(func: &str, right: TokenStream) -> TokenStream {
+    let call = method(func, right);
+    if cfg!(feature = "nightly") {
+        let none = cold(quote! { None });
+        quote! {
+            match #call {
+                Some(v) => Some(v),
+                None => #none,
+            }
+        }
+    } else {
+        call
+    }
+}
+
+/// The expected expansion of a checked operation, where `value` is computed
+/// from the operands `l` and `r`.
+fn checked_match(left: TokenStream, right: TokenStream, value: TokenStream) -> TokenStream {
+    quote! {
+        match #left {
+            Some(l) => match #right {
+                Some(r) => #value,
+                None => None,
+            },
+            None => None,
+        }
+    }
+}
+
+/// The expected expansion of a `try_checked!` operation, where `value` is
+/// computed from the operands `l` and `r`.
+fn try_checked_match(left: TokenStream, right: TokenStream, value: TokenStream) -> TokenStream {
+    quote! {
+        match #left {
+            Ok(l) => match #right {
+                Ok(r) => #value,
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// The expected result of the `func` checked method in `try_checked!`.
+fn try_checked_value(func: &str, error: TokenStream) -> TokenStream {
+    let call = method(func, quote! { r });
+    let error = cold(quote! { Err(#error) });
+    quote! {
+        match #call {
+            Some(v) => Ok(v),
+            None => #error,
+        }
+    }
+}
+
+/// The expected expansion of a wrapping or saturating operation.
+fn bound_op(left: TokenStream, func: &str, right: TokenStream) -> TokenStream {
+    let call = method(func, quote! { r });
+    quote! {
+        {
+            let l = #left;
+            let r = #right;
+            #call
+        }
+    }
+}
+
+/// Like [`bound_op`], in a const context, where the methods are not
+/// qualified since trait methods cannot be called there.
+fn const_bound_op(left: TokenStream, func: &str, right: TokenStream) -> TokenStream {
+    let func = format_ident!("{func}");
+    quote! {
+        {
+            let l = #left;
+            let r = #right;
+            l.#func(r)
+        }
+    }
+}
+
+/// The expected call of the integer method `func` on the left operand `l`,
+/// which is qualified with the `Integer` trait with the `qualified` feature.
+fn method(func: &str, right: TokenStream) -> TokenStream {
+    let func = format_ident!("{func}");
+    if cfg!(feature = "qualified") {
+        quote! {
+            ::arithmetic_mode::__private::Integer::#func(
+                ::arithmetic_mode::__private::Operand::integer(l),
+                #right,
+            )
+        }
+    } else {
+        quote! { l.#func(#right) }
+    }
+}
+
+fn assert_expands(
+    mode_impl: fn(TokenStream) -> syn::Result<TokenStream>,
+    input: TokenStream,
+    expected: TokenStream,
+) {
+    assert_eq!(expected.to_string(), mode_impl(input).unwrap().to_string());
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_add() {
+    assert_expands(
+        panicking_impl,
+        quote! { a + 55_i32 },
+        panicking_op(
+            quote! { a },
+            "checked_add",
+            quote! { 55_i32 },
+            "Add",
+            "a + 55_i32",
+        ),
+    );
+    assert_expands(
+        wrapping_impl,
+        quote! { a + 55_i32 },
+        bound_op(quote! { a }, "wrapping_add", quote! { 55_i32 }),
+    );
+    assert_expands(
+        saturating_impl,
+        quote! { a + 55_i32 },
+        bound_op(quote! { a }, "saturating_add", quote! { 55_i32 }),
+    );
+    assert_expands(
+        checked_impl,
+        quote! { a + 55_i32 },
+        checked_match(
+            quote! { Some(a) },
+            quote! { Some(55_i32) },
+            checked_value("checked_add", quote! { r }),
+        ),
+    );
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_sub() {
+    assert_expands(
+        panicking_impl,
+        quote! { a - 55_i32 },
+        panicking_op(
+            quote! { a },
+            "checked_sub",
+            quote! { 55_i32 },
+            "Sub",
+            "a - 55_i32",
+        ),
+    );
+    assert_expands(
+        wrapping_impl,
+        quote! { a - 55_i32 },
+        bound_op(quote! { a }, "wrapping_sub", quote! { 55_i32 }),
+    );
+    assert_expands(
+        saturating_impl,
+        quote! { a - 55_i32 },
+        bound_op(quote! { a }, "saturating_sub", quote! { 55_i32 }),
+    );
+    assert_expands(
+        checked_impl,
+        quote! { a - 55_i32 },
+        checked_match(
+            quote! { Some(a) },
+            quote! { Some(55_i32) },
+            checked_value("checked_sub", quote! { r }),
+        ),
+    );
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_mul() {
+    assert_expands(
+        panicking_impl,
+        quote! { a * 55_i32 },
+        panicking_op(
+            quote! { a },
+            "checked_mul",
+            quote! { 55_i32 },
+            "Mul",
+            "a * 55_i32",
+        ),
+    );
+    assert_expands(
+        wrapping_impl,
+        quote! { a * 55_i32 },
+        bound_op(quote! { a }, "wrapping_mul", quote! { 55_i32 }),
+    );
+    assert_expands(
+        saturating_impl,
+        quote! { a * 55_i32 },
+        bound_op(quote! { a }, "saturating_mul", quote! { 55_i32 }),
+    );
+    assert_expands(
+        checked_impl,
+        quote! { a * 55_i32 },
+        checked_match(
+            quote! { Some(a) },
+            quote! { Some(55_i32) },
+            checked_value("checked_mul", quote! { r }),
+        ),
+    );
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_compound() {
+    let sub = panicking_op(
+        quote! { a },
+        "checked_sub",
+        quote! { 55_i32 },
+        "Sub",
+        "a - 55_i32",
+    );
+    assert_expands(
+        panicking_impl,
+        quote! { a - 55_i32 + 121_i32 },
+        panicking_op(
+            sub,
+            "checked_add",
+            quote! { 121_i32 },
+            "Add",
+            "a - 55_i32 + 121_i32",
+        ),
+    );
+    let sub = bound_op(quote! { a }, "wrapping_sub", quote! { 55_i32 });
+    assert_expands(
+        wrapping_impl,
+        quote! { a - 55_i32 + 121_i32 },
+        bound_op(sub, "wrapping_add", quote! { 121_i32 }),
+    );
+    let sub = bound_op(quote! { a }, "saturating_sub", quote! { 55_i32 });
+    assert_expands(
+        saturating_impl,
+        quote! { a - 55_i32 + 121_i32 },
+        bound_op(sub, "saturating_add", quote! { 121_i32 }),
+    );
+    let sub = checked_match(
+        quote! { Some(a) },
+        quote! { Some(55_i32) },
+        checked_value("checked_sub", quote! { r }),
+    );
+    assert_expands(
+        checked_impl,
+        quote! { a - 55_i32 + 121_i32 },
+        checked_match(
+            sub,
+            quote! { Some(121_i32) },
+            checked_value("checked_add", quote! { r }),
+        ),
+    );
+}
+
+#[test]
+fn test_unchanged_expressions() {
+    assert_expansion!(panicking_impl! { 42 }.unwrap(), { 42 });
+    assert_expansion!(panicking_impl! { -42 }.unwrap(), { -42 });
+    assert_expansion!(panicking_impl! { 1 || 2 }.unwrap(), { 1 || 2 });
+    assert_expansion!(panicking_impl! { 1 | 2 }.unwrap(), { 1 | 2 });
+    assert_expansion!(panicking_impl! { 1 ^ 2 }.unwrap(), { 1 ^ 2 });
+    assert_expansion!(panicking_impl! { -1 & 2 }.unwrap(), { -1 & 2 });
+
+    assert_expansion!(wrapping_impl! { 42 }.unwrap(), { 42 });
+    assert_expansion!(wrapping_impl! { -42 }.unwrap(), { -42 });
+    assert_expansion!(wrapping_impl! { 1 || 2 }.unwrap(), { 1 || 2 });
+    assert_expansion!(wrapping_impl! { 1 | 2 }.unwrap(), { 1 | 2 });
+    assert_expansion!(wrapping_impl! { 1 ^ 2 }.unwrap(), { 1 ^ 2 });
+    assert_expansion!(wrapping_impl! { -1 & 2 }.unwrap(), { -1 & 2 });
+
+    assert_expansion!(saturating_impl! { 42 }.unwrap(), { 42 });
+    assert_expansion!(saturating_impl! { -42 }.unwrap(), { -42 });
+    assert_expansion!(saturating_impl! { 1 || 2 }.unwrap(), { 1 || 2 });
+    assert_expansion!(saturating_impl! { 1 | 2 }.unwrap(), { 1 | 2 });
+    assert_expansion!(saturating_impl! { 1 ^ 2 }.unwrap(), { 1 ^ 2 });
+    assert_expansion!(saturating_impl! { -1 & 2 }.unwrap(), { -1 & 2 });
+
+    assert_expansion!(checked_impl! { 42 }.unwrap(), { Some(42) });
+    assert_expands(
+        checked_impl,
+        quote! { -42 },
+        quote! {
+            match Some(42) {
+                Some(v) => Some(-v),
+                None => None,
+            }
+        },
+    );
+    assert_expands(
+        checked_impl,
+        quote! { 1 || 2 },
+        checked_match(
+            quote! { Some(1) },
+            quote! { Some(2) },
+            quote! { Some(l || r) },
+        ),
+    );
+    assert_expands(
+        checked_impl,
+        quote! { 1 ^ 2 },
+        checked_match(
+            quote! { Some(1) },
+            quote! { Some(2) },
+            quote! { Some(l ^ r) },
+        ),
+    );
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_bitshift() {
+    let shl = panicking_op(quote! { a }, "checked_shl", quote! { 2 }, "Shl", "a << 2");
+    assert_expands(
+        panicking_impl,
+        quote! { a << 2 >> 3 },
+        panicking_op(shl, "checked_shr", quote! { 3 }, "Shr", "a << 2 >> 3"),
+    );
+    let shl = bound_op(quote! { a }, "wrapping_shl", quote! { 2 });
+    assert_expands(
+        wrapping_impl,
+        quote! { a << 2 >> 3 },
+        bound_op(shl, "wrapping_shr", quote! { 3 }),
+    );
+    let shl = checked_match(
+        quote! { Some(a) },
+        quote! { Some(2) },
+        checked_value("checked_shl", quote! { r }),
+    );
+    assert_expands(
+        checked_impl,
+        quote! { a << 2 >> 3 },
+        checked_match(
+            shl,
+            quote! { Some(3) },
+            checked_value("checked_shr", quote! { r }),
+        ),
+    );
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_hashing() {
+    let mul = bound_op(quote! { h }, "wrapping_mul", quote! { 33 });
+    assert_expands(
+        hashing_impl,
+        quote! { h * 33 + 7 },
+        bound_op(mul, "wrapping_add", quote! { 7 }),
+    );
+    assert_expands(hashing_impl, quote! { 5381 * 33 }, quote! { 177573_u64 });
+    assert_expands(
+        hashing_impl,
+        quote! { u32: 1 << 31 },
+        quote! { 2147483648_u32 },
+    );
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_rem() {
+    assert_expands(
+        saturating_impl,
+        quote! { a % 5_i32 },
+        bound_op(quote! { a }, "wrapping_rem", quote! { 5_i32 }),
+    );
+}
+
+#[test]
+fn test_bitshift_no_saturating() {
+    saturating_impl(quote! { 1 << 2 >> 3 }).unwrap_err();
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_division_flavor() {
+    let floor = |func: &str| {
+        let func = format_ident!("{func}");
+        quote! {
+            {
+                let l = a;
+                let r = *::core::borrow::Borrow::borrow(&b);
+                {
+                    use ::arithmetic_mode::__private::Division as _;
+                    l.#func(r)
+                }
+            }
+        }
+    };
+    assert_expands(
+        wrapping_impl,
+        quote! { div = floor; a / b },
+        floor("wrapping_div_floor"),
+    );
+    assert_expands(
+        saturating_impl,
+        quote! { div = floor; a % b },
+        floor("wrapping_rem_floor"),
+    );
+    // The other operations and the literals are unchanged
+    assert_expands(
+        wrapping_impl,
+        quote! { div = ceil; a + 1_i32 },
+        bound_op(quote! { a }, "wrapping_add", quote! { 1_i32 }),
+    );
+    assert_expands(
+        wrapping_impl,
+        quote! { div = ceil; -7_i32 / 2_i32 },
+        quote! {
+            {
+                let l = -7_i32;
+                let r = 2_i32;
+                {
+                    use ::arithmetic_mode::__private::Division as _;
+                    l.wrapping_div_ceil(r)
+                }
+            }
+        },
+    );
+    assert_expands(
+        wrapping_impl,
+        quote! { div = trunc; -7_i32 / 2_i32 },
+        quote! { -3_i32 },
+    );
+    let error = checked_impl(quote! { div = round; a / b }).unwrap_err();
+    assert_eq!(
+        "Unknown division, expected one of trunc, floor, ceil, euclid, half_even",
+        error.to_string()
+    );
+    let error = checked_impl(quote! { div = euclid; const a / b }).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("Division flavors are not supported in const contexts"));
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_reference_operands() {
+    assert_expands(
+        panicking_impl,
+        quote! { &a + b },
+        panicking_op(
+            quote! { &a },
+            "checked_add",
+            quote! { *::core::borrow::Borrow::borrow(&b) },
+            "Add",
+            "& a + b",
+        ),
+    );
+    assert_expands(
+        wrapping_impl,
+        quote! { a + b },
+        bound_op(
+            quote! { a },
+            "wrapping_add",
+            quote! { *::core::borrow::Borrow::borrow(&b) },
+        ),
+    );
+    let mul = bound_op(quote! { b }, "saturating_mul", quote! { 2 });
+    assert_expands(
+        saturating_impl,
+        quote! { a + b * 2 },
+        bound_op(quote! { a }, "saturating_add", mul),
+    );
+    assert_expands(
+        checked_impl,
+        quote! { a + b },
+        checked_match(
+            quote! { Some(a) },
+            quote! { Some(b) },
+            checked_value(
+                "checked_add",
+                quote! { *::core::borrow::Borrow::borrow(&r) },
+            ),
+        ),
+    );
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_checked_option_operands() {
+    assert_expands(
+        checked_impl,
+        quote! { opt(a) + b as u8 },
+        checked_match(
+            quote! { a },
+            quote! { Some(b as u8) },
+            checked_value("checked_add", quote! { r }),
+        ),
+    );
+    assert_expands(
+        checked_impl,
+        quote! { 1_u8 + opt(a.checked_div(b)) },
+        checked_match(
+            quote! { Some(1_u8) },
+            quote! { a.checked_div(b) },
+            checked_value(
+                "checked_add",
+                quote! { *::core::borrow::Borrow::borrow(&r) },
+            ),
+        ),
+    );
+}
+
+#[test]
+fn test_all_errors_reported() {
+    let error = panicking_impl(quote! { a + f(x) * g(y) }).unwrap_err();
+    let messages: Vec<_> = error.into_iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        vec![
+            "Unsupported expression `f (x)`",
+            "Unsupported expression `g (y)`"
+        ],
+        messages
+    );
+    let error = arithmetic_mode_impl(quote! { checked }, quote! { fn f() { a += 1; b -= 2; } })
+        .unwrap_err();
+    assert_eq!(2, error.into_iter().count());
+}
+
+#[test]
+fn test_limitation_help() {
+    let error = wrapping_impl(quote! { 1 + x }).unwrap_err().to_string();
+    assert!(error.starts_with("Cannot infer the integer type of `1`"));
+    assert!(error.contains("help: add a type suffix like `1_i32`"));
+    wrapping_impl(quote! { x + 1 }).unwrap();
+    wrapping_impl(quote! { u8: 1 + 2 }).unwrap();
+    let error = wrapping_impl(quote! { x += 1_u8 }).unwrap_err().to_string();
+    assert!(error.contains("help: assign the result instead"));
+    let error = saturating_impl(quote! { x << 1 }).unwrap_err().to_string();
+    assert!(error.contains("help: use `checked!` or `wrapping!`"));
+    let error = arithmetic_mode_impl(quote! { try_checked }, quote! { fn f() { a += b; } })
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("help: use `#[try_fn]`"));
+}
+
+#[test]
+fn test_no_arithmetic_warning() {
+    let expansion = quote! { { flags | mask } };
+    let warned = warn_without_arithmetic("wrapping", quote! { flags | mask }, expansion.clone());
+    let warned = warned.to_string();
+    assert!(warned.contains("NO_ARITHMETIC_TO_TRANSFORM"));
+    assert!(warned.contains("has no arithmetic operations for `wrapping!` to transform"));
+    for item in [
+        quote! { flags | a + b },
+        quote! { -(a * b) },
+        quote! { u8: 1 + 2 },
+        quote! { "message": x << 1 },
+    ] {
+        let unchanged = warn_without_arithmetic("panicking", item, expansion.clone());
+        assert_eq!(unchanged.to_string(), expansion.to_string());
+    }
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_panicking_message() {
+    let call = method("checked_add", quote! { r });
+    let panic = cold(operation_panic("Add", "a + 55_i32", Some("budget")));
+    let expected = quote! {
+        {
+            let l = a;
+            let r = 55_i32;
+            match #call {
+                ::core::option::Option::Some(v) => v,
+                ::core::option::Option::None => #panic,
+            }
+        }
+    };
+    assert_expands(panicking_impl, quote! { "budget": a + 55_i32 }, expected);
+    wrapping_impl(quote! { "budget": a + 55_i32 }).unwrap_err();
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_verified() {
+    let call = method("checked_mul", quote! { r });
+    let panic = cold(operation_panic("Mul", "a * 2", None));
+    let expected = quote! {
+        {
+            let l = a;
+            let r = 2;
+            #[cfg(kani)]
+            ::kani::assert(l.checked_mul(r).is_some(), "attempt to multiply with overflow: `a * 2`");
+            match #call {
+                ::core::option::Option::Some(v) => v,
+                ::core::option::Option::None => #panic,
+            }
+        }
+    };
+    assert_expands(verified_impl, quote! { a * 2 }, expected);
+    let output = verified_impl(quote! { a / 2 }).unwrap().to_string();
+    assert!(output.contains(r#"assert (r != 0 , "attempt to divide by zero: `a / 2`")"#));
+    let output = verified_impl(quote! { const A + 1 }).unwrap().to_string();
+    assert!(!output.contains("kani"));
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_mixed() {
+    let mul = method("wrapping_mul", quote! { r });
+    let add = method("saturating_add", quote! { r });
+    let expected = quote! {
+        {
+            let l = a;
+            let r = *::core::borrow::Borrow::borrow(&{
+                let l = b;
+                let r = *::core::borrow::Borrow::borrow(&c);
+                #mul
+            });
+            #add
+        }
+    };
+    // `quote!` does not keep the extended operators joint
+    let input = |source: &str| source.parse::<TokenStream>().unwrap();
+    assert_expands(mixed_impl, input("a +| b *% c"), expected);
+    let expansion = mixed_impl(input("a * b +% c")).unwrap().to_string();
+    assert!(expansion.contains("wrapping_add"));
+    assert!(expansion.contains("checked_mul"));
+    assert!(!expansion.contains("__arithmetic_mode_wrapping"));
+    let error = mixed_impl(input("f(a +% b)")).unwrap_err().to_string();
+    assert!(error.contains("only supported in the arithmetic of `mixed!`"));
+    assert_expands(
+        mixed_impl,
+        input("[wrapping] a - b *[saturating] c"),
+        mixed_impl(input("a -% b *| c")).unwrap(),
+    );
+    let expansion = mixed_impl(input("[checked] a + (b *[wrapping] c)")).unwrap();
+    assert!(expansion.to_string().contains("checked_add"));
+    let error = mixed_impl(input("a * (b +[checked] c)"))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("A checked operation cannot be an operand of a panicking operation"));
+}
+
+#[test]
+fn test_modes() {
+    let expansion = modes_impl(quote! { u8 = saturating, _ = wrapping; a + (b * c) as u16 })
+        .unwrap()
+        .to_string();
+    assert!(expansion.starts_with("{ enum __ArithmeticModes { }"));
+    for (ty, mode) in [
+        ("u8", "Saturating"),
+        ("u16", "Wrapping"),
+        ("i128", "Wrapping"),
+    ] {
+        let declaration = format!(
+            "ModeOf < {ty} > for __ArithmeticModes {{ type Mode = :: arithmetic_mode :: \
+            __private :: modes :: {mode} ; }}"
+        );
+        assert!(
+            expansion.contains(&declaration),
+            "{declaration} in {expansion}"
+        );
+    }
+    // The multiplication in the cast is transformed too
+    assert!(expansion.contains("modes :: add :: < __ArithmeticModes , _ >"));
+    assert!(expansion.contains("modes :: mul :: < __ArithmeticModes , _ >"));
+    assert!(expansion.contains("unreachable"));
+    let expansion = modes_impl(quote! { u8 = saturating, usize = checked; a + b })
+        .unwrap()
+        .to_string();
+    assert!(expansion.contains("Option :: None"));
+    let error = |item| modes_impl(item).unwrap_err().to_string();
+    assert!(error(quote! { u8 = checked, usize = try_checked; a + b })
+        .starts_with("checked and try_checked modes cannot both be given"));
+    assert_eq!(
+        "The mode of `u8` is given more than once",
+        error(quote! { u8 = checked, u8 = wrapping; a + b })
+    );
+    assert!(error(quote! { f32 = checked; a + b }).starts_with("modes! starts with the modes"));
+    assert!(error(quote! { a + b }).starts_with("modes! starts with the modes"));
+    assert!(error(quote! { u8 = checked; const a + b })
+        .starts_with("modes! is not supported in const contexts"));
+}
+
+#[test]
+fn test_bench() {
+    let expansion = bench_impl(quote! { [wrapping] a * (b + 2) - -c + *d })
+        .unwrap()
+        .to_string();
+    for operand in ["(a)", "(b)", "(2)", "(* d)"] {
+        let black_box = format!(":: core :: hint :: black_box {operand}");
+        assert!(expansion.contains(&black_box), "{operand} in {expansion}");
+    }
+    assert!(expansion.contains("- :: core :: hint :: black_box (c)"));
+    assert!(expansion.contains("wrapping_mul"));
+    let expansion = bench_impl(quote! { [checked] opt(a) + b }).unwrap();
+    assert!(expansion.to_string().starts_with("match a {"));
+    let expansion = bench_impl(quote! { a + b }).unwrap();
+    assert!(expansion.to_string().contains("checked_add"));
+}
+
+#[test]
+fn test_trace() {
+    let expansion = trace_impl(quote! { [wrapping] a * b | c })
+        .unwrap()
+        .to_string();
+    assert!(expansion.contains("wrapping_mul"));
+    assert!(expansion.contains("\"{:?} * {:?} = {:?}\""));
+    assert_eq!(1, expansion.matches("__private :: trace").count());
+    let expansion = trace_impl(quote! { [checked] opt(a) + b }).unwrap();
+    let expansion = expansion.to_string();
+    assert!(expansion.starts_with("match a {"));
+    assert!(expansion.contains(":: core :: option :: Option :: Some (lhs)"));
+    let error = trace_impl(quote! { const a + b }).unwrap_err().to_string();
+    assert!(error.starts_with("trace! cannot be used in const contexts"));
+}
+
+#[test]
+fn test_checked_sum() {
+    assert_expands(
+        checked_sum_impl,
+        quote! { u64: a, b * 2, c as u64 },
+        checked_impl(quote! { u64: a + (b * 2) + c as u64 }).unwrap(),
+    );
+    assert_expands(
+        checked_product_impl,
+        quote! { [try_checked] a + 1, b },
+        try_checked_impl(quote! { (a + 1) * b }).unwrap(),
+    );
+    let error = checked_sum_impl(quote! { [wrapping] a, b }).unwrap_err();
+    assert_eq!(
+        "checked_sum! only supports the `checked` and `try_checked` modes",
+        error.to_string()
+    );
+    let error = checked_product_impl(quote! {}).unwrap_err();
+    assert_eq!(
+        "checked_product! needs at least one expression",
+        error.to_string()
+    );
+}
+
+#[test]
+fn test_overflow_matrix() {
+    let expansion = overflow_matrix_impl(quote! { u8, i16 in -3..=3 }).unwrap();
+    let file: syn::File = syn::parse2(expansion).unwrap();
+    let names: Vec<String> = file
+        .items
+        .iter()
+        .map(|item| match item {
+            syn::Item::Fn(test) => test.sig.ident.to_string(),
+            _ => panic!("Unexpected item {}", item.to_token_stream()),
+        })
+        .collect();
+    assert_eq!(vec!["overflow_matrix_u8", "overflow_matrix_i16"], names);
+    let error = overflow_matrix_impl(quote! { u32 })
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        "Unsupported type `u32`, expected one of u8, i8, u16, i16",
+        error
+    );
+}
+
+#[test]
+fn test_record() {
+    let expansion = record_impl(quote! { &mut log; [saturating] a * b | c })
+        .unwrap()
+        .to_string();
+    assert!(expansion.starts_with("{ let recorder = & mut log ;"));
+    assert!(expansion.contains("saturating_mul"));
+    assert_eq!(1, expansion.matches("Recorder :: record").count());
+    let error = record_impl(quote! { a + b }).unwrap_err().to_string();
+    assert_eq!("expected `;`", error);
+    let error = record_impl(quote! { log; const a + b }).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("record! cannot be used in const"));
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_explain() {
+    let expansion = explain_impl(quote! { checked, a + b * c }).unwrap();
+    let expansion = expansion.to_string();
+    let checked = checked_impl(quote! { a + b * c }).unwrap();
+    let note = format!("checked! {{ a + b * c }}` expands to `{checked}`");
+    assert!(expansion.contains(&note), "{expansion}");
+    assert!(expansion.ends_with(&format!("{checked} }}")));
+    let error = explain_impl(quote! { modulo, a + b }).unwrap_err();
+    assert_eq!("Unknown arithmetic mode `modulo`", error.to_string());
+}
+
+#[test]
+fn test_dot_product() {
+    assert_expands(
+        dot_product_impl,
+        quote! { [saturating] a, b.samples },
+        quote! { ::arithmetic_mode::__private::dot_saturating(&(a)[..], &(b.samples)[..]) },
+    );
+    let error = dot_product_impl(quote! { a, b, c }).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("dot_product! takes two vectors"));
+}
+
+#[test]
+fn test_mac() {
+    assert_expands(
+        mac_impl,
+        quote! { acc, taps[i], x + 1 },
+        quote! { ::arithmetic_mode::__private::mac_panicking(acc, taps[i], x + 1) },
+    );
+    let error = mac_impl(quote! { [checked] a, b }).unwrap_err();
+    assert!(error.to_string().starts_with("mac! takes three operands"));
+    let expansion =
+        arithmetic_mode_impl(quote! { saturating }, quote! { fn f() { acc += (a * 2); } }).unwrap();
+    let expected = quote! {
+        fn f() {
+            {
+                let __arithmetic_mode_a = a;
+                let __arithmetic_mode_b = 2;
+                let __arithmetic_mode_place = &mut acc;
+                *__arithmetic_mode_place = ::arithmetic_mode::__private::mac_saturating(
+                    *__arithmetic_mode_place,
+                    *::core::borrow::Borrow::borrow(&__arithmetic_mode_a),
+                    __arithmetic_mode_b,
+                );
+            };
+        }
+    };
+    assert_eq!(expected.to_string(), expansion.to_string());
+    let expansion = fallible_fn_impl(
+        Mode::Checked,
+        quote! {},
+        quote! { fn f() { acc += a * b; } },
+    )
+    .unwrap();
+    assert!(expansion
+        .to_string()
+        .contains("(:: arithmetic_mode :: __private :: mac_checked ("));
+}
+
+#[test]
+fn test_mul_div() {
+    assert_expands(
+        mul_div_impl,
+        quote! { [checked] a, b, c + 1, rounding = ceil },
+        quote! {
+            ::arithmetic_mode::__private::mul_div_checked(
+                a,
+                b,
+                c + 1,
+                ::arithmetic_mode::__private::Rounding::Ceil,
+            )
+        },
+    );
+    let error = mul_div_impl(quote! { a, b, c, rounding = half }).unwrap_err();
+    assert_eq!(
+        "Unknown rounding, expected one of down, up, floor, ceil, nearest",
+        error.to_string()
+    );
+    let error = mul_div_impl(quote! { a, b }).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("mul_div! takes three operands"));
+    assert_expands(
+        percent_of_impl,
+        quote! { [saturating] x, p },
+        mul_div_impl(quote! { [saturating] x, p, 100 }).unwrap(),
+    );
+    assert_expands(
+        bps_of_impl,
+        quote! { x, bps, rounding = nearest },
+        mul_div_impl(quote! { x, bps, 10_000, rounding = nearest }).unwrap(),
+    );
+    let error = bps_of_impl(quote! { x }).unwrap_err();
+    assert!(error.to_string().starts_with("bps_of! takes two operands"));
+    assert_expands(
+        lerp_impl,
+        quote! { [wrapping] a, b, t, 100, rounding = nearest },
+        quote! {
+            ::arithmetic_mode::__private::lerp_wrapping(
+                a,
+                b,
+                t,
+                100,
+                ::arithmetic_mode::__private::Rounding::Nearest,
+            )
+        },
+    );
+    let error = lerp_impl(quote! { a, b, t }).unwrap_err();
+    assert!(error.to_string().starts_with("lerp! takes four operands"));
+}
+
+#[test]
+fn test_channels() {
+    assert_expands(
+        blend_add_impl,
+        quote! { a, b + 1, c },
+        quote! {
+            ::arithmetic_mode::__private::Channel::blend_add(
+                ::arithmetic_mode::__private::Channel::blend_add(a, b + 1),
+                c
+            )
+        },
+    );
+    let error = blend_add_impl(quote! { a }).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("blend_add! takes two or more"));
+    assert_expands(
+        scale_u8_impl,
+        quote! { x, alpha, 255, rounding = down },
+        quote! {
+            ::arithmetic_mode::__private::Channel::scale(
+                x,
+                alpha,
+                255,
+                ::arithmetic_mode::__private::Rounding::Down,
+            )
+        },
+    );
+    let error = scale_u8_impl(quote! { x, alpha }).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("scale_u8! takes three operands"));
+}
+
+#[test]
+fn test_evm() {
+    assert_expands(
+        evm_impl,
+        quote! { a * b ** 2 },
+        quote! {
+            {
+                let l = ::arithmetic_mode::evm::U256::from(a);
+                let r = {
+                    let l = ::arithmetic_mode::evm::U256::from(b);
+                    let r = ::arithmetic_mode::evm::U256::from_limbs([2u64, 0u64, 0u64, 0u64]);
+                    l.wrapping_pow(r)
+                };
+                l.wrapping_mul(r)
+            }
+        },
+    );
+    // `**` groups to the right, and binds tighter than the unary operators
+    assert_expands(
+        evm_impl,
+        quote! { -a ** b ** c },
+        quote! {
+            ({
+                let l = ::arithmetic_mode::evm::U256::from(a);
+                let r = {
+                    let l = ::arithmetic_mode::evm::U256::from(b);
+                    let r = ::arithmetic_mode::evm::U256::from(c);
+                    l.wrapping_pow(r)
+                };
+                l.wrapping_pow(r)
+            })
+            .wrapping_neg()
+        },
+    );
+    assert_expands(
+        evm_impl,
+        quote! { a / 0x1_0000_0000_0000_0000 },
+        quote! {
+            {
+                let l = ::arithmetic_mode::evm::U256::from(a);
+                let r = ::arithmetic_mode::evm::U256::from_limbs([0u64, 1u64, 0u64, 0u64]);
+                ::arithmetic_mode::__private::evm_div(l, r)
+            }
+        },
+    );
+    let error = evm_impl(quote! { a += 1 }).unwrap_err();
+    assert!(error.to_string().starts_with("Compound assignments"));
+}
+
+#[test]
+fn test_modfield() {
+    let modulus = const_block(quote! { ::arithmetic_mode::__private::Modulus::new(7_u64 as u64) });
+    assert_expands(
+        modfield_impl,
+        quote! { 7; a * 3 },
+        quote! {
+            {
+                let modulus = #modulus;
+                ::arithmetic_mode::__private::Modulus::narrow(7_u64, {
+                    let l = modulus.reduce(a);
+                    let r = modulus.reduce(3_u128);
+                    modulus.mul(l, r)
+                })
+            }
+        },
+    );
+    let error = modfield_impl(quote! { P; a % b }).unwrap_err();
+    assert_eq!(
+        "modfield! only supports `+`, `-`, `*` and `/`",
+        error.to_string()
+    );
+}
+
+#[test]
+fn test_ct_checked() {
+    assert_expands(
+        ct_checked_impl,
+        quote! { a + 1_u8 },
+        quote! {
+            {
+                let (v, valid) = {
+                    let (l, l_valid) = (a, ::arithmetic_mode::__private::subtle::Choice::from(1_u8));
+                    let (r, r_valid) = (1_u8, ::arithmetic_mode::__private::subtle::Choice::from(1_u8));
+                    let valid = l_valid & r_valid;
+                    let (v, overflow) = l.overflowing_add(r);
+                    (v, valid & ::arithmetic_mode::__private::subtle::Choice::from(u8::from(!overflow)))
+                };
+                ::arithmetic_mode::__private::subtle::CtOption::new(v, valid)
+            }
+        },
+    );
+    let error = ct_checked_impl(quote! { const a + 1_u8 }).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("ct_checked! cannot be used in const contexts"));
+}
+
+#[test]
+fn test_qformat() {
+    assert_expands(
+        qformat_impl,
+        quote! { Q15; a * 0.5 },
+        quote! {
+            {
+                let l = a;
+                let r = ::arithmetic_mode::__private::Fixed::saturate(16384_i128);
+                ::arithmetic_mode::__private::Fixed::q_mul::<15>(l, r)
+            }
+        },
+    );
+    let error = qformat_impl(quote! { Q0; a * b }).unwrap_err();
+    assert!(error.to_string().starts_with("Expected a Q format"));
+    let error = qformat_impl(quote! { Q15; a % b }).unwrap_err();
+    assert!(error.to_string().starts_with("qformat! only supports"));
+}
+
+#[test]
+fn test_elementwise() {
+    let expansion = elementwise_impl(quote! { [wrapping] a * (b + 2) - -c })
+        .unwrap()
+        .to_string();
+    for operand in ["a", "b", "c"] {
+        let lane = format!(":: arithmetic_mode :: __private :: lane (& {operand} , index , lanes)");
+        assert!(expansion.contains(&lane), "{operand} in {expansion}");
+    }
+    assert!(expansion.contains("let r = 2 ;"));
+    assert!(expansion.contains("wrapping_mul"));
+    let error = elementwise_impl(quote! { const a + b }).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("elementwise! cannot be used in const"));
+}
+
+#[test]
+fn test_forbid() {
+    assert_expands(
+        forbid_impl,
+        quote! { div, rem; [wrapping] a * b },
+        wrapping_impl(quote! { a * b }).unwrap(),
+    );
+    let error = forbid_impl(quote! { div; a / b + f(c / d) }).unwrap_err();
+    let messages: Vec<String> = error.into_iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        vec!["The `div` operator `/` is forbidden in this expression"; 2],
+        messages
+    );
+    let error = forbid_impl(quote! { modulo; a % b })
+        .unwrap_err()
+        .to_string();
+    assert!(error.starts_with("Unknown operator `modulo`"));
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_constant_folding() {
+    assert_expands(
+        panicking_impl,
+        quote! { u8: 2 * 3 + x },
+        panicking_op(
+            quote! { 6_u8 },
+            "checked_add",
+            quote! { *::core::borrow::Borrow::borrow(&x) },
+            "Add",
+            "6_u8 + x",
+        ),
+    );
+    assert_expands(
+        checked_impl,
+        quote! { (1_u32 << 31) / 2_u32 },
+        quote! { Some(1073741824_u32) },
+    );
+    assert_expands(wrapping_impl, quote! { 255_u8 + 1_u8 }, quote! { 0_u8 });
+    assert_expands(
+        saturating_impl,
+        quote! { i8: -100 - 100 },
+        quote! { i8::MIN },
+    );
+    assert_expands(panicking_impl, quote! { -50_i8 * 2_i8 }, quote! { -100_i8 });
+    assert_expands(panicking_impl, quote! { u8::MAX - 1_u8 }, quote! { 254_u8 });
+    let error = checked_impl(quote! { 200_u8 + 30_u8 + 30_u8 }).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "This operation always fails: attempt to add with overflow: `230_u8 + 30_u8`"
+    );
+    let error = try_checked_impl(quote! { i32: x + 1 / 0 }).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "This operation always fails: attempt to divide by zero: `1i32 / 0i32`"
+    );
+    panicking_impl(quote! { 1_u32 << 32 }).unwrap_err();
+    // Left to runtime
+    assert_expands(
+        wrapping_impl,
+        quote! { 1_i32 / 0_i32 },
+        bound_op(quote! { 1_i32 }, "wrapping_div", quote! { 0_i32 }),
+    );
+    assert_expands(
+        wrapping_impl,
+        quote! { 1_usize + 1_usize },
+        bound_op(quote! { 1_usize }, "wrapping_add", quote! { 1_usize }),
+    );
+}
+
+#[test]
+fn test_deep_expression() {
+    // Each operation is profiled with the source of its nested operations,
+    // whose size grows quadratically with the depth
+    let depth = if cfg!(feature = "profile") {
+        1_000
+    } else {
+        10_000
+    };
+    let terms = vec!["x"; depth].join(" + ");
+    let expand =
+        move || with_deep_stack(|| wrapping_impl(terms.parse().unwrap()).unwrap().to_string());
+    // Without `deep-stack`, the stack of the compiler is increased instead
+    let output = if cfg!(feature = "deep-stack") {
+        expand()
+    } else {
+        std::thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(expand)
+            .unwrap()
+            .join()
+            .unwrap()
+    };
+    assert_eq!(output.matches("wrapping_add").count(), depth - 1);
+}
+
+#[test]
+fn test_fast_checked() {
+    let expansion = fast_checked_impl(quote! { u8: a * b + 1 })
+        .unwrap()
+        .to_string();
+    assert!(expansion.contains("wrapping_mul"));
+    assert!(expansion.contains("let __arithmetic_mode_v3 : i32"));
+    assert!(expansion.contains("1u8"));
+    assert_eq!(1, expansion.matches(" if ").count());
+    let error = fast_checked_impl(quote! { a + b }).unwrap_err().to_string();
+    assert!(error.contains("needs the type of the operands"));
+    let error = fast_checked_impl(quote! { u64: a + b })
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("does not support `u64`"));
+    let error = fast_checked_impl(quote! { u8: a / b })
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("only supports `+`, `-` and `*`"));
+}
+
+#[test]
+fn test_const_checked() {
+    let message = "attempt to add with overflow: `A + 1`";
+    let panic = cold(quote! { ::core::panic!("{}", #message) });
+    let expected = const_block(quote! {
+        {
+            let l = A;
+            let r = 1;
+            match l.checked_add(r) {
+                ::core::option::Option::Some(v) => v,
+                ::core::option::Option::None => #panic,
+            }
+        }
+    });
+    assert_expands(const_checked_impl, quote! { A + 1 }, expected);
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_try_checked() {
+    let error = operation_error("Add", "a + 55_i32");
+    assert_expands(
+        try_checked_impl,
+        quote! { a + 55_i32 },
+        try_checked_match(
+            quote! { Ok::<_, ::arithmetic_mode::ArithmeticError>(a) },
+            quote! { Ok::<_, ::arithmetic_mode::ArithmeticError>(55_i32) },
+            try_checked_value("checked_add", error),
+        ),
+    );
+    assert_expands(
+        try_checked_impl,
+        quote! { parse(s)? | 1 },
+        try_checked_match(
+            quote! {
+                (parse(s)).map_err(::core::convert::Into::<::arithmetic_mode::ArithmeticError>::into)
+            },
+            quote! { Ok::<_, ::arithmetic_mode::ArithmeticError>(1) },
+            quote! { Ok(l | r) },
+        ),
+    );
+}
+
+#[test]
+fn test_control_flow() {
+    let result = try_checked_impl(quote! { a * b + 1_u32 }).unwrap();
+    assert_expands(
+        control_flow_impl,
+        quote! { a * b + 1_u32 },
+        quote! {
+            match #result {
+                ::core::result::Result::Ok(v) => ::core::ops::ControlFlow::Continue(v),
+                ::core::result::Result::Err(e) => ::core::ops::ControlFlow::Break(e),
+            }
+        },
+    );
+    let expansion = passthrough("control_flow", quote! { a + b }, quote! { expansion });
+    assert!(expansion
+        .to_string()
+        .contains(&quote! { ::core::ops::ControlFlow::Continue(v) }.to_string()));
+}
+
+#[test]
+fn test_try_checked_custom_error() {
+    let expansion = try_checked_impl(quote! { a + 55_i32 }).unwrap();
+    assert_expands(
+        try_checked_impl,
+        quote! { err = MyError::MathOverflow; a + 55_i32 },
+        quote! {
+            match #expansion {
+                ::core::result::Result::Ok(v) => ::core::result::Result::Ok(v),
+                ::core::result::Result::Err(_) => ::core::result::Result::Err(MyError::MathOverflow),
+            }
+        },
+    );
+    let error = try_checked_impl(quote! { err = MyError::MathOverflow a + 1 }).unwrap_err();
+    assert_eq!("expected `;`", error.to_string());
+    // Comparisons with a variable named `err` are expressions
+    assert!(try_checked_impl(quote! { err == a + 1_u8 }).is_ok());
+}
+
+#[test]
+fn test_const() {
+    assert_expands(
+        wrapping_impl,
+        quote! { const a + b },
+        const_bound_op(quote! { a }, "wrapping_add", quote! { b }),
+    );
+    let error = cold(quote! {
+        Err(::arithmetic_mode::ArithmeticError::new(::arithmetic_mode::Operation::Mul))
+    });
+    let value = quote! {
+        match l.checked_mul(r) {
+            Some(v) => Ok(v),
+            None => #error,
+        }
+    };
+    assert_expands(
+        try_checked_impl,
+        quote! { const u8: a * 2 },
+        try_checked_match(
+            quote! { Ok::<_, ::arithmetic_mode::ArithmeticError>(a) },
+            quote! { Ok::<_, ::arithmetic_mode::ArithmeticError>(2u8) },
+            value,
+        ),
+    );
+    try_checked_impl(quote! { const parse(s)? + 1 }).unwrap_err();
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_literal_type_propagation() {
+    let mul = bound_op(quote! { x }, "wrapping_mul", quote! { 3i32 });
+    assert_expands(
+        wrapping_impl,
+        quote! { x * 3 + 4_i32 },
+        bound_op(mul, "wrapping_add", quote! { 4_i32 }),
+    );
+    assert_expands(
+        wrapping_impl,
+        quote! { 2 - x as u8 },
+        bound_op(quote! { 2u8 }, "wrapping_sub", quote! { x as u8 }),
+    );
+    assert_expands(
+        wrapping_impl,
+        quote! { (x as u64) << 2 },
+        bound_op(quote! { (x as u64) }, "wrapping_shl", quote! { 2 }),
+    );
+    let add = bound_op(quote! { 1_u8 }, "wrapping_add", quote! { 2_i8 });
+    assert_expands(
+        wrapping_impl,
+        quote! { 1_u8 + 2_i8 - 3 },
+        bound_op(add, "wrapping_sub", quote! { 3 }),
+    );
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_literal_type_prefix() {
+    let mul = bound_op(
+        quote! { 2u64 },
+        "wrapping_mul",
+        quote! { *::core::borrow::Borrow::borrow(&x) },
+    );
+    assert_expands(
+        wrapping_impl,
+        quote! { u64: 2 * x + 3 },
+        bound_op(mul, "wrapping_add", quote! { 3u64 }),
+    );
+    assert_expands(
+        wrapping_impl,
+        quote! { i8: 2 - 1_u8 },
+        bound_op(quote! { 2i8 }, "wrapping_sub", quote! { 1_u8 }),
+    );
+    wrapping_impl(quote! { foo: 1 + 2 }).unwrap_err();
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_type_hint() {
+    let hint = quote! { { let __arithmetic_mode_hint: u8 = 2u8; __arithmetic_mode_hint } };
+    assert_expands(
+        wrapping_impl,
+        quote! { (2: u8) + x },
+        bound_op(
+            hint,
+            "wrapping_add",
+            quote! { *::core::borrow::Borrow::borrow(&x) },
+        ),
+    );
+    let hint = quote! {
+        match Some(x) {
+            Some(v) => Some::<i8>(v),
+            None => None,
+        }
+    };
+    assert_expands(
+        checked_impl,
+        quote! { (x: i8) * 2 },
+        checked_match(
+            hint,
+            quote! { Some(2i8) },
+            checked_value("checked_mul", quote! { r }),
+        ),
+    );
+    assert_eq!(
+        rewrite_type_hints(quote! { (|x: u8| x)(1) }).to_string(),
+        quote! { (|x: u8| x)(1) }.to_string()
+    );
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_attribute() {
+    let add = bound_op(quote! { a }, "wrapping_add", quote! { 1 });
+    let mul = bound_op(quote! { g(#add) }, "wrapping_mul", quote! { 2 });
+    let expansion = arithmetic_mode_impl(
+        quote! { wrapping },
+        quote! {
+            fn f(a: u8) -> u8 {
+                g(a + 1) * 2
+            }
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        quote! { fn f(a: u8) -> u8 { #mul } }.to_string(),
+        expansion.to_string()
+    );
+    let add = bound_op(
+        quote! { *__arithmetic_mode_place },
+        "saturating_add",
+        quote! { *::core::borrow::Borrow::borrow(&__arithmetic_mode_rhs) },
+    );
+    let expansion =
+        arithmetic_mode_impl(quote! { saturating }, quote! { fn f() { a += b; } }).unwrap();
+    let expected = quote! {
+        fn f() {
+            {
+                let __arithmetic_mode_rhs = b;
+                let __arithmetic_mode_place = &mut a;
+                *__arithmetic_mode_place = #add;
+            };
+        }
+    };
+    assert_eq!(expected.to_string(), expansion.to_string());
+    let expansion = arithmetic_mode_impl(
+        quote! { panicking },
+        quote! { const fn f(a: u8) -> u8 { a + 1 } },
+    )
+    .unwrap();
+    let message = "attempt to add with overflow: `a + 1`";
+    let panic = cold(quote! { ::core::panic!("{}", #message) });
+    let expected = quote! {
+        const fn f(a: u8) -> u8 {
+            {
+                let l = a;
+                let r = 1;
+                match l.checked_add(r) {
+                    ::core::option::Option::Some(v) => v,
+                    ::core::option::Option::None => #panic,
+                }
+            }
+        }
+    };
+    assert_eq!(expected.to_string(), expansion.to_string());
+    arithmetic_mode_impl(quote! { checked }, quote! { fn f() { a += b; } }).unwrap_err();
+    arithmetic_mode_impl(quote! { unknown }, quote! { fn f() {} }).unwrap_err();
+}
+
+#[test]
+fn test_attribute_operators() {
+    let expansion = arithmetic_mode_impl(
+        quote! { add = "wrapping", mul = "saturating" },
+        quote! { fn f(a: u8, b: u8) -> u8 { a * b + a / b } },
+    )
+    .unwrap()
+    .to_string();
+    for func in ["wrapping_add", "saturating_mul", "checked_div"] {
+        assert!(expansion.contains(func), "{func} in {expansion}");
+    }
+    // The compound assignments use the mode of their operator
+    let expansion = arithmetic_mode_impl(
+        quote! { checked, add = "wrapping" },
+        quote! { fn f(mut a: u8) { a += 1; } },
+    )
+    .unwrap()
+    .to_string();
+    assert!(expansion.contains("wrapping_add"));
+    let error = |attr| {
+        arithmetic_mode_impl(attr, quote! { fn f(a: u8) -> u8 { a * a + 1 } })
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(error(quote! { mul = "checked" })
+        .starts_with("A checked operation cannot be an operand of a panicking operation"));
+    assert!(error(quote! { neg = "wrapping" }).starts_with("Unknown operator `neg`"));
+    assert_eq!(
+        "Expected the mode in quotes, like `add = \"wrapping\"`",
+        error(quote! { add = wrapping })
+    );
+    assert_eq!(
+        "Unknown arithmetic mode `fast`",
+        error(quote! { add = "fast" })
+    );
+    assert_eq!(
+        "The mode of `add` is given more than once",
+        error(quote! { add = "wrapping", add = "checked" })
+    );
+}
+
+#[test]
+fn test_policy() {
+    let config = r#"
+        # The rules of the payments team
+        [policy.payments]
+        mode = "checked"
+        mul = 'wrapping' # the hashes
+        audit = true
+
+        [policy.payments.modules]
+        fees = "saturating"
+        "billing::refunds" = "panicking"
+
+        [policy.pixels.types]
+        u8 = "saturating"
+    "#;
+    let policy = parse_policy(config, "payments").unwrap();
+    assert_eq!(Some(Mode::Checked), policy.mode);
+    assert_eq!(vec![("mul", Mode::Wrapping)], policy.operators);
+    assert!(policy.audit);
+    assert!(policy.types.is_empty());
+    let fees = (vec!["fees".to_string()], Mode::Saturating);
+    let refunds = (
+        vec!["billing".to_string(), "refunds".to_string()],
+        Mode::Panicking,
+    );
+    assert_eq!(vec![fees, refunds], policy.modules);
+    let policy = parse_policy(config, "pixels").unwrap();
+    assert_eq!(None, policy.mode);
+    assert_eq!("u8", policy.types[0].0.to_string());
+    assert_eq!(Mode::Saturating, policy.types[0].1);
+    let error = |config: String| parse_policy(&config, "payments").unwrap_err();
+    assert_eq!(
+        "The policy `payments` is not declared",
+        error(config.replace("payments", "billing"))
+    );
+    assert_eq!(
+        "line 2: Unknown key `neg`, expected `mode`, `audit` or one of add, sub, mul, div, rem, \
+        shl, shr",
+        error("[policy.payments]\nneg = \"wrapping\"".to_string())
+    );
+    assert_eq!(
+        "line 2: Unknown arithmetic mode `fast`",
+        error("[policy.payments]\nmode = \"fast\"".to_string())
+    );
+    assert_eq!(
+        "line 3: `mode` is given more than once",
+        error("[policy.payments]\nmode = \"checked\"\nmode = \"wrapping\"".to_string())
+    );
+    assert_eq!(
+        "line 1: Unknown table `payments`, expected a policy like `[policy.payments]`",
+        error("[payments]".to_string())
+    );
+    assert_eq!(
+        "line 2: Unterminated string",
+        error("[policy.payments]\nmode = \"checked".to_string())
+    );
+    assert_eq!(
+        "The checked mode cannot be used with the modes of the types, which only support \
+        panicking, wrapping and saturating mode",
+        error(
+            "[policy.payments]\nmode = \"checked\"\n[policy.payments.types]\nu8 = \"wrapping\""
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn test_policy_tracked() {
+    // The config file of the tests is the one at the root of the workspace
+    let config = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .join("arithmetic-mode.toml");
+    let config = config.to_str().unwrap();
+    let tracked = quote! { const _: &[u8] = ::core::include_bytes!(#config); }.to_string();
+    let expansion = |attr, item| arithmetic_mode_impl(attr, item).unwrap().to_string();
+    let item_fn = quote! { fn f(a: u32) -> u32 { a + 1 } };
+    // In the bodies of the items that may be in an impl block
+    let output = expansion(quote! { policy = "hashes" }, item_fn.clone());
+    assert!(output.starts_with(&format!("fn f (a : u32) -> u32 {{ {tracked}")));
+    let output = expansion(
+        quote! { policy = "hashes" },
+        quote! { const A: u32 = B + 1; },
+    );
+    assert!(output.starts_with(&format!("const A : u32 = {{ {tracked}")));
+    // Next to the other items
+    let output = expansion(quote! { policy = "hashes" }, quote! { impl S { #item_fn } });
+    assert!(output.ends_with(&tracked));
+    assert!(!expansion(quote! { wrapping }, item_fn).contains("include_bytes"));
+}
+
+#[test]
+fn test_closure() {
+    let expansion = closure_impl(quote! { wrapping, |x: u8| x * 2 + 1 })
+        .unwrap()
+        .to_string();
+    for func in ["wrapping_mul", "wrapping_add"] {
+        assert!(expansion.contains(func), "{func} in {expansion}");
+    }
+    let expansion = closure_impl(quote! { saturating, mul = "wrapping", |x: u8| x * 2 + 1 })
+        .unwrap()
+        .to_string();
+    for func in ["wrapping_mul", "saturating_add"] {
+        assert!(expansion.contains(func), "{func} in {expansion}");
+    }
+    // The closure is left unchanged
+    assert_expands(
+        closure_impl,
+        quote! { skip, |x: u8| x * 2 },
+        quote! { |x: u8| x * 2 },
+    );
+    let error = |item| closure_impl(item).unwrap_err().to_string();
+    let expected =
+        "closure! takes the mode and a closure, like `closure!(wrapping, |x| x * 2 + 1)`";
+    assert_eq!(expected, error(quote! { |x: u8| x * 2 }));
+    assert_eq!(expected, error(quote! { wrapping, x * 2 }));
+    assert_eq!(
+        "Unknown arithmetic mode `fast`",
+        error(quote! { fast, |x: u8| x * 2 })
+    );
+}
+
+#[test]
+fn test_default() {
+    // Without `ARITHMETIC_MODE_DEFAULT`, the mode is selected among all of
+    // them by the cfgs of `arithmetic-mode`
+    let expansion = default_impl(quote! { a * b }).unwrap().to_string();
+    assert!(expansion.contains("option_env"), "{expansion}");
+    assert!(
+        expansion.contains(":: arithmetic_mode :: __default !"),
+        "{expansion}"
+    );
+    for func in ["checked_mul", "wrapping_mul", "saturating_mul"] {
+        assert!(expansion.contains(func), "{func} in {expansion}");
+    }
+    let expansion = arithmetic_mode_impl(
+        quote! { default, audit },
+        quote! { fn f(a: u8, b: u8) -> u8 { a * b } },
+    )
+    .unwrap()
+    .to_string();
+    assert!(
+        expansion.starts_with(":: arithmetic_mode :: __default !"),
+        "{expansion}"
+    );
+    assert_eq!(3, expansion.matches("fn f").count(), "{expansion}");
+    assert!(
+        expansion.contains("uses saturating arithmetic"),
+        "{expansion}"
+    );
+}
+
+#[test]
+fn test_stmt() {
+    let value = wrapping_impl(quote! { a + b }).unwrap();
+    assert_expands(
+        stmt_impl,
+        quote! { wrapping: let total = a + b; },
+        quote! { let total = #value; },
+    );
+    assert_expands(
+        stmt_impl,
+        quote! { wrapping: total = a * b; },
+        wrapping_impl(quote! { a * b })
+            .map(|value| quote! { total = #value; })
+            .unwrap(),
+    );
+    let expansion = stmt_impl(quote! { checked: let Some(total) = a * b else { return }; })
+        .unwrap()
+        .to_string();
+    assert!(expansion.contains("checked_mul"), "{expansion}");
+    assert!(expansion.ends_with("else { return } ;"), "{expansion}");
+    let error = |item| stmt_impl(item).unwrap_err().to_string();
+    assert_eq!(
+        "stmt! takes a single statement",
+        error(quote! { checked: a; b; })
+    );
+    assert_eq!(
+        "stmt! only supports `let` statements with a value and assignments",
+        error(quote! { checked: let total; })
+    );
+    assert_eq!(
+        "stmt! only supports `let` statements with a value and assignments",
+        error(quote! { wrapping: total += a; })
+    );
+    assert_eq!(
+        "Unknown arithmetic mode `fast`",
+        error(quote! { fast: let total = a + b; })
+    );
+}
+
+#[test]
+fn test_attribute_audit() {
+    let expansion = arithmetic_mode_impl(
+        quote! { wrapping, audit },
+        quote! {
+            fn f(a: u8) -> u8 {
+                #[arithmetic_mode(skip)]
+                let b = a * 2;
+                f(b) + 1
+            }
+        },
+    )
+    .unwrap()
+    .to_string();
+    assert!(expansion.contains("arithmetic_mode audit: `f (b) + 1` uses wrapping arithmetic"));
+    assert!(expansion.contains("arithmetic_mode audit: `a * 2` is skipped"));
+    assert_eq!(2, expansion.matches("deprecated (note").count());
+    let expansion = arithmetic_mode_impl(quote! { wrapping }, quote! { fn f(a: u8) { a + 1 } })
+        .unwrap()
+        .to_string();
+    assert!(!expansion.contains("audit"));
+    arithmetic_mode_impl(quote! { wrapping, unknown }, quote! { fn f() {} }).unwrap_err();
+}
+
+#[test]
+fn test_attribute_conflicts() {
+    let expansion = arithmetic_mode_impl(
+        quote! { checked },
+        quote! {
+            fn f(a: u8, b: u8) -> Option<u8> {
+                let c = wrapping! { a * 31 } ^ checked! { a * b }?;
+                Some(a.saturating_sub(b) + c.checked_add(1)?)
+            }
+        },
+    )
+    .unwrap()
+    .to_string();
+    assert!(expansion.contains("`wrapping!` overrides the checked mode"));
+    assert!(expansion.contains("`saturating_sub` keeps its explicit arithmetic"));
+    assert_eq!(2, expansion.matches("deprecated (note").count());
+    let expansion = fallible_fn_impl(
+        Mode::Checked,
+        TokenStream::new(),
+        quote! {
+            #[arithmetic_mode(wrapping)]
+            fn f(a: u8) -> u8 { a + 1 }
+        },
+    )
+    .unwrap()
+    .to_string();
+    assert!(expansion
+        .contains("transformed twice, by `#[checked_fn]` and again by `#[arithmetic_mode]`"));
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_checked_fn() {
+    let expansion = fallible_fn_impl(
+        Mode::Checked,
+        TokenStream::new(),
+        quote! {
+            fn f(a: u8) -> u8 {
+                if a == 0 { return 1; }
+                a * 2
+            }
+        },
+    )
+    .unwrap();
+    let mul = checked_match(
+        quote! { Some(a) },
+        quote! { Some(2) },
+        checked_value("checked_mul", quote! { r }),
+    );
+    let expected = quote! {
+        fn f(a: u8) -> ::core::option::Option<u8> {
+            ::core::option::Option::Some({
+                if a == 0 { return ::core::option::Option::Some(1); }
+                (#mul)?
+            })
+        }
+    };
+    assert_eq!(expected.to_string(), expansion.to_string());
+}
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn test_try_fn() {
+    let expansion = fallible_fn_impl(
+        Mode::TryChecked,
+        quote! { MyError },
+        quote! {
+            fn f(a: u8) -> u8 {
+                a + 1
+            }
+        },
+    )
+    .unwrap();
+    let add = try_checked_match(
+        quote! { Ok::<_, ::arithmetic_mode::ArithmeticError>(a) },
+        quote! { Ok::<_, ::arithmetic_mode::ArithmeticError>(1) },
+        try_checked_value("checked_add", operation_error("Add", "a + 1")),
+    );
+    let expected = quote! {
+        fn f(a: u8) -> ::core::result::Result<u8, MyError> {
+            ::core::result::Result::Ok({
+                (#add)?
+            })
+        }
+    };
+    assert_eq!(expected.to_string(), expansion.to_string());
+    fallible_fn_impl(Mode::Checked, quote! { MyError }, quote! { fn f() {} }).unwrap_err();
+}
+
+#[test]
+fn test_deny_unchecked_arithmetic() {
+    let output = deny_unchecked_arithmetic_impl(
+        TokenStream::new(),
+        quote! {
+            fn f(a: u8) -> u8 {
+                let b = wrapping! { a + 1 };
+                #[arithmetic_mode(skip)]
+                let c = b * 2;
+                #[arithmetic_mode(checked)]
+                fn g(a: u8) -> Option<u8> { a << 1 }
+                c - a
+            }
+        },
+    )
+    .unwrap()
+    .to_string();
+    assert_eq!(1, output.matches("compile_error").count());
+    assert!(output.contains("Unchecked arithmetic operator `-`"));
+    assert_eq!(1, output.matches("skip").count());
+}
+
+#[test]
+fn test_enforce() {
+    let output = enforce_impl(
+        quote! { default = "checked", allow = ["wrapping", "skip"] },
+        quote! {
+            mod m {
+                fn f(a: u8) -> Option<u8> {
+                    let b = wrapping! { a + 1 };
+                    #[arithmetic_mode(skip)]
+                    let c = b * 2;
+                    let d = saturating! { c + 1 };
+                    checked! { d * 2 }
+                }
+                #[arithmetic_mode(panicking)]
+                fn g(a: u8) -> u8 { a << 1 }
+                #[arithmetic_mode(checked, add = "wrapping", mul = "saturating")]
+                fn h(a: u8) -> Option<u8> { a * 2 + 1 }
+            }
+        },
+    )
+    .unwrap()
+    .to_string();
+    assert_eq!(3, output.matches("compile_error").count());
+    assert!(output.contains("The `saturating` mode is not allowed here"));
+    assert!(output.contains("The `panicking` mode is not allowed here"));
+    assert_eq!(2, output.matches("The `saturating` mode").count());
+    // The modes of a policy of `arithmetic-mode.toml`
+    let output = enforce_impl(
+        quote! { default = "checked", allow = ["wrapping"] },
+        quote! {
+            #[arithmetic_mode(policy = "pixels")]
+            mod m {}
+        },
+    )
+    .unwrap()
+    .to_string();
+    assert_eq!(1, output.matches("compile_error").count());
+    assert!(output.contains("The `saturating` mode is not allowed here"));
+    enforce_impl(quote! { allow = ["checked"] }, quote! { mod m {} }).unwrap_err();
+    enforce_impl(quote! { default = "skip" }, quote! { mod m {} }).unwrap_err();
+    enforce_impl(quote! { default = "unknown" }, quote! { mod m {} }).unwrap_err();
+}
+
+#[test]
+fn test_differential_test() {
+    let output = differential_test_impl(quote! {
+        fn f(a: u8 in 0..=255) -> u8 { a * 2 - 1 }
+    })
+    .unwrap()
+    .to_string();
+    assert!(output.contains("wrapping! differs from the truncated exact result"));
+    let output = differential_test_impl(quote! {
+        fn f(a: u8 in 0..=255) -> u8 { a / 2 - 1 }
+    })
+    .unwrap()
+    .to_string();
+    assert!(!output.contains("wrapping! differs from the truncated exact result"));
+    differential_test_impl(quote! {
+        fn f(a: u8 in 0..=255) -> u8 { a << 2 }
+    })
+    .unwrap_err();
+}
+
+#[test]
+fn test_proptest_modes() {
+    let output = proptest_modes_impl(quote! { |a: u8, b: u8| a * b })
+        .unwrap()
+        .to_string();
+    assert!(output.contains("saturating! does not clamp"));
+    let output = proptest_modes_impl(quote! { |a: u8, b: u8| a * b + 7 })
+        .unwrap()
+        .to_string();
+    assert!(!output.contains("saturating! does not clamp"));
+    let output = proptest_modes_impl(quote! { |a: i8, b: i8| a % b })
+        .unwrap()
+        .to_string();
+    assert!(!output.contains("saturating! does not clamp"));
+    proptest_modes_impl(quote! { |a: u8| a >> 2 }).unwrap_err();
+    proptest_modes_impl(quote! { |(a, b): (u8, u8)| a * b }).unwrap_err();
+}
+
+#[test]
+fn test_passthrough() {
+    let selected = |original: TokenStream| {
+        quote! { ::arithmetic_mode::__passthrough! { { #original } { expansion } } }.to_string()
+    };
+    let expansion = || quote! { expansion };
+    assert_eq!(
+        selected(quote! {
+            a * 2u8 + { let __arithmetic_mode_hint: u8 = b; __arithmetic_mode_hint }
+        }),
+        passthrough("wrapping", quote! { a * 2 + (b: u8) }, expansion()).to_string()
+    );
+    assert_eq!(
+        selected(quote! { ::core::option::Option::Some(a - 1_i32) }),
+        passthrough("checked", quote! { a - 1_i32 }, expansion()).to_string()
+    );
+    assert_eq!(
+        selected(const_block(quote! { 60_u32 * 60_u32 })),
+        passthrough(
+            "const_checked",
+            quote! { "hour": 60_u32 * 60_u32 },
+            expansion()
+        )
+        .to_string()
+    );
+    assert_eq!(
+        selected(quote! {
+            ::core::result::Result::Ok::<_, ::arithmetic_mode::ArithmeticError>(a + b * c)
+        }),
+        passthrough(
+            "mixed",
+            "[try_checked] a + b *% c".parse().unwrap(),
+            expansion()
+        )
+        .to_string()
+    );
+    assert_eq!(
+        selected(quote! {
+            match ::core::result::Result::Ok::<_, ::arithmetic_mode::ArithmeticError>(a - b) {
+                ::core::result::Result::Ok(v) => ::core::result::Result::Ok(v),
+                ::core::result::Result::Err(_) => ::core::result::Result::Err(Error::Overflow),
+            }
+        }),
+        passthrough(
+            "try_checked",
+            quote! { err = Error::Overflow; a - b },
+            expansion()
+        )
+        .to_string()
+    );
+    let expansion = passthrough_attribute(
+        "checked_fn",
+        TokenStream::new(),
+        quote! { fn f(a: u8) -> u8 { a + 1 } },
+        quote! { expansion },
+    );
+    let original = quote! {
+        fn f(a: u8) -> ::core::option::Option<u8> {
+            ::core::option::Option::Some({ (::core::option::Option::Some(a + 1))? })
+        }
+    };
+    assert_eq!(selected(original), expansion.to_string());
+    // Operands that are already an `Option` need the checks
+    assert_eq!(
+        "expansion",
+        passthrough("checked", quote! { opt(a) + 1_u8 }, quote! { expansion }).to_string()
+    );
+    assert_eq!(
+        "expansion",
+        passthrough("forbid", quote! { a + 1 }, quote! { expansion }).to_string()
+    );
+}
+
+#[test]
+fn test_hoist() {
+    let hoist = |expr: TokenStream| {
+        let (temporaries, expr) = hoist(syn::parse2(expr).unwrap());
+        let temporaries: Vec<_> = temporaries
+            .iter()
+            .map(|(name, value)| quote! { #name = #value }.to_string())
+            .collect();
+        (temporaries, expr.to_token_stream().to_string())
+    };
+    assert_eq!(
+        (
+            vec![quote! { __arithmetic_mode_h0 = a * b }.to_string()],
+            quote! { (__arithmetic_mode_h0) + (__arithmetic_mode_h0) / c }.to_string()
+        ),
+        hoist(quote! { (a * b) + (a * b) / c })
+    );
+    // The outer operations reuse the temporaries of the inner ones
+    assert_eq!(
+        (
+            vec![
+                quote! { __arithmetic_mode_h0 = a * b }.to_string(),
+                quote! { __arithmetic_mode_h1 = __arithmetic_mode_h0 + c }.to_string(),
+            ],
+            quote! { (__arithmetic_mode_h1) * (__arithmetic_mode_h1) - __arithmetic_mode_h0 }
+                .to_string()
+        ),
+        hoist(quote! { (a * b + c) * (a * b + c) - a * b })
+    );
+    // Operations with side effects or without checks are left in place
+    for expr in [
+        quote! { v[i] * 2 + v[i] * 2 },
+        quote! { opt(a) * 2 + opt(a) * 2 },
+        quote! { (a & b) + (a & b) },
+    ] {
+        assert!(hoist(expr).0.is_empty());
+    }
+}
+
+/// The expected overflow `branch` of `expression` with the `profile` feature,
+/// which first counts the overflow.
+#[cfg(feature = "profile")]
+fn profiled(expression: &str, branch: TokenStream) -> TokenStream {
+    quote! {
+        {
+            static SITE: ::arithmetic_mode::profile::Site =
+                ::arithmetic_mode::profile::Site::__new(
+                    #expression,
+                    ::arithmetic_mode::Location::__new(
+                        ::core::file!(), ::core::line!(), ::core::column!()
+                    )
+                );
+            SITE.__record();
+            #branch
+        }
+    }
+}
+
+// The other snapshot tests are the ones without the `profile` feature
+#[cfg(feature = "profile")]
+#[test]
+fn test_profile() {
+    let panic = cold(profiled(
+        "a + 55_i32",
+        operation_panic("Add", "a + 55_i32", None),
+    ));
+    let call = method("checked_add", quote! { r });
+    assert_expands(
+        panicking_impl,
+        quote! { a + 55_i32 },
+        quote! {
+            {
+                let l = a;
+                let r = 55_i32;
+                match #call {
+                    ::core::option::Option::Some(v) => v,
+                    ::core::option::Option::None => #panic,
+                }
+            }
+        },
+    );
+    // The wrapping operations are checked first to count their overflows
+    let overflow = profiled("a * 3", method("wrapping_mul", quote! { r }));
+    let call = method("checked_mul", quote! { r });
+    assert_expands(
+        wrapping_impl,
+        quote! { a * 3 },
+        quote! {
+            {
+                let l = a;
+                let r = 3;
+                match #call {
+                    ::core::option::Option::Some(v) => v,
+                    ::core::option::Option::None => #overflow,
+                }
+            }
+        },
+    );
+    let none = cold(profiled("a - 1_u8", quote! { None }));
+    let call = method("checked_sub", quote! { r });
+    assert_expands(
+        checked_impl,
+        quote! { a - 1_u8 },
+        checked_match(
+            quote! { Some(a) },
+            quote! { Some(1_u8) },
+            quote! {
+                match #call {
+                    Some(v) => Some(v),
+                    None => #none,
+                }
+            },
+        ),
+    );
+    let error = operation_error("Shl", "a << 2");
+    let error = cold(profiled("a << 2", quote! { Err(#error) }));
+    let call = method("checked_shl", quote! { r });
+    assert_expands(
+        try_checked_impl,
+        quote! { a << 2 },
+        try_checked_match(
+            quote! { Ok::<_, ::arithmetic_mode::ArithmeticError>(a) },
+            quote! { Ok::<_, ::arithmetic_mode::ArithmeticError>(2) },
+            quote! {
+                match #call {
+                    Some(v) => Ok(v),
+                    None => #error,
+                }
+            },
+        ),
+    );
+    // The statics cannot be used in const contexts
+    assert_expands(
+        wrapping_impl,
+        quote! { const a + b },
+        const_bound_op(quote! { a }, "wrapping_add", quote! { b }),
+    );
+}
+
+// The overflows are reported with `defmt`, unless they abort
+#[cfg(all(feature = "defmt", not(feature = "abort")))]
+#[test]
+fn test_defmt() {
+    let expansion = panicking_impl(quote! { a + b }).unwrap().to_string();
+    assert!(expansion.contains("ArithmeticError :: __defmt_panic_operation"));
+    assert!(!expansion.contains("__panic_operation"));
+}
+
+// The expansion is the one without the features changing the operations
+#[cfg(all(
+    feature = "pretty",
+    not(any(feature = "nightly", feature = "qualified", feature = "profile"))
+))]
+#[test]
+fn test_expand_pretty() {
+    use crate::pretty::expand_pretty;
+
+    let expected = "\
+match Some(a) {
+    Some(l) => {
+        match Some(1u8) {
+            Some(r) => l.checked_add(r),
+            None => None,
+        }
+    }
+    None => None,
+}
+";
+    assert_eq!(expected, expand_pretty(Mode::Checked, "u8: a + 1").unwrap());
+    expand_pretty(Mode::Wrapping, "a +").unwrap_err();
+}
+
+/// The identifiers and parentheses of `tokens`, with the line and column
+/// where their spans start.
+fn span_starts(tokens: TokenStream, starts: &mut Vec<(String, (usize, usize))>) {
+    for token in tokens {
+        let start = token.span().start();
+        match token {
+            TokenTree::Group(group) => {
+                if group.delimiter() == Delimiter::Parenthesis {
+                    starts.push(("(".to_string(), (start.line, start.column)));
+                }
+                span_starts(group.stream(), starts);
+            }
+            TokenTree::Ident(ident) => starts.push((ident.to_string(), (start.line, start.column))),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_operand_spans() {
+    let source = "x * (a + b) * c - y";
+    let mut expected = Vec::new();
+    span_starts(source.parse().unwrap(), &mut expected);
+    for (name, f) in [
+        (
+            "panicking",
+            panicking_impl as fn(TokenStream) -> syn::Result<TokenStream>,
+        ),
+        ("wrapping", wrapping_impl),
+        ("saturating", saturating_impl),
+        ("checked", checked_impl),
+        ("try_checked", try_checked_impl),
+        ("mixed", mixed_impl),
+    ] {
+        let mut starts = Vec::new();
+        span_starts(f(source.parse().unwrap()).unwrap(), &mut starts);
+        for token in &expected {
+            assert!(
+                starts.contains(token),
+                "{name}! loses the span of {token:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_incomplete_expression() {
+    let incomplete = |name: &str, source: &str| {
+        incomplete_expression(name, source.parse().unwrap()).map(|tokens| tokens.to_string())
+    };
+    assert_eq!(Some("foo .".to_string()), incomplete("checked", "foo."));
+    assert_eq!(
+        Some("a + foo .".to_string()),
+        incomplete("wrapping", "u8: a + foo.")
+    );
+    assert_eq!(
+        Some("a *".to_string()),
+        incomplete("panicking", r#""budget": const a *"#)
+    );
+    // Complete expressions fail for other reasons, and the other macros have
+    // their own syntax
+    assert_eq!(None, incomplete("checked", "(a: u8) + f(b)"));
+    assert_eq!(None, incomplete("mixed", "a +% b."));
+}
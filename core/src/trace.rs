@@ -0,0 +1,193 @@
+//! The `trace!` macro, which transforms an expression like the mode given in
+//! brackets, like `trace! { [checked] a * b + c }`, and prints each operation
+//! with its operands and result at runtime, like `200 * 2 = 144`.
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+use syn::{Expr, ExprBinary, Type, UnOp};
+
+use crate::attribute::Mode;
+use crate::engine::{self, Strategy};
+use crate::{
+    checked_op, fold, input, mixed, Checked, Options, Panicking, Saturating, TryChecked, Wrapping,
+};
+
+/// Expands `trace! { item }`.
+pub fn trace_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    let (mode, item) = mixed::split_default_mode(item)?;
+    let (expr, options) = input::parse_expr(item)?;
+    if options.is_const {
+        return Err(syn::Error::new_spanned(
+            &expr,
+            "trace! cannot be used in const contexts, where nothing can be printed",
+        ));
+    }
+    traced(expr, mode, options, &|binary, [lhs, rhs, result]| {
+        let format = format!("{{:?}} {} {{:?}} = {{:?}}", binary.op.to_token_stream());
+        Ok(quote_spanned! { binary.op.span()=>
+            ::arithmetic_mode::__private::trace(
+                ::core::format_args!(#format, #lhs, #rhs, #result),
+            );
+        })
+    })
+}
+
+/// The statements that observe an operation, given the bindings of its
+/// operands and its result.
+pub(crate) type Observer<'a> = &'a dyn Fn(&ExprBinary, [&Ident; 3]) -> syn::Result<TokenStream>;
+
+/// Transforms `expr` into `mode`, running the statements of `observe` after
+/// each operation that can overflow.
+pub(crate) fn traced(
+    expr: Expr,
+    mode: Mode,
+    options: Options,
+    observe: Observer,
+) -> syn::Result<TokenStream> {
+    let expr = fold::fold_constants(expr, mode, options.division)?;
+    match mode {
+        Mode::Panicking | Mode::Verified => {
+            let options = Options {
+                verified: mode == Mode::Verified,
+                ..options
+            };
+            let base = Panicking {
+                message: None,
+                options,
+            };
+            engine::transform_expr(
+                &expr,
+                &Traced {
+                    base,
+                    mode,
+                    observe,
+                },
+            )
+        }
+        Mode::Wrapping => {
+            let base = Wrapping { options };
+            engine::transform_expr(
+                &expr,
+                &Traced {
+                    base,
+                    mode,
+                    observe,
+                },
+            )
+        }
+        Mode::Saturating => {
+            let base = Saturating { options };
+            engine::transform_expr(
+                &expr,
+                &Traced {
+                    base,
+                    mode,
+                    observe,
+                },
+            )
+        }
+        Mode::Checked => {
+            let base = Checked { options };
+            engine::transform_expr(
+                &expr,
+                &Traced {
+                    base,
+                    mode,
+                    observe,
+                },
+            )
+        }
+        Mode::TryChecked => {
+            let base = TryChecked { options };
+            engine::transform_expr(
+                &expr,
+                &Traced {
+                    base,
+                    mode,
+                    observe,
+                },
+            )
+        }
+    }
+}
+
+/// The operations of the `base` strategy of `mode`, which are also observed
+/// by `observe`.
+struct Traced<'a, S> {
+    base: S,
+    mode: Mode,
+    observe: Observer<'a>,
+}
+
+impl<S: Strategy> Strategy for Traced<'_, S> {
+    fn is_fallible(&self) -> bool {
+        self.base.is_fallible()
+    }
+
+    fn operand(&self, expr: &Expr) -> TokenStream {
+        self.base.operand(expr)
+    }
+
+    fn binary(
+        &self,
+        binary: &ExprBinary,
+        left: TokenStream,
+        right: TokenStream,
+    ) -> syn::Result<TokenStream> {
+        // The operators without overflow are not observed
+        if checked_op(binary.op)?.is_none() {
+            return self.base.binary(binary, left, right);
+        }
+        let span = binary.op.span();
+        let name = |name| Ident::new(name, Span::mixed_site().located_at(span));
+        let (lhs, rhs, result) = (name("lhs"), name("rhs"), name("result"));
+        let observed = (self.observe)(binary, [&lhs, &rhs, &result])?;
+        if !self.mode.is_fallible() {
+            let value = self.base.binary(binary, quote! { #lhs }, quote! { #rhs })?;
+            return Ok(quote_spanned! { span=>
+                {
+                    let #lhs = #left;
+                    let #rhs = #right;
+                    let #result = #value;
+                    #observed
+                    #result
+                }
+            });
+        }
+        // The operands are printed without the wrapper of the fallible modes
+        let success = self.mode.success();
+        let failure = match self.mode {
+            Mode::TryChecked => quote! { Err(e) => Err(e) },
+            _ => quote! { None => None },
+        };
+        let value =
+            self.base
+                .binary(binary, quote! { #success(#lhs) }, quote! { #success(#rhs) })?;
+        Ok(quote_spanned! { span=>
+            match #left {
+                #success(#lhs) => match #right {
+                    #success(#rhs) => {
+                        let #result = #value;
+                        #observed
+                        #result
+                    }
+                    #failure,
+                },
+                #failure,
+            }
+        })
+    }
+
+    fn unary(&self, op: &UnOp, operand: TokenStream) -> TokenStream {
+        self.base.unary(op, operand)
+    }
+
+    fn type_hint(&self, operand: TokenStream, ty: &Type) -> TokenStream {
+        self.base.type_hint(operand, ty)
+    }
+
+    fn other(&self, expr: &Expr) -> syn::Result<TokenStream> {
+        self.base.other(expr)
+    }
+}
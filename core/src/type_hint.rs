@@ -0,0 +1,104 @@
+//! Inline type hints on operands, like `(x: u8) + y`.
+//!
+//! Since type ascription is not valid Rust syntax, parenthesized hints are
+//! rewritten before parsing into a typed binding of the form
+//! `{ let __arithmetic_mode_hint: u8 = x; __arithmetic_mode_hint }`, which is
+//! recognized by [`TypeHint::from_block`] when transforming the expression.
+
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::{Expr, ExprBlock, Local, LocalInit, Pat, PatIdent, PatType, Stmt, Type};
+
+use crate::unsupported_expression;
+
+const HINT_BINDING: &str = "__arithmetic_mode_hint";
+
+/// An operand with an inline type hint.
+pub(crate) struct TypeHint {
+    pub expr: Expr,
+    pub ty: Type,
+}
+
+impl TypeHint {
+    /// Extracts the type hint from the typed binding generated by
+    /// [`rewrite_type_hints`]. Other blocks are not supported in the macros.
+    pub(crate) fn from_block(mut block: ExprBlock) -> syn::Result<TypeHint> {
+        if let Some((expr, ty)) = parts_mut(&mut block) {
+            return Ok(TypeHint {
+                expr: expr.clone(),
+                ty: ty.clone(),
+            });
+        }
+        Err(unsupported_expression(&Expr::Block(block)))
+    }
+}
+
+/// Returns the hinted expression and its type if `block` is a typed binding
+/// generated by [`rewrite_type_hints`].
+pub(crate) fn parts_mut(block: &mut ExprBlock) -> Option<(&mut Expr, &Type)> {
+    let [Stmt::Local(Local {
+        pat: Pat::Type(PatType { pat, ty, .. }),
+        init:
+            Some(LocalInit {
+                expr,
+                diverge: None,
+                ..
+            }),
+        ..
+    }), Stmt::Expr(Expr::Path(result), None)] = &mut block.block.stmts[..]
+    else {
+        return None;
+    };
+    let Pat::Ident(PatIdent { ident, .. }) = &**pat else {
+        return None;
+    };
+    (ident == HINT_BINDING && result.path.is_ident(HINT_BINDING)).then_some((&mut **expr, &**ty))
+}
+
+/// Rewrites parenthesized operands of the form `(expr: Type)` into typed
+/// bindings that can be parsed as an expression.
+pub(crate) fn rewrite_type_hints(tokens: TokenStream) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => {
+                let stream = rewrite_type_hints(group.stream());
+                if group.delimiter() == Delimiter::Parenthesis {
+                    if let Some((expr, ty)) = split_type_hint(&stream) {
+                        let binding = Ident::new(HINT_BINDING, Span::call_site());
+                        let mut block = Group::new(
+                            Delimiter::Brace,
+                            quote! { let #binding: #ty = #expr; #binding },
+                        );
+                        block.set_span(group.span());
+                        return TokenTree::Group(block);
+                    }
+                }
+                let mut new_group = Group::new(group.delimiter(), stream);
+                new_group.set_span(group.span());
+                TokenTree::Group(new_group)
+            }
+            token => token,
+        })
+        .collect()
+}
+
+/// Splits `expr: Type` at its top-level colon. Path separators (`::`) and
+/// closure parameters (`|x: u8| x`) are not type hints.
+fn split_type_hint(stream: &TokenStream) -> Option<(TokenStream, TokenStream)> {
+    let tokens: Vec<TokenTree> = stream.clone().into_iter().collect();
+    let is_colon =
+        |i: usize| matches!(tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == ':');
+    let position = (0..tokens.len())
+        .find(|&i| is_colon(i) && !is_colon(i + 1) && !is_colon(i.wrapping_sub(1)))?;
+    let (expr, ty) = (&tokens[..position], &tokens[position + 1..]);
+    let is_closure = match &expr.first() {
+        Some(TokenTree::Punct(p)) => p.as_char() == '|',
+        Some(TokenTree::Ident(ident)) => ident == "move",
+        _ => false,
+    };
+    if expr.is_empty() || ty.is_empty() || is_closure {
+        return None;
+    }
+    Some((expr.iter().cloned().collect(), ty.iter().cloned().collect()))
+}
@@ -0,0 +1,72 @@
+//! The `checked_sum!` and `checked_product!` macros, which fold any number of
+//! comma-separated expressions with checked addition or multiplication, like
+//! `checked_sum!(header, body, trailer)`.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::{token, Expr, Ident, Token};
+
+use crate::attribute::Mode;
+use crate::mixed;
+
+/// Expands `checked_sum! { item }`.
+pub fn checked_sum_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    fold_terms("checked_sum", item, quote! { + })
+}
+
+/// Expands `checked_product! { item }`.
+pub fn checked_product_impl(item: TokenStream) -> syn::Result<TokenStream> {
+    fold_terms("checked_product", item, quote! { * })
+}
+
+/// Folds the terms of `name! { item }` with `op` in `checked` mode, or in the
+/// mode given in brackets at the start, which is `checked` or `try_checked`.
+/// The terms can be preceded by the prefixes of the mode macros, like
+/// `const u64: a, b, 1`.
+fn fold_terms(name: &str, item: TokenStream, op: TokenStream) -> syn::Result<TokenStream> {
+    let (mode, item) = mixed::split_mode(item)?;
+    let mode = match mode {
+        None => Mode::Checked,
+        Some((mode, _)) if mode.is_fallible() => mode,
+        Some((_, ident)) => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("{name}! only supports the `checked` and `try_checked` modes"),
+            ))
+        }
+    };
+    let (prefix, terms) = (|input: ParseStream| {
+        let mut prefix = TokenStream::new();
+        // The prefixes are parsed again by the mode
+        if input.peek(Token![const]) && !input.peek2(token::Brace) {
+            let token: Token![const] = input.parse()?;
+            prefix.extend(quote! { #token });
+        }
+        if input.peek(Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+            let ty: Ident = input.parse()?;
+            let colon: Token![:] = input.parse()?;
+            prefix.extend(quote! { #ty #colon });
+        }
+        let terms = Punctuated::<Expr, Token![,]>::parse_terminated(input)?;
+        Ok((prefix, terms))
+    })
+    .parse2(item)?;
+    if terms.is_empty() {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!("{name}! needs at least one expression"),
+        ));
+    }
+    // Only the operations need parentheses to keep their precedence
+    let mut terms = terms.iter().map(|term| match term {
+        Expr::Binary(_) => quote! { (#term) },
+        _ => quote! { #term },
+    });
+    let mut folded = terms.next().unwrap_or_default();
+    for term in terms {
+        folded.extend(quote! { #op #term });
+    }
+    mode.expand(quote! { #prefix #folded })
+}
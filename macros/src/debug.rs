@@ -0,0 +1,59 @@
+//! Printing of the macro expansions for debugging, enabled by setting the
+//! `ARITHMETIC_MODE_DEBUG` environment variable to `1` during compilation.
+
+use arithmetic_mode_core::with_deep_stack;
+use proc_macro2::TokenStream;
+
+/// Whether the expansions are printed.
+fn enabled() -> bool {
+    std::env::var_os("ARITHMETIC_MODE_DEBUG").is_some_and(|value| value == "1")
+}
+
+/// Expands the function-like macro `name` with `f`, printing its input and
+/// expansion if enabled. Like all the expansions, it runs on a stack deep
+/// enough for machine-generated expressions.
+pub(crate) fn trace(
+    name: &str,
+    item: TokenStream,
+    f: impl FnOnce(TokenStream) -> syn::Result<TokenStream>,
+) -> syn::Result<TokenStream> {
+    with_deep_stack(|| {
+        if !enabled() {
+            return f(item);
+        }
+        let input = format!("{name}! {{ {item} }}");
+        let output = f(item);
+        print(&input, &output);
+        output
+    })
+}
+
+/// Expands the attribute macro `name` with `f`, printing its input and
+/// expansion if enabled.
+pub(crate) fn trace_attribute(
+    name: &str,
+    attr: TokenStream,
+    item: TokenStream,
+    f: impl FnOnce(TokenStream, TokenStream) -> syn::Result<TokenStream>,
+) -> syn::Result<TokenStream> {
+    with_deep_stack(|| {
+        if !enabled() {
+            return f(attr, item);
+        }
+        let input = if attr.is_empty() {
+            format!("#[{name}] {item}")
+        } else {
+            format!("#[{name}({attr})] {item}")
+        };
+        let output = f(attr, item);
+        print(&input, &output);
+        output
+    })
+}
+
+fn print(input: &str, output: &syn::Result<TokenStream>) {
+    match output {
+        Ok(tokens) => eprintln!("arithmetic_mode: {input}\n  expands to: {tokens}"),
+        Err(e) => eprintln!("arithmetic_mode: {input}\n  fails with: {e}"),
+    }
+}
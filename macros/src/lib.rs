@@ -0,0 +1,1473 @@
+//! Procedural macros for the [`arithmetic-mode`](https://docs.rs/arithmetic-mode)
+//! crate. Use that crate instead of depending on this one directly, since the
+//! generated code refers to items defined there. The transformations are
+//! implemented in `arithmetic-mode-core`.
+
+#![warn(missing_docs)]
+
+extern crate proc_macro;
+
+mod debug;
+
+use arithmetic_mode_core::{
+    arithmetic_mode_impl, assert_no_overflow_impl, bench_impl, blend_add_impl, bps_of_impl,
+    checked_impl, checked_product_impl, checked_sum_impl, closure_impl, const_checked_impl,
+    control_flow_impl, ct_checked_impl, default_impl, deny_unchecked_arithmetic_impl,
+    differential_test_impl, dot_product_impl, elementwise_impl, enforce_impl, evm_impl,
+    explain_impl, fallible_fn_impl, fast_checked_impl, forbid_impl, hashing_impl,
+    incomplete_expression, lerp_impl, mac_impl, mixed_impl, modes_impl, modfield_impl,
+    mul_div_impl, overflow_matrix_impl, panicking_impl, passthrough, passthrough_attribute,
+    percent_of_impl, proptest_modes_impl, qformat_impl, record_impl, saturating_impl,
+    scale_u8_impl, stmt_impl, trace_impl, try_checked_impl, verified_impl, warn_without_arithmetic,
+    wrapping_impl, Mode,
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Macro that changes the arithmetic operations (add, sub, mul, div, and shift)
+/// inside it to be panicking operations. That is, if any of the operation
+/// overflows, it will panic.
+///
+/// The panic message includes the failed expression and its operands, like
+/// ``attempt to add with overflow: `255_u8 + 1_u8` with operands 255 and 1``.
+/// In const contexts, the operands are left out. The location of the panic is
+/// the operator that overflowed, like for compiler errors in the expansion.
+///
+/// The panic is formatted by a single `#[cold]` function of the runtime crate,
+/// so each operation only adds a comparison and a call to the hot path.
+///
+/// ```should_panic
+/// use arithmetic_mode::panicking;
+///
+/// let x = 255_u8;
+/// assert_eq!(0, panicking! { x + 1_u8 });
+/// ```
+///
+/// A message can be given before the expression, which is included in the
+/// panic message before the description of the failed operation:
+///
+/// ```should_panic
+/// use arithmetic_mode::panicking;
+///
+/// let (elapsed, frame_cost) = (u32::MAX, 16_u32);
+/// // Panics with "frame budget overflow: attempt to add with overflow: ..."
+/// panicking! { "frame budget overflow": elapsed + frame_cost };
+/// ```
+#[proc_macro]
+pub fn panicking(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("panicking", item, panicking_impl)
+}
+
+/// Macro that changes the arithmetic operations (add, sub, mul, div, and
+/// shift) inside it to be panicking operations, like [`panicking!`], that also
+/// assert the absence of overflow with `kani::assert` when verified with the
+/// [Kani](https://github.com/model-checking/kani) model checker. A proof
+/// harness calling the code then proves that no operation overflows, with the
+/// description of the operation as the name of the failed check.
+///
+/// The assertions are only compiled with `cfg(kani)`, which the crate has to
+/// declare as an expected cfg in its `Cargo.toml`:
+///
+/// ```toml
+/// [lints.rust]
+/// unexpected_cfgs = { level = "warn", check-cfg = ["cfg(kani)"] }
+/// ```
+///
+/// Otherwise, and in const contexts, it behaves like `panicking!`.
+///
+/// ```rust
+/// use arithmetic_mode::verified;
+///
+/// fn area(width: u8, height: u8) -> u8 {
+///     verified! { width * height }
+/// }
+///
+/// #[cfg(kani)]
+/// #[kani::proof]
+/// fn area_does_not_overflow() {
+///     let (width, height): (u8, u8) = (kani::any(), kani::any());
+///     kani::assume(width <= 15 && height <= 17);
+///     area(width, height);
+/// }
+///
+/// assert_eq!(255, area(15, 17));
+/// ```
+#[proc_macro]
+pub fn verified(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("verified", item, verified_impl)
+}
+
+/// Macro that changes the arithmetic operations (add, sub, mul, div, and shift)
+/// inside it to the default mode of the build, which is `panicking`, unless
+/// selected with `RUSTFLAGS='--cfg arithmetic_mode_default="saturating"'` or
+/// with the `ARITHMETIC_MODE_DEFAULT` environment variable during the
+/// compilation, which takes precedence. This lets a whole workspace change its
+/// policy for an experiment without editing every call site. Only `panicking`,
+/// `wrapping` and `saturating` can be the default, since they evaluate to the
+/// same type. `#[arithmetic_mode(default)]` does the same for an item.
+///
+/// ```rust
+/// use arithmetic_mode::default;
+///
+/// let (width, height) = (30_u8, 5_u8);
+/// assert_eq!(151, default! { width * height + 1 });
+/// ```
+#[proc_macro]
+pub fn default(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("default", item, default_impl)
+}
+
+/// Macro that changes the arithmetic operations (add, sub, mul, div, and shift)
+/// inside it to be wrapping operations if any operations or intermediate step
+/// overflows.
+///
+/// ```rust
+/// use arithmetic_mode::wrapping;
+///
+/// assert_eq!(0, wrapping! { 255_u8 + 1_u8 });
+/// ```
+#[proc_macro]
+pub fn wrapping(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("wrapping", item, wrapping_impl)
+}
+
+/// Macro for hash functions, which wraps like [`wrapping!`], with `u64` as
+/// the type of the literals that are only combined with each other, like
+/// `5381 * 33`, so that the constants of hash functions need no suffixes. The
+/// other literals get the type of the operands they are combined with, and
+/// the bit operators and the shifts of rotations, like `h << 5 | h >> 59`,
+/// are kept, with the shift amounts wrapped at the width of the type.
+///
+/// ```rust
+/// use arithmetic_mode::hashing;
+///
+/// // djb2
+/// let mut h = hashing! { 5381 * 33 };
+/// for &b in b"hash" {
+///     let b = u64::from(b);
+///     h = hashing! { (h << 5) + h + b };
+/// }
+/// assert_eq!(210_591_396_713, h);
+///
+/// let k = 0x9E37_79B9_u32;
+/// assert_eq!(0x79B9_9E37, hashing! { u32: k << 16 | k >> 16 });
+/// ```
+///
+/// A type stated before the expression, like `u32:` above, is the type of
+/// all the unsuffixed literals, like in the other modes.
+#[proc_macro]
+pub fn hashing(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("hashing", item, hashing_impl)
+}
+
+/// Macro that changes the arithmetic operations (add, sub, mul, div, and shift)
+/// inside it to be saturating operations if any operations or intermediate step
+/// overflows.
+///
+/// ```rust
+/// use arithmetic_mode::saturating;
+///
+/// assert_eq!(255, saturating! { 255_u8 + 1_u8 });
+/// ```
+///
+/// The remainder `%` never saturates, since its only overflow, `MIN % -1`, is
+/// 0. Like `/`, it panics if the divisor is zero.
+///
+/// ```rust
+/// use arithmetic_mode::saturating;
+///
+/// assert_eq!(0, saturating! { i8::MIN % -1 });
+/// ```
+#[proc_macro]
+pub fn saturating(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("saturating", item, saturating_impl)
+}
+
+/// Macro that changes the arithmetic operations (add, sub, mul, div, and shift)
+/// inside it to be checked operations that returns an `Option`, which would be
+/// `None` if the operation or any intermediate step overflows.
+///
+/// ```rust
+/// use arithmetic_mode::checked;
+///
+/// let x = 255_u8;
+/// assert_eq!(Some(254), checked! { x - 1_u8 });
+/// assert_eq!(None, checked! { x + 1_u8 });
+/// ```
+///
+/// Operands are wrapped in `Some`, unless they are marked with `opt(...)`, in
+/// which case they must already be an `Option` and are spliced into the chain
+/// as-is.
+///
+/// ```rust
+/// use arithmetic_mode::checked;
+///
+/// let quotient = 10_u8.checked_div(0);
+/// assert_eq!(None, checked! { opt(quotient) + 1_u8 });
+/// let x = 7_u8;
+/// assert_eq!(Some(14), checked! { x + opt(Some(x)) });
+/// ```
+///
+/// To use it in `const fn`, start the input with `const`. The operands are then
+/// not dereferenced through the non-const `Borrow` trait, so they cannot be
+/// references. This works the same for all the modes, and is implied inside
+/// `#[arithmetic_mode(..)] const fn`.
+///
+/// ```rust
+/// use arithmetic_mode::checked;
+///
+/// const fn area(width: u16, height: u16) -> Option<u16> {
+///     checked! { const width * height + 1 }
+/// }
+///
+/// const AREA: Option<u16> = area(300, 300);
+/// assert_eq!(None, AREA);
+/// ```
+#[proc_macro]
+pub fn checked(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("checked", item, checked_impl)
+}
+
+/// Macro that changes the arithmetic operations (add, sub, mul, div, and shift)
+/// inside it to be checked operations that returns a `Result`, which would be
+/// an `Err(ArithmeticError)` describing the failed operation if the operation
+/// or any intermediate step overflows.
+///
+/// ```rust
+/// use arithmetic_mode::{try_checked, Operation};
+///
+/// let x = 255_u8;
+/// assert_eq!(Ok(254), try_checked! { x - 1_u8 });
+/// let error = try_checked! { x + 1_u8 }.unwrap_err();
+/// assert_eq!(Operation::Add, error.operation());
+/// assert!(error
+///     .to_string()
+///     .starts_with("attempt to add with overflow: `x + 1_u8` with operands 255 and 1"));
+/// ```
+///
+/// Operands marked with `?` must already be a `Result` whose error type
+/// implements `Into<ArithmeticError>`, and are spliced into the chain so that
+/// their errors are propagated together with overflow errors.
+///
+/// ```rust
+/// use arithmetic_mode::{try_checked, ArithmeticError, Operation};
+///
+/// let scale = 4_u8;
+/// assert_eq!(Ok(202), try_checked! { u8::try_from(50_u32)? * scale + 2_u8 });
+/// assert_eq!(
+///     Err(ArithmeticError::new(Operation::Conversion)),
+///     try_checked! { u8::try_from(500_u32)? * scale + 2_u8 }
+/// );
+/// ```
+///
+/// The error can be replaced with one of the caller's, stated before the
+/// expression like `err = MyError::MathOverflow;`, which is only evaluated if
+/// an operation fails. Frameworks with their own error enums, like in smart
+/// contracts, can then use the macro without converting the errors.
+///
+/// ```rust
+/// use arithmetic_mode::try_checked;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum ContractError {
+///     MathOverflow,
+/// }
+///
+/// fn withdraw(balance: u64, amount: u64) -> Result<u64, ContractError> {
+///     try_checked! { err = ContractError::MathOverflow; balance - amount }
+/// }
+///
+/// assert_eq!(Ok(70), withdraw(100, 30));
+/// assert_eq!(Err(ContractError::MathOverflow), withdraw(30, 100));
+/// ```
+#[proc_macro]
+pub fn try_checked(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("try_checked", item, try_checked_impl)
+}
+
+/// Macro that changes the arithmetic operations inside it like
+/// [`try_checked!`], and evaluates to a `ControlFlow` instead of a `Result`,
+/// which breaks with the `ArithmeticError` if any operation overflows. The
+/// callbacks of `try_fold` or of visitors can then stop on an overflow, and
+/// return the value with `?`, like a `Result`.
+///
+/// ```rust
+/// use arithmetic_mode::{control_flow, Operation};
+/// use core::ops::ControlFlow;
+///
+/// let sum = [10_u8, 20, 30].iter().try_fold(0_u8, |acc, x| control_flow! { acc + x });
+/// assert_eq!(ControlFlow::Continue(60), sum);
+///
+/// let sum = [100_u8, 100, 100].iter().try_fold(0_u8, |acc, x| control_flow! { acc + x });
+/// let ControlFlow::Break(error) = sum else {
+///     panic!("the sum overflows");
+/// };
+/// assert_eq!(Operation::Add, error.operation());
+/// ```
+#[proc_macro]
+pub fn control_flow(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("control_flow", item, control_flow_impl)
+}
+
+/// Macro that adds any number of comma-separated expressions with checked
+/// addition, like [`checked!`] with `+` between them, and returns `None` if
+/// any addition overflows. Each expression is transformed like an operand of
+/// `checked!`.
+///
+/// ```rust
+/// use arithmetic_mode::checked_sum;
+///
+/// let (header, body, trailer) = (16_u8, 200_u8, 4_u8);
+/// assert_eq!(Some(220), checked_sum!(header, body, trailer));
+/// assert_eq!(None, checked_sum!(header, body, trailer, body));
+/// ```
+///
+/// The input can start with the prefixes of the mode macros, like
+/// `checked_sum!(u64: a, b, 1)`, and with `[try_checked]` to return a
+/// `Result` instead:
+///
+/// ```rust
+/// use arithmetic_mode::checked_sum;
+///
+/// let (header, body) = (200_u8, 100_u8);
+/// let error = checked_sum!([try_checked] header, body).unwrap_err();
+/// assert_eq!(Some("header + body"), error.expression());
+/// ```
+#[proc_macro]
+pub fn checked_sum(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("checked_sum", item, checked_sum_impl)
+}
+
+/// Macro that multiplies any number of comma-separated expressions with
+/// checked multiplication, like [`checked_sum!`] does with addition.
+///
+/// ```rust
+/// use arithmetic_mode::checked_product;
+///
+/// let (width, height, depth) = (64_u16, 64_u16, 4_u16);
+/// assert_eq!(Some(16384), checked_product!(width, height, depth));
+/// assert_eq!(None, checked_product!(width, height, depth, width));
+/// ```
+#[proc_macro]
+pub fn checked_product(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("checked_product", item, checked_product_impl)
+}
+
+/// Macro for tests that evaluates the arithmetic operations (add, sub, mul,
+/// div, and shift) inside it like [`try_checked!`], and fails the test if any
+/// of them overflows. The panic message describes the failed operation and its
+/// operands. Otherwise, the value of the expression is returned.
+///
+/// ```rust
+/// use arithmetic_mode::assert_no_overflow;
+///
+/// let (width, height) = (300_u32, 200_u32);
+/// assert_eq!(60_001, assert_no_overflow! { width * height + 1 });
+/// ```
+///
+/// ```should_panic
+/// use arithmetic_mode::assert_no_overflow;
+///
+/// let (width, height) = (300_u16, 300_u16);
+/// // Panics with "assertion failed: no overflow in `width * height + 1`:
+/// // attempt to multiply with overflow: `width * height` with operands 300 and 300, ..."
+/// assert_no_overflow! { width * height + 1 };
+/// ```
+///
+/// Like in [`panicking!`], a message can be given before the expression.
+#[proc_macro]
+pub fn assert_no_overflow(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("assert_no_overflow", item, assert_no_overflow_impl)
+}
+
+/// Macro that generates a test comparing [`checked!`], [`wrapping!`] and
+/// [`saturating!`] with the exact result of an expression, computed with
+/// `i128`, for all the combinations of the given ranges of its parameters.
+///
+/// The test checks that whenever `checked!` succeeds, its result is the exact
+/// one, and the other modes agree with it. If the expression only uses `+`, `-`
+/// and `*`, it also checks that `wrapping!` is the exact result truncated to the
+/// type. Shifts are not supported, since checked shifts don't detect the bits
+/// shifted out, and literals must be unsuffixed, so that they can be used with
+/// `i128`.
+///
+/// ```rust
+/// arithmetic_mode::differential_test! {
+///     fn mul_add(a: u8 in 0..=255, b: u8 in 0..=16) -> u8 {
+///         a * b + 7
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn differential_test(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match debug::trace("differential_test", item.into(), differential_test_impl) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Macro that generates an exhaustive test of the modes for each of the given
+/// small integer types, over all the pairs of its values, like
+/// `overflow_matrix! { u8, i8 }`. For each of `+`, `-`, `*`, `/` and `%`, the
+/// test compares [`checked!`], [`try_checked!`], [`wrapping!`],
+/// [`saturating!`] and [`panicking!`] with the exact result computed with
+/// `i32`.
+///
+/// The types are `u8`, `i8`, `u16` and `i16`, whose tests are named like
+/// `overflow_matrix_u8`. All the pairs of 16-bit values take long to test, so
+/// their operands can be limited to a range, like `i16 in -300..=300`.
+///
+/// ```rust
+/// arithmetic_mode::overflow_matrix! { u8, i8, u16 in 0..=300 }
+/// ```
+#[proc_macro]
+pub fn overflow_matrix(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("overflow_matrix", item, overflow_matrix_impl)
+}
+
+/// Macro that checks the relationships between the modes for an expression
+/// with property tests, using randomly generated values of its parameters. It
+/// requires the `proptest` feature of `arithmetic-mode`.
+///
+/// Like [`differential_test!`], it checks that whenever [`checked!`] succeeds,
+/// its result is the exact one, and the other modes agree with it. It also
+/// checks that [`panicking!`] panics exactly when `checked!` fails, and, for a
+/// single operation, that [`saturating!`] saturates at a bound of the type
+/// when `checked!` fails. The same restrictions on shifts and literals apply.
+///
+/// ```rust,ignore
+/// #[test]
+/// fn mul_add() {
+///     arithmetic_mode::proptest_modes!(|a: u8, b: u8| a * b + 7);
+/// }
+/// ```
+#[proc_macro]
+pub fn proptest_modes(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match debug::trace("proptest_modes", item.into(), proptest_modes_impl) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => expression_error(&e, None),
+    }
+}
+
+/// Macro that evaluates the arithmetic operations (add, sub, mul, div, and
+/// shift) inside it at compile time, in a `const` block, so that an overflow is
+/// a compile error in every build profile. The operands can only be constants
+/// and const generic parameters.
+///
+/// ```rust
+/// use arithmetic_mode::const_checked;
+///
+/// const ROWS: usize = 16;
+/// const COLS: usize = 8;
+/// assert_eq!(132, const_checked! { ROWS * COLS + 4 });
+/// ```
+///
+/// With the `msrv` feature, the `const` block, which needs Rust 1.79, is a
+/// plain block instead, so the operations are only evaluated at compile time in
+/// const contexts, like the initializer of a `const`, and panic at runtime
+/// elsewhere.
+///
+/// Like in [`panicking!`], a message can be given before the expression, and is
+/// included in the compile error together with the failed operation.
+///
+/// ```compile_fail
+/// use arithmetic_mode::const_checked;
+///
+/// const FRAMES: u8 = 200;
+/// // error: "frame count overflow: attempt to multiply with overflow: `FRAMES * 2`"
+/// const TOTAL: u8 = const_checked! { "frame count overflow": FRAMES * 2 };
+/// ```
+#[proc_macro]
+pub fn const_checked(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("const_checked", item, const_checked_impl)
+}
+
+/// Macro that evaluates like [`checked!`], returning `None` if any operation
+/// overflows, but without branching after every operation. The operands are
+/// widened into a larger type, where the chain of additions, subtractions and
+/// multiplications cannot overflow, and the intermediate results are compared
+/// with the bounds of the type without branching, so that there is only one
+/// branch at the end.
+///
+/// The type of the operands has to be given before the expression, and can be
+/// `i8`, `u8`, `i16`, `u16`, `i32`, `u32`, `i64` or `isize`. Unlike
+/// [`checked!`], all the operands are evaluated even if an operation overflows.
+///
+/// ```rust
+/// use arithmetic_mode::fast_checked;
+///
+/// let (r, g, b) = (200_u8, 100_u8, 30_u8);
+/// assert_eq!(Some(230), fast_checked! { u8: (r - g) * 2 + b });
+/// // Like `checked!`, `r + g` overflows even though the final result would fit
+/// assert_eq!(None, fast_checked! { u8: r + g - b });
+/// ```
+#[proc_macro]
+pub fn fast_checked(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("fast_checked", item, fast_checked_impl)
+}
+
+/// Macro that mixes the modes in one expression, with the operators of Zig:
+/// `+%`, `-%` and `*%` wrap, `+|`, `-|` and `*|` saturate, and the plain
+/// operators panic like in [`panicking!`].
+///
+/// ```rust
+/// use arithmetic_mode::mixed;
+///
+/// let (hash, byte) = (u32::MAX, 20_u32);
+/// assert_eq!(7, mixed! { hash *% 16 +% byte + 3 });
+/// assert_eq!(255, mixed! { 200_u8 +| 100 });
+/// ```
+///
+/// ```should_panic
+/// use arithmetic_mode::mixed;
+///
+/// let total = 250_u8;
+/// mixed! { total +| 10 + 1 };
+/// ```
+///
+/// Any operator can also be followed by its mode in brackets, like
+/// `a +[checked] b`, and the mode of the operators without one can be given in
+/// brackets at the start. An operation in `checked` or `try_checked` mode can
+/// only be an operand of an operation in the same mode, since it evaluates to
+/// an `Option` or a `Result`.
+///
+/// ```rust
+/// use arithmetic_mode::mixed;
+///
+/// let (offset, length, stride) = (u8::MAX, 10_u8, 4_u8);
+/// assert_eq!(None, mixed! { [checked] offset + length *[saturating] stride });
+/// assert_eq!(Some(43), mixed! { [checked] (offset +[wrapping] length) * stride + 7 });
+/// assert_eq!(255, mixed! { length *[wrapping] 100 +| offset });
+/// ```
+#[proc_macro]
+pub fn mixed(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("mixed", item, mixed_impl)
+}
+
+/// Macro that gives each integer type its own mode in one expression, like
+/// `modes! { u8 = saturating, usize = checked; expr }`, for e.g. pixel math
+/// next to buffer offsets. The types not given panic like in [`panicking!`],
+/// unless their mode is given as `_ = mode`. Each operation is in the mode of
+/// the type of its operands, which is only known when the expression is type
+/// checked. The operations in casts are transformed too, since the casts are
+/// what mixes the types.
+///
+/// ```rust
+/// use arithmetic_mode::modes;
+///
+/// let (pixel, gain) = (200_u8, 2_u8);
+/// let (offset, end) = (1920_usize, usize::MAX);
+/// // The pixel saturates, and the offset is checked
+/// assert_eq!(
+///     Some(2175),
+///     modes! { u8 = saturating, usize = checked; offset + (pixel * gain) as usize }
+/// );
+/// assert_eq!(
+///     None,
+///     modes! { u8 = saturating, usize = checked; end + (pixel * gain) as usize }
+/// );
+/// ```
+///
+/// If `checked` or `try_checked` mode is given, the expression evaluates to an
+/// `Option` or a `Result` like in that mode, even if only the operations of the
+/// other types are in it, and the two cannot both be given. As in
+/// [`saturating!`], bit shifts of the types in `saturating` mode are not
+/// supported.
+#[proc_macro]
+pub fn modes(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("modes", item, modes_impl)
+}
+
+/// Macro that transforms an expression of arrays for each of their elements,
+/// like the mode given in brackets at the start, or like [`panicking!`] by
+/// default, e.g. to mix pixel or audio buffers. The result is the array of the
+/// results for each element, whose loop the optimizer can vectorize.
+///
+/// ```rust
+/// use arithmetic_mode::elementwise;
+///
+/// let (left, right) = ([200_u8, 10, 255, 0], [100_u8, 20, 1, 0]);
+/// assert_eq!([255, 30, 255, 0], elementwise! { [saturating] left + right });
+/// assert_eq!([44, 30, 0, 0], elementwise! { [wrapping] left + right });
+/// ```
+///
+/// The arrays must have the same length, which is inferred from their types.
+/// Integers among the operands, like `gain` in `samples * gain`, apply to
+/// every element. In the fallible modes, each element is an `Option` or a
+/// `Result`:
+///
+/// ```rust
+/// use arithmetic_mode::elementwise;
+///
+/// let (samples, gain) = ([1000_i16, -20_000, 300], 2_i16);
+/// assert_eq!(
+///     [Some(2000), None, Some(600)],
+///     elementwise! { [checked] samples * gain }
+/// );
+/// ```
+#[proc_macro]
+pub fn elementwise(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("elementwise", item, elementwise_impl)
+}
+
+/// Macro that computes the dot product of two arrays or slices of integers,
+/// like `dot_product! { [saturating] coefficients, samples }`, in the mode
+/// given in brackets at the start, or like [`panicking!`] by default. The
+/// products are summed in a wider integer type, like `i64` for `i16`, so only
+/// the final sum can overflow, when it is narrowed back to the type of the
+/// elements.
+///
+/// ```rust
+/// use arithmetic_mode::dot_product;
+///
+/// let (taps, samples) = ([3_i16, -2, 1], [20_000_i16, 30_000, 10_000]);
+/// // `3 * 20_000` would already overflow `i16`
+/// assert_eq!(10_000, dot_product! { taps, samples });
+/// let inverted = [20_000_i16, -30_000, 0];
+/// assert_eq!(None, dot_product! { [checked] taps, inverted });
+/// assert_eq!(i16::MAX, dot_product! { [saturating] taps, inverted });
+/// ```
+///
+/// The vectors must have the same length, and panic otherwise. The sum is
+/// accumulated in a type with four times the width for the types up to 32
+/// bits, so it cannot overflow for vectors shorter than 65536 elements of
+/// 8-bit types or any practical length of the wider ones, and in `i128` or
+/// `u128` for the 64-bit types, where it saturates. `u128` and `i128` are not
+/// supported. In `wrapping` mode, the sum is wrapped like `a[0] * b[0] +
+/// a[1] * b[1] + ...` would be.
+#[proc_macro]
+pub fn dot_product(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("dot_product", item, dot_product_impl)
+}
+
+/// Macro that computes the multiply-accumulate `acc + a * b`, like
+/// `mac! { [saturating] acc, coefficient, sample }`, in the mode given in
+/// brackets at the start, or like [`panicking!`] by default. The product is
+/// computed exactly in a wider integer type, like `i32` for `i16`, and only
+/// the sum is narrowed back to the type of the operands, so `a * b` can
+/// overflow on its own as long as `acc + a * b` fits.
+///
+/// ```rust
+/// use arithmetic_mode::mac;
+///
+/// let (acc, coefficient, sample) = (-30_000_i16, 300_i16, 100_i16);
+/// // `300 * 100` would already overflow `i16`
+/// assert_eq!(0, mac! { acc, coefficient, sample });
+/// assert_eq!(None, mac! { [checked] 10_000_i16, coefficient, sample });
+/// assert_eq!(i16::MAX, mac! { [saturating] 10_000_i16, coefficient, sample });
+/// ```
+///
+/// In the blocks of [`#[arithmetic_mode]`](macro@arithmetic_mode),
+/// [`#[checked_fn]`](macro@checked_fn) and [`#[try_fn]`](macro@try_fn),
+/// `acc += a * b` is computed like `mac!` in the mode of the attribute, except
+/// in const contexts. `u128` and `i128` have no wider type, so their product
+/// overflows on its own like in `acc + a * b`. In `wrapping` mode, the result
+/// is wrapped like `acc + a * b` would be.
+#[proc_macro]
+pub fn mac(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("mac", item, mac_impl)
+}
+
+/// Macro that computes `a * b / c`, like `mul_div! { amount, fee_bps, 10_000 }`,
+/// in the mode given in brackets at the start, or like [`panicking!`] by
+/// default. The product is computed in a wider integer type, like `u128` for
+/// `u64`, so it cannot overflow, and only the quotient is narrowed back to the
+/// type of the operands in the mode.
+///
+/// ```rust
+/// use arithmetic_mode::mul_div;
+///
+/// let (amount, fee_bps) = (u64::MAX / 2, 30_u64);
+/// // `amount * fee_bps` would overflow `u64`
+/// assert_eq!(27_670_116_110_564_327, mul_div! { amount, fee_bps, 10_000 });
+/// assert_eq!(None, mul_div! { [checked] amount, 3, 1 });
+/// assert_eq!(u64::MAX, mul_div! { [saturating] amount, 3, 1 });
+/// ```
+///
+/// The quotient is rounded toward zero like `/`, or as given after the
+/// operands, like `rounding = ceil`. The roundings are `down` (toward zero),
+/// `up` (away from zero), `floor`, `ceil` and `nearest` (away from zero when
+/// halfway):
+///
+/// ```rust
+/// use arithmetic_mode::mul_div;
+///
+/// let (a, b) = (-7_i32, 3_i32);
+/// assert_eq!(-5, mul_div! { a, b, 4 });
+/// assert_eq!(-6, mul_div! { a, b, 4, rounding = floor });
+/// assert_eq!(-5, mul_div! { a, b, 4, rounding = ceil });
+/// assert_eq!(-5, mul_div! { a, b, 4, rounding = nearest });
+/// assert_eq!(Some(-6), mul_div! { [checked] a, b, 4, rounding = up });
+/// ```
+///
+/// Dividing by zero panics, other than in the fallible modes. `u128` and
+/// `i128` are not supported, since there is no wider type.
+#[proc_macro]
+pub fn mul_div(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("mul_div", item, mul_div_impl)
+}
+
+/// Macro that computes the percentage `p` of `x`, like
+/// `percent_of! { price, discount }`, as `x * p / 100` with [`mul_div!`], so
+/// the product cannot overflow. The mode and the rounding are given like in
+/// `mul_div!`.
+///
+/// ```rust
+/// use arithmetic_mode::percent_of;
+///
+/// let (price, discount) = (u32::MAX, 15_u32);
+/// assert_eq!(644_245_094, percent_of! { price, discount });
+/// assert_eq!(None, percent_of! { [checked] price, 120 });
+/// assert_eq!(u32::MAX, percent_of! { [saturating] price, 120 });
+/// assert_eq!(2, percent_of! { 7_u8, 25, rounding = nearest });
+/// ```
+#[proc_macro]
+pub fn percent_of(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("percent_of", item, percent_of_impl)
+}
+
+/// Macro that computes the basis points `bps` of `x`, like
+/// `bps_of! { amount, fee_bps }`, as `x * bps / 10_000` with [`mul_div!`], so
+/// the product cannot overflow. The mode and the rounding are given like in
+/// `mul_div!`. The 8-bit types are not supported, since they cannot hold
+/// 10 000.
+///
+/// ```rust
+/// use arithmetic_mode::bps_of;
+///
+/// let (amount, fee_bps) = (1_000_000_007_u64, 30_u64);
+/// assert_eq!(3_000_000, bps_of! { amount, fee_bps });
+/// assert_eq!(Ok(3_000_001), bps_of! { [try_checked] amount, fee_bps, rounding = ceil });
+/// ```
+#[proc_macro]
+pub fn bps_of(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("bps_of", item, bps_of_impl)
+}
+
+/// Macro that interpolates between `a` and `b`, like
+/// `lerp! { start, end, step, steps }` for `a + (b - a) * t / t_max`, in the
+/// mode given in brackets at the start, or like [`panicking!`] by default. The
+/// difference and the product are computed in a wider integer type, like
+/// `u128` for `u64`, so they cannot overflow even when `b` is smaller than
+/// `a`, and only the interpolated value is narrowed back to the type of the
+/// operands in the mode.
+///
+/// ```rust
+/// use arithmetic_mode::lerp;
+///
+/// let (start, end) = (u64::MAX - 100, 100_u64);
+/// // `end - start` and `(end - start) * t` would overflow `u64`
+/// assert_eq!(9_223_372_036_854_775_808, lerp! { start, end, 1, 2 });
+/// assert_eq!(None, lerp! { [checked] start, end, 3, 2 });
+/// assert_eq!(0, lerp! { [saturating] start, end, 3, 2 });
+/// let (a, b) = (i32::MIN, i32::MAX);
+/// assert_eq!(-1, lerp! { a, b, 1, 2, rounding = floor });
+/// ```
+///
+/// `t` is usually between `0` and `t_max`, but can be outside to extrapolate.
+/// The step `(b - a) * t / t_max` is rounded toward zero, i.e. toward `a`, or
+/// as given after the operands like in [`mul_div!`]. Dividing by zero panics,
+/// other than in the fallible modes. `u128` and `i128` are not supported,
+/// since there is no wider type.
+#[proc_macro]
+pub fn lerp(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("lerp", item, lerp_impl)
+}
+
+/// Macro that adds the 8-bit channels of images, like
+/// `blend_add! { dst, glow }`, saturating at `u8::MAX` instead of wrapping
+/// around to dark. The channels are `u8` values, or pixels of them like
+/// `[u8; 4]`, whose channels are added separately.
+///
+/// ```rust
+/// use arithmetic_mode::blend_add;
+///
+/// let (dst, glow, spark) = (200_u8, 40_u8, 30_u8);
+/// assert_eq!(255, blend_add! { dst, glow, spark });
+/// let (pixel, light) = ([10_u8, 200, 30, 255], [20_u8, 100, 0, 1]);
+/// assert_eq!([30, 255, 30, 255], blend_add! { pixel, light });
+/// ```
+#[proc_macro]
+pub fn blend_add(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("blend_add", item, blend_add_impl)
+}
+
+/// Macro that scales the 8-bit channels of images by `num / den`, like
+/// `scale_u8! { color, alpha, 255 }`, as `x * num / den` with [`mul_div!`],
+/// so the product cannot overflow, saturating at `u8::MAX` when `num` is
+/// larger than `den`. The channel is a `u8` value, or a pixel of them like
+/// `[u8; 4]`, whose channels are scaled separately.
+///
+/// ```rust
+/// use arithmetic_mode::scale_u8;
+///
+/// let (color, alpha) = (200_u8, 128_u8);
+/// assert_eq!(100, scale_u8! { color, alpha, 255 });
+/// assert_eq!(255, scale_u8! { color, 3, 2 });
+/// let pixel = [255_u8, 101, 0, 255];
+/// assert_eq!([128, 51, 0, 128], scale_u8! { pixel, alpha, 255 });
+/// ```
+///
+/// The result is rounded to the nearest value, away from zero when halfway,
+/// or as given after the operands like in `mul_div!`, e.g.
+/// `scale_u8! { color, alpha, 255, rounding = down }`. Dividing by zero
+/// panics.
+#[proc_macro]
+pub fn scale_u8(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("scale_u8", item, scale_u8_impl)
+}
+
+/// Macro that changes the arithmetic operations inside it to be checked
+/// operations for constant-time code, which returns a `subtle::CtOption`. It
+/// requires the `subtle` feature of `arithmetic-mode`.
+///
+/// Like [`checked!`], the result is none if any operation overflows or divides
+/// by zero. Each operation computes whether it overflowed without branching
+/// though, and all of them are always evaluated, so that the timing does not
+/// depend on whether the expression overflowed.
+///
+/// ```rust,ignore
+/// use arithmetic_mode::ct_checked;
+///
+/// let (a, b) = (200_u8, 2_u8);
+/// assert_eq!(Some(202), Option::from(ct_checked! { a + b }));
+/// assert!(bool::from(ct_checked! { a * b + 1 }.is_none()));
+/// assert!(bool::from(ct_checked! { a / (b - 2) }.is_none()));
+/// ```
+///
+/// Division by zero is detected without branching too, but the division
+/// itself may not take constant time on all processors.
+#[proc_macro]
+pub fn ct_checked(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("ct_checked", item, ct_checked_impl)
+}
+
+/// Macro that computes an expression with the integer semantics of the
+/// Ethereum Virtual Machine, e.g. to simulate the math of a contract off-chain.
+/// It requires the `evm` feature of `arithmetic-mode`.
+///
+/// The operands are converted to `arithmetic_mode::evm::U256`, and all the
+/// operations wrap modulo 2^256. Dividing by zero and shifting by 256 bits or
+/// more give zero instead of failing, and `**` is the exponentiation, which
+/// binds tighter than the other operators and groups to the right, like in
+/// Solidity. The result is a `U256`.
+///
+/// ```rust,ignore
+/// use arithmetic_mode::evm;
+/// use arithmetic_mode::evm::U256;
+///
+/// let (amount, price) = (U256::from(3), 2_000_u64);
+/// assert_eq!(U256::from(6_000), evm! { amount * price * 10 ** 18 / 10 ** 18 });
+/// assert_eq!(U256::ZERO, evm! { amount % 0 });
+/// assert_eq!(U256::MAX, evm! { 0 - 1 });
+/// ```
+///
+/// Since `**` is parsed as a multiplication by a dereference, the references
+/// among the operands have to be dereferenced outside of `evm!`.
+#[proc_macro]
+pub fn evm(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("evm", item, evm_impl)
+}
+
+/// Macro that computes an expression modulo a constant, like
+/// `modfield!(P; a * b + c)`, e.g. for the arithmetic of cryptographic fields.
+/// The residues are reduced with the Barrett factor of the modulus, which is
+/// computed at compile time, instead of dividing after each operation like
+/// `%` does.
+///
+/// The modulus is an unsigned integer of at most 64 bits, which is also the
+/// type of the result, or `u64` for an unsuffixed literal. The operands are
+/// unsigned integers of any width, and are reduced first. `/` multiplies by
+/// the inverse of the divisor, and panics if it has none, like zero.
+///
+/// ```rust
+/// use arithmetic_mode::modfield;
+///
+/// const P: u64 = 0xffff_ffff_0000_0001;
+///
+/// let (a, b) = (P - 1, 3_u64);
+/// assert_eq!(P - 3, modfield!(P; a * b));
+/// assert_eq!(1, modfield!(P; a / a));
+/// assert_eq!(2, modfield!(P; -a + 1));
+/// assert_eq!(4, modfield!(7_u8; 5 * 5 - 2 * 7));
+/// ```
+#[proc_macro]
+pub fn modfield(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("modfield", item, modfield_impl)
+}
+
+/// Macro that computes an expression of fixed-point numbers in a Q format,
+/// like `qformat!(Q15; gain * sample + offset)`, over the signed integers that
+/// hold them, like `i16` for `Q15`.
+///
+/// Additions, subtractions and negations saturate. Multiplications are
+/// rounded to the nearest value and shifted back into the format, like
+/// `(a as i32 * b as i32 + (1 << 14)) >> 15` for `Q15`, and divisions are
+/// rounded likewise, before saturating. The integer literals are raw values,
+/// while the float literals are converted into the format, like `0.5` into
+/// `16384` for `Q15`, saturating at its bounds.
+///
+/// ```rust
+/// use arithmetic_mode::qformat;
+///
+/// let (gain, sample) = (16384_i16, -20000_i16);
+/// assert_eq!(-10000, qformat!(Q15; gain * sample));
+/// assert_eq!(i16::MIN, qformat!(Q15; sample - 0.5));
+/// assert_eq!(12207, qformat!(Q15; sample * sample));
+/// assert_eq!(-26844, qformat!(Q15; gain / sample));
+/// assert_eq!(-24576, qformat!(Q15; -0.75 + 0 * gain));
+/// ```
+#[proc_macro]
+pub fn qformat(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("qformat", item, qformat_impl)
+}
+
+/// Macro for benchmarking the modes, which transforms the expression like the
+/// mode given in brackets at the start, or like [`panicking!`] by default, and
+/// passes each operand through [`core::hint::black_box`]. The optimizer then
+/// cannot constant-fold the expression or hoist it out of the benchmark loop,
+/// so that comparing the modes, e.g. with `criterion`, measures their
+/// operations.
+///
+/// ```rust
+/// use arithmetic_mode::bench;
+///
+/// let (a, b, c) = (200_u8, 2_u8, 7_u8);
+/// assert_eq!(151, bench! { [wrapping] a * b + c });
+/// assert_eq!(None, bench! { [checked] a * b + c });
+/// assert_eq!(255, bench! { [saturating] a * b + c });
+/// ```
+///
+/// Like in the modes, operands that are already an `Option` or a `Result`,
+/// like `opt(x)` in `checked` mode and `x?` in `try_checked` mode, are left
+/// unchanged.
+#[proc_macro]
+pub fn bench(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("bench", item, bench_impl)
+}
+
+/// Macro for debugging an expression, which transforms it like the mode given
+/// in brackets at the start, or like [`panicking!`] by default, and prints
+/// each operation with its operands and result, like `200 * 2 = 144`. The
+/// operations are printed to stderr with the `std` feature, or with the hook
+/// set with `arithmetic_mode::trace::set_hook`.
+///
+/// ```rust
+/// use arithmetic_mode::trace;
+///
+/// let (a, b, c) = (200_u8, 2_u8, 7_u8);
+/// // Prints `200 * 2 = 144` and `144 + 7 = 151`
+/// assert_eq!(151, trace! { [wrapping] a * b + c });
+/// ```
+///
+/// In the fallible modes, the operations after the one that failed are not
+/// evaluated, so they are not printed either. Operations in const contexts
+/// cannot be traced.
+#[proc_macro]
+pub fn trace(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("trace", item, trace_impl)
+}
+
+/// Macro that records each operation of the expression, with its operands,
+/// its result and whether it overflowed, into the recorder before the
+/// expression, like `record! { &mut log; [checked] a * b + c }`. The recorder
+/// is a mutable reference to an `arithmetic_mode::record::Recorder`, like a
+/// `Vec` with the `std` feature, and the expression is transformed like the
+/// mode given in brackets, or like [`panicking!`] by default.
+///
+/// ```rust
+/// use arithmetic_mode::record;
+/// use arithmetic_mode::record::{Recorder, Step};
+///
+/// struct Overflows(usize);
+///
+/// impl Recorder for Overflows {
+///     fn record(&mut self, step: Step) {
+///         self.0 += usize::from(step.overflowed());
+///     }
+/// }
+///
+/// let mut overflows = Overflows(0);
+/// let (a, b, c) = (200_u8, 2_u8, 7_u8);
+/// assert_eq!(255, record! { &mut overflows; [saturating] a * b + c });
+/// assert_eq!(2, overflows.0);
+/// ```
+///
+/// Like in [`trace!`], the operations after the one that failed in the
+/// fallible modes are not evaluated, so they are not recorded either.
+#[proc_macro]
+pub fn record(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("record", item, record_impl)
+}
+
+/// Macro that expands the expression after the mode, like
+/// `explain!(checked, a + b * c)`, like the macro of that mode, and shows the
+/// expansion in a compiler warning, to see what the mode does with the
+/// expression without `cargo expand`, e.g. in code review.
+///
+/// ```rust
+/// use arithmetic_mode::explain;
+///
+/// let (a, b, c) = (1_u8, 2_u8, 3_u8);
+/// // warning: use of deprecated constant `EXPLAIN`:
+/// //   arithmetic_mode: `checked! { a + b * c }` expands to `...`
+/// # #[allow(deprecated)]
+/// assert_eq!(Some(7), explain!(checked, a + b * c));
+/// ```
+///
+/// Like the other warnings of the macros, it is reported as the use of a
+/// deprecated constant, so the macro is only meant to be used temporarily.
+#[proc_macro]
+pub fn explain(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("explain", item, explain_impl)
+}
+
+/// Macro that makes the given operators a compile error in the expression,
+/// like `forbid! { div, rem; price * quantity }` to reject lossy division in
+/// money or fixed-point code. The operators are named `add`, `sub`, `mul`,
+/// `div`, `rem`, `shl` and `shr`, and are also rejected in nested expressions
+/// like function arguments. The rest of the expression is transformed like in
+/// [`mixed!`], so the mode can be given in brackets:
+///
+/// ```rust
+/// use arithmetic_mode::forbid;
+///
+/// let (price, quantity) = (1999_u32, 3_u32);
+/// assert_eq!(Some(5997), forbid! { div, rem; [checked] price * quantity });
+/// ```
+///
+/// ```compile_fail
+/// use arithmetic_mode::forbid;
+///
+/// let (total, count) = (5997_u32, 3_u32);
+/// // error: The `div` operator `/` is forbidden in this expression
+/// let average = forbid! { div, rem; [checked] total / count };
+/// ```
+#[proc_macro]
+pub fn forbid(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("forbid", item, forbid_impl)
+}
+
+/// Attribute that changes all the arithmetic operations (add, sub, mul, div,
+/// and shift) inside a function, an `impl` block, an inline module or a trait
+/// (in its default method bodies) to the given mode, one of `panicking`,
+/// `wrapping`, `saturating`, `checked`, `try_checked` or `verified`.
+///
+/// This is equivalent to wrapping every arithmetic expression in the item,
+/// including those in nested blocks, loops, closures and modules, in the
+/// corresponding macro. Nested items can override the mode with their own
+/// attribute. Compound assignments like `+=` are also transformed, except in
+/// the `checked` and `try_checked` modes. Arithmetic inside other macro
+/// invocations is left unchanged.
+///
+/// Statements, block expressions and nested items marked with
+/// `#[arithmetic_mode(skip)]` are also left unchanged, e.g. to keep the
+/// default arithmetic in a few places of an otherwise `checked` module.
+///
+/// ```rust
+/// use arithmetic_mode::arithmetic_mode;
+///
+/// #[arithmetic_mode(wrapping)]
+/// fn hash(bytes: &[u8]) -> u32 {
+///     let mut hash = 2166136261_u32;
+///     for byte in bytes {
+///         hash ^= *byte as u32;
+///         hash *= 16777619;
+///     }
+///     hash
+/// }
+///
+/// assert_eq!(0xe40c292c, hash(b"a"));
+///
+/// struct Brightness(u8);
+///
+/// #[arithmetic_mode(saturating)]
+/// impl Brightness {
+///     fn brighten(&self, amount: u8) -> Brightness {
+///         Brightness(self.0 + amount)
+///     }
+///
+///     fn darken(&self, amount: u8) -> Brightness {
+///         Brightness(self.0 - amount)
+///     }
+/// }
+///
+/// assert_eq!(255, Brightness(200).brighten(100).0);
+/// assert_eq!(0, Brightness(50).darken(100).0);
+/// ```
+///
+/// Each operator can also be given its own mode, like
+/// `#[arithmetic_mode(add = "wrapping", mul = "checked")]`, for the operators
+/// `add`, `sub`, `mul`, `div`, `rem`, `shl` and `shr`. The other operators use
+/// the mode given first, if any, or panic like in [`panicking!`]. As in
+/// [`mixed!`], an operation in `checked` or `try_checked` mode can only be an
+/// operand of an operation in the same mode.
+///
+/// ```rust
+/// use arithmetic_mode::arithmetic_mode;
+///
+/// #[arithmetic_mode(saturating, mul = "checked", rem = "wrapping")]
+/// fn slot(hash: u32, buckets: u32, count: u32, size: u32) -> (u32, Option<u32>) {
+///     (hash % buckets + count, count * size)
+/// }
+///
+/// assert_eq!((10, Some(12)), slot(27, 10, 3, 4));
+/// assert_eq!((u32::MAX, None), slot(27, 10, u32::MAX, 2));
+/// ```
+///
+/// The mode `default`, like in `#[arithmetic_mode(default)]`, is the default
+/// mode of the build, like in [`default!`].
+///
+/// A team can keep its rules in the named policies of an
+/// `arithmetic-mode.toml` file, in the directory of the crate or of one of its
+/// parents like the workspace root, and apply them with
+/// `#[arithmetic_mode(policy = "payments")]`. A policy gives the `mode` of the
+/// item, the modes of its operators, and the `audit` flag, which the arguments
+/// of the attribute override, and can give the integer types their own mode
+/// like [`modes!`], and the modules inside the item their own mode, by the
+/// last segments of their path:
+///
+/// ```toml
+/// [policy.payments]
+/// mode = "checked"
+///
+/// [policy.payments.modules]
+/// fees = "saturating"
+///
+/// [policy.pixels]
+/// mode = "wrapping"
+///
+/// [policy.pixels.types]
+/// u8 = "saturating"
+/// ```
+///
+/// ```rust
+/// use arithmetic_mode::arithmetic_mode;
+///
+/// #[arithmetic_mode(policy = "pixels")]
+/// fn offset(base: usize, pixel: u8, gain: u8) -> usize {
+///     base + (pixel * gain) as usize
+/// }
+///
+/// assert_eq!(254, offset(usize::MAX, 200, 2));
+/// ```
+///
+/// Cargo doesn't know that the file is read, so the crates using it have to be
+/// rebuilt after it changes, like with `touch src/lib.rs`.
+///
+/// For security reviews, the `audit` flag, like in
+/// `#[arithmetic_mode(checked, audit)]`, reports every operation in the item
+/// with a warning at its operator, stating the mode it uses, or that it is
+/// skipped and uses the default arithmetic. Since proc macros cannot emit notes
+/// on stable Rust, these are reported as uses of a deprecated constant, and
+/// can be silenced with `#[allow(deprecated)]`.
+///
+/// The mode macros nested inside the item, like `wrapping! { a * 31 }`, keep
+/// their own mode, and so do explicit calls like `a.wrapping_add(b)`. When
+/// their mode differs from the one of the attribute, they are reported with a
+/// warning in the same way, as is a function transformed by more than one of
+/// `#[arithmetic_mode]`, `#[checked_fn]` and `#[try_fn]`. Nested items with
+/// their own attribute are only transformed by that attribute.
+///
+/// It can also be applied to `const` and `static` items. With `panicking`,
+/// overflows in their initializers become compile errors.
+///
+/// ```compile_fail
+/// use arithmetic_mode::arithmetic_mode;
+///
+/// const ROWS: u8 = 16;
+/// const COLS: u8 = 16;
+///
+/// #[arithmetic_mode(panicking)]
+/// const TABLE_SIZE: u8 = ROWS * COLS + 1;
+/// ```
+#[proc_macro_attribute]
+pub fn arithmetic_mode(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    mode_attribute("arithmetic_mode", attr, item, arithmetic_mode_impl)
+}
+
+/// Macro that applies a mode to all the arithmetic operations in a closure,
+/// like [`arithmetic_mode`] does for items, since attributes on closures are
+/// unstable. It takes the arguments of the attribute followed by the closure,
+/// like `closure!(wrapping, |x| x * 2 + 1)`, which is handy in iterator
+/// pipelines.
+///
+/// ```rust
+/// use arithmetic_mode::closure;
+///
+/// let hashes: Vec<u8> = [100_u8, 200, 250]
+///     .iter()
+///     .map(closure!(wrapping, |&b| b * 31 + 7))
+///     .collect();
+/// assert_eq!(vec![35, 63, 77], hashes);
+///
+/// let total = [200_u8, 100].iter().try_fold(0_u8, closure!(checked, |acc, &b| acc + b));
+/// assert_eq!(None, total);
+/// ```
+#[proc_macro]
+pub fn closure(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("closure", item, closure_impl)
+}
+
+/// Macro that applies a mode to a single statement in an otherwise unchanged
+/// function, since attributes on statements are unstable. It takes the mode
+/// followed by a `let` statement, which binds the value the mode evaluates
+/// to, or an assignment, whose right side is transformed. With
+/// parentheses, like `stmt!(checked: let total = a + b * c;);`, the macro is
+/// followed by a semicolon of its own.
+///
+/// ```rust
+/// use arithmetic_mode::stmt;
+///
+/// fn total(a: u8, b: u8, c: u8) -> Option<u8> {
+///     let count = a + 1;
+///     stmt! { checked: let total = count + b * c; }
+///     total
+/// }
+///
+/// assert_eq!(Some(13), total(2, 2, 5));
+/// assert_eq!(None, total(2, 20, 20));
+/// ```
+///
+/// A `let` statement can have an `else` branch, which is taken when the
+/// pattern does not match the value of the mode:
+///
+/// ```rust
+/// use arithmetic_mode::stmt;
+///
+/// fn area(width: u32, height: u32) -> u32 {
+///     stmt! { checked: let Some(area) = width * height else { return u32::MAX }; }
+///     area
+/// }
+///
+/// assert_eq!(6, area(2, 3));
+/// assert_eq!(u32::MAX, area(1 << 16, 1 << 16));
+/// ```
+#[proc_macro]
+pub fn stmt(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expression_macro("stmt", item, stmt_impl)
+}
+
+/// Attribute that changes all the arithmetic operations (add, sub, mul, div,
+/// and shift) inside a function to be checked operations, and changes the
+/// function to return `Option<T>` instead of `T`. If any operation overflows,
+/// the function returns `None`.
+///
+/// Closures and nested items inside the function are left unchanged, since
+/// the failures inside them cannot be propagated out of the function.
+///
+/// ```rust
+/// use arithmetic_mode::checked_fn;
+///
+/// #[checked_fn]
+/// fn area(width: u8, height: u8) -> u8 {
+///     if width == 0 {
+///         return 0;
+///     }
+///     let area = width * height;
+///     area + 1
+/// }
+///
+/// assert_eq!(Some(13), area(3, 4));
+/// assert_eq!(Some(0), area(0, 4));
+/// assert_eq!(None, area(16, 16));
+/// ```
+#[proc_macro_attribute]
+pub fn checked_fn(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    mode_attribute("checked_fn", attr, item, |attr, item| {
+        fallible_fn_impl(Mode::Checked, attr, item)
+    })
+}
+
+/// Attribute that changes all the arithmetic operations inside a function to
+/// be checked operations like [`checked_fn`], but changes the function to
+/// return `Result<T, ArithmeticError>` instead, so that overflows can be
+/// handled together with the other errors of the caller.
+///
+/// A different error type can be given as the argument, like
+/// `#[try_fn(MyError)]`, as long as it implements `From<ArithmeticError>`.
+/// Other errors propagated with `?` inside the function are converted to that
+/// type as usual.
+///
+/// ```rust
+/// use arithmetic_mode::{try_fn, ArithmeticError, Operation};
+///
+/// #[derive(Debug, PartialEq)]
+/// enum ParseError {
+///     Invalid,
+///     Overflow,
+/// }
+///
+/// impl From<ArithmeticError> for ParseError {
+///     fn from(_: ArithmeticError) -> Self {
+///         ParseError::Overflow
+///     }
+/// }
+///
+/// #[try_fn(ParseError)]
+/// fn parse_decimal(s: &str) -> u8 {
+///     let mut value = 0_u8;
+///     for c in s.chars() {
+///         let digit = c.to_digit(10).ok_or(ParseError::Invalid)? as u8;
+///         value = value * 10 + digit;
+///     }
+///     value
+/// }
+///
+/// assert_eq!(Ok(42), parse_decimal("42"));
+/// assert_eq!(Err(ParseError::Invalid), parse_decimal("4x"));
+/// assert_eq!(Err(ParseError::Overflow), parse_decimal("256"));
+///
+/// #[try_fn]
+/// fn double(a: u8) -> u8 {
+///     a * 2
+/// }
+///
+/// assert_eq!(Operation::Mul, double(128).unwrap_err().operation());
+/// ```
+#[proc_macro_attribute]
+pub fn try_fn(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    mode_attribute("try_fn", attr, item, |attr, item| {
+        fallible_fn_impl(Mode::TryChecked, attr, item)
+    })
+}
+
+/// Attribute that rejects the arithmetic operations (add, sub, mul, div, rem,
+/// and shift, including compound assignments) in an item unless they are
+/// inside one of the mode macros, so that a mode has to be chosen explicitly
+/// for each of them. The item itself is left unchanged.
+///
+/// Nested items with their own `#[arithmetic_mode]`, `#[checked_fn]` or
+/// `#[try_fn]` attribute are accepted, as are the statements and expressions
+/// marked with `#[arithmetic_mode(skip)]`. Since the types of the operands are
+/// not known to the macro, floating point arithmetic has to be marked as
+/// skipped as well. Arithmetic inside other macro invocations is not checked.
+///
+/// ```rust
+/// use arithmetic_mode::{deny_unchecked_arithmetic, wrapping};
+///
+/// #[deny_unchecked_arithmetic]
+/// fn mix(a: u32, b: u32) -> u32 {
+///     wrapping! { a * 31 + b }
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use arithmetic_mode::deny_unchecked_arithmetic;
+///
+/// #[deny_unchecked_arithmetic]
+/// fn mix(a: u32, b: u32) -> u32 {
+///     a * 31 + b
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn deny_unchecked_arithmetic(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    match debug::trace_attribute(
+        "deny_unchecked_arithmetic",
+        attr.into(),
+        item.into(),
+        deny_unchecked_arithmetic_impl,
+    ) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Attribute that enforces an arithmetic policy on an item, usually a module.
+/// Like [`deny_unchecked_arithmetic`], it rejects the arithmetic operations
+/// that don't use one of the modes explicitly, but it also rejects the modes
+/// other than the `default` mode and the ones listed in `allow`. The
+/// `#[arithmetic_mode(skip)]` marker is only accepted if `"skip"` is listed in
+/// `allow`.
+///
+/// ```rust
+/// #[arithmetic_mode::enforce(default = "checked", allow = ["wrapping"])]
+/// mod math {
+///     use arithmetic_mode::{checked, wrapping};
+///
+///     pub fn average(a: u32, b: u32) -> Option<u32> {
+///         checked! { (a + b) / 2 }
+///     }
+///
+///     pub fn mix(a: u32, b: u32) -> u32 {
+///         wrapping! { a * 31 + b }
+///     }
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #[arithmetic_mode::enforce(default = "checked", allow = ["wrapping"])]
+/// mod math {
+///     use arithmetic_mode::saturating;
+///
+///     pub fn sum(a: u8, b: u8) -> u8 {
+///         saturating! { a + b }
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn enforce(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    match debug::trace_attribute("enforce", attr.into(), item.into(), enforce_impl) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Expands the function-like macro `name` with `f`, warning if its input has
+/// no arithmetic operations to transform.
+fn expression_macro(
+    name: &str,
+    item: proc_macro::TokenStream,
+    f: fn(TokenStream) -> syn::Result<TokenStream>,
+) -> proc_macro::TokenStream {
+    let item = TokenStream::from(item);
+    match debug::trace(name, item.clone(), |input| {
+        let expansion = f(input.clone())?;
+        let expansion = warn_without_arithmetic(name, input.clone(), expansion);
+        Ok(passthrough(name, input, expansion))
+    }) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => expression_error(&e, incomplete_expression(name, item)),
+    }
+}
+
+/// Expands the attribute `name` that applies a mode to the item with `f`,
+/// keeping the original operators for `--cfg arithmetic_mode_passthrough`.
+fn mode_attribute(
+    name: &str,
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+    f: impl FnOnce(TokenStream, TokenStream) -> syn::Result<TokenStream>,
+) -> proc_macro::TokenStream {
+    match debug::trace_attribute(name, attr.into(), item.into(), |attr, item| {
+        let expansion = f(attr.clone(), item.clone())?;
+        Ok(passthrough_attribute(name, attr, item, expansion))
+    }) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Reports `e` in the position of an expression, where several
+/// `compile_error!` invocations have to be in a block. The `incomplete`
+/// expression of the input, if any, is kept after them, so that IDEs can still
+/// complete it while it is being typed.
+fn expression_error(e: &syn::Error, incomplete: Option<TokenStream>) -> proc_macro::TokenStream {
+    let errors = e.to_compile_error();
+    quote! { { #errors #incomplete } }.into()
+}
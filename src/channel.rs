@@ -0,0 +1,46 @@
+//! The 8-bit channels of [`blend_add!`](crate::blend_add) and
+//! [`scale_u8!`](crate::scale_u8), which are single `u8` values or pixels of
+//! them, like `[u8; 4]` for RGBA.
+
+use crate::mul_div::{mul_div_saturating, Rounding};
+
+/// A `u8` channel, or a pixel of channels that are blended and scaled
+/// separately.
+pub trait Channel: Copy {
+    /// The sum of `self` and `rhs`, saturated at `u8::MAX`.
+    fn blend_add(self, rhs: Self) -> Self;
+
+    /// `self * num / den`, computed in a wider type and saturated at
+    /// `u8::MAX`. Panics if `den` is zero.
+    fn scale(self, num: u8, den: u8, rounding: Rounding) -> Self;
+}
+
+impl Channel for u8 {
+    #[inline(always)]
+    fn blend_add(self, rhs: Self) -> Self {
+        self.saturating_add(rhs)
+    }
+
+    #[inline(always)]
+    #[track_caller]
+    fn scale(self, num: u8, den: u8, rounding: Rounding) -> Self {
+        mul_div_saturating(self, num, den, rounding)
+    }
+}
+
+impl<const N: usize> Channel for [u8; N] {
+    #[inline(always)]
+    fn blend_add(self, rhs: Self) -> Self {
+        core::array::from_fn(|i| self[i].saturating_add(rhs[i]))
+    }
+
+    #[inline(always)]
+    #[track_caller]
+    fn scale(self, num: u8, den: u8, rounding: Rounding) -> Self {
+        // Checked once, since an empty pixel would not divide at all
+        if den == 0 {
+            panic!("attempt to divide by zero");
+        }
+        self.map(|channel| mul_div_saturating(channel, num, den, rounding))
+    }
+}
@@ -0,0 +1,241 @@
+//! [`Checked`], the checked counterpart of `core::num::Wrapping` and
+//! `core::num::Saturating`, whose operators evaluate like [`checked!`](crate::checked)
+//! without a macro, so that checked arithmetic can be written in generic code
+//! with plain operators, or built one operation at a time, like
+//! `Checked::from(a).add(b).mul(c).finish()`, for the expressions only known
+//! at runtime.
+
+use core::iter::{Product, Sum};
+use core::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Shl, ShlAssign, Shr,
+    ShrAssign, Sub, SubAssign,
+};
+
+/// An integer whose arithmetic is checked, which is `None` once an operation
+/// overflows or divides by zero, and stays `None` through the operations that
+/// follow, like the expression of [`checked!`](crate::checked).
+///
+/// ```rust
+/// use arithmetic_mode::Checked;
+///
+/// let price = Checked::new(200_u8);
+/// assert_eq!(Some(250), (price + 50).get());
+/// assert_eq!(None, (price * 2 + 1).get());
+///
+/// let total: Checked<u8> = [100_u8, 100, 100].into_iter().sum();
+/// assert_eq!(None, total.get());
+/// ```
+///
+/// The operations are also methods, which do not need the traits in scope,
+/// for the expressions built at runtime, like by an interpreter:
+///
+/// ```rust
+/// use arithmetic_mode::Checked;
+///
+/// let (a, b, c) = (20_u32, 22, 3);
+/// assert_eq!(Some(126), Checked::from(a).add(b).mul(c).finish());
+/// assert_eq!(None, Checked::from(a).sub(b).finish());
+///
+/// // Like a formula read from a config file
+/// let formula = [('+', 5), ('*', 4), ('/', 0)];
+/// let value = formula
+///     .into_iter()
+///     .fold(Checked::from(10_u32), |value, (op, rhs)| match op {
+///         '+' => value.add(rhs),
+///         '*' => value.mul(rhs),
+///         _ => value.div(rhs),
+///     });
+/// assert_eq!(None, value.finish());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Checked<T>(pub Option<T>);
+
+impl<T> Checked<T> {
+    /// The integer `value`, which has not overflowed.
+    #[inline(always)]
+    pub const fn new(value: T) -> Self {
+        Checked(Some(value))
+    }
+
+    /// The value, or `None` if an operation overflowed.
+    #[inline(always)]
+    pub fn get(self) -> Option<T> {
+        self.0
+    }
+
+    /// The value of the operations built with the methods, or `None` if any
+    /// of them overflowed or divided by zero, like [`get`](Self::get).
+    #[inline(always)]
+    pub fn finish(self) -> Option<T> {
+        self.0
+    }
+}
+
+/// Zero, like the `Default` of the integers.
+impl<T: Default> Default for Checked<T> {
+    #[inline(always)]
+    fn default() -> Self {
+        Checked::new(T::default())
+    }
+}
+
+impl<T> From<T> for Checked<T> {
+    #[inline(always)]
+    fn from(value: T) -> Self {
+        Checked::new(value)
+    }
+}
+
+impl<T> From<Checked<T>> for Option<T> {
+    #[inline(always)]
+    fn from(checked: Checked<T>) -> Self {
+        checked.0
+    }
+}
+
+macro_rules! impl_checked {
+    (signed: $($ty:ident)*) => {
+        $(
+            impl_checked!($ty);
+
+            impl Neg for Checked<$ty> {
+                type Output = Self;
+
+                #[inline(always)]
+                fn neg(self) -> Self {
+                    Checked(self.0.and_then($ty::checked_neg))
+                }
+            }
+
+            impl Checked<$ty> {
+                /// Negates the value, like `-self`.
+                #[allow(clippy::should_implement_trait)] // Without `Neg` in scope
+                #[inline(always)]
+                pub fn neg(self) -> Self {
+                    Neg::neg(self)
+                }
+            }
+        )*
+    };
+    (unsigned: $($ty:ident)*) => {
+        $(
+            impl_checked!($ty);
+        )*
+    };
+    ($ty:ident) => {
+        impl_checked!(@binary $ty: Add add AddAssign add_assign checked_add);
+        impl_checked!(@binary $ty: Sub sub SubAssign sub_assign checked_sub);
+        impl_checked!(@binary $ty: Mul mul MulAssign mul_assign checked_mul);
+        impl_checked!(@binary $ty: Div div DivAssign div_assign checked_div);
+        impl_checked!(@binary $ty: Rem rem RemAssign rem_assign checked_rem);
+        impl_checked!(@shift $ty: Shl shl ShlAssign shl_assign checked_shl);
+        impl_checked!(@shift $ty: Shr shr ShrAssign shr_assign checked_shr);
+        impl_checked!(@fold $ty: Sum sum Add add 0);
+        impl_checked!(@fold $ty: Product product Mul mul 1);
+
+        // The methods of the operators, which do not need the traits in scope
+        #[allow(clippy::should_implement_trait)]
+        impl Checked<$ty> {
+            impl_checked!(@methods $ty: "Adds" Add add, "Subtracts" Sub sub,
+                "Multiplies by" Mul mul, "Divides by" Div div,
+                "Takes the remainder of the division by" Rem rem);
+
+            /// Shifts the value left by `rhs` bits, like `self << rhs`.
+            #[inline(always)]
+            pub fn shl(self, rhs: u32) -> Self {
+                Shl::shl(self, rhs)
+            }
+
+            /// Shifts the value right by `rhs` bits, like `self >> rhs`.
+            #[inline(always)]
+            pub fn shr(self, rhs: u32) -> Self {
+                Shr::shr(self, rhs)
+            }
+
+            /// Raises the value to the power `exp`.
+            #[inline(always)]
+            pub fn pow(self, exp: u32) -> Self {
+                Checked(self.0.and_then(|value| value.checked_pow(exp)))
+            }
+        }
+    };
+    (@binary $ty:ident: $trait:ident $method:ident $assign:ident $assign_method:ident $func:ident) => {
+        impl $trait for Checked<$ty> {
+            type Output = Self;
+
+            #[inline(always)]
+            fn $method(self, rhs: Self) -> Self {
+                Checked(self.0.zip(rhs.0).and_then(|(l, r)| l.$func(r)))
+            }
+        }
+
+        impl $trait<$ty> for Checked<$ty> {
+            type Output = Self;
+
+            #[inline(always)]
+            fn $method(self, rhs: $ty) -> Self {
+                Checked(self.0.and_then(|l| l.$func(rhs)))
+            }
+        }
+
+        impl $assign for Checked<$ty> {
+            #[inline(always)]
+            fn $assign_method(&mut self, rhs: Self) {
+                *self = $trait::$method(*self, rhs);
+            }
+        }
+
+        impl $assign<$ty> for Checked<$ty> {
+            #[inline(always)]
+            fn $assign_method(&mut self, rhs: $ty) {
+                *self = $trait::$method(*self, rhs);
+            }
+        }
+    };
+    // The shifts take a `u32`, like the checked shifts of the integers
+    (@shift $ty:ident: $trait:ident $method:ident $assign:ident $assign_method:ident $func:ident) => {
+        impl $trait<u32> for Checked<$ty> {
+            type Output = Self;
+
+            #[inline(always)]
+            fn $method(self, rhs: u32) -> Self {
+                Checked(self.0.and_then(|l| l.$func(rhs)))
+            }
+        }
+
+        impl $assign<u32> for Checked<$ty> {
+            #[inline(always)]
+            fn $assign_method(&mut self, rhs: u32) {
+                *self = $trait::$method(*self, rhs);
+            }
+        }
+    };
+    (@methods $ty:ident: $($doc:literal $trait:ident $method:ident),*) => {
+        $(
+            #[doc = concat!($doc, " `rhs`, like the operator.")]
+            #[inline(always)]
+            pub fn $method(self, rhs: $ty) -> Self {
+                $trait::$method(self, rhs)
+            }
+        )*
+    };
+    (@fold $ty:ident: $trait:ident $method:ident $op_trait:ident $op:ident $identity:literal) => {
+        impl $trait for Checked<$ty> {
+            #[inline(always)]
+            fn $method<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Checked::new($identity), |acc, value| $op_trait::$op(acc, value))
+            }
+        }
+
+        impl $trait<$ty> for Checked<$ty> {
+            #[inline(always)]
+            fn $method<I: Iterator<Item = $ty>>(iter: I) -> Self {
+                iter.fold(Checked::new($identity), |acc, value| $op_trait::$op(acc, value))
+            }
+        }
+    };
+}
+
+impl_checked!(signed: i8 i16 i32 i64 i128 isize);
+impl_checked!(unsigned: u8 u16 u32 u64 u128 usize);
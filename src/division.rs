@@ -0,0 +1,259 @@
+//! The divisions of the flavors of the modes, like `div = floor; a / b`,
+//! whose quotients are rounded toward negative or positive infinity, or to
+//! the nearest integer with the halves rounded to even, instead of toward
+//! zero. The Euclidean divisions are the methods of the integer
+//! types, other than the saturating one, which they don't have.
+
+/// The divisions and the remainders of the flavors of the modes.
+pub trait Division: Sized {
+    /// `self / rhs` rounded toward negative infinity, or `None` if `rhs` is
+    /// zero or the quotient overflows.
+    fn checked_div_floor(self, rhs: Self) -> Option<Self>;
+
+    /// `self / rhs` rounded toward positive infinity, or `None` if `rhs` is
+    /// zero or the quotient overflows.
+    fn checked_div_ceil(self, rhs: Self) -> Option<Self>;
+
+    /// The remainder of [`Division::checked_div_floor`], which has the sign of
+    /// `rhs`.
+    fn checked_rem_floor(self, rhs: Self) -> Option<Self>;
+
+    /// The remainder of [`Division::checked_div_ceil`], which has the
+    /// opposite sign of `rhs`, so it overflows for the unsigned types unless
+    /// it is zero.
+    fn checked_rem_ceil(self, rhs: Self) -> Option<Self>;
+
+    /// `self / rhs` rounded to the nearest integer, and to the even one when
+    /// halfway, or `None` if `rhs` is zero or the quotient overflows.
+    fn checked_div_half_even(self, rhs: Self) -> Option<Self>;
+
+    /// The remainder of [`Division::checked_div_half_even`], which is at most
+    /// half of `rhs`, and can have the opposite sign of `self`, so it
+    /// overflows for the unsigned types when the quotient is rounded up.
+    fn checked_rem_half_even(self, rhs: Self) -> Option<Self>;
+
+    /// Like [`Division::checked_div_floor`], wrapping around at the bounds of
+    /// the type. Panics if `rhs` is zero.
+    fn wrapping_div_floor(self, rhs: Self) -> Self;
+
+    /// Like [`Division::checked_div_ceil`], wrapping around at the bounds of
+    /// the type. Panics if `rhs` is zero.
+    fn wrapping_div_ceil(self, rhs: Self) -> Self;
+
+    /// Like [`Division::checked_rem_floor`], wrapping around at the bounds of
+    /// the type. Panics if `rhs` is zero.
+    fn wrapping_rem_floor(self, rhs: Self) -> Self;
+
+    /// Like [`Division::checked_rem_ceil`], wrapping around at the bounds of
+    /// the type. Panics if `rhs` is zero.
+    fn wrapping_rem_ceil(self, rhs: Self) -> Self;
+
+    /// Like [`Division::checked_div_half_even`], wrapping around at the
+    /// bounds of the type. Panics if `rhs` is zero.
+    fn wrapping_div_half_even(self, rhs: Self) -> Self;
+
+    /// Like [`Division::checked_rem_half_even`], wrapping around at the
+    /// bounds of the type. Panics if `rhs` is zero.
+    fn wrapping_rem_half_even(self, rhs: Self) -> Self;
+
+    /// Like [`Division::checked_div_floor`], saturating at the bounds of the
+    /// type. Panics if `rhs` is zero.
+    fn saturating_div_floor(self, rhs: Self) -> Self;
+
+    /// Like [`Division::checked_div_ceil`], saturating at the bounds of the
+    /// type. Panics if `rhs` is zero.
+    fn saturating_div_ceil(self, rhs: Self) -> Self;
+
+    /// Like [`Division::checked_div_half_even`], saturating at the bounds of
+    /// the type. Panics if `rhs` is zero.
+    fn saturating_div_half_even(self, rhs: Self) -> Self;
+
+    /// Like `checked_div_euclid`, saturating at the bounds of the type.
+    /// Panics if `rhs` is zero.
+    fn saturating_div_euclid(self, rhs: Self) -> Self;
+}
+
+macro_rules! impl_division {
+    (signed: $($ty:ident)*) => {
+        $(
+            impl Division for $ty {
+                // The quotient is only adjusted away from zero when it is not
+                // a bound, which has no remainder
+                #[inline(always)]
+                fn checked_div_floor(self, rhs: Self) -> Option<Self> {
+                    let (q, r) = (self.checked_div(rhs)?, self % rhs);
+                    Some(if r != 0 && (r < 0) != (rhs < 0) { q - 1 } else { q })
+                }
+
+                #[inline(always)]
+                fn checked_div_ceil(self, rhs: Self) -> Option<Self> {
+                    let (q, r) = (self.checked_div(rhs)?, self % rhs);
+                    Some(if r != 0 && (r < 0) == (rhs < 0) { q + 1 } else { q })
+                }
+
+                #[inline(always)]
+                fn checked_div_half_even(self, rhs: Self) -> Option<Self> {
+                    let (q, r) = (self.checked_div(rhs)?, self % rhs);
+                    if !round_half_even(r.unsigned_abs(), rhs.unsigned_abs(), q % 2 != 0) {
+                        return Some(q);
+                    }
+                    Some(if (r < 0) != (rhs < 0) { q - 1 } else { q + 1 })
+                }
+
+                // The remainder is only adjusted toward the opposite sign
+                #[inline(always)]
+                fn checked_rem_floor(self, rhs: Self) -> Option<Self> {
+                    let r = self.checked_rem(rhs)?;
+                    Some(if r != 0 && (r < 0) != (rhs < 0) { r + rhs } else { r })
+                }
+
+                #[inline(always)]
+                fn checked_rem_ceil(self, rhs: Self) -> Option<Self> {
+                    let r = self.checked_rem(rhs)?;
+                    Some(if r != 0 && (r < 0) == (rhs < 0) { r - rhs } else { r })
+                }
+
+                #[inline(always)]
+                fn checked_rem_half_even(self, rhs: Self) -> Option<Self> {
+                    let (q, r) = (self.checked_div(rhs)?, self % rhs);
+                    if !round_half_even(r.unsigned_abs(), rhs.unsigned_abs(), q % 2 != 0) {
+                        return Some(r);
+                    }
+                    Some(if (r < 0) != (rhs < 0) { r + rhs } else { r - rhs })
+                }
+
+                // The only overflow of the remainders is `MIN % -1`, which is
+                // zero
+                impl_division!(@fallback $ty: wrapping_rem_ceil = checked_rem_ceil, REM, 0);
+                impl_division!(@fallback $ty: wrapping_rem_half_even = checked_rem_half_even, REM, 0);
+                impl_division!(@common $ty);
+            }
+        )*
+    };
+    (unsigned: $($ty:ident)*) => {
+        $(
+            impl Division for $ty {
+                #[inline(always)]
+                fn checked_div_floor(self, rhs: Self) -> Option<Self> {
+                    self.checked_div(rhs)
+                }
+
+                // The quotient is at most half of `MAX` when it is rounded up
+                #[inline(always)]
+                fn checked_div_ceil(self, rhs: Self) -> Option<Self> {
+                    let (q, r) = (self.checked_div(rhs)?, self % rhs);
+                    Some(if r != 0 { q + 1 } else { q })
+                }
+
+                #[inline(always)]
+                fn checked_div_half_even(self, rhs: Self) -> Option<Self> {
+                    let (q, r) = (self.checked_div(rhs)?, self % rhs);
+                    Some(if round_half_even(r, rhs, q % 2 != 0) { q + 1 } else { q })
+                }
+
+                #[inline(always)]
+                fn checked_rem_floor(self, rhs: Self) -> Option<Self> {
+                    self.checked_rem(rhs)
+                }
+
+                #[inline(always)]
+                fn checked_rem_ceil(self, rhs: Self) -> Option<Self> {
+                    match self.checked_rem(rhs)? {
+                        0 => Some(0),
+                        _ => None,
+                    }
+                }
+
+                #[inline(always)]
+                fn checked_rem_half_even(self, rhs: Self) -> Option<Self> {
+                    let (q, r) = (self.checked_div(rhs)?, self % rhs);
+                    (!round_half_even(r, rhs, q % 2 != 0)).then_some(r)
+                }
+
+                #[inline(always)]
+                #[track_caller]
+                fn wrapping_rem_ceil(self, rhs: Self) -> Self {
+                    impl_division!(@zero rhs, REM, ());
+                    match self % rhs {
+                        0 => 0,
+                        r => r.wrapping_sub(rhs),
+                    }
+                }
+
+                #[inline(always)]
+                #[track_caller]
+                fn wrapping_rem_half_even(self, rhs: Self) -> Self {
+                    impl_division!(@zero rhs, REM, ());
+                    let (q, r) = (self / rhs, self % rhs);
+                    if round_half_even(r, rhs, q % 2 != 0) {
+                        r.wrapping_sub(rhs)
+                    } else {
+                        r
+                    }
+                }
+
+                impl_division!(@common $ty);
+            }
+        )*
+    };
+    // The only overflow of the signed divisions is `MIN / -1`, whose quotient
+    // is exact, so it wraps around to `MIN` and saturates at `MAX` in all the
+    // flavors, and its remainder is zero
+    (@common $ty:ident) => {
+        impl_division!(@fallback $ty: wrapping_div_floor = checked_div_floor, DIV, $ty::MIN);
+        impl_division!(@fallback $ty: wrapping_div_ceil = checked_div_ceil, DIV, $ty::MIN);
+        impl_division!(@fallback $ty: wrapping_div_half_even = checked_div_half_even, DIV, $ty::MIN);
+        impl_division!(@fallback $ty: wrapping_rem_floor = checked_rem_floor, REM, 0);
+        impl_division!(@fallback $ty: saturating_div_floor = checked_div_floor, DIV, $ty::MAX);
+        impl_division!(@fallback $ty: saturating_div_ceil = checked_div_ceil, DIV, $ty::MAX);
+        impl_division!(@fallback $ty: saturating_div_half_even = checked_div_half_even, DIV, $ty::MAX);
+        impl_division!(@fallback $ty: saturating_div_euclid = checked_div_euclid, DIV, $ty::MAX);
+    };
+    // The method `$method`, which is the result of `$checked`, or the
+    // `overflow` value if it fails without dividing by zero
+    (@fallback $ty:ident: $method:ident = $checked:ident, $op:ident, $overflow:expr) => {
+        #[inline(always)]
+        #[track_caller]
+        fn $method(self, rhs: Self) -> Self {
+            match self.$checked(rhs) {
+                Some(value) => value,
+                None => impl_division!(@zero rhs, $op, $overflow),
+            }
+        }
+    };
+    // Panics if `rhs` is zero, like the operators, and evaluates to `value`
+    // otherwise
+    (@zero $rhs:ident, DIV, $value:expr) => {{
+        if $rhs == 0 {
+            panic!("attempt to divide by zero");
+        }
+        $value
+    }};
+    (@zero $rhs:ident, REM, $value:expr) => {{
+        if $rhs == 0 {
+            panic!("attempt to calculate the remainder with a divisor of zero");
+        }
+        $value
+    }};
+}
+
+/// Whether the quotient of a division with the `remainder` and the `divisor`,
+/// as magnitudes, is rounded away from zero to the nearest integer, with the
+/// halves rounded to even, given whether its truncated value is `odd`.
+/// Written so that it cannot overflow, like `2 * remainder > divisor`, and
+/// without a wider type, so that it also works for the 128-bit types.
+#[inline(always)]
+fn round_half_even<T: Ord + core::ops::Sub<Output = T> + Copy>(
+    remainder: T,
+    divisor: T,
+    odd: bool,
+) -> bool {
+    match remainder.cmp(&(divisor - remainder)) {
+        core::cmp::Ordering::Less => false,
+        core::cmp::Ordering::Equal => odd,
+        core::cmp::Ordering::Greater => true,
+    }
+}
+
+impl_division!(signed: i8 i16 i32 i64 i128 isize);
+impl_division!(unsigned: u8 u16 u32 u64 u128 usize);
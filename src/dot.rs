@@ -0,0 +1,160 @@
+//! The dot products of [`dot_product!`](crate::dot_product), which are
+//! accumulated in a wider integer type and narrowed once at the end, and the
+//! multiply-accumulates of [`mac!`](crate::mac), which narrow `acc + a * b`
+//! after adding the exact product.
+
+use crate::wide::Widen;
+use crate::{ArithmeticError, Operation};
+
+/// The dot product of `a` and `b` in the accumulator.
+#[track_caller]
+fn accumulate<T: Widen>(a: &[T], b: &[T]) -> T::Wide {
+    check_lengths(a, b);
+    a.iter()
+        .zip(b)
+        .fold(T::Wide::default(), |acc, (&a, &b)| a.multiply_add(b, acc))
+}
+
+/// Panics if the vectors have different lengths, which would otherwise be
+/// truncated to the shorter one.
+#[track_caller]
+fn check_lengths<T>(a: &[T], b: &[T]) {
+    if a.len() != b.len() {
+        panic!(
+            "dot_product! of vectors of different lengths {} and {}",
+            a.len(),
+            b.len()
+        );
+    }
+}
+
+/// The dot product in `panicking` mode.
+#[track_caller]
+pub fn dot_panicking<T: Widen>(a: &[T], b: &[T]) -> T {
+    match T::narrow(accumulate(a, b)) {
+        Some(value) => value,
+        None => panic!("attempt to compute the dot product with overflow"),
+    }
+}
+
+/// The dot product in `wrapping` mode.
+#[track_caller]
+pub fn dot_wrapping<T: Widen>(a: &[T], b: &[T]) -> T {
+    check_lengths(a, b);
+    a.iter().zip(b).fold(T::default(), |acc, (&a, &b)| {
+        a.wrapping_multiply_add(b, acc)
+    })
+}
+
+/// The dot product in `saturating` mode.
+#[track_caller]
+pub fn dot_saturating<T: Widen>(a: &[T], b: &[T]) -> T {
+    T::saturate(accumulate(a, b))
+}
+
+/// The dot product in `checked` mode.
+#[track_caller]
+pub fn dot_checked<T: Widen>(a: &[T], b: &[T]) -> Option<T> {
+    T::narrow(accumulate(a, b))
+}
+
+/// The dot product in `try_checked` mode, whose overflow is reported as the
+/// conversion to the type.
+#[track_caller]
+pub fn dot_try_checked<T: Widen>(a: &[T], b: &[T]) -> Result<T, ArithmeticError> {
+    dot_checked(a, b).ok_or(ArithmeticError::new(Operation::Conversion))
+}
+
+/// An integer type whose multiply-accumulates `acc + a * b` are computed with
+/// the exact product, in [`Widen::Wide`] for the types that have one. The
+/// 128-bit types have no wider type, so their product can overflow on its
+/// own, like in `acc + a * b`.
+pub trait MultiplyAccumulate: Copy {
+    /// `self + a * b`, if it fits in the type.
+    fn checked_mac(self, a: Self, b: Self) -> Option<Self>;
+
+    /// `self + a * b`, saturated at the bounds of the type.
+    fn saturating_mac(self, a: Self, b: Self) -> Self;
+
+    /// `self + a * b`, wrapped around at the bounds of the type.
+    fn wrapping_mac(self, a: Self, b: Self) -> Self;
+}
+
+macro_rules! impl_multiply_accumulate {
+    ($($ty:ident),* ; $($narrow:ident),*) => {
+        $(
+            impl MultiplyAccumulate for $ty {
+                #[inline(always)]
+                fn checked_mac(self, a: Self, b: Self) -> Option<Self> {
+                    Self::narrow(a.multiply_add(b, self.widen()))
+                }
+
+                #[inline(always)]
+                fn saturating_mac(self, a: Self, b: Self) -> Self {
+                    Self::saturate(a.multiply_add(b, self.widen()))
+                }
+
+                #[inline(always)]
+                fn wrapping_mac(self, a: Self, b: Self) -> Self {
+                    a.wrapping_multiply_add(b, self)
+                }
+            }
+        )*
+        $(
+            impl MultiplyAccumulate for $narrow {
+                #[inline(always)]
+                fn checked_mac(self, a: Self, b: Self) -> Option<Self> {
+                    self.checked_add(a.checked_mul(b)?)
+                }
+
+                #[inline(always)]
+                fn saturating_mac(self, a: Self, b: Self) -> Self {
+                    self.saturating_add(a.saturating_mul(b))
+                }
+
+                #[inline(always)]
+                fn wrapping_mac(self, a: Self, b: Self) -> Self {
+                    self.wrapping_add(a.wrapping_mul(b))
+                }
+            }
+        )*
+    };
+}
+
+impl_multiply_accumulate!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize; u128, i128);
+
+/// The multiply-accumulate `acc + a * b` in `panicking` mode.
+#[inline]
+#[track_caller]
+pub fn mac_panicking<T: MultiplyAccumulate>(acc: T, a: T, b: T) -> T {
+    match acc.checked_mac(a, b) {
+        Some(value) => value,
+        None => panic!("attempt to multiply and accumulate with overflow"),
+    }
+}
+
+/// The multiply-accumulate `acc + a * b` in `wrapping` mode.
+#[inline]
+pub fn mac_wrapping<T: MultiplyAccumulate>(acc: T, a: T, b: T) -> T {
+    acc.wrapping_mac(a, b)
+}
+
+/// The multiply-accumulate `acc + a * b` in `saturating` mode.
+#[inline]
+pub fn mac_saturating<T: MultiplyAccumulate>(acc: T, a: T, b: T) -> T {
+    acc.saturating_mac(a, b)
+}
+
+/// The multiply-accumulate `acc + a * b` in `checked` mode.
+#[inline]
+pub fn mac_checked<T: MultiplyAccumulate>(acc: T, a: T, b: T) -> Option<T> {
+    acc.checked_mac(a, b)
+}
+
+/// The multiply-accumulate `acc + a * b` in `try_checked` mode, whose
+/// overflow is reported as the addition to the accumulator.
+#[inline]
+pub fn mac_try_checked<T: MultiplyAccumulate>(acc: T, a: T, b: T) -> Result<T, ArithmeticError> {
+    acc.checked_mac(a, b)
+        .ok_or(ArithmeticError::new(Operation::Add))
+}
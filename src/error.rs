@@ -0,0 +1,470 @@
+use core::fmt::{self, Debug, Display, Write};
+use core::num::TryFromIntError;
+
+/// Error returned by [`try_checked!`](crate::try_checked) when an arithmetic
+/// operation fails. Errors produced by the macros also describe the failed
+/// operation, its operands and its location, which are included when the error
+/// is displayed.
+///
+/// ```rust
+/// use arithmetic_mode::{try_checked, ErrorKind, Operation};
+///
+/// let x = 200_u8;
+/// let error = try_checked! { x + 100 }.unwrap_err();
+/// assert_eq!(Operation::Add, error.operation());
+/// assert_eq!(Some("x + 100"), error.expression());
+/// assert_eq!(Some(("200", "100")), error.operands());
+///
+/// let error = try_checked! { x / 0 }.unwrap_err();
+/// assert_eq!(ErrorKind::DivisionByZero, error.kind());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArithmeticError {
+    operation: Operation,
+    kind: ErrorKind,
+    details: Option<Details>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Details {
+    expression: &'static str,
+    location: &'static Location,
+    operands: [Operand; 2],
+}
+
+impl ArithmeticError {
+    /// Creates an error for an `operation` that overflowed, without any
+    /// details.
+    pub const fn new(operation: Operation) -> Self {
+        ArithmeticError {
+            operation,
+            kind: ErrorKind::Overflow,
+            details: None,
+        }
+    }
+
+    /// Sets the reason of the failure.
+    pub const fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    #[doc(hidden)]
+    #[cold]
+    pub fn __operation(
+        operation: Operation,
+        expression: &'static str,
+        location: &'static Location,
+        left: &dyn Debug,
+        right: &dyn Debug,
+    ) -> Self {
+        ArithmeticError {
+            operation,
+            kind: ErrorKind::Overflow,
+            details: Some(Details {
+                expression,
+                location,
+                operands: [Operand::new(left), Operand::new(right)],
+            }),
+        }
+    }
+
+    /// Panics with the error of the failed operation, after `message` if any.
+    /// Shared by all the operations of `panicking!`, so that formatting the
+    /// panic is not inlined into every expansion.
+    #[doc(hidden)]
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    #[allow(clippy::too_many_arguments)]
+    pub fn __panic_operation(
+        operation: Operation,
+        expression: &'static str,
+        location: &'static Location,
+        left: &dyn Debug,
+        right: &dyn Debug,
+        kind: ErrorKind,
+        message: Option<&str>,
+    ) -> ! {
+        let error = Self::__operation(operation, expression, location, left, right).with_kind(kind);
+        match message {
+            Some(message) => panic!("{message}: {error}"),
+            None => panic!("{error}"),
+        }
+    }
+
+    /// Panics with `defmt::panic!` instead of [`__panic_operation`] with the
+    /// `defmt` feature, so that the message is formatted on the host instead
+    /// of with `core::fmt`. The operands are formatted with `defmt` too, so
+    /// they are passed by type.
+    ///
+    /// [`__panic_operation`]: ArithmeticError::__panic_operation
+    #[cfg(feature = "defmt")]
+    #[doc(hidden)]
+    #[cold]
+    #[inline(never)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn __defmt_panic_operation<L: defmt::Format, R: defmt::Format>(
+        operation: Operation,
+        expression: &'static str,
+        location: &'static Location,
+        left: &L,
+        right: &R,
+        kind: ErrorKind,
+        message: Option<&str>,
+    ) -> ! {
+        let error = Self::new(operation).with_kind(kind);
+        match message {
+            Some(message) => defmt::panic!(
+                "{=str}: {}: `{=str}` with operands {} and {}, at {}",
+                message,
+                error,
+                expression,
+                left,
+                right,
+                location
+            ),
+            None => defmt::panic!(
+                "{}: `{=str}` with operands {} and {}, at {}",
+                error,
+                expression,
+                left,
+                right,
+                location
+            ),
+        }
+    }
+
+    /// Aborts the program after a failed operation, without unwinding or
+    /// formatting a message, instead of [`__panic_operation`] with the `abort`
+    /// feature.
+    ///
+    /// [`__panic_operation`]: ArithmeticError::__panic_operation
+    #[doc(hidden)]
+    #[cold]
+    #[inline(never)]
+    pub fn __abort() -> ! {
+        #[cfg(feature = "nightly")]
+        core::intrinsics::abort();
+        #[cfg(all(feature = "std", not(feature = "nightly")))]
+        std::process::abort();
+        #[cfg(not(any(feature = "std", feature = "nightly")))]
+        trap();
+    }
+
+    /// The operation that failed.
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// The reason of the failure.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The source code of the failed operation, like `a + b`.
+    pub fn expression(&self) -> Option<&'static str> {
+        self.details.map(|details| details.expression)
+    }
+
+    /// The `Debug` representations of the left and right operands of the
+    /// failed operation. Representations longer than 40 bytes are truncated.
+    pub fn operands(&self) -> Option<(&str, &str)> {
+        let [left, right] = &self.details.as_ref()?.operands;
+        Some((left.as_str(), right.as_str()))
+    }
+
+    /// The location of the failed operation in the source code.
+    pub fn location(&self) -> Option<&'static Location> {
+        self.details.map(|details| details.location)
+    }
+}
+
+impl Display for ArithmeticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = match self.operation {
+            Operation::Add => "add",
+            Operation::Sub => "subtract",
+            Operation::Mul => "multiply",
+            Operation::Div => "divide",
+            Operation::Rem => "calculate the remainder",
+            Operation::Shl => "shift left",
+            Operation::Shr => "shift right",
+            Operation::Conversion => return f.write_str("integer conversion overflowed"),
+        };
+        match (self.kind, self.operation) {
+            (ErrorKind::DivisionByZero, Operation::Rem) => {
+                f.write_str("attempt to calculate the remainder with a divisor of zero")?
+            }
+            (ErrorKind::DivisionByZero, _) => f.write_str("attempt to divide by zero")?,
+            (ErrorKind::Overflow, _) => write!(f, "attempt to {verb} with overflow")?,
+        }
+        if let Some(Details {
+            expression,
+            location,
+            operands: [left, right],
+        }) = &self.details
+        {
+            write!(
+                f,
+                ": `{expression}` with operands {left} and {right}, at {location}"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Like the `Display` implementation, with the description of the operation
+/// interned by `defmt`.
+#[cfg(feature = "defmt")]
+impl defmt::Format for ArithmeticError {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        let verb = match self.operation {
+            Operation::Add => "add",
+            Operation::Sub => "subtract",
+            Operation::Mul => "multiply",
+            Operation::Div => "divide",
+            Operation::Rem => "calculate the remainder",
+            Operation::Shl => "shift left",
+            Operation::Shr => "shift right",
+            Operation::Conversion => return defmt::write!(f, "integer conversion overflowed"),
+        };
+        match (self.kind, self.operation) {
+            (ErrorKind::DivisionByZero, Operation::Rem) => {
+                defmt::write!(
+                    f,
+                    "attempt to calculate the remainder with a divisor of zero"
+                )
+            }
+            (ErrorKind::DivisionByZero, _) => defmt::write!(f, "attempt to divide by zero"),
+            (ErrorKind::Overflow, _) => defmt::write!(f, "attempt to {=str} with overflow", verb),
+        }
+        if let Some(Details {
+            expression,
+            location,
+            operands: [left, right],
+        }) = &self.details
+        {
+            defmt::write!(
+                f,
+                ": `{=str}` with operands {=str} and {=str}, at {}",
+                expression,
+                left.as_str(),
+                right.as_str(),
+                location
+            );
+        }
+    }
+}
+
+#[cfg(not(feature = "msrv"))]
+impl core::error::Error for ArithmeticError {}
+
+// `core::error::Error` needs Rust 1.81
+#[cfg(all(feature = "msrv", feature = "std"))]
+impl std::error::Error for ArithmeticError {}
+
+/// Reports overflows while processing the data as invalid data, and divisions
+/// by zero as invalid input, since the divisor is usually a parameter like a
+/// block size or a sample rate.
+#[cfg(feature = "std")]
+impl From<ArithmeticError> for std::io::Error {
+    fn from(e: ArithmeticError) -> Self {
+        let kind = match e.kind {
+            ErrorKind::Overflow => std::io::ErrorKind::InvalidData,
+            ErrorKind::DivisionByZero => std::io::ErrorKind::InvalidInput,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
+
+/// A failed integer conversion means the value overflows the target type, so
+/// `u8::try_from(x)?` can be used as an operand in `try_checked!`.
+impl From<TryFromIntError> for ArithmeticError {
+    fn from(_: TryFromIntError) -> Self {
+        ArithmeticError::new(Operation::Conversion)
+    }
+}
+
+/// The kind of operation that failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Operation {
+    /// Addition `+`.
+    Add,
+    /// Subtraction `-`.
+    Sub,
+    /// Multiplication `*`.
+    Mul,
+    /// Division `/`, which also fails when dividing by zero.
+    Div,
+    /// Remainder `%`, which also fails when dividing by zero.
+    Rem,
+    /// Left shift `<<`, which fails when shifting by the bit width or more.
+    Shl,
+    /// Right shift `>>`, which fails when shifting by the bit width or more.
+    Shr,
+    /// Integer conversion, like `u8::try_from(x)`.
+    Conversion,
+}
+
+/// The reason an operation failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The result does not fit in the type.
+    Overflow,
+    /// The divisor of a division or remainder is zero.
+    DivisionByZero,
+}
+
+/// The location of a failed operation in the source code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Location {
+    file: &'static str,
+    line: u32,
+    column: u32,
+}
+
+impl Location {
+    #[doc(hidden)]
+    pub const fn __new(file: &'static str, line: u32, column: u32) -> Self {
+        Location { file, line, column }
+    }
+
+    /// The name of the source file.
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    /// The line number in the source file, starting from 1.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The column number in the source file, starting from 1.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Location {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "{=str}:{=u32}:{=u32}", self.file, self.line, self.column)
+    }
+}
+
+const OPERAND_CAPACITY: usize = 40;
+
+/// The `Debug` representation of an operand, stored inline since the crate
+/// cannot allocate. 40 bytes is enough for all the primitive integers.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Operand {
+    bytes: [u8; OPERAND_CAPACITY],
+    len: u8,
+}
+
+impl Operand {
+    pub(crate) fn new(value: &dyn Debug) -> Self {
+        let mut operand = Operand {
+            bytes: [0; OPERAND_CAPACITY],
+            len: 0,
+        };
+        // Truncation is reported as an error, and the prefix is kept
+        let _ = write!(operand, "{value:?}");
+        operand
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        // Only whole characters are written to the buffer
+        core::str::from_utf8(&self.bytes[..usize::from(self.len)]).unwrap_or_default()
+    }
+}
+
+impl Write for Operand {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let len = usize::from(self.len);
+        let mut end = s.len().min(OPERAND_CAPACITY - len);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.bytes[len..len + end].copy_from_slice(&s.as_bytes()[..end]);
+        self.len += end as u8;
+        if end == s.len() {
+            Ok(())
+        } else {
+            Err(fmt::Error)
+        }
+    }
+}
+
+impl Debug for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Terminates without the `std` feature, with the instruction raising an
+/// illegal instruction fault on the common architectures, so that neither the
+/// panic handler nor `core::fmt` is reached.
+#[cfg(not(any(feature = "std", feature = "nightly")))]
+fn trap() -> ! {
+    // SAFETY: The instructions only raise a fault, without touching memory
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    unsafe {
+        core::arch::asm!("ud2", options(noreturn, nomem, nostack))
+    };
+    #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+    unsafe {
+        core::arch::asm!("udf #0", options(noreturn, nomem, nostack))
+    };
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    unsafe {
+        core::arch::asm!("unimp", options(noreturn, nomem, nostack))
+    };
+    #[cfg(target_arch = "wasm32")]
+    core::arch::wasm32::unreachable();
+    #[cfg(not(any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv32",
+        target_arch = "riscv64",
+        target_arch = "wasm32",
+    )))]
+    cannot_unwind();
+}
+
+/// Terminates on the other architectures, where a panic that cannot unwind
+/// out of an `extern "C"` function aborts. It still goes through `panic_fmt`
+/// and the panic handler of the firmware, with a constant message.
+#[cfg(not(any(
+    feature = "std",
+    feature = "nightly",
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "arm",
+    target_arch = "aarch64",
+    target_arch = "riscv32",
+    target_arch = "riscv64",
+    target_arch = "wasm32",
+)))]
+extern "C" fn cannot_unwind() -> ! {
+    panic!()
+}
@@ -0,0 +1,46 @@
+//! The 256-bit unsigned integers of [`evm!`](crate::evm!), which follows the
+//! integer semantics of the Ethereum Virtual Machine, with the `evm` feature.
+//!
+//! ```rust
+//! use arithmetic_mode::evm;
+//! use arithmetic_mode::evm::U256;
+//!
+//! let (balance, amount) = (U256::from(5), U256::from(7));
+//! assert_eq!(U256::MAX - U256::from(1), evm! { balance - amount });
+//! assert_eq!(U256::ZERO, evm! { balance / 0 });
+//! assert_eq!(U256::from(1) << 255, evm! { 2 ** 255 });
+//! ```
+
+pub use ruint::aliases::U256;
+
+/// The quotient of `DIV`, which is zero when dividing by zero.
+#[doc(hidden)]
+pub fn __div(a: U256, b: U256) -> U256 {
+    a.checked_div(b).unwrap_or(U256::ZERO)
+}
+
+/// The remainder of `MOD`, which is zero when dividing by zero.
+#[doc(hidden)]
+pub fn __rem(a: U256, b: U256) -> U256 {
+    a.checked_rem(b).unwrap_or(U256::ZERO)
+}
+
+/// The left shift of `SHL`, which is zero when shifting by 256 bits or more.
+#[doc(hidden)]
+pub fn __shl(value: U256, shift: U256) -> U256 {
+    if shift >= U256::from(256) {
+        U256::ZERO
+    } else {
+        value << shift
+    }
+}
+
+/// The right shift of `SHR`, which is zero when shifting by 256 bits or more.
+#[doc(hidden)]
+pub fn __shr(value: U256, shift: U256) -> U256 {
+    if shift >= U256::from(256) {
+        U256::ZERO
+    } else {
+        value >> shift
+    }
+}
@@ -0,0 +1,97 @@
+//! The integer methods that the macros call with the `qualified` feature.
+//! They are called through [`Integer`], which is only implemented for the
+//! primitive integers, since a method call would prefer a method with the same
+//! name of the type of the operand, like an inherent `wrapping_add` of a
+//! wrapper type with different semantics.
+
+/// The methods of the primitive integers used by the modes, each of which
+/// calls the inherent method with the same name.
+#[allow(missing_docs)]
+pub trait Integer: Sized {
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn checked_div(self, rhs: Self) -> Option<Self>;
+    fn checked_rem(self, rhs: Self) -> Option<Self>;
+    fn checked_shl(self, rhs: u32) -> Option<Self>;
+    fn checked_shr(self, rhs: u32) -> Option<Self>;
+    fn wrapping_add(self, rhs: Self) -> Self;
+    fn wrapping_sub(self, rhs: Self) -> Self;
+    fn wrapping_mul(self, rhs: Self) -> Self;
+    fn wrapping_div(self, rhs: Self) -> Self;
+    fn wrapping_rem(self, rhs: Self) -> Self;
+    fn wrapping_shl(self, rhs: u32) -> Self;
+    fn wrapping_shr(self, rhs: u32) -> Self;
+    fn saturating_add(self, rhs: Self) -> Self;
+    fn saturating_sub(self, rhs: Self) -> Self;
+    fn saturating_mul(self, rhs: Self) -> Self;
+    fn saturating_div(self, rhs: Self) -> Self;
+}
+
+/// A primitive integer or a reference to one, like the left operands, which
+/// method calls dereference automatically.
+pub trait Operand {
+    /// The type of the integer.
+    type Integer: Integer;
+
+    /// The integer, dereferenced if needed.
+    fn integer(self) -> Self::Integer;
+}
+
+macro_rules! impl_integer {
+    ($($ty:ident)*) => {
+        $(
+            impl Integer for $ty {
+                impl_integer!(@methods $ty {
+                    checked_add(Self) -> Option<Self>,
+                    checked_sub(Self) -> Option<Self>,
+                    checked_mul(Self) -> Option<Self>,
+                    checked_div(Self) -> Option<Self>,
+                    checked_rem(Self) -> Option<Self>,
+                    checked_shl(u32) -> Option<Self>,
+                    checked_shr(u32) -> Option<Self>,
+                    wrapping_add(Self) -> Self,
+                    wrapping_sub(Self) -> Self,
+                    wrapping_mul(Self) -> Self,
+                    wrapping_div(Self) -> Self,
+                    wrapping_rem(Self) -> Self,
+                    wrapping_shl(u32) -> Self,
+                    wrapping_shr(u32) -> Self,
+                    saturating_add(Self) -> Self,
+                    saturating_sub(Self) -> Self,
+                    saturating_mul(Self) -> Self,
+                    saturating_div(Self) -> Self,
+                });
+            }
+
+            impl Operand for $ty {
+                type Integer = $ty;
+
+                #[inline(always)]
+                fn integer(self) -> $ty {
+                    self
+                }
+            }
+
+            impl Operand for &$ty {
+                type Integer = $ty;
+
+                #[inline(always)]
+                fn integer(self) -> $ty {
+                    *self
+                }
+            }
+        )*
+    };
+    (@methods $ty:ident { $($method:ident($rhs:ty) -> $output:ty,)* }) => {
+        $(
+            #[inline(always)]
+            #[track_caller]
+            fn $method(self, rhs: $rhs) -> $output {
+                <$ty>::$method(self, rhs)
+            }
+        )*
+    };
+}
+
+impl_integer!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
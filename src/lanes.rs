@@ -0,0 +1,54 @@
+//! The elements of the operands of [`elementwise!`](crate::elementwise).
+
+use core::marker::PhantomData;
+
+/// An operand of `elementwise!` with `N` elements: an array of length `N`, or
+/// an integer that applies to every element.
+pub trait Lanes<const N: usize> {
+    /// The type of the elements.
+    type Lane;
+
+    /// The element at `index`.
+    fn lane(&self, index: usize) -> Self::Lane;
+}
+
+impl<T: Copy, const N: usize> Lanes<N> for [T; N] {
+    type Lane = T;
+
+    fn lane(&self, index: usize) -> T {
+        self[index]
+    }
+}
+
+macro_rules! impl_lanes {
+    ($($ty:ident)*) => {
+        $(
+            impl<const N: usize> Lanes<N> for $ty {
+                type Lane = $ty;
+
+                fn lane(&self, _: usize) -> $ty {
+                    *self
+                }
+            }
+        )*
+    };
+}
+
+impl_lanes!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+/// The element of `operand` at `index`, where `lanes` carries the length of
+/// the arrays, which is inferred from the arrays among the operands.
+#[inline(always)]
+pub fn lane<A: Lanes<N>, const N: usize>(
+    operand: &A,
+    index: usize,
+    _lanes: PhantomData<[(); N]>,
+) -> A::Lane {
+    operand.lane(index)
+}
+
+/// The array of the results of `f` for each index of the arrays.
+#[inline(always)]
+pub fn lanes<T, const N: usize>(_lanes: PhantomData<[(); N]>, f: impl FnMut(usize) -> T) -> [T; N] {
+    core::array::from_fn(f)
+}
@@ -0,0 +1,88 @@
+//! Checked sizes and layouts of allocations, like the `count *
+//! size_of::<T>() + header` of allocators and FFI code, which return an
+//! [`ArithmeticError`] describing the overflowed operation instead of
+//! allocating a truncated buffer.
+//!
+//! ```rust
+//! use arithmetic_mode::layout;
+//! use arithmetic_mode::Operation;
+//!
+//! #[repr(C)]
+//! struct Header {
+//!     len: u32,
+//! }
+//!
+//! assert_eq!(Ok(4 + 3 * 8), layout::size::<u64>(3, 4));
+//! let error = layout::size::<u64>(usize::MAX / 4, 4).unwrap_err();
+//! assert_eq!(Operation::Mul, error.operation());
+//!
+//! // The array of `u64` starts at the next multiple of 8 after the header
+//! let (layout, offset) = layout::with_header::<Header, u64>(3).unwrap();
+//! assert_eq!((8, 8 + 3 * 8, 8), (offset, layout.size(), layout.align()));
+//! ```
+//!
+//! The allocations are limited to `isize::MAX` bytes, like the ones of
+//! [`Layout`]. Larger sizes are reported as the failed conversion to `isize`,
+//! i.e. [`Operation::Conversion`].
+
+use core::alloc::Layout;
+use core::mem::{align_of, size_of};
+
+use crate::{ArithmeticError, Operation};
+
+/// The size in bytes of `count` values of `T` after `header` bytes, `header +
+/// count * size_of::<T>()`, without any padding between them.
+pub const fn size<T>(count: usize, header: usize) -> Result<usize, ArithmeticError> {
+    let Some(array) = count.checked_mul(size_of::<T>()) else {
+        return Err(ArithmeticError::new(Operation::Mul));
+    };
+    match header.checked_add(array) {
+        Some(size) => Ok(size),
+        None => Err(ArithmeticError::new(Operation::Add)),
+    }
+}
+
+/// The layout of an array of `count` values of `T`, like [`Layout::array`].
+pub const fn array<T>(count: usize) -> Result<Layout, ArithmeticError> {
+    match size::<T>(count, 0) {
+        Ok(size) => from_size_align(size, align_of::<T>()),
+        Err(e) => Err(e),
+    }
+}
+
+/// The layout of a header `H` followed by an array of `count` values of `T`,
+/// like a C struct ending with a flexible array member, and the offset of the
+/// array, like [`Layout::extend`]. The size is padded to the alignment of the
+/// layout, so that it can also be used for arrays of such structs.
+pub const fn with_header<H, T>(count: usize) -> Result<(Layout, usize), ArithmeticError> {
+    // The offset is rounded up to a multiple of the alignment of `T`, which
+    // cannot overflow since the size of `H` is at most `isize::MAX`
+    let align = align_of::<T>();
+    let offset = (size_of::<H>() + align - 1) & !(align - 1);
+    let size = match size::<T>(count, offset) {
+        Ok(size) => size,
+        Err(e) => return Err(e),
+    };
+    let align = if align_of::<H>() > align {
+        align_of::<H>()
+    } else {
+        align
+    };
+    // A size that overflows when padded is also larger than `isize::MAX`
+    let Some(padded) = size.checked_add(align - 1) else {
+        return Err(ArithmeticError::new(Operation::Conversion));
+    };
+    match from_size_align(padded & !(align - 1), align) {
+        Ok(layout) => Ok((layout, offset)),
+        Err(e) => Err(e),
+    }
+}
+
+/// The layout of `size` bytes with `align`, if the size rounded up to the
+/// alignment is at most `isize::MAX`.
+const fn from_size_align(size: usize, align: usize) -> Result<Layout, ArithmeticError> {
+    match Layout::from_size_align(size, align) {
+        Ok(layout) => Ok(layout),
+        Err(_) => Err(ArithmeticError::new(Operation::Conversion)),
+    }
+}
@@ -0,0 +1,146 @@
+//! The `a + (b - a) * t / t_max` of [`lerp!`](crate::lerp), whose difference
+//! and product are computed in a wider integer type, so that only the
+//! interpolated value can overflow.
+
+use crate::mul_div::Rounding;
+use crate::wide::Widen;
+use crate::{ArithmeticError, ErrorKind, Operation};
+
+/// An integer type that is interpolated in its wider type.
+pub trait Lerp: Widen {
+    /// `a + (b - a) * t / t_max` in the wider type, and whether it is below
+    /// zero for the unsigned types, whose wider types cannot hold it. The
+    /// value is wrapped around at the bounds of the wider type then. Returns
+    /// `None` if `t_max` is zero.
+    fn lerp(
+        a: Self,
+        b: Self,
+        t: Self,
+        t_max: Self,
+        rounding: Rounding,
+    ) -> Option<(Self::Wide, bool)>;
+}
+
+macro_rules! impl_lerp_unsigned {
+    ($($ty:ident),*) => {
+        $(
+            impl Lerp for $ty {
+                #[inline(always)]
+                fn lerp(
+                    a: Self,
+                    b: Self,
+                    t: Self,
+                    t_max: Self,
+                    rounding: Rounding,
+                ) -> Option<(Self::Wide, bool)> {
+                    // The product of two values plus a value always fits
+                    if b >= a {
+                        let step = Self::divide((b - a).multiply(t), t_max, rounding)?;
+                        return Some((a.widen() + step, false));
+                    }
+                    // The step is subtracted, so the roundings toward the
+                    // infinities are swapped
+                    let rounding = match rounding {
+                        Rounding::Floor => Rounding::Ceil,
+                        Rounding::Ceil => Rounding::Floor,
+                        rounding => rounding,
+                    };
+                    let step = Self::divide((a - b).multiply(t), t_max, rounding)?;
+                    Some(a.widen().overflowing_sub(step))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_lerp_signed {
+    ($($ty:ident),*) => {
+        $(
+            impl Lerp for $ty {
+                #[inline(always)]
+                fn lerp(
+                    a: Self,
+                    b: Self,
+                    t: Self,
+                    t_max: Self,
+                    rounding: Rounding,
+                ) -> Option<(Self::Wide, bool)> {
+                    // The difference fits in one more bit, and its product
+                    // with `t` plus `a` still fits in the wider type, even for
+                    // `i64` in `i128`
+                    let difference = b.widen() - a.widen();
+                    let step = Self::divide(difference * t.widen(), t_max, rounding)?;
+                    Some((a.widen() + step, false))
+                }
+            }
+        )*
+    };
+}
+
+impl_lerp_unsigned!(u8, u16, u32, u64, usize);
+impl_lerp_signed!(i8, i16, i32, i64, isize);
+
+/// The interpolated value, panicking when `t_max` is zero like the modes that
+/// do not fail.
+#[track_caller]
+fn interpolate<T: Lerp>(a: T, b: T, t: T, t_max: T, rounding: Rounding) -> (T::Wide, bool) {
+    match T::lerp(a, b, t, t_max, rounding) {
+        Some(value) => value,
+        None => panic!("attempt to divide by zero"),
+    }
+}
+
+/// `a + (b - a) * t / t_max` in `panicking` mode.
+#[track_caller]
+pub fn lerp_panicking<T: Lerp>(a: T, b: T, t: T, t_max: T, rounding: Rounding) -> T {
+    match interpolate(a, b, t, t_max, rounding) {
+        (value, false) => match T::narrow(value) {
+            Some(value) => value,
+            None => panic!("attempt to interpolate with overflow"),
+        },
+        (_, true) => panic!("attempt to interpolate with overflow"),
+    }
+}
+
+/// `a + (b - a) * t / t_max` in `wrapping` mode.
+#[track_caller]
+pub fn lerp_wrapping<T: Lerp>(a: T, b: T, t: T, t_max: T, rounding: Rounding) -> T {
+    // The wider type wraps around at a multiple of the bounds of the type
+    T::truncate(interpolate(a, b, t, t_max, rounding).0)
+}
+
+/// `a + (b - a) * t / t_max` in `saturating` mode.
+#[track_caller]
+pub fn lerp_saturating<T: Lerp>(a: T, b: T, t: T, t_max: T, rounding: Rounding) -> T {
+    match interpolate(a, b, t, t_max, rounding) {
+        (value, false) => T::saturate(value),
+        (_, true) => T::default(),
+    }
+}
+
+/// `a + (b - a) * t / t_max` in `checked` mode.
+pub fn lerp_checked<T: Lerp>(a: T, b: T, t: T, t_max: T, rounding: Rounding) -> Option<T> {
+    match T::lerp(a, b, t, t_max, rounding)? {
+        (value, false) => T::narrow(value),
+        (_, true) => None,
+    }
+}
+
+/// `a + (b - a) * t / t_max` in `try_checked` mode, whose overflow is
+/// reported as the addition to `a`, and the division by zero as the division.
+pub fn lerp_try_checked<T: Lerp>(
+    a: T,
+    b: T,
+    t: T,
+    t_max: T,
+    rounding: Rounding,
+) -> Result<T, ArithmeticError> {
+    let Some(value) = T::lerp(a, b, t, t_max, rounding) else {
+        return Err(ArithmeticError::new(Operation::Div).with_kind(ErrorKind::DivisionByZero));
+    };
+    match value {
+        (value, false) => T::narrow(value),
+        (_, true) => None,
+    }
+    .ok_or(ArithmeticError::new(Operation::Add))
+}
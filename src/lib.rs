@@ -14,6 +14,36 @@
 //! wrapping! { 1_i32 + 2_i32 - 3_i32 };
 //! ```
 //!
+//! To apply a mode to all the arithmetic in a function, `impl` block, module,
+//! trait, const or static, use the [`arithmetic_mode`] attribute instead:
+//! ```rust
+//! # use arithmetic_mode::arithmetic_mode;
+//! #[arithmetic_mode(wrapping)]
+//! fn mix(a: u32, b: u32) -> u32 {
+//!     a * 31 + b
+//! }
+//! ```
+//!
+//! A closure can be given its mode with [`closure!`], taking the same
+//! arguments as the attribute, like `closure!(wrapping, |x| x * 2 + 1)`, and
+//! a single statement with [`stmt!`], like
+//! `stmt! { checked: let total = a + b * c; }`.
+//! The modes of the attribute can also be kept in the named policies of an
+//! `arithmetic-mode.toml` file, like `#[arithmetic_mode(policy = "payments")]`.
+//!
+//! To require a mode to be chosen explicitly for all the arithmetic in an item,
+//! use [`deny_unchecked_arithmetic`], or [`enforce`] to also restrict which
+//! modes can be used:
+//! ```rust
+//! #[arithmetic_mode::enforce(default = "checked", allow = ["wrapping"])]
+//! mod math {
+//!     # use arithmetic_mode::checked;
+//!     pub fn average(a: u32, b: u32) -> Option<u32> {
+//!         checked! { (a + b) / 2 }
+//!     }
+//! }
+//! ```
+//!
 //! ## Supported operations:
 //! * Add `+`
 //! * Sub `-`
@@ -22,329 +52,458 @@
 //! * Shl `<<` (except `saturating`, due to https://github.com/rust-lang/libs-team/issues/230)
 //! * Shr `>>` (except `saturating`, due to https://github.com/rust-lang/libs-team/issues/230)
 //!
+//! Other operators, like `|` or `&&`, are left unchanged. A macro without any of
+//! the supported operations, like `wrapping! { flags | mask }`, warns that there
+//! is nothing to transform, since it suggests that overflows are handled. The
+//! warning is reported as the use of a deprecated constant, so it can be turned
+//! into an error with `#[deny(deprecated)]` or silenced with `#[allow(deprecated)]`.
+//!
+//! Operations whose operands are integer literals of a known type or bounds like
+//! `u8::MAX`, such as `60_u32 * 60_u32` or `u8: 200 + 100`, are evaluated during
+//! the expansion, so they have no runtime checks. If such an operation overflows or divides by zero,
+//! it is a compile error in the modes that would panic or return an error, while
+//! [`wrapping!`] and [`saturating!`] expand to the wrapped or saturated value.
+//! `isize` and `usize` literals are left to runtime, since their size depends on
+//! the target.
+//!
+//! ## Modes
+//! * [`panicking!`] panics if any operation overflows.
+//! * [`wrapping!`] wraps around at the boundary of the type.
+//!   [`hashing!`] does the same for hash functions, with `u64` literals by
+//!   default, like `hashing! { (h << 5) + h + b }`.
+//! * [`saturating!`] saturates at the numeric bounds of the type.
+//! * [`checked!`] returns `None` if any operation overflows.
+//!   [`fast_checked!`] does the same with a single branch, for chains of
+//!   additions, subtractions and multiplications of narrow types.
+//! * [`try_checked!`] returns `Err(ArithmeticError)` if any operation
+//!   overflows.
+//!   [`control_flow!`] returns `ControlFlow::Break(ArithmeticError)` instead,
+//!   for the callbacks of `try_fold` and visitors.
+//!   [`checked_sum!`] and [`checked_product!`] add or multiply any number of
+//!   expressions in these modes, like `checked_sum!(a, b, c)`.
+//! * [`const_checked!`] evaluates the expression at compile time, and fails to
+//!   compile if any operation overflows.
+//! * [`verified!`] panics like [`panicking!`], and asserts that no operation
+//!   overflows when verified with the Kani model checker.
+//!
+//! [`mixed!`] mixes the modes in one expression, with Zig-style operators:
+//! `a *% b +| c` wraps the multiplication and saturates the addition. Any
+//! operator can also be annotated with its mode, like `a *[wrapping] b
+//! +[checked] c`.
+//! [`modes!`] gives each integer type its own mode instead, like
+//! `modes! { u8 = saturating, usize = checked; expr }`.
+//! [`forbid!`] additionally makes some operators a compile error, like
+//! `forbid! { div, rem; price * quantity }`.
+//!
+//! Without a macro, [`Checked`] is the checked counterpart of the `Wrapping`
+//! and `Saturating` of the standard library, whose plain operators evaluate to
+//! `None` once an operation overflows, so that checked arithmetic can be
+//! written in generic code, like `Checked::new(price) * quantity + fee`, or
+//! built at runtime with its methods, like
+//! `Checked::from(a).add(b).mul(c).finish()`.
+//! [`Panicking`] panics instead, in all builds, with the operands in the
+//! message, and [`Strict`] also panics when `<<` shifts out any bit.
+//!
+//! In tests, [`assert_no_overflow!`] fails the test with a description of the
+//! failed operation if any operation overflows, and [`differential_test!`]
+//! generates a test comparing the modes with the exact result.
+//! [`overflow_matrix!`] tests the modes for all the pairs of operands of the
+//! small integer types, like `overflow_matrix! { u8, i8 }`. With the
+//! `proptest` feature, `proptest_modes!` checks how the modes relate to each
+//! other with property tests.
+//!
+//! [`elementwise!`] applies a mode to each element of arrays of the same
+//! length, like `elementwise! { [saturating] left + right }` to mix audio
+//! buffers, with the integers among the operands applying to every element.
+//! [`dot_product!`] computes the dot product of two arrays or slices in a
+//! wider integer type, and only narrows the final sum in the given mode.
+//! [`mac!`] is its single step, `acc + a * b` with the exact product, like
+//! `mac! { [saturating] acc, tap, sample }`, and `acc += a * b` is computed
+//! like it in the blocks of `#[arithmetic_mode]`.
+//! Likewise, [`mul_div!`] computes `a * b / c` without overflowing on the
+//! product, like `mul_div! { [checked] amount, fee_bps, 10_000 }`, and so do
+//! [`percent_of!`] and [`bps_of!`] for percentages and basis points, and
+//! [`lerp!`] for the interpolation `a + (b - a) * t / t_max`.
+//! [`blend_add!`] and [`scale_u8!`] saturate the 8-bit channels of images,
+//! like `scale_u8! { color, alpha, 255 }`, which is rounded to the nearest
+//! value.
+//! For field arithmetic, [`modfield!`] computes an expression modulo a
+//! constant, like `modfield!(P; a * b + c)`, with the Barrett reduction of the
+//! modulus precomputed at compile time instead of dividing.
+//! For DSP code, [`qformat!`] computes an expression of fixed-point numbers in
+//! a Q format, like `qformat!(Q15; gain * sample)`, with saturating additions
+//! and rounded multiplications.
+//! For allocators and FFI code, the functions of [`layout`] compute sizes like
+//! `count * size_of::<T>() + header` and build the [`Layout`](core::alloc::Layout)
+//! of arrays, with an [`ArithmeticError`] if they overflow.
+//!
+//! To compare the modes in benchmarks, [`bench!`] transforms an expression
+//! like the given mode, like `bench! { [checked] a * b + c }`, with its
+//! operands hidden from the optimizer.
+//!
+//! To debug an expression, [`trace!`] transforms it like the given mode, and
+//! prints each operation with its operands and result, like `200 * 2 = 144`,
+//! to stderr or to the hook set with [`trace::set_hook`]. [`record!`] records
+//! them into a buffer instead, like `record! { &mut log; [checked] a * b }`,
+//! to reconstruct how a value was derived.
+//!
+//! ## Division
+//! `/` rounds its quotient toward zero, like the operator, which is rarely what
+//! code on negative values wants. Starting the input of a mode with
+//! `div = floor;`, `div = ceil;` or `div = euclid;` rounds it toward negative
+//! infinity, toward positive infinity, or to the Euclidean quotient instead,
+//! and `div = half_even;` to the nearest integer with the halves rounded to
+//! even, the banker's rounding of financial code. The overflows are handled
+//! like the mode, and `%` is the matching remainder:
+//!
+//! ```rust
+//! use arithmetic_mode::{checked, saturating, try_checked, wrapping};
+//!
+//! let (a, b) = (-7_i32, 2_i32);
+//! assert_eq!(Some(-4), checked! { div = floor; a / b });
+//! assert_eq!(Some(1), checked! { div = floor; a % b });
+//! assert_eq!(-3, wrapping! { div = ceil; a / b });
+//! assert_eq!(i32::MAX, saturating! { div = euclid; i32::MIN / -1 });
+//!
+//! let (len, page) = (10_000_usize, 4096_usize);
+//! assert_eq!(Ok(3), try_checked! { div = ceil; len / page });
+//!
+//! // 2.5 and 3.5 cents are rounded to 2 and 4
+//! let (cents, shares) = (15_i64, 6_i64);
+//! assert_eq!(Some(2), checked! { div = half_even; cents / shares });
+//! assert_eq!(Some(4), checked! { div = half_even; (cents + 6) / shares });
+//! ```
+//!
+//! With `ceil`, the remainder has the opposite sign of the divisor, so it
+//! overflows for the unsigned types unless it is zero, and so does the
+//! remainder of `half_even` when the quotient is rounded up. [`try_checked!`] takes
+//! the flavor after its error, like `try_checked! { err = E; div = ceil; a / b }`.
+//! The flavors are not supported in const contexts.
+//!
+//! ## Const contexts
+//! The macros can be used in `const fn` and constants by starting their input
+//! with `const`, like `checked! { const a * b + 1 }`. In const contexts,
+//! operands cannot be references, errors of [`try_checked!`] only have the
+//! [`Operation`], and panic messages do not include the operands. The
+//! [`arithmetic_mode`] attribute does this automatically for `const fn`,
+//! constants and statics.
+//!
+//! This includes array lengths and const generic arguments:
+//! ```rust
+//! # use arithmetic_mode::panicking;
+//! const HEADER: usize = 4;
+//! fn packet() -> [u8; panicking!(const HEADER * 2 + 1)] {
+//!     [0; 9]
+//! }
+//! ```
+//! Like for the default operators, generic parameters cannot be used there
+//! until `generic_const_exprs` is stabilized.
+//!
+//! ## Evaluation order
+//! Operands are evaluated exactly once, from left to right, like the default
+//! operators. [`checked!`] and [`try_checked!`] stop at the first operation
+//! that fails, so the operands after it are not evaluated.
+//!
+//! ## Errors
+//! [`ArithmeticError::kind`] tells a division by zero apart from an overflow.
+//! [`ArithmeticError`] implements `Error`, so it can be propagated with `?`
+//! into `anyhow::Result`, `Box<dyn Error>` or an error enum with a
+//! `#[from] ArithmeticError` variant. With the `std` feature, it can also be
+//! converted into `std::io::Error`, as `InvalidData` for overflows and
+//! `InvalidInput` for divisions by zero, so that parsers can propagate it with
+//! `?` like their other I/O errors. [`try_checked!`] can also return an error
+//! of the caller's instead, like
+//! `try_checked! { err = MyError::MathOverflow; balance - amount }`.
+//!
+//! ## Passthrough
+//! To measure the runtime cost of the modes, compile with
+//! `RUSTFLAGS="--cfg arithmetic_mode_passthrough"`. The mode macros and
+//! attributes then leave the operators unchanged, like the arithmetic outside
+//! of them, while the fallible modes still evaluate to `Some` or `Ok`. The cfg
+//! has to be set for `arithmetic-mode` itself, which is why it is given in
+//! `RUSTFLAGS`.
+//!
+//! ## Default mode
+//! [`default!`] and `#[arithmetic_mode(default)]` use the default mode of the
+//! build, so that a whole workspace can change its policy for an experiment
+//! without editing every call site. It is selected with
+//! `RUSTFLAGS='--cfg arithmetic_mode_default="saturating"'`, or with the
+//! `ARITHMETIC_MODE_DEFAULT` environment variable during the compilation,
+//! which takes precedence, and is `panicking` otherwise. Only `panicking`,
+//! `wrapping` and `saturating` can be the default, since they evaluate to the
+//! same type.
+//!
+//! ## Qualified calls
+//! The macros call methods like `wrapping_add` on the operands, so an operand
+//! whose type has an inherent method with the same name, like a wrapper type,
+//! would silently use that method instead. With the `qualified` feature, the
+//! methods are called through a trait that is only implemented for the
+//! primitive integers, so that such operands fail to compile instead. Since
+//! trait methods cannot be called in const contexts, the expansions for const
+//! contexts still use method calls.
+//!
+//! The `ux` feature implies `qualified`, and implements the trait for the
+//! integers of non-standard widths of the `ux` crate, like `u5` or `u24`, so
+//! that the arithmetic of protocol fields and hardware registers can be
+//! written in the modes, like `wrapping! { counter + step }`. `ux` only
+//! defines their wrapping additions and subtractions, so the other
+//! operations are computed in the integer that stores them, like `u32` for
+//! `u24`, and checked or wrapped to their width.
+//!
+//! Likewise, the `glam` feature implements it for the integer vectors of
+//! `glam`, like `IVec2` and `UVec3`, whose operations apply to each element,
+//! like `saturating! { pos + vel * ticks }`. The checked modes fail if any
+//! element overflows. Both operands are vectors, with scalars given as
+//! `IVec2::splat(x)`, except for the shift amounts, which shift every element.
+//!
+//! ## Hoisting
+//! With the `hoist` feature, the operations that are repeated in a
+//! [`checked!`] expression, like `a * b` in `(a * b) + (a * b) / c`, are
+//! checked and computed once into a temporary that the expression reuses,
+//! which shrinks both the generated code and the checks at runtime. Only the
+//! operations of variables, literals and other such operations without side
+//! effects are hoisted, so the result is the same.
+//!
+//! ## Profiling
+//! With the `profile` feature, the operations of the macros count their
+//! overflows, which [`profile::sites`] lists with their locations, e.g. to find
+//! out which of many operations actually overflow in production before
+//! choosing their mode. The operations in const contexts are not counted.
+//!
+//! ## Aborting
+//! For firmware that cannot afford the panic machinery, the `abort` feature
+//! makes [`panicking!`] and the panicking mode abort the program on overflow,
+//! without formatting a message. With the `std` feature, it calls
+//! `std::process::abort`, and with the `nightly` feature the `abort`
+//! intrinsic. Otherwise, it executes the illegal instruction of the
+//! architecture, like `ud2` on x86 or `udf` on ARM, which faults without
+//! reaching the panic handler. On the architectures without one, it panics
+//! in a function that cannot unwind, which still goes through the panic
+//! handler, with a constant message. The operations in const contexts still
+//! panic, since that is a compile error.
+//!
+//! ## defmt
+//! On microcontrollers that log with [`defmt`](https://defmt.ferrous-systems.com),
+//! e.g. over RTT, the `defmt` feature makes [`panicking!`] and the panicking
+//! mode panic with `defmt::panic!`, whose format strings are interned instead
+//! of formatted with `core::fmt`, and implements `defmt::Format` for
+//! [`ArithmeticError`] and the types it contains. The firmware then has to
+//! provide the `defmt` global logger and panic handler.
+//!
+//! ## Constant time
+//! With the `subtle` feature, `ct_checked!` is like [`checked!`] for
+//! constant-time code: each operation computes whether it overflowed without
+//! branching, and the expression evaluates to a `subtle::CtOption`, so the
+//! timing does not reveal whether it overflowed. Division by zero is detected
+//! the same way, although the division itself may not take constant time on
+//! all processors.
+//!
+//! ## EVM
+//! With the `evm` feature, `evm!` computes an expression with the integer
+//! semantics of the Ethereum Virtual Machine, like
+//! `evm! { balance * price / 10 ** 18 }`, so that simulators can express the
+//! math of contracts with the operators. The operands are converted to the
+//! 256-bit `evm::U256` of [`ruint`](https://docs.rs/ruint), all the operations
+//! wrap modulo 2^256, dividing by zero gives zero, and `**` is the
+//! exponentiation.
+//!
+//! ## Nightly
+//! With the `nightly` feature, which needs a nightly compiler, the overflow
+//! branches of [`panicking!`], [`checked!`], [`try_checked!`] and
+//! [`fast_checked!`] are marked as cold, so that hot loops are optimized for
+//! the operations that do not overflow.
+//!
+//! ## Minimum Rust version
+//! The crate needs Rust 1.81 by default, for `core::error::Error`, which the
+//! errors implement, `const` blocks (1.79), which the expansions use for the
+//! locations of the errors and [`const_checked!`], and the diagnostic
+//! attributes (1.78), which explain the unsupported operations of the modes.
+//!
+//! With the `msrv` feature, it compiles with Rust 1.71, its `rust-version`,
+//! which syn, proc-macro2 and quote need. The expansions then store the
+//! locations in `static`s instead, [`const_checked!`] is only evaluated at
+//! compile time in const contexts, the errors implement `std::error::Error`
+//! only with the `std` feature, and the diagnostics are the default ones.
+//! Newer integer methods, like `midpoint`, are not used in either case.
+//!
+//! ## Debugging
+//! To see the expansion of a single expression, [`explain!`] expands it like
+//! the given mode, like `explain!(checked, a + b * c)`, and shows the expansion
+//! in a compiler warning.
+//!
+//! If the `ARITHMETIC_MODE_DEBUG` environment variable is set to `1` during
+//! compilation, each macro prints its input and its expansion to stderr. Since
+//! Cargo does not rebuild the crate when the variable changes, a rebuild may
+//! have to be forced, e.g. with `touch src/lib.rs`.
+//!
 //! ## Known issues
 //! * For most operations, constraining the numeric literals are required (e.g.
 //!   `2_i32` instead of `2`), due to
-//!   <https://github.com/rust-lang/rust/issues/24124>.
+//!   <https://github.com/rust-lang/rust/issues/24124>. It is enough to
+//!   constrain one literal (or cast one operand), since the type is propagated to
+//!   the other unsuffixed literals in the expression.
+//!   Alternatively, the type of all unsuffixed literals can be stated at the
+//!   front of the macro, like `wrapping! { u64: 1 + 2 - 3 }`.
+//!   Individual operands can also be annotated with their type, like
+//!   `wrapping! { (1: u64) + x }`.
+//!   Operations whose type cannot be inferred are reported with these
+//!   suggestions.
+//! * The expressions with thousands of terms, like machine-generated sums,
+//!   can overflow the stack of the compiler, which can be increased with e.g.
+//!   `RUST_MIN_STACK=268435456`. With the `deep-stack` feature, the macros
+//!   expand them on a larger stack, with `stacker`. Each operation expands into
+//!   a block nested in the one of the next operation though, so compiling the
+//!   expansion can still need the larger stack of the compiler.
 
+#![no_std]
 #![warn(missing_docs)]
+#![cfg_attr(
+    feature = "nightly",
+    feature(core_intrinsics),
+    allow(internal_features)
+)]
 
-extern crate proc_macro;
+#[cfg(feature = "std")]
+extern crate std;
 
-use anyhow::bail;
-use proc_macro2::TokenStream;
-use quote::quote;
-use syn::{Expr, ExprBinary, ExprGroup, ExprLit, ExprParen, ExprUnary};
-
-/// Macro that changes the arithmetic operations (add, sub, mul, div, and shift)
-/// inside it to be panicking operations. That is, if any of the operation
-/// overflows, it will panic.
-///
-/// ```should_panic
-/// use arithmetic_mode::panicking;
-///
-/// assert_eq!(0, panicking! { 255_u8 + 1_u8 });
-/// ```
-#[proc_macro]
-pub fn panicking(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    match panicking_impl(item.into()) {
-        Ok(tokens) => tokens.into(),
-        Err(e) => {
-            let error_message = format!("Error: {e}");
-            quote! { compile_error!(#error_message) }.into()
-        }
-    }
-}
+mod channel;
+mod checked;
+mod division;
+mod dot;
+mod error;
+#[cfg(feature = "evm")]
+pub mod evm;
+#[cfg(feature = "qualified")]
+mod integer;
+mod lanes;
+pub mod layout;
+mod lerp;
+mod modes;
+mod modfield;
+mod mul_div;
+#[cfg(feature = "ux")]
+mod narrow;
+mod panicking;
+#[cfg(feature = "profile")]
+pub mod profile;
+mod qformat;
+pub mod record;
+pub mod trace;
+#[cfg(feature = "glam")]
+mod vector;
+mod wide;
 
-fn panicking_impl(item: TokenStream) -> anyhow::Result<TokenStream> {
-    fn recurse(expr: syn::Expr) -> anyhow::Result<TokenStream> {
-        Ok(match expr {
-            Expr::Binary(ExprBinary {
-                left, op, right, ..
-            }) => {
-                let new_left = recurse(*left)?;
-                let new_right = recurse(*right)?;
-                checked_op(op)?
-                    .map(|func| quote! { (#new_left).#func(#new_right).unwrap() })
-                    .unwrap_or_else(|| quote! { #new_left #op #new_right })
-            }
-            Expr::Cast(_) => quote! { #expr },
-            // Recurse inside the following items
-            Expr::Index(_) => quote! { #expr },
-            Expr::Lit(_) => quote! { #expr },
-            Expr::Paren(ExprParen { expr, .. }) => {
-                let new_expr = recurse(*expr)?;
-                quote! { ( #new_expr ) }
-            }
-            Expr::Path(_) => quote! { #expr },
-            Expr::Reference(_) => quote! { #expr },
-            Expr::Unary(_) => quote! { #expr },
-            Expr::Group(ExprGroup { expr, .. }) => recurse(*expr)?,
-            _ => bail!("Unexpected expression token {expr:?}"),
-        })
-    }
+#[cfg(feature = "subtle")]
+pub use arithmetic_mode_macros::ct_checked;
+#[cfg(feature = "evm")]
+pub use arithmetic_mode_macros::evm;
+#[cfg(feature = "proptest")]
+pub use arithmetic_mode_macros::proptest_modes;
+pub use arithmetic_mode_macros::{
+    arithmetic_mode, assert_no_overflow, bench, blend_add, bps_of, checked, checked_fn,
+    checked_product, checked_sum, closure, const_checked, control_flow, default,
+    deny_unchecked_arithmetic, differential_test, dot_product, elementwise, enforce, explain,
+    fast_checked, forbid, hashing, lerp, mac, mixed, modes, modfield, mul_div, overflow_matrix,
+    panicking, percent_of, qformat, record, saturating, scale_u8, stmt, trace, try_checked, try_fn,
+    verified, wrapping,
+};
+pub use checked::Checked;
+pub use error::{ArithmeticError, ErrorKind, Location, Operation};
+pub use panicking::{Panicking, Strict};
 
-    recurse(syn::parse2(item)?)
+/// Selects the original operators of a mode macro when compiled with
+/// `--cfg arithmetic_mode_passthrough`, and its expansion otherwise.
+#[cfg(arithmetic_mode_passthrough)]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __passthrough {
+    ({ $($original:tt)* } { $($expansion:tt)* }) => { $($original)* };
 }
 
-/// Macro that changes the arithmetic operations (add, sub, mul, div, and shift)
-/// inside it to be wrapping operations if any operations or intermediate step
-/// overflows.
-///
-/// ```rust
-/// use arithmetic_mode::wrapping;
-///
-/// assert_eq!(0, wrapping! { 255_u8 + 1_u8 });
-/// ```
-#[proc_macro]
-pub fn wrapping(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    match wrapping_impl(item.into()) {
-        Ok(tokens) => tokens.into(),
-        Err(e) => {
-            let error_message = format!("Error: {e}");
-            quote! { compile_error!(#error_message) }.into()
-        }
-    }
+/// Selects the original operators of a mode macro when compiled with
+/// `--cfg arithmetic_mode_passthrough`, and its expansion otherwise.
+#[cfg(not(arithmetic_mode_passthrough))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __passthrough {
+    ({ $($original:tt)* } { $($expansion:tt)* }) => { $($expansion)* };
 }
 
-fn wrapping_impl(item: TokenStream) -> anyhow::Result<TokenStream> {
-    fn recurse(expr: syn::Expr) -> anyhow::Result<TokenStream> {
-        Ok(match expr {
-            Expr::Binary(ExprBinary {
-                left, op, right, ..
-            }) => {
-                let new_left = recurse(*left)?;
-                let new_right = recurse(*right)?;
-                wrapping_op(op)?
-                    .map(|func| quote! { (#new_left).#func(#new_right) })
-                    .unwrap_or_else(|| quote! { #new_left #op #new_right })
-            }
-            Expr::Cast(_) => quote! { #expr },
-            // Recurse inside the following items
-            Expr::Index(_) => quote! { #expr },
-            Expr::Lit(_) => quote! { #expr },
-            Expr::Paren(ExprParen { expr, .. }) => {
-                let new_expr = recurse(*expr)?;
-                quote! { ( #new_expr ) }
-            }
-            Expr::Path(_) => quote! { #expr },
-            Expr::Reference(_) => quote! { #expr },
-            Expr::Unary(_) => quote! { #expr },
-            Expr::Group(ExprGroup { expr, .. }) => recurse(*expr)?,
-            _ => bail!("Unexpected expression token {expr:?}"),
-        })
-    }
-
-    recurse(syn::parse2(item)?)
+/// Selects the expansion of `default!` in the mode of
+/// `--cfg arithmetic_mode_default`, among the expansions in `panicking`,
+/// `wrapping` and `saturating` mode.
+#[cfg(arithmetic_mode_default = "wrapping")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __default {
+    ({ $($panicking:tt)* } { $($wrapping:tt)* } { $($saturating:tt)* }) => { $($wrapping)* };
 }
 
-/// Macro that changes the arithmetic operations (add, sub, mul, div, and shift)
-/// inside it to be saturating operations if any operations or intermediate step
-/// overflows.
-///
-/// ```rust
-/// use arithmetic_mode::saturating;
-///
-/// assert_eq!(255, saturating! { 255_u8 + 1_u8 });
-/// ```
-#[proc_macro]
-pub fn saturating(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    match saturating_impl(item.into()) {
-        Ok(tokens) => tokens.into(),
-        Err(e) => {
-            let error_message = format!("Error: {e}");
-            quote! { compile_error!(#error_message) }.into()
-        }
-    }
+/// Selects the expansion of `default!` in the mode of
+/// `--cfg arithmetic_mode_default`, among the expansions in `panicking`,
+/// `wrapping` and `saturating` mode.
+#[cfg(arithmetic_mode_default = "saturating")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __default {
+    ({ $($panicking:tt)* } { $($wrapping:tt)* } { $($saturating:tt)* }) => { $($saturating)* };
 }
 
-fn saturating_impl(item: TokenStream) -> anyhow::Result<TokenStream> {
-    fn recurse(expr: syn::Expr) -> anyhow::Result<TokenStream> {
-        Ok(match expr {
-            Expr::Binary(ExprBinary {
-                left, op, right, ..
-            }) => {
-                let new_left = recurse(*left)?;
-                let new_right = recurse(*right)?;
-                match op {
-                    syn::BinOp::Add(_) => quote! { (#new_left).saturating_add(#new_right) },
-                    syn::BinOp::Sub(_) => quote! { (#new_left).saturating_sub(#new_right) },
-                    syn::BinOp::Mul(_) => quote! { (#new_left).saturating_mul(#new_right) },
-                    syn::BinOp::Div(_) => quote! { (#new_left).saturating_div(#new_right) },
-                    syn::BinOp::Rem(_) => quote! { (#new_left).saturating_rem(#new_right) },
-                    syn::BinOp::Shl(_) => {
-                        bail!("Saturating bit shifts are not supported (https://github.com/rust-lang/libs-team/issues/230)")
-                    }
-                    syn::BinOp::Shr(_) => {
-                        bail!("Saturating bit shifts are not supported (https://github.com/rust-lang/libs-team/issues/230)")
-                    }
-                    syn::BinOp::And(_)
-                    | syn::BinOp::Or(_)
-                    | syn::BinOp::BitXor(_)
-                    | syn::BinOp::BitAnd(_)
-                    | syn::BinOp::BitOr(_)
-                    | syn::BinOp::Eq(_)
-                    | syn::BinOp::Lt(_)
-                    | syn::BinOp::Le(_)
-                    | syn::BinOp::Ne(_)
-                    | syn::BinOp::Ge(_)
-                    | syn::BinOp::Gt(_) => quote! { #new_left #op #new_right },
-                    syn::BinOp::AddAssign(_)
-                    | syn::BinOp::SubAssign(_)
-                    | syn::BinOp::MulAssign(_)
-                    | syn::BinOp::DivAssign(_)
-                    | syn::BinOp::RemAssign(_)
-                    | syn::BinOp::BitXorAssign(_)
-                    | syn::BinOp::BitAndAssign(_)
-                    | syn::BinOp::BitOrAssign(_)
-                    | syn::BinOp::ShlAssign(_)
-                    | syn::BinOp::ShrAssign(_) => bail!("Unsupported operation"),
-                    _ => bail!("Unknown op: {op:?}"),
-                }
-            }
-            Expr::Cast(_) => quote! { #expr },
-            // Recurse inside the following items
-            Expr::Index(_) => quote! { #expr },
-            Expr::Lit(_) => quote! { #expr },
-            Expr::Paren(ExprParen { expr, .. }) => {
-                let new_expr = recurse(*expr)?;
-                quote! { ( #new_expr ) }
-            }
-            Expr::Path(_) => quote! { #expr },
-            Expr::Reference(_) => quote! { #expr },
-            Expr::Unary(_) => quote! { #expr },
-            Expr::Group(ExprGroup { expr, .. }) => recurse(*expr)?,
-            _ => bail!("Unexpected expression token {expr:?}"),
-        })
-    }
-
-    recurse(syn::parse2(item)?)
+/// Selects the expansion of `default!` in the mode of
+/// `--cfg arithmetic_mode_default`, among the expansions in `panicking`,
+/// `wrapping` and `saturating` mode.
+#[cfg(not(any(
+    arithmetic_mode_default = "wrapping",
+    arithmetic_mode_default = "saturating"
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __default {
+    ({ $($panicking:tt)* } { $($wrapping:tt)* } { $($saturating:tt)* }) => { $($panicking)* };
 }
 
-/// Macro that changes the arithmetic operations (add, sub, mul, div, and shift)
-/// inside it to be checked operations that returns an `Option`, which would be
-/// `None` if the operation or any intermediate step overflows.
-///
-/// ```rust
-/// use arithmetic_mode::checked;
-///
-/// assert_eq!(Some(254), checked! { 255_u8 - 1_u8 });
-/// assert_eq!(None, checked! { 255_u8 + 1_u8 });
-/// ```
-#[proc_macro]
-pub fn checked(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    match checked_impl(item.into()) {
-        Ok(tokens) => tokens.into(),
-        Err(e) => {
-            let error_message = format!("Error: {e}");
-            quote! { compile_error!(#error_message) }.into()
-        }
-    }
-}
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "proptest")]
+    pub use proptest;
+    #[cfg(feature = "proptest")]
+    pub use std::panic::catch_unwind;
+    #[cfg(feature = "subtle")]
+    pub use subtle;
 
-fn checked_impl(item: TokenStream) -> anyhow::Result<TokenStream> {
-    fn recurse(expr: syn::Expr) -> anyhow::Result<TokenStream> {
-        Ok(match expr {
-            Expr::Binary(ExprBinary {
-                left, op, right, ..
-            }) => {
-                let new_left = recurse(*left)?;
-                let new_right = recurse(*right)?;
-                checked_op(op)?
-                    .map(
-                        |func| quote! { (#new_left).zip(#new_right).and_then(|(l, r)| l.#func(r)) },
-                    )
-                    .unwrap_or_else(|| quote! { (#new_left).zip(#new_right).map(|(l, r)| l #op r) })
-            }
-            Expr::Cast(_) => quote! { #expr },
-            // Recurse inside the following items
-            Expr::Index(_) => quote! { #expr },
-            Expr::Lit(ExprLit { lit, .. }) => quote! { Some(#lit) },
-            Expr::Paren(ExprParen { expr, .. }) => {
-                let new_expr = recurse(*expr)?;
-                quote! { ( #new_expr ) }
-            }
-            Expr::Path(_) => quote! { #expr },
-            Expr::Reference(_) => quote! { #expr },
-            Expr::Unary(ExprUnary { op, expr, .. }) => {
-                let new_expr = recurse(*expr)?;
-                quote! { (#new_expr).map(|v| #op v) }
-            }
-            Expr::Group(ExprGroup { expr, .. }) => recurse(*expr)?,
-            _ => bail!("Unexpected expression token {expr:?}"),
-        })
-    }
+    #[cfg(feature = "qualified")]
+    pub use crate::integer::{Integer, Operand};
 
-    recurse(syn::parse2(item)?)
-}
+    #[cfg(feature = "evm")]
+    pub use crate::evm::{__div as evm_div, __rem as evm_rem, __shl as evm_shl, __shr as evm_shr};
 
-fn checked_op(op: syn::BinOp) -> anyhow::Result<Option<TokenStream>> {
-    Ok(match op {
-        syn::BinOp::Add(_) => Some(quote! { checked_add }),
-        syn::BinOp::Sub(_) => Some(quote! { checked_sub }),
-        syn::BinOp::Mul(_) => Some(quote! { checked_mul }),
-        syn::BinOp::Div(_) => Some(quote! { checked_div }),
-        syn::BinOp::Rem(_) => Some(quote! { checked_rem }),
-        syn::BinOp::Shl(_) => Some(quote! { checked_shl }),
-        syn::BinOp::Shr(_) => Some(quote! { checked_shr }),
-        syn::BinOp::And(_)
-        | syn::BinOp::Or(_)
-        | syn::BinOp::BitXor(_)
-        | syn::BinOp::BitAnd(_)
-        | syn::BinOp::BitOr(_)
-        | syn::BinOp::Eq(_)
-        | syn::BinOp::Lt(_)
-        | syn::BinOp::Le(_)
-        | syn::BinOp::Ne(_)
-        | syn::BinOp::Ge(_)
-        | syn::BinOp::Gt(_) => None,
-        syn::BinOp::AddAssign(_)
-        | syn::BinOp::SubAssign(_)
-        | syn::BinOp::MulAssign(_)
-        | syn::BinOp::DivAssign(_)
-        | syn::BinOp::RemAssign(_)
-        | syn::BinOp::BitXorAssign(_)
-        | syn::BinOp::BitAndAssign(_)
-        | syn::BinOp::BitOrAssign(_)
-        | syn::BinOp::ShlAssign(_)
-        | syn::BinOp::ShrAssign(_) => bail!("Unsupported operation"),
-        _ => bail!("Unknown op: {op:?}"),
-    })
-}
+    pub use crate::channel::Channel;
+    pub use crate::division::Division;
+    pub use crate::dot::{
+        dot_checked, dot_panicking, dot_saturating, dot_try_checked, dot_wrapping, mac_checked,
+        mac_panicking, mac_saturating, mac_try_checked, mac_wrapping, MultiplyAccumulate,
+    };
+    pub use crate::lanes::{lane, lanes, Lanes};
+    pub use crate::lerp::{
+        lerp_checked, lerp_panicking, lerp_saturating, lerp_try_checked, lerp_wrapping, Lerp,
+    };
+    pub use crate::modfield::{Modular, Modulus, Residue};
+    /// The modes of the integer types of `modes!`.
+    pub mod modes {
+        pub use crate::modes::*;
+    }
+    pub use crate::mul_div::{
+        mul_div_checked, mul_div_panicking, mul_div_saturating, mul_div_try_checked,
+        mul_div_wrapping, Rounding,
+    };
+    pub use crate::qformat::Fixed;
+    pub use crate::trace::__trace as trace;
+    pub use crate::wide::Widen;
 
-fn wrapping_op(op: syn::BinOp) -> anyhow::Result<Option<TokenStream>> {
-    Ok(match op {
-        syn::BinOp::Add(_) => Some(quote! { wrapping_add }),
-        syn::BinOp::Sub(_) => Some(quote! { wrapping_sub }),
-        syn::BinOp::Mul(_) => Some(quote! { wrapping_mul }),
-        syn::BinOp::Div(_) => Some(quote! { wrapping_div }),
-        syn::BinOp::Rem(_) => Some(quote! { wrapping_rem }),
-        syn::BinOp::Shl(_) => Some(quote! { wrapping_shl }),
-        syn::BinOp::Shr(_) => Some(quote! { wrapping_shr }),
-        syn::BinOp::And(_)
-        | syn::BinOp::Or(_)
-        | syn::BinOp::BitXor(_)
-        | syn::BinOp::BitAnd(_)
-        | syn::BinOp::BitOr(_)
-        | syn::BinOp::Eq(_)
-        | syn::BinOp::Lt(_)
-        | syn::BinOp::Le(_)
-        | syn::BinOp::Ne(_)
-        | syn::BinOp::Ge(_)
-        | syn::BinOp::Gt(_) => None,
-        syn::BinOp::AddAssign(_)
-        | syn::BinOp::SubAssign(_)
-        | syn::BinOp::MulAssign(_)
-        | syn::BinOp::DivAssign(_)
-        | syn::BinOp::RemAssign(_)
-        | syn::BinOp::BitXorAssign(_)
-        | syn::BinOp::BitAndAssign(_)
-        | syn::BinOp::BitOrAssign(_)
-        | syn::BinOp::ShlAssign(_)
-        | syn::BinOp::ShrAssign(_) => bail!("Unsupported operation"),
-        _ => bail!("Unknown op: {op:?}"),
-    })
+    /// Hints that the overflow branch calling it is unlikely to be taken.
+    #[cfg(feature = "nightly")]
+    #[inline(always)]
+    pub const fn cold_path() {
+        core::intrinsics::cold_path()
+    }
 }
-
-#[cfg(test)]
-mod tests;
@@ -0,0 +1,149 @@
+//! The modes of the integer types in [`modes!`](crate::modes), like
+//! `modes! { u8 = saturating, usize = checked; expr }`. The macro cannot see
+//! the types of the operands, so it declares which mode each integer type is
+//! in with [`ModeOf`], and each operation is dispatched to the mode of its
+//! type when the expression is type checked.
+
+/// How an operation failed in its mode.
+pub enum Failure {
+    /// In `checked` or `try_checked` mode, where the expression evaluates to
+    /// `None` or an `ArithmeticError`.
+    Fails,
+    /// In `panicking` mode.
+    Panics,
+}
+
+/// The mode of the integer type `T` in the expression of a `modes!`.
+pub trait ModeOf<T> {
+    /// The mode, like [`Saturating`].
+    type Mode: TypeMode<T>;
+}
+
+/// A mode of the operations of the integer type `T`.
+pub trait TypeMode<T> {
+    /// `l + r` in this mode.
+    fn add(l: T, r: T) -> Result<T, Failure>;
+
+    /// `l - r` in this mode.
+    fn sub(l: T, r: T) -> Result<T, Failure>;
+
+    /// `l * r` in this mode.
+    fn mul(l: T, r: T) -> Result<T, Failure>;
+
+    /// `l / r` in this mode.
+    fn div(l: T, r: T) -> Result<T, Failure>;
+
+    /// `l % r` in this mode.
+    fn rem(l: T, r: T) -> Result<T, Failure>;
+}
+
+/// The bit shifts of the integer type `T` in a mode, which `saturating` mode
+/// doesn't have.
+#[cfg_attr(
+    not(feature = "msrv"),
+    diagnostic::on_unimplemented(
+        message = "Saturating bit shifts are not supported",
+        label = "`{T}` is in `saturating` mode",
+        note = "use `checked` or `wrapping` mode for the type of the shift instead"
+    )
+)]
+pub trait TypeShift<T> {
+    /// `l << r` in this mode.
+    fn shl(l: T, r: u32) -> Result<T, Failure>;
+
+    /// `l >> r` in this mode.
+    fn shr(l: T, r: u32) -> Result<T, Failure>;
+}
+
+/// `panicking` mode.
+pub enum Panicking {}
+
+/// `wrapping` mode.
+pub enum Wrapping {}
+
+/// `saturating` mode.
+pub enum Saturating {}
+
+/// `checked` and `try_checked` mode, which only differ in what the whole
+/// expression evaluates to.
+pub enum Fallible {}
+
+macro_rules! impl_modes {
+    ($($ty:ident)*) => {
+        $(
+            impl_modes!(@checked $ty, Panicking, Failure::Panics);
+            impl_modes!(@checked $ty, Fallible, Failure::Fails);
+
+            impl TypeMode<$ty> for Wrapping {
+                impl_modes!(@infallible $ty, $ty: add = wrapping_add, sub = wrapping_sub,
+                    mul = wrapping_mul, div = wrapping_div, rem = wrapping_rem);
+            }
+
+            impl TypeShift<$ty> for Wrapping {
+                impl_modes!(@infallible $ty, u32: shl = wrapping_shl, shr = wrapping_shr);
+            }
+
+            // The only overflow, `MIN % -1`, is 0, so wrapping is already
+            // saturating
+            impl TypeMode<$ty> for Saturating {
+                impl_modes!(@infallible $ty, $ty: add = saturating_add, sub = saturating_sub,
+                    mul = saturating_mul, div = saturating_div, rem = wrapping_rem);
+            }
+        )*
+    };
+    (@checked $ty:ident, $mode:ident, $failure:path) => {
+        impl TypeMode<$ty> for $mode {
+            impl_modes!(@fallible $ty, $ty, $failure: add = checked_add, sub = checked_sub,
+                mul = checked_mul, div = checked_div, rem = checked_rem);
+        }
+
+        impl TypeShift<$ty> for $mode {
+            impl_modes!(@fallible $ty, u32, $failure: shl = checked_shl, shr = checked_shr);
+        }
+    };
+    (@fallible $ty:ident, $rhs:ident, $failure:path: $($method:ident = $func:ident),*) => {
+        $(
+            #[inline(always)]
+            fn $method(l: $ty, r: $rhs) -> Result<$ty, Failure> {
+                l.$func(r).ok_or($failure)
+            }
+        )*
+    };
+    (@infallible $ty:ident, $rhs:ident: $($method:ident = $func:ident),*) => {
+        $(
+            #[inline(always)]
+            #[track_caller]
+            fn $method(l: $ty, r: $rhs) -> Result<$ty, Failure> {
+                Ok(l.$func(r))
+            }
+        )*
+    };
+}
+
+impl_modes!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+
+macro_rules! dispatch {
+    ($($method:ident($rhs:ty): $trait:ident, $doc:literal;)*) => {
+        $(
+            #[doc = concat!("`", $doc, "` in the mode of `T` in `M`.")]
+            #[inline(always)]
+            #[track_caller]
+            pub fn $method<M: ModeOf<T>, T>(l: T, r: $rhs) -> Result<T, Failure>
+            where
+                M::Mode: $trait<T>,
+            {
+                <M::Mode as $trait<T>>::$method(l, r)
+            }
+        )*
+    };
+}
+
+dispatch! {
+    add(T): TypeMode, "l + r";
+    sub(T): TypeMode, "l - r";
+    mul(T): TypeMode, "l * r";
+    div(T): TypeMode, "l / r";
+    rem(T): TypeMode, "l % r";
+    shl(u32): TypeShift, "l << r";
+    shr(u32): TypeShift, "l >> r";
+}
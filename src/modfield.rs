@@ -0,0 +1,167 @@
+//! The modular arithmetic of [`modfield!`](crate::modfield), which reduces
+//! with the Barrett factor of its modulus, computed once at compile time,
+//! instead of dividing.
+
+/// The unsigned integers that can be the operands of
+/// [`modfield!`](crate::modfield).
+pub trait Residue: Copy {
+    /// The value in the widest type.
+    fn widen(self) -> u128;
+}
+
+/// The unsigned integers that can be the modulus of
+/// [`modfield!`](crate::modfield). They are at most 64 bits wide, so that the
+/// product of two residues fits in `u128`.
+pub trait Modular: Residue {
+    /// The `residue`, which is less than a modulus of the type.
+    fn narrow(residue: u64) -> Self;
+}
+
+macro_rules! impl_residue {
+    ($($ty:ident),*) => {
+        $(
+            impl Residue for $ty {
+                #[inline(always)]
+                fn widen(self) -> u128 {
+                    self as u128
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_modular {
+    ($($ty:ident),*) => {
+        $(
+            impl Modular for $ty {
+                #[inline(always)]
+                fn narrow(residue: u64) -> Self {
+                    residue as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_residue!(u8, u16, u32, u64, u128, usize);
+impl_modular!(u8, u16, u32, u64, usize);
+
+/// A modulus with its Barrett factor, `floor((2^128 - 1) / modulus)`.
+#[derive(Clone, Copy, Debug)]
+pub struct Modulus {
+    modulus: u64,
+    factor: u128,
+}
+
+impl Modulus {
+    /// Precomputes the Barrett factor of `modulus`, which is meant to be
+    /// evaluated at compile time.
+    pub const fn new(modulus: u64) -> Self {
+        assert!(modulus > 1, "the modulus of modfield! must be at least 2");
+        Modulus {
+            modulus,
+            factor: u128::MAX / modulus as u128,
+        }
+    }
+
+    /// The residue of `value`.
+    #[inline]
+    pub fn reduce(self, value: impl Residue) -> u64 {
+        self.reduce_wide(value.widen())
+    }
+
+    /// The residue of `value` with Barrett reduction. The estimated quotient
+    /// is at most 2 less than the actual one, so the remainder is corrected
+    /// by at most 2 subtractions.
+    #[inline]
+    fn reduce_wide(self, value: u128) -> u64 {
+        let modulus = self.modulus as u128;
+        let quotient = high_mul(value, self.factor);
+        let mut remainder = value - quotient * modulus;
+        while remainder >= modulus {
+            remainder -= modulus;
+        }
+        remainder as u64
+    }
+
+    /// The sum of the residues `a` and `b`.
+    #[inline]
+    pub fn add(self, a: u64, b: u64) -> u64 {
+        let sum = a as u128 + b as u128;
+        if sum >= self.modulus as u128 {
+            (sum - self.modulus as u128) as u64
+        } else {
+            sum as u64
+        }
+    }
+
+    /// The difference of the residues `a` and `b`.
+    #[inline]
+    pub fn sub(self, a: u64, b: u64) -> u64 {
+        if a >= b {
+            a - b
+        } else {
+            self.modulus - (b - a)
+        }
+    }
+
+    /// The product of the residues `a` and `b`.
+    #[inline]
+    pub fn mul(self, a: u64, b: u64) -> u64 {
+        self.reduce_wide(a as u128 * b as u128)
+    }
+
+    /// The quotient of the residues `a` and `b`, which is the product of `a`
+    /// and the inverse of `b`.
+    #[inline]
+    #[track_caller]
+    pub fn div(self, a: u64, b: u64) -> u64 {
+        self.mul(a, self.inverse(b))
+    }
+
+    /// The negation of the residue `a`.
+    #[inline]
+    pub fn neg(self, a: u64) -> u64 {
+        self.sub(0, a)
+    }
+
+    /// The inverse of the residue `a`, with the extended Euclidean algorithm.
+    /// Panics if `a` has no inverse, which is only zero when the modulus is
+    /// prime.
+    #[track_caller]
+    fn inverse(self, a: u64) -> u64 {
+        let (mut r0, mut r1) = (self.modulus as i128, a as i128);
+        let (mut t0, mut t1) = (0_i128, 1_i128);
+        while r1 != 0 {
+            let quotient = r0 / r1;
+            (r0, r1) = (r1, r0 - quotient * r1);
+            (t0, t1) = (t1, t0 - quotient * t1);
+        }
+        if r0 != 1 {
+            panic!(
+                "attempt to divide by {a}, which has no inverse modulo {}",
+                self.modulus
+            );
+        }
+        t0.rem_euclid(self.modulus as i128) as u64
+    }
+
+    /// The `residue` in the type of the `modulus` it was reduced with.
+    #[inline(always)]
+    pub fn narrow<T: Modular>(_modulus: T, residue: u64) -> T {
+        T::narrow(residue)
+    }
+}
+
+/// The high 128 bits of the 256-bit product of `a` and `b`.
+#[inline]
+fn high_mul(a: u128, b: u128) -> u128 {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & MASK);
+    let (b_high, b_low) = (b >> 64, b & MASK);
+    let low = a_low * b_low;
+    let cross_a = a_high * b_low;
+    let cross_b = a_low * b_high;
+    let middle = (low >> 64) + (cross_a & MASK) + (cross_b & MASK);
+    a_high * b_high + (cross_a >> 64) + (cross_b >> 64) + (middle >> 64)
+}
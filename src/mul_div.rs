@@ -0,0 +1,70 @@
+//! The `a * b / c` of [`mul_div!`](crate::mul_div), whose product is computed
+//! in a wider integer type, so that only the quotient can overflow.
+
+use crate::wide::Widen;
+use crate::{ArithmeticError, ErrorKind, Operation};
+
+/// How `mul_div!` rounds the quotient.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Toward zero, like `/`.
+    Down,
+    /// Away from zero.
+    Up,
+    /// Toward negative infinity.
+    Floor,
+    /// Toward positive infinity.
+    Ceil,
+    /// To the nearest integer, and away from zero when halfway.
+    Nearest,
+}
+
+/// The quotient in the wider type, panicking when dividing by zero like the
+/// modes that do not fail.
+#[track_caller]
+fn quotient<T: Widen>(a: T, b: T, c: T, rounding: Rounding) -> T::Wide {
+    match T::divide(a.multiply(b), c, rounding) {
+        Some(quotient) => quotient,
+        None => panic!("attempt to divide by zero"),
+    }
+}
+
+/// `a * b / c` in `panicking` mode.
+#[track_caller]
+pub fn mul_div_panicking<T: Widen>(a: T, b: T, c: T, rounding: Rounding) -> T {
+    match T::narrow(quotient(a, b, c, rounding)) {
+        Some(value) => value,
+        None => panic!("attempt to multiply and divide with overflow"),
+    }
+}
+
+/// `a * b / c` in `wrapping` mode.
+#[track_caller]
+pub fn mul_div_wrapping<T: Widen>(a: T, b: T, c: T, rounding: Rounding) -> T {
+    T::truncate(quotient(a, b, c, rounding))
+}
+
+/// `a * b / c` in `saturating` mode.
+#[track_caller]
+pub fn mul_div_saturating<T: Widen>(a: T, b: T, c: T, rounding: Rounding) -> T {
+    T::saturate(quotient(a, b, c, rounding))
+}
+
+/// `a * b / c` in `checked` mode.
+pub fn mul_div_checked<T: Widen>(a: T, b: T, c: T, rounding: Rounding) -> Option<T> {
+    T::narrow(T::divide(a.multiply(b), c, rounding)?)
+}
+
+/// `a * b / c` in `try_checked` mode, whose failure is reported as the
+/// division.
+pub fn mul_div_try_checked<T: Widen>(
+    a: T,
+    b: T,
+    c: T,
+    rounding: Rounding,
+) -> Result<T, ArithmeticError> {
+    let error = ArithmeticError::new(Operation::Div);
+    let quotient =
+        T::divide(a.multiply(b), c, rounding).ok_or(error.with_kind(ErrorKind::DivisionByZero))?;
+    T::narrow(quotient).ok_or(error)
+}
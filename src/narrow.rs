@@ -0,0 +1,218 @@
+//! The [`Integer`] methods of the integers of non-standard widths of the `ux`
+//! crate, like `u5` and `u24`, with the `ux` feature. `ux` only defines the
+//! wrapping additions and subtractions, so the operations are computed in the
+//! primitive integer that stores the value, like `u32` for `u24`, and checked
+//! or wrapped to the width of the type afterwards. The types wider than 64
+//! bits and `i1` are not supported, since `ux` does not convert them to and
+//! from the primitive integers.
+
+use crate::integer::{Integer, Operand};
+
+macro_rules! impl_narrow {
+    ($storage:ident: $($ty:ident)*) => {
+        $(
+            impl Integer for ::ux::$ty {
+                #[inline(always)]
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    narrow($storage::from(self).checked_add($storage::from(rhs))?)
+                }
+
+                #[inline(always)]
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    narrow($storage::from(self).checked_sub($storage::from(rhs))?)
+                }
+
+                #[inline(always)]
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    narrow($storage::from(self).checked_mul($storage::from(rhs))?)
+                }
+
+                #[inline(always)]
+                fn checked_div(self, rhs: Self) -> Option<Self> {
+                    narrow($storage::from(self).checked_div($storage::from(rhs))?)
+                }
+
+                // `MIN % -1` fits in the storage, but fails like `MIN / -1`
+                // does for the primitive integers
+                #[inline(always)]
+                fn checked_rem(self, rhs: Self) -> Option<Self> {
+                    self.checked_div(rhs)?;
+                    narrow($storage::from(self).checked_rem($storage::from(rhs))?)
+                }
+
+                #[inline(always)]
+                fn checked_shl(self, rhs: u32) -> Option<Self> {
+                    (rhs < Self::BITS).then(|| self.wrapping_shl(rhs))
+                }
+
+                #[inline(always)]
+                fn checked_shr(self, rhs: u32) -> Option<Self> {
+                    (rhs < Self::BITS).then(|| self.wrapping_shr(rhs))
+                }
+
+                #[inline(always)]
+                fn wrapping_add(self, rhs: Self) -> Self {
+                    Self::wrapping_add(self, rhs)
+                }
+
+                #[inline(always)]
+                fn wrapping_sub(self, rhs: Self) -> Self {
+                    Self::wrapping_sub(self, rhs)
+                }
+
+                #[inline(always)]
+                fn wrapping_mul(self, rhs: Self) -> Self {
+                    wrap($storage::from(self).wrapping_mul($storage::from(rhs)), Self::BITS)
+                }
+
+                #[inline(always)]
+                #[track_caller]
+                fn wrapping_div(self, rhs: Self) -> Self {
+                    wrap($storage::from(self).wrapping_div($storage::from(rhs)), Self::BITS)
+                }
+
+                #[inline(always)]
+                #[track_caller]
+                fn wrapping_rem(self, rhs: Self) -> Self {
+                    wrap($storage::from(self).wrapping_rem($storage::from(rhs)), Self::BITS)
+                }
+
+                #[inline(always)]
+                #[allow(clippy::modulo_one)] // `u1` only shifts by zero
+                fn wrapping_shl(self, rhs: u32) -> Self {
+                    wrap($storage::from(self) << (rhs % Self::BITS), Self::BITS)
+                }
+
+                #[inline(always)]
+                #[allow(clippy::modulo_one)] // `u1` only shifts by zero
+                fn wrapping_shr(self, rhs: u32) -> Self {
+                    wrap($storage::from(self) >> (rhs % Self::BITS), Self::BITS)
+                }
+
+                #[inline(always)]
+                fn saturating_add(self, rhs: Self) -> Self {
+                    match Integer::checked_add(self, rhs) {
+                        Some(v) => v,
+                        None if rhs < Self::default() => Self::MIN,
+                        None => Self::MAX,
+                    }
+                }
+
+                #[inline(always)]
+                fn saturating_sub(self, rhs: Self) -> Self {
+                    match Integer::checked_sub(self, rhs) {
+                        Some(v) => v,
+                        None if rhs < Self::default() => Self::MAX,
+                        None => Self::MIN,
+                    }
+                }
+
+                #[inline(always)]
+                fn saturating_mul(self, rhs: Self) -> Self {
+                    let zero = Self::default();
+                    match Integer::checked_mul(self, rhs) {
+                        Some(v) => v,
+                        None if (self < zero) != (rhs < zero) => Self::MIN,
+                        None => Self::MAX,
+                    }
+                }
+
+                // Only `MIN / -1` overflows, to `MAX + 1`
+                #[inline(always)]
+                #[track_caller]
+                fn saturating_div(self, rhs: Self) -> Self {
+                    if rhs == Self::default() {
+                        panic!("attempt to divide by zero");
+                    }
+                    Integer::checked_div(self, rhs).unwrap_or(Self::MAX)
+                }
+            }
+
+            impl Operand for ::ux::$ty {
+                type Integer = ::ux::$ty;
+
+                #[inline(always)]
+                fn integer(self) -> ::ux::$ty {
+                    self
+                }
+            }
+
+            impl Operand for &::ux::$ty {
+                type Integer = ::ux::$ty;
+
+                #[inline(always)]
+                fn integer(self) -> ::ux::$ty {
+                    *self
+                }
+            }
+        )*
+    };
+}
+
+/// The `value` of the storage type, if it fits in the narrower `T`.
+#[inline(always)]
+fn narrow<S, T: TryFrom<S>>(value: S) -> Option<T> {
+    T::try_from(value).ok()
+}
+
+/// The `value` of the storage type wrapped around at the bounds of `T`, which
+/// is `bits` wide, by shifting out the higher bits. The shift back to the
+/// right extends the sign of the signed types.
+#[inline(always)]
+fn wrap<S, T>(value: S, bits: u32) -> T
+where
+    S: Wrap,
+    T: TryFrom<S>,
+{
+    let shift = S::BITS - bits;
+    match T::try_from(value.shl(shift).shr(shift)) {
+        Ok(value) => value,
+        Err(_) => unreachable!("the wrapped value fits in the type"),
+    }
+}
+
+/// The shifts of the primitive integers that store the values of `ux`.
+trait Wrap: Sized {
+    const BITS: u32;
+
+    fn shl(self, shift: u32) -> Self;
+
+    fn shr(self, shift: u32) -> Self;
+}
+
+macro_rules! impl_wrap {
+    ($($storage:ident)*) => {
+        $(
+            impl Wrap for $storage {
+                const BITS: u32 = $storage::BITS;
+
+                #[inline(always)]
+                fn shl(self, shift: u32) -> Self {
+                    self << shift
+                }
+
+                #[inline(always)]
+                fn shr(self, shift: u32) -> Self {
+                    self >> shift
+                }
+            }
+        )*
+    };
+}
+
+impl_wrap!(u8 u16 u32 u64 i8 i16 i32 i64);
+
+impl_narrow!(u8: u1 u2 u3 u4 u5 u6 u7);
+impl_narrow!(u16: u9 u10 u11 u12 u13 u14 u15);
+impl_narrow!(u32: u17 u18 u19 u20 u21 u22 u23 u24 u25 u26 u27 u28 u29 u30 u31);
+impl_narrow!(u64:
+    u33 u34 u35 u36 u37 u38 u39 u40 u41 u42 u43 u44 u45 u46 u47 u48
+    u49 u50 u51 u52 u53 u54 u55 u56 u57 u58 u59 u60 u61 u62 u63
+);
+impl_narrow!(i8: i2 i3 i4 i5 i6 i7);
+impl_narrow!(i16: i9 i10 i11 i12 i13 i14 i15);
+impl_narrow!(i32: i17 i18 i19 i20 i21 i22 i23 i24 i25 i26 i27 i28 i29 i30 i31);
+impl_narrow!(i64:
+    i33 i34 i35 i36 i37 i38 i39 i40 i41 i42 i43 i44 i45 i46 i47 i48
+    i49 i50 i51 i52 i53 i54 i55 i56 i57 i58 i59 i60 i61 i62 i63
+);
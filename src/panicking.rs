@@ -0,0 +1,272 @@
+//! [`Panicking`] and [`Strict`], the integers whose operators panic on
+//! overflow in all builds, like [`panicking!`](crate::panicking), for the code
+//! that opts into the mode with a type instead of a macro. Their operators
+//! take the wrapper or its integer, so that an unsuffixed literal has the
+//! type of the other operand, like the `3` of `Panicking(price) * 3`.
+
+use core::fmt::{self, Debug, Display};
+use core::iter::{Product, Sum};
+use core::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Shl, ShlAssign, Shr,
+    ShrAssign, Sub, SubAssign,
+};
+
+use crate::{ArithmeticError, ErrorKind, Operation};
+
+/// An integer whose operators panic if an operation overflows, in all builds,
+/// with the operands in the message, like
+/// `attempt to multiply with overflow: 200 * 2`.
+///
+/// ```rust
+/// use arithmetic_mode::Panicking;
+///
+/// let price = Panicking(200_u8);
+/// assert_eq!(Panicking(250), price + 50);
+/// ```
+///
+/// ```should_panic
+/// use arithmetic_mode::Panicking;
+///
+/// let price = Panicking(200_u8);
+/// // Panics with "attempt to multiply with overflow: 200 * 2"
+/// let _ = price * 2;
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Panicking<T>(pub T);
+
+/// Like [`Panicking`], and `<<` also panics if it shifts out any bit of the
+/// value, like the multiplication by a power of two it stands for, instead of
+/// only if the shift is not smaller than the number of bits.
+///
+/// ```should_panic
+/// use arithmetic_mode::Strict;
+///
+/// let flags = Strict(0b1100_0000_u8);
+/// // Panics with "attempt to shift left with overflow: 192 << 1"
+/// let _ = flags << 1;
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Strict<T>(pub T);
+
+/// Panics with the failure of `operation` with the operands `left` and
+/// `right`. Shared by all the operators, so that formatting the panic is not
+/// inlined into each of them.
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn fail(operation: Operation, kind: ErrorKind, left: &dyn Debug, right: &dyn Debug) -> ! {
+    let symbol = match operation {
+        Operation::Add => "+",
+        Operation::Sub => "-",
+        Operation::Mul => "*",
+        Operation::Div => "/",
+        Operation::Rem => "%",
+        Operation::Shl => "<<",
+        Operation::Shr => ">>",
+        Operation::Conversion => "as",
+    };
+    let error = ArithmeticError::new(operation).with_kind(kind);
+    panic!("{error}: {left:?} {symbol} {right:?}")
+}
+
+/// The value of an operation, or a panic if it failed, which is a division
+/// by zero if `right` is zero for the divisions.
+#[inline(always)]
+#[track_caller]
+fn value<T: Debug + Default + PartialEq>(
+    result: Option<T>,
+    operation: Operation,
+    left: T,
+    right: T,
+) -> T {
+    match result {
+        Some(value) => value,
+        None => {
+            let kind = match operation {
+                Operation::Div | Operation::Rem if right == T::default() => {
+                    ErrorKind::DivisionByZero
+                }
+                _ => ErrorKind::Overflow,
+            };
+            fail(operation, kind, &left, &right)
+        }
+    }
+}
+
+macro_rules! impl_wrappers {
+    (signed: $($ty:ident)*) => {
+        $(
+            impl_wrappers!($ty);
+            impl_wrappers!(@neg $ty: Panicking, Strict);
+        )*
+    };
+    (unsigned: $($ty:ident)*) => {
+        $(
+            impl_wrappers!($ty);
+        )*
+    };
+    ($ty:ident) => {
+        impl_wrappers!(@binary $ty: Add add AddAssign add_assign checked_add, Add);
+        impl_wrappers!(@binary $ty: Sub sub SubAssign sub_assign checked_sub, Sub);
+        impl_wrappers!(@binary $ty: Mul mul MulAssign mul_assign checked_mul, Mul);
+        impl_wrappers!(@binary $ty: Div div DivAssign div_assign checked_div, Div);
+        impl_wrappers!(@binary $ty: Rem rem RemAssign rem_assign checked_rem, Rem);
+        impl_wrappers!(@shift $ty, Panicking: Shl shl ShlAssign shl_assign, Shl,
+            |l: $ty, r| l.checked_shl(r));
+        impl_wrappers!(@shift $ty, Panicking: Shr shr ShrAssign shr_assign, Shr,
+            |l: $ty, r| l.checked_shr(r));
+        // The value has to be shifted back to itself
+        impl_wrappers!(@shift $ty, Strict: Shl shl ShlAssign shl_assign, Shl,
+            |l: $ty, r| l.checked_shl(r).filter(|value| value >> r == l));
+        impl_wrappers!(@shift $ty, Strict: Shr shr ShrAssign shr_assign, Shr,
+            |l: $ty, r| l.checked_shr(r));
+        impl_wrappers!(@fold $ty: Sum sum add 0, Product product mul 1);
+    };
+    (@binary $ty:ident: $trait:ident $method:ident $assign:ident $assign_method:ident
+        $func:ident, $operation:ident) => {
+        impl_wrappers!(@binary $ty, Panicking: $trait $method $assign $assign_method $func,
+            $operation);
+        impl_wrappers!(@binary $ty, Strict: $trait $method $assign $assign_method $func,
+            $operation);
+    };
+    (@binary $ty:ident, $wrapper:ident: $trait:ident $method:ident $assign:ident
+        $assign_method:ident $func:ident, $operation:ident) => {
+        impl $trait for $wrapper<$ty> {
+            type Output = Self;
+
+            #[inline(always)]
+            #[track_caller]
+            fn $method(self, rhs: Self) -> Self {
+                let (l, r) = (self.0, rhs.0);
+                $wrapper(value(l.$func(r), Operation::$operation, l, r))
+            }
+        }
+
+        impl $trait<$ty> for $wrapper<$ty> {
+            type Output = Self;
+
+            #[inline(always)]
+            #[track_caller]
+            fn $method(self, rhs: $ty) -> Self {
+                $trait::$method(self, $wrapper(rhs))
+            }
+        }
+
+        impl $assign for $wrapper<$ty> {
+            #[inline(always)]
+            #[track_caller]
+            fn $assign_method(&mut self, rhs: Self) {
+                *self = $trait::$method(*self, rhs);
+            }
+        }
+
+        impl $assign<$ty> for $wrapper<$ty> {
+            #[inline(always)]
+            #[track_caller]
+            fn $assign_method(&mut self, rhs: $ty) {
+                *self = $trait::$method(*self, rhs);
+            }
+        }
+    };
+    // The shifts take a `u32`, like the checked shifts of the integers
+    (@shift $ty:ident, $wrapper:ident: $trait:ident $method:ident $assign:ident
+        $assign_method:ident, $operation:ident, $shift:expr) => {
+        impl $trait<u32> for $wrapper<$ty> {
+            type Output = Self;
+
+            #[inline(always)]
+            #[track_caller]
+            fn $method(self, rhs: u32) -> Self {
+                let l = self.0;
+                match ($shift)(l, rhs) {
+                    Some(value) => $wrapper(value),
+                    None => fail(Operation::$operation, ErrorKind::Overflow, &l, &rhs),
+                }
+            }
+        }
+
+        impl $assign<u32> for $wrapper<$ty> {
+            #[inline(always)]
+            #[track_caller]
+            fn $assign_method(&mut self, rhs: u32) {
+                *self = $trait::$method(*self, rhs);
+            }
+        }
+    };
+    (@neg $ty:ident: $($wrapper:ident),*) => {
+        $(
+            impl Neg for $wrapper<$ty> {
+                type Output = Self;
+
+                #[inline(always)]
+                #[track_caller]
+                fn neg(self) -> Self {
+                    $wrapper(value(self.0.checked_neg(), Operation::Sub, 0, self.0))
+                }
+            }
+        )*
+    };
+    (@fold $ty:ident: $($trait:ident $method:ident $op:ident $identity:literal),*) => {
+        $(
+            impl_wrappers!(@fold $ty, Panicking: $trait $method $op $identity);
+            impl_wrappers!(@fold $ty, Strict: $trait $method $op $identity);
+        )*
+    };
+    (@fold $ty:ident, $wrapper:ident: $trait:ident $method:ident $op:ident $identity:literal) => {
+        impl $trait for $wrapper<$ty> {
+            #[inline(always)]
+            #[track_caller]
+            fn $method<I: Iterator<Item = Self>>(iter: I) -> Self {
+                // A loop instead of `fold`, whose closure would lose the caller
+                let mut acc = $wrapper($identity);
+                for value in iter {
+                    acc = acc.$op(value);
+                }
+                acc
+            }
+        }
+
+        impl $trait<$ty> for $wrapper<$ty> {
+            #[inline(always)]
+            #[track_caller]
+            fn $method<I: Iterator<Item = $ty>>(iter: I) -> Self {
+                let mut acc = $wrapper($identity);
+                for value in iter {
+                    acc = acc.$op(value);
+                }
+                acc
+            }
+        }
+    };
+}
+
+impl_wrappers!(signed: i8 i16 i32 i64 i128 isize);
+impl_wrappers!(unsigned: u8 u16 u32 u64 u128 usize);
+
+impl<T> From<T> for Panicking<T> {
+    #[inline(always)]
+    fn from(value: T) -> Self {
+        Panicking(value)
+    }
+}
+
+impl<T> From<T> for Strict<T> {
+    #[inline(always)]
+    fn from(value: T) -> Self {
+        Strict(value)
+    }
+}
+
+impl<T: Display> Display for Panicking<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: Display> Display for Strict<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
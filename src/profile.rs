@@ -0,0 +1,132 @@
+//! The overflow counts of the operations of the macros, which are recorded
+//! with the `profile` feature, to find out which operations actually overflow
+//! before choosing their mode.
+//!
+//! Each operation is a [`Site`], identified by the location of its operator,
+//! which stays the same across runs of the same source. A site is only
+//! registered when it first overflows, so the operations that never overflow
+//! cost nothing but the check they already have.
+//!
+//! ```rust
+//! use arithmetic_mode::{profile, wrapping};
+//!
+//! fn hash(a: u32, b: u32) -> u32 {
+//!     wrapping! { a * 31 + b }
+//! }
+//!
+//! hash(u32::MAX, 1);
+//! // E.g. at the end of `main`
+//! for site in profile::sites() {
+//!     eprintln!("{site}");
+//! }
+//! assert!(profile::sites().any(|site| site.expression() == "a * 31"));
+//! ```
+
+use core::fmt::{self, Display};
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+use crate::Location;
+
+/// The sites that have overflowed, as a linked list of the statics of the
+/// expansions, most recent first.
+static SITES: AtomicPtr<Site> = AtomicPtr::new(ptr::null_mut());
+
+/// An operation of the macros whose overflows are counted.
+#[derive(Debug)]
+pub struct Site {
+    expression: &'static str,
+    location: Location,
+    overflows: AtomicUsize,
+    next: AtomicPtr<Site>,
+}
+
+impl Site {
+    #[doc(hidden)]
+    pub const fn __new(expression: &'static str, location: Location) -> Self {
+        Site {
+            expression,
+            location,
+            overflows: AtomicUsize::new(0),
+            next: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Counts an overflow of the operation, registering the site on its first
+    /// overflow.
+    #[doc(hidden)]
+    #[cold]
+    #[inline(never)]
+    pub fn __record(&'static self) {
+        if self.overflows.fetch_add(1, Ordering::Relaxed) != 0 {
+            return;
+        }
+        let site = self as *const Site as *mut Site;
+        let mut head = SITES.load(Ordering::Relaxed);
+        loop {
+            self.next.store(head, Ordering::Relaxed);
+            match SITES.compare_exchange_weak(head, site, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// The source code of the operation, like `a * 31`.
+    pub fn expression(&self) -> &'static str {
+        self.expression
+    }
+
+    /// The location of the operator in the source code.
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// The number of times that the operation has overflowed or divided by
+    /// zero.
+    pub fn overflows(&self) -> usize {
+        self.overflows.load(Ordering::Relaxed)
+    }
+}
+
+impl Display for Site {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: `{}` overflowed {} times",
+            self.location,
+            self.expression,
+            self.overflows()
+        )
+    }
+}
+
+/// The sites that have overflowed at least once so far, most recent first.
+pub fn sites() -> Sites {
+    Sites {
+        next: site(SITES.load(Ordering::Acquire)),
+    }
+}
+
+/// The iterator of [`sites`].
+#[derive(Clone, Debug)]
+pub struct Sites {
+    next: Option<&'static Site>,
+}
+
+impl Iterator for Sites {
+    type Item = &'static Site;
+
+    fn next(&mut self) -> Option<&'static Site> {
+        let current = self.next?;
+        self.next = site(current.next.load(Ordering::Acquire));
+        Some(current)
+    }
+}
+
+/// The site that a pointer of the list points to, if any.
+fn site(pointer: *mut Site) -> Option<&'static Site> {
+    // SAFETY: The pointers of the list are either null or the address of a
+    // static `Site`, which is never mutated other than through its atomics
+    unsafe { pointer.as_ref() }
+}
@@ -0,0 +1,97 @@
+//! The fixed-point operations of [`qformat!`](crate::qformat) on the signed
+//! integers, whose lowest `N` bits are the fraction.
+
+/// A signed integer that holds a fixed-point number in a Q format.
+pub trait Fixed: Copy {
+    /// The sum of `self` and `rhs`, saturated at the bounds of the type.
+    fn q_add(self, rhs: Self) -> Self;
+
+    /// The difference of `self` and `rhs`, saturated at the bounds of the
+    /// type.
+    fn q_sub(self, rhs: Self) -> Self;
+
+    /// The negation of `self`, saturated at the bounds of the type.
+    fn q_neg(self) -> Self;
+
+    /// The product of `self` and `rhs` with `N` fractional bits, rounded to
+    /// the nearest value, with the halves rounded up, and saturated at the
+    /// bounds of the type.
+    fn q_mul<const N: u32>(self, rhs: Self) -> Self;
+
+    /// The quotient of `self` and `rhs` with `N` fractional bits, rounded to
+    /// the nearest value, with the halves rounded away from zero, and
+    /// saturated at the bounds of the type. Panics if `rhs` is zero.
+    fn q_div<const N: u32>(self, rhs: Self) -> Self;
+
+    /// The `raw` value, saturated at the bounds of the type.
+    fn saturate(raw: i128) -> Self;
+}
+
+macro_rules! impl_fixed {
+    ($($ty:ident => $wide:ident),*) => {
+        $(
+            impl Fixed for $ty {
+                #[inline(always)]
+                fn q_add(self, rhs: Self) -> Self {
+                    self.saturating_add(rhs)
+                }
+
+                #[inline(always)]
+                fn q_sub(self, rhs: Self) -> Self {
+                    self.saturating_sub(rhs)
+                }
+
+                #[inline(always)]
+                fn q_neg(self) -> Self {
+                    self.saturating_neg()
+                }
+
+                #[inline(always)]
+                fn q_mul<const N: u32>(self, rhs: Self) -> Self {
+                    let () = Fraction::<N, { $ty::BITS }>::CHECK;
+                    // The product of two values and the half always fit
+                    let product = self as $wide * rhs as $wide;
+                    let rounded = (product + (1 << (N - 1))) >> N;
+                    rounded.clamp($ty::MIN as $wide, $ty::MAX as $wide) as $ty
+                }
+
+                #[inline(always)]
+                #[track_caller]
+                fn q_div<const N: u32>(self, rhs: Self) -> Self {
+                    let () = Fraction::<N, { $ty::BITS }>::CHECK;
+                    if rhs == 0 {
+                        panic!("attempt to divide by zero");
+                    }
+                    let (dividend, divisor) = ((self as $wide) << N, rhs as $wide);
+                    let half = divisor.abs() / 2;
+                    let rounded = if dividend < 0 {
+                        (dividend - half) / divisor
+                    } else {
+                        (dividend + half) / divisor
+                    };
+                    rounded.clamp($ty::MIN as $wide, $ty::MAX as $wide) as $ty
+                }
+
+                #[inline(always)]
+                fn saturate(raw: i128) -> Self {
+                    raw.clamp($ty::MIN as i128, $ty::MAX as i128) as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed!(i8 => i32, i16 => i32, i32 => i64, i64 => i128);
+
+/// The `N` fractional bits of a Q format in a type of `BITS` bits.
+struct Fraction<const N: u32, const BITS: u32>;
+
+impl<const N: u32, const BITS: u32> Fraction<N, BITS> {
+    /// Fails to compile if the Q format does not fit in the type with its sign.
+    /// An associated constant instead of a `const` block, which needs Rust
+    /// 1.79.
+    const CHECK: () = assert!(
+        N >= 1 && N < BITS,
+        "the Q format of qformat! has more fractional bits than the type"
+    );
+}
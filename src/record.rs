@@ -0,0 +1,136 @@
+//! The operation log of [`record!`](crate::record), which records each
+//! operation of an expression as a [`Step`], to reconstruct how a value was
+//! derived, e.g. in simulations and replay debugging.
+//!
+//! The steps are pushed into a [`Recorder`], which is implemented for `Vec`
+//! with the `std` feature, and can be implemented for other buffers, like the
+//! ring buffers of firmware.
+//!
+//! ```rust
+//! use arithmetic_mode::record::{Recorder, Step};
+//! use arithmetic_mode::{record, Operation};
+//!
+//! /// The last steps, like in the ring buffer of firmware.
+//! #[derive(Default)]
+//! struct Last {
+//!     steps: [Option<Step>; 4],
+//!     next: usize,
+//! }
+//!
+//! impl Recorder for Last {
+//!     fn record(&mut self, step: Step) {
+//!         self.steps[self.next % 4] = Some(step);
+//!         self.next += 1;
+//!     }
+//! }
+//!
+//! let mut last = Last::default();
+//! let (a, b, c) = (200_u8, 2_u8, 7_u8);
+//! assert_eq!(151, record! { &mut last; [wrapping] a * b + c });
+//!
+//! let [Some(mul), Some(add), ..] = last.steps else { unreachable!() };
+//! assert_eq!(Operation::Mul, mul.operation());
+//! assert_eq!(("200", "2"), mul.operands());
+//! assert_eq!("144", mul.result());
+//! assert!(mul.overflowed());
+//! assert_eq!("a * b + c", add.expression());
+//! assert!(!add.overflowed());
+//! ```
+
+use core::fmt::{self, Debug, Display};
+
+use crate::error::Operand;
+use crate::{Location, Operation};
+
+/// An operation of [`record!`](crate::record), with its operands and result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    operation: Operation,
+    expression: &'static str,
+    location: &'static Location,
+    operands: [Operand; 2],
+    result: Operand,
+    overflowed: bool,
+}
+
+impl Step {
+    #[doc(hidden)]
+    pub fn __new(
+        operation: Operation,
+        expression: &'static str,
+        location: &'static Location,
+        left: &dyn Debug,
+        right: &dyn Debug,
+        result: &dyn Debug,
+        overflowed: bool,
+    ) -> Self {
+        Step {
+            operation,
+            expression,
+            location,
+            operands: [Operand::new(left), Operand::new(right)],
+            result: Operand::new(result),
+            overflowed,
+        }
+    }
+
+    /// The operation, like [`Operation::Add`].
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// The source code of the operation, like `a * b`.
+    pub fn expression(&self) -> &'static str {
+        self.expression
+    }
+
+    /// The location of the operator in the source code.
+    pub fn location(&self) -> &'static Location {
+        self.location
+    }
+
+    /// The `Debug` representations of the left and right operands.
+    pub fn operands(&self) -> (&str, &str) {
+        (self.operands[0].as_str(), self.operands[1].as_str())
+    }
+
+    /// The `Debug` representation of the result in the mode, like `144` in
+    /// `wrapping` mode or `None` in `checked` mode.
+    pub fn result(&self) -> &str {
+        self.result.as_str()
+    }
+
+    /// Whether the operation overflowed or divided by zero, whatever the mode
+    /// made of it.
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (left, right) = self.operands();
+        write!(
+            f,
+            "{}: `{}` with operands {left} and {right} = {}",
+            self.location, self.expression, self.result
+        )?;
+        if self.overflowed {
+            f.write_str(" (overflowed)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A buffer that [`record!`](crate::record) pushes the steps into.
+pub trait Recorder {
+    /// Records the `step` of an operation, after the steps of its operands.
+    fn record(&mut self, step: Step);
+}
+
+#[cfg(feature = "std")]
+impl Recorder for std::vec::Vec<Step> {
+    fn record(&mut self, step: Step) {
+        self.push(step);
+    }
+}
@@ -0,0 +1,47 @@
+//! The output of [`trace!`](crate::trace), which prints each operation with its
+//! operands and result, like `200 * 2 = 144`.
+//!
+//! With the `std` feature, the operations are printed to stderr, unless a hook
+//! is set. Without it, like on microcontrollers, nothing is printed until a
+//! hook is set, e.g. to log them over a serial port.
+//!
+//! ```rust
+//! use arithmetic_mode::trace;
+//!
+//! fn log(operation: core::fmt::Arguments<'_>) {
+//!     // E.g. to the logger of the crate
+//!     println!("trace: {operation}");
+//! }
+//!
+//! trace::set_hook(log);
+//! let (a, b) = (200_u8, 2_u8);
+//! assert_eq!(Some(207), trace! { [checked] a + b + 5 });
+//! ```
+
+use core::fmt;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// The hook that prints the operations, as a `fn(fmt::Arguments<'_>)`, or null
+/// if none is set.
+static HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Prints the operations of [`trace!`](crate::trace) with `hook` instead of
+/// the default, which is stderr with the `std` feature and nothing otherwise.
+pub fn set_hook(hook: fn(fmt::Arguments<'_>)) {
+    HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+/// Prints an operation of `trace!`.
+#[doc(hidden)]
+pub fn __trace(operation: fmt::Arguments<'_>) {
+    let hook = HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: The hook is only ever set from a `fn(fmt::Arguments<'_>)`
+        let hook: fn(fmt::Arguments<'_>) = unsafe { core::mem::transmute(hook) };
+        hook(operation);
+    } else {
+        #[cfg(feature = "std")]
+        std::eprintln!("{operation}");
+    }
+}
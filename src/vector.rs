@@ -0,0 +1,97 @@
+//! The [`Integer`] methods of the integer vectors of `glam`, like `IVec2` and
+//! `UVec3`, with the `glam` feature. Each operation is applied to the
+//! elements, and the checked operations fail if any element overflows. Both
+//! operands are vectors, so scalars are given with `IVec2::splat(x)`, except
+//! for the shift amounts, which shift every element.
+
+use core::array;
+
+use crate::integer::{Integer, Operand};
+
+macro_rules! impl_vector {
+    ($($vec:ident: [$scalar:ident; $n:literal]),* $(,)?) => {
+        $(
+            impl Integer for ::glam::$vec {
+                impl_vector!(@checked [$scalar; $n] {
+                    checked_add(Self),
+                    checked_sub(Self),
+                    checked_mul(Self),
+                    checked_div(Self),
+                    checked_rem(Self),
+                    checked_shl(u32),
+                    checked_shr(u32),
+                });
+                impl_vector!(@map [$scalar; $n] {
+                    wrapping_add(Self),
+                    wrapping_sub(Self),
+                    wrapping_mul(Self),
+                    wrapping_div(Self),
+                    wrapping_rem(Self),
+                    wrapping_shl(u32),
+                    wrapping_shr(u32),
+                    saturating_add(Self),
+                    saturating_sub(Self),
+                    saturating_mul(Self),
+                    saturating_div(Self),
+                });
+            }
+
+            impl Operand for ::glam::$vec {
+                type Integer = ::glam::$vec;
+
+                #[inline(always)]
+                fn integer(self) -> ::glam::$vec {
+                    self
+                }
+            }
+
+            impl Operand for &::glam::$vec {
+                type Integer = ::glam::$vec;
+
+                #[inline(always)]
+                fn integer(self) -> ::glam::$vec {
+                    *self
+                }
+            }
+        )*
+    };
+    (@checked [$scalar:ident; $n:literal] { $($method:ident($rhs:ident),)* }) => {
+        $(
+            #[inline(always)]
+            fn $method(self, rhs: $rhs) -> Option<Self> {
+                let (a, b) = (self.to_array(), impl_vector!(@elements rhs: $rhs; $n));
+                let mut result = a;
+                for i in 0..$n {
+                    result[i] = <$scalar>::$method(a[i], b[i])?;
+                }
+                Some(Self::from_array(result))
+            }
+        )*
+    };
+    (@map [$scalar:ident; $n:literal] { $($method:ident($rhs:ident),)* }) => {
+        $(
+            #[inline(always)]
+            #[track_caller]
+            fn $method(self, rhs: $rhs) -> Self {
+                let (a, b) = (self.to_array(), impl_vector!(@elements rhs: $rhs; $n));
+                Self::from_array(array::from_fn(|i| <$scalar>::$method(a[i], b[i])))
+            }
+        )*
+    };
+    // The elements of the right operand, which are all the same shift amount
+    // for the shifts
+    (@elements $rhs:ident: Self; $n:literal) => { $rhs.to_array() };
+    (@elements $rhs:ident: u32; $n:literal) => { [$rhs; $n] };
+}
+
+impl_vector!(
+    I8Vec2: [i8; 2], I8Vec3: [i8; 3], I8Vec4: [i8; 4],
+    U8Vec2: [u8; 2], U8Vec3: [u8; 3], U8Vec4: [u8; 4],
+    I16Vec2: [i16; 2], I16Vec3: [i16; 3], I16Vec4: [i16; 4],
+    U16Vec2: [u16; 2], U16Vec3: [u16; 3], U16Vec4: [u16; 4],
+    IVec2: [i32; 2], IVec3: [i32; 3], IVec4: [i32; 4],
+    UVec2: [u32; 2], UVec3: [u32; 3], UVec4: [u32; 4],
+    I64Vec2: [i64; 2], I64Vec3: [i64; 3], I64Vec4: [i64; 4],
+    U64Vec2: [u64; 2], U64Vec3: [u64; 3], U64Vec4: [u64; 4],
+    USizeVec2: [usize; 2], USizeVec3: [usize; 3], USizeVec4: [usize; 4],
+);
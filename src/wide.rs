@@ -0,0 +1,139 @@
+//! The wider integer types that [`dot_product!`](crate::dot_product) and
+//! [`mul_div!`](crate::mul_div) compute in, so that only their result is
+//! narrowed back to the type of the operands.
+
+use crate::mul_div::Rounding;
+
+/// An integer type whose products are computed in the wider `Wide`, which
+/// holds the product of any two values and the sums of many of them.
+pub trait Widen: Copy + Default {
+    /// The wider type, like `u32` for `u8`.
+    type Wide: Copy + Default;
+
+    /// The value in the wider type.
+    fn widen(self) -> Self::Wide;
+
+    /// The exact product of `self` and `rhs`.
+    fn multiply(self, rhs: Self) -> Self::Wide;
+
+    /// The quotient of `wide` and `divisor` rounded with `rounding`, or `None`
+    /// if `divisor` is zero.
+    fn divide(wide: Self::Wide, divisor: Self, rounding: Rounding) -> Option<Self::Wide>;
+
+    /// The product of `self` and `rhs` added to `acc`, saturating at the
+    /// bounds of the accumulator.
+    fn multiply_add(self, rhs: Self, acc: Self::Wide) -> Self::Wide;
+
+    /// The product of `self` and `rhs` added to `acc`, wrapping around at the
+    /// bounds of the type.
+    fn wrapping_multiply_add(self, rhs: Self, acc: Self) -> Self;
+
+    /// The `wide` value, if it fits in the type.
+    fn narrow(wide: Self::Wide) -> Option<Self>;
+
+    /// The `wide` value, saturated at the bounds of the type.
+    fn saturate(wide: Self::Wide) -> Self;
+
+    /// The `wide` value, wrapped around at the bounds of the type.
+    fn truncate(wide: Self::Wide) -> Self;
+}
+
+macro_rules! impl_widen {
+    ($($ty:ident => $wide:ident),* ; $divide:ident) => {
+        $(
+            impl Widen for $ty {
+                type Wide = $wide;
+
+                #[inline(always)]
+                fn widen(self) -> $wide {
+                    self as $wide
+                }
+
+                #[inline(always)]
+                fn multiply(self, rhs: Self) -> $wide {
+                    // The product of two values always fits
+                    self as $wide * rhs as $wide
+                }
+
+                #[inline(always)]
+                fn divide(wide: $wide, divisor: Self, rounding: Rounding) -> Option<$wide> {
+                    $divide!(wide, divisor as $wide, rounding)
+                }
+
+                #[inline(always)]
+                fn multiply_add(self, rhs: Self, acc: $wide) -> $wide {
+                    acc.saturating_add(self.multiply(rhs))
+                }
+
+                #[inline(always)]
+                fn wrapping_multiply_add(self, rhs: Self, acc: Self) -> Self {
+                    acc.wrapping_add(self.wrapping_mul(rhs))
+                }
+
+                #[inline(always)]
+                fn narrow(wide: $wide) -> Option<Self> {
+                    $ty::try_from(wide).ok()
+                }
+
+                #[inline(always)]
+                fn saturate(wide: $wide) -> Self {
+                    wide.clamp($ty::MIN as $wide, $ty::MAX as $wide) as $ty
+                }
+
+                #[inline(always)]
+                fn truncate(wide: $wide) -> Self {
+                    wide as $ty
+                }
+            }
+        )*
+    };
+}
+
+/// The quotient of unsigned integers, rounded with `rounding`.
+macro_rules! divide_unsigned {
+    ($dividend:expr, $divisor:expr, $rounding:expr) => {{
+        let (dividend, divisor) = ($dividend, $divisor);
+        if divisor == 0 {
+            return None;
+        }
+        let (quotient, remainder) = (dividend / divisor, dividend % divisor);
+        let round_up = match $rounding {
+            Rounding::Down | Rounding::Floor => false,
+            Rounding::Up | Rounding::Ceil => remainder != 0,
+            // Written so that it cannot overflow, like `2 * remainder >= divisor`
+            Rounding::Nearest => remainder >= divisor - remainder,
+        };
+        Some(if round_up { quotient + 1 } else { quotient })
+    }};
+}
+
+/// The quotient of signed integers, rounded with `rounding`.
+macro_rules! divide_signed {
+    ($dividend:expr, $divisor:expr, $rounding:expr) => {{
+        let (dividend, divisor) = ($dividend, $divisor);
+        if divisor == 0 {
+            return None;
+        }
+        // The product of two values is never `MIN`, so this cannot overflow
+        let (quotient, remainder) = (dividend / divisor, dividend % divisor);
+        let negative = (remainder < 0) != (divisor < 0);
+        let away_from_zero = remainder != 0
+            && match $rounding {
+                Rounding::Down => false,
+                Rounding::Up => true,
+                Rounding::Floor => negative,
+                Rounding::Ceil => !negative,
+                Rounding::Nearest => {
+                    remainder.unsigned_abs() >= divisor.unsigned_abs() - remainder.unsigned_abs()
+                }
+            };
+        Some(match (away_from_zero, negative) {
+            (false, _) => quotient,
+            (true, false) => quotient + 1,
+            (true, true) => quotient - 1,
+        })
+    }};
+}
+
+impl_widen!(u8 => u32, u16 => u64, u32 => u128, u64 => u128, usize => u128; divide_unsigned);
+impl_widen!(i8 => i32, i16 => i64, i32 => i128, i64 => i128, isize => i128; divide_signed);
@@ -0,0 +1,46 @@
+//! Checks that the overflows abort the program with the `abort` feature,
+//! with `cargo test --features abort --test abort`. The test runs this binary
+//! again to overflow in a child process, since the abort cannot be caught.
+//! The signals are the ones of x86, where the abort of `nightly` is `ud2`.
+#![cfg(all(
+    feature = "abort",
+    unix,
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+
+use arithmetic_mode::panicking;
+use std::hint::black_box;
+use std::os::unix::process::ExitStatusExt;
+use std::process::Command;
+
+/// The environment variable making [`test_child_overflow`] overflow.
+const CHILD: &str = "ARITHMETIC_MODE_ABORT_CHILD";
+
+/// The signal terminating the child: `SIGABRT` from `std::process::abort`,
+/// and otherwise `SIGILL` from `ud2`.
+const SIGNAL: i32 = if cfg!(all(feature = "std", not(feature = "nightly"))) {
+    6
+} else {
+    4
+};
+
+/// Overflows in the child process of [`test_abort`].
+#[test]
+fn test_child_overflow() {
+    if std::env::var_os(CHILD).is_some() {
+        let x = black_box(255_u8);
+        black_box(panicking! { x + 1_u8 });
+    }
+}
+
+#[test]
+fn test_abort() {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "test_child_overflow", "--nocapture"])
+        .env(CHILD, "1")
+        .output()
+        .unwrap();
+    assert_eq!(Some(SIGNAL), output.status.signal(), "{output:?}");
+    // No panic message is formatted
+    assert_eq!("", String::from_utf8_lossy(&output.stderr));
+}
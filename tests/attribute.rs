@@ -0,0 +1,700 @@
+#![no_std]
+
+use arithmetic_mode::{
+    arithmetic_mode, checked_fn, closure, deny_unchecked_arithmetic, stmt, try_fn, ArithmeticError,
+    Operation,
+};
+
+#[arithmetic_mode(wrapping)]
+fn wrapping_sum(values: &[u8]) -> u8 {
+    let mut sum = 0_u8;
+    for value in values {
+        sum += value;
+    }
+    sum
+}
+
+#[arithmetic_mode(saturating)]
+fn saturating_scale(values: &[u8], factor: u8) -> [u8; 2] {
+    let scale = |v: u8| v * factor + 1;
+    [scale(values[0]), scale(values[values.len() - 1])]
+}
+
+#[arithmetic_mode(panicking)]
+fn panicking_nested(x: u8) -> u8 {
+    if x > 10 {
+        let y = { x * 2 };
+        (y - 1).min(x + x)
+    } else {
+        match x {
+            0 => u8::MAX + x,
+            _ => x - 1,
+        }
+    }
+}
+
+#[arithmetic_mode(checked)]
+fn checked_mul_add(a: u8, b: u8, c: u8) -> Option<u8> {
+    a * b + c
+}
+
+#[arithmetic_mode(try_checked)]
+fn try_checked_mul_add(a: u8, b: u8, c: u8) -> Result<u8, ArithmeticError> {
+    let product = (a * b)?;
+    product + c
+}
+
+#[arithmetic_mode(verified)]
+fn verified_area(width: u8, height: u8) -> u8 {
+    width * height
+}
+
+#[arithmetic_mode(add = "wrapping", mul = "saturating", div = "panicking")]
+fn operator_modes(a: u8, b: u8, c: u8) -> u8 {
+    let mut sum = a * b + c / 2;
+    sum += c;
+    sum - 1
+}
+
+#[arithmetic_mode(wrapping, mul = "checked")]
+fn checked_product(a: u8, b: u8) -> Option<u8> {
+    a * (b + 255)
+}
+
+#[arithmetic_mode(checked, audit)]
+#[allow(deprecated)] // The audit notes are reported as deprecation warnings
+fn audited_mul_add(a: u8, b: u8, c: u8) -> Option<u8> {
+    #[arithmetic_mode(skip)]
+    let sum = a + b;
+    sum * c + 1
+}
+
+#[test]
+fn test_wrapping_function() {
+    assert_eq!(4, wrapping_sum(&[200, 30, 30]));
+}
+
+#[test]
+fn test_saturating_function() {
+    assert_eq!([255, 5], saturating_scale(&[200, 2], 2));
+}
+
+#[test]
+fn test_panicking_function() {
+    assert_eq!(39, panicking_nested(20));
+    assert_eq!(255, panicking_nested(0));
+    assert_eq!(4, panicking_nested(5));
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic]
+fn test_panicking_function_overflow() {
+    panicking_nested(200);
+}
+
+#[test]
+fn test_verified_function() {
+    assert_eq!(255, verified_area(15, 17));
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic(expected = "attempt to multiply with overflow")]
+fn test_verified_function_overflow() {
+    verified_area(16, 16);
+}
+
+#[test]
+fn test_operator_modes() {
+    // 255 saturated, + 5 wrapped, + 10 wrapped
+    assert_eq!(13, operator_modes(100, 100, 10));
+    assert_eq!(Some(100), checked_product(100, 2));
+    assert_eq!(None, checked_product(100, 4));
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic(expected = "attempt to subtract with overflow")]
+fn test_operator_modes_overflow() {
+    operator_modes(0, 0, 0);
+}
+
+#[test]
+fn test_audited_function() {
+    assert_eq!(Some(16), audited_mul_add(2, 3, 3));
+    assert_eq!(None, audited_mul_add(2, 3, 100));
+}
+
+#[test]
+fn test_checked_function() {
+    assert_eq!(Some(7), checked_mul_add(2, 3, 1));
+    assert_eq!(None, checked_mul_add(20, 30, 1));
+}
+
+#[test]
+fn test_try_checked_function() {
+    assert_eq!(Ok(7), try_checked_mul_add(2, 3, 1));
+    assert_eq!(
+        Err(Operation::Mul),
+        try_checked_mul_add(20, 30, 1).map_err(|e| e.operation())
+    );
+    assert_eq!(
+        Err(Operation::Add),
+        try_checked_mul_add(1, 255, 1).map_err(|e| e.operation())
+    );
+}
+
+#[derive(Debug, PartialEq)]
+struct Meters(u32);
+
+#[arithmetic_mode(saturating)]
+impl Meters {
+    const MAX_STEP: u32 = u32::MAX / 2 + 1;
+
+    fn add(&self, other: &Meters) -> Meters {
+        Meters(self.0 + other.0)
+    }
+
+    fn step(&self, steps: u32) -> Meters {
+        Meters(self.0 + steps * Self::MAX_STEP)
+    }
+
+    #[arithmetic_mode(checked)]
+    fn checked_sub(&self, other: &Meters) -> Option<Meters> {
+        (self.0 - other.0).map(Meters)
+    }
+}
+
+#[test]
+fn test_saturating_impl() {
+    assert_eq!(Meters(u32::MAX), Meters(u32::MAX - 1).add(&Meters(2)));
+    assert_eq!(Meters(u32::MAX), Meters(0).step(2));
+    assert_eq!(None, Meters(1).checked_sub(&Meters(2)));
+    assert_eq!(Some(Meters(1)), Meters(2).checked_sub(&Meters(1)));
+}
+
+#[arithmetic_mode(checked)]
+mod fixed_point {
+    pub const SCALE: u32 = 1000;
+
+    pub fn from_int(value: u32) -> Option<u32> {
+        value * SCALE
+    }
+
+    pub mod ops {
+        pub fn mul(a: u32, b: u32) -> Option<u32> {
+            a * b / super::SCALE
+        }
+    }
+
+    #[arithmetic_mode::checked_fn]
+    pub fn square(a: u32) -> u32 {
+        a * a
+    }
+
+    #[arithmetic_mode::arithmetic_mode(wrapping)]
+    pub mod hashing {
+        pub fn mix(a: u32, b: u32) -> u32 {
+            a * 31 + b
+        }
+    }
+}
+
+#[test]
+fn test_checked_module() {
+    assert_eq!(Some(3000), fixed_point::from_int(3));
+    assert_eq!(None, fixed_point::from_int(u32::MAX));
+    assert_eq!(Some(6000), fixed_point::ops::mul(2000, 3000));
+    assert_eq!(None, fixed_point::ops::mul(2_000_000, 3_000_000));
+    assert_eq!(1, fixed_point::hashing::mix(u32::MAX, 32));
+    assert_eq!(Some(9), fixed_point::square(3));
+    assert_eq!(None, fixed_point::square(u32::MAX));
+}
+
+#[arithmetic_mode(panicking)]
+trait Counter {
+    fn count(&self) -> u8;
+
+    fn next(&self) -> u8 {
+        self.count() + 1
+    }
+
+    #[arithmetic_mode(wrapping)]
+    fn wrapping_next(&self) -> u8 {
+        self.count() + 1
+    }
+}
+
+struct FullCounter;
+
+impl Counter for FullCounter {
+    fn count(&self) -> u8 {
+        u8::MAX
+    }
+}
+
+struct EmptyCounter;
+
+impl Counter for EmptyCounter {
+    fn count(&self) -> u8 {
+        0
+    }
+}
+
+#[test]
+fn test_panicking_trait() {
+    assert_eq!(1, EmptyCounter.next());
+    assert_eq!(0, FullCounter.wrapping_next());
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic]
+fn test_panicking_trait_overflow() {
+    FullCounter.next();
+}
+
+const ROWS: usize = 16;
+const COLS: usize = 8;
+const HEADER: usize = 4;
+
+#[arithmetic_mode(panicking)]
+const TABLE_SIZE: usize = ROWS * COLS + HEADER;
+
+#[arithmetic_mode(wrapping)]
+static WRAPPED: u8 = (ROWS * COLS) as u8 * 2 + 1;
+
+#[arithmetic_mode(saturating)]
+const fn saturating_area(width: u16, height: u16) -> u16 {
+    let mut area = width * height;
+    area += HEADER as u16;
+    area
+}
+
+struct Table;
+
+#[arithmetic_mode(panicking)]
+impl Table {
+    const CELLS: usize = ROWS * COLS;
+    const BYTES: [u8; Self::CELLS * 2] = [0; ROWS * COLS * 2];
+}
+
+#[test]
+fn test_const_items() {
+    assert_eq!(132, TABLE_SIZE);
+    assert_eq!(1, WRAPPED);
+    assert_eq!(u16::MAX, saturating_area(1000, 1000));
+    assert_eq!(24, saturating_area(4, 5));
+    assert_eq!(256, Table::BYTES.len());
+    const AREA: u16 = saturating_area(300, 300);
+    assert_eq!(u16::MAX, AREA);
+}
+
+#[arithmetic_mode(panicking)]
+const fn panicking_const_area(width: u8, height: u8) -> u8 {
+    width * height
+}
+
+#[test]
+#[should_panic(expected = "attempt to multiply with overflow: `width * height`")]
+fn test_panicking_const_fn_message() {
+    panicking_const_area(16, 16);
+}
+
+#[arithmetic_mode(panicking)]
+const fn panicking_const_ratio(a: u8, b: u8) -> u8 {
+    a / b
+}
+
+#[test]
+#[should_panic(expected = "attempt to divide by zero: `a / b`")]
+fn test_panicking_const_fn_division_by_zero() {
+    panicking_const_ratio(1, 0);
+}
+
+#[checked_fn]
+fn checked_average(values: &[u32]) -> u32 {
+    if values.is_empty() {
+        return 0;
+    }
+    let mut sum = 0_u32;
+    for value in values {
+        sum += value;
+    }
+    sum / values.len() as u32
+}
+
+#[checked_fn]
+fn checked_mul(a: i8, b: i8) -> i8 {
+    a * b
+}
+
+#[checked_fn]
+fn checked_unit(values: &mut [u8]) {
+    for value in values.iter_mut() {
+        *value = *value * 2;
+    }
+}
+
+#[checked_fn]
+fn checked_filter(taps: &[i16], samples: &[i16]) -> i16 {
+    let mut acc = 0_i16;
+    for (tap, sample) in taps.iter().zip(samples) {
+        acc += tap * sample;
+    }
+    acc
+}
+
+#[arithmetic_mode(saturating)]
+fn saturating_filter(taps: &[i16], samples: &[i16]) -> i16 {
+    let mut acc = 0_i16;
+    for (tap, sample) in taps.iter().zip(samples) {
+        acc += tap * sample;
+    }
+    acc
+}
+
+#[test]
+fn test_checked_fn() {
+    assert_eq!(Some(2), checked_average(&[1, 2, 3]));
+    assert_eq!(Some(0), checked_average(&[]));
+    assert_eq!(None, checked_average(&[u32::MAX, 1]));
+    assert_eq!(Some(-128), checked_mul(-64, 2));
+    assert_eq!(None, checked_mul(-128, -1));
+    let mut values = [1, 2, 200];
+    assert_eq!(None, checked_unit(&mut values));
+    assert_eq!([2, 4, 200], values);
+    assert_eq!(Some(()), checked_unit(&mut values[..2]));
+}
+
+#[test]
+fn test_multiply_accumulate() {
+    // `300 * 200` overflows `i16` on its own, but not once accumulated
+    let taps = [300, 300];
+    assert_eq!(Some(30_000), checked_filter(&taps, &[-100, 200]));
+    assert_eq!(None, checked_filter(&taps, &[100, 100]));
+    assert_eq!(30_000, saturating_filter(&taps, &[-100, 200]));
+    assert_eq!(i16::MAX, saturating_filter(&taps, &[100, 100]));
+}
+
+#[derive(Debug, PartialEq)]
+enum SumError {
+    Empty,
+    Overflow(ArithmeticError),
+}
+
+impl From<ArithmeticError> for SumError {
+    fn from(e: ArithmeticError) -> Self {
+        SumError::Overflow(e)
+    }
+}
+
+#[try_fn(SumError)]
+fn try_sum(values: &[i16]) -> i16 {
+    let (first, rest) = values.split_first().ok_or(SumError::Empty)?;
+    let mut sum = *first;
+    for value in rest {
+        sum += value;
+    }
+    sum
+}
+
+#[try_fn]
+fn try_shift(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        return a;
+    }
+    (a << b) - 1
+}
+
+#[test]
+fn test_try_fn() {
+    assert_eq!(Ok(6), try_sum(&[1, 2, 3]));
+    assert_eq!(Err(SumError::Empty), try_sum(&[]));
+    assert!(matches!(
+        try_sum(&[i16::MIN, -1]),
+        Err(SumError::Overflow(e)) if e.operation() == Operation::Add
+    ));
+    assert_eq!(Ok(7), try_shift(1, 3));
+    assert_eq!(Ok(0), try_shift(0, 0));
+    assert_eq!(
+        Err(Operation::Sub),
+        try_shift(0, 1).map_err(|e| e.operation())
+    );
+    assert_eq!(
+        Err(Operation::Shl),
+        try_shift(1, 32).map_err(|e| e.operation())
+    );
+}
+
+#[arithmetic_mode(checked)]
+mod skip {
+    use arithmetic_mode::arithmetic_mode;
+
+    pub fn statement(a: u32, b: u32) -> Option<u32> {
+        #[arithmetic_mode(skip)]
+        let sum: u32 = a + b;
+        sum * 2
+    }
+
+    pub fn expression(a: u32, b: u32) -> u32 {
+        #[arithmetic_mode(skip)]
+        {
+            a * 31 + b
+        }
+    }
+
+    #[arithmetic_mode(skip)]
+    pub fn item(a: u32) -> u32 {
+        a - 1
+    }
+}
+
+#[test]
+fn test_skip() {
+    assert_eq!(Some(6), skip::statement(1, 2));
+    assert_eq!(None, skip::statement(u32::MAX / 2, 1));
+    assert_eq!(33, skip::expression(1, 2));
+    assert_eq!(1, skip::item(2));
+}
+
+#[deny_unchecked_arithmetic]
+mod enforced {
+    use arithmetic_mode::{arithmetic_mode, checked, wrapping};
+
+    pub fn mix(a: u32, b: u32) -> u32 {
+        wrapping! { a * 31 + b }
+    }
+
+    pub fn average(a: u32, b: u32) -> Option<u32> {
+        checked! { (a + b) / 2 }
+    }
+
+    #[arithmetic_mode(saturating)]
+    pub fn sum(a: u8, b: u8) -> u8 {
+        a + b
+    }
+
+    pub fn scale(x: f32) -> f32 {
+        #[arithmetic_mode(skip)]
+        {
+            x * 2.0
+        }
+    }
+}
+
+#[test]
+fn test_deny_unchecked_arithmetic() {
+    assert_eq!(33, enforced::mix(1, 2));
+    assert_eq!(None, enforced::average(u32::MAX, 1));
+    assert_eq!(255, enforced::sum(200, 100));
+    assert_eq!(3.0, enforced::scale(1.5));
+}
+
+#[arithmetic_mode::enforce(default = "checked", allow = ["wrapping", "skip"])]
+mod policy {
+    use arithmetic_mode::{checked, checked_fn, wrapping};
+
+    pub fn average(a: u32, b: u32) -> Option<u32> {
+        checked! { (a + b) / 2 }
+    }
+
+    #[checked_fn]
+    pub fn area(width: u32, height: u32) -> u32 {
+        width * height
+    }
+
+    pub fn mix(a: u32, b: u32) -> u32 {
+        wrapping! { a * 31 + b }
+    }
+
+    pub fn len(items: &[u8]) -> usize {
+        #[arithmetic_mode(skip)]
+        {
+            items.len() + 1
+        }
+    }
+}
+
+#[test]
+fn test_enforce() {
+    assert_eq!(Some(2), policy::average(1, 3));
+    assert_eq!(None, policy::area(u32::MAX, 2));
+    assert_eq!(33, policy::mix(1, 2));
+    assert_eq!(3, policy::len(&[1, 2]));
+}
+
+#[arithmetic_mode(checked)]
+const fn checked_const_area(width: u8, height: u8) -> Option<u8> {
+    width * height + 1
+}
+
+const CHECKED_AREA: Option<u8> = checked_const_area(16, 16);
+
+#[test]
+fn test_checked_const_fn() {
+    assert_eq!(None, CHECKED_AREA);
+    assert_eq!(Some(13), checked_const_area(3, 4));
+}
+
+struct Sequence(core::cell::Cell<u32>);
+
+impl Sequence {
+    fn next(&self, value: u8) -> u8 {
+        self.0.set(self.0.get() * 10 + u32::from(value));
+        value
+    }
+
+    fn take(&self) -> u32 {
+        self.0.replace(0)
+    }
+}
+
+#[arithmetic_mode(panicking)]
+fn panicking_sequence(s: &Sequence) -> u8 {
+    s.next(1) + s.next(2) * s.next(3)
+}
+
+#[arithmetic_mode(wrapping)]
+fn wrapping_sequence(s: &Sequence) -> u8 {
+    s.next(1) + s.next(2) * s.next(3)
+}
+
+#[arithmetic_mode(saturating)]
+fn saturating_sequence(s: &Sequence) -> u8 {
+    s.next(1) + s.next(2) * s.next(3)
+}
+
+#[arithmetic_mode(checked)]
+fn checked_sequence(s: &Sequence) -> Option<u8> {
+    s.next(1) + s.next(2) * s.next(3)
+}
+
+#[arithmetic_mode(try_checked)]
+fn try_checked_sequence(s: &Sequence) -> Result<u8, ArithmeticError> {
+    s.next(1) + s.next(2) * s.next(3)
+}
+
+#[test]
+fn test_evaluation_order() {
+    let s = Sequence(core::cell::Cell::new(0));
+    assert_eq!(7, panicking_sequence(&s));
+    assert_eq!(123, s.take());
+    assert_eq!(7, wrapping_sequence(&s));
+    assert_eq!(123, s.take());
+    assert_eq!(7, saturating_sequence(&s));
+    assert_eq!(123, s.take());
+    assert_eq!(Some(7), checked_sequence(&s));
+    assert_eq!(123, s.take());
+    assert_eq!(Ok(7), try_checked_sequence(&s));
+    assert_eq!(123, s.take());
+}
+
+#[test]
+fn test_closure() {
+    let mut hashes = [100_u8, 200, 250];
+    hashes
+        .iter_mut()
+        .for_each(closure!(wrapping, |b| *b = *b * 31 + 7));
+    assert_eq!([35, 63, 77], hashes);
+    let scale = closure!(saturating, |v: u8, factor: u8| v * factor + 1);
+    assert_eq!(255, scale(100, 3));
+    let sum = |values: &[u8]| {
+        values
+            .iter()
+            .try_fold(0_u8, closure!(checked, |acc, &b| acc + b))
+    };
+    assert_eq!(Some(250), sum(&[200, 50]));
+    assert_eq!(None, sum(&[200, 100]));
+    let area = closure!(try_checked, |w: u8, h: u8| w * h);
+    assert_eq!(Operation::Mul, area(16, 16).unwrap_err().operation());
+    let mixed = closure!(saturating, rem = "wrapping", |hash: u8, count: u8| hash
+        % 10
+        + count);
+    assert_eq!(255, mixed(27, 250));
+}
+
+fn stmt_total(a: u8, b: u8, c: u8) -> (Option<u8>, u8) {
+    let count = a + 1;
+    stmt! { checked: let total = count + b * c; }
+    let mut wrapped = 1;
+    if c > 0 {
+        stmt! { wrapping: wrapped = count * b * c; }
+    }
+    (total, wrapped)
+}
+
+fn stmt_area(width: u32, height: u32) -> u32 {
+    stmt!(try_checked: let Ok(area) = width * height else { return u32::MAX };);
+    area
+}
+
+#[test]
+fn test_stmt() {
+    assert_eq!((Some(13), 30), stmt_total(2, 2, 5));
+    assert_eq!((None, 176), stmt_total(2, 20, 20));
+    assert_eq!(6, stmt_area(2, 3));
+    assert_eq!(u32::MAX, stmt_area(1 << 16, 1 << 16));
+}
+
+// The policies are declared in `arithmetic-mode.toml` at the root of the crate
+#[arithmetic_mode(policy = "payments")]
+mod payments {
+    pub fn total(price: u32, quantity: u32) -> Option<u32> {
+        price * quantity
+    }
+
+    pub mod fees {
+        pub fn fee(amount: u32, bps: u32) -> u32 {
+            amount * bps / 10_000
+        }
+    }
+}
+
+#[arithmetic_mode(policy = "pixels")]
+fn pixel_offset(base: usize, pixel: u8, gain: u8) -> usize {
+    base + (pixel * gain) as usize
+}
+
+#[arithmetic_mode(policy = "hashes", mul = "saturating")]
+fn policy_hash(h: u8, b: u8) -> (u8, u8) {
+    (h * 31 + b, h - b)
+}
+
+struct Ledger(u32);
+
+// The config file is tracked next to the impl block, and in the body of the
+// items inside one
+#[arithmetic_mode(policy = "hashes")]
+impl Ledger {
+    fn mix(&self, b: u32) -> u32 {
+        self.0 * 31 + b
+    }
+}
+
+impl Ledger {
+    #[arithmetic_mode(policy = "hashes")]
+    const SEED: u32 = u32::MAX * 31;
+
+    #[arithmetic_mode(policy = "hashes")]
+    fn seeded(&self) -> u32 {
+        Self::SEED + self.0
+    }
+}
+
+#[test]
+fn test_policy() {
+    assert_eq!(Some(600), payments::total(200, 3));
+    assert_eq!(None, payments::total(u32::MAX, 2));
+    assert_eq!(60, payments::fees::fee(200_000, 3));
+    assert_eq!(u32::MAX / 10_000, payments::fees::fee(u32::MAX, 2));
+    assert_eq!(3, pixel_offset(2, 1, 1));
+    assert_eq!(254, pixel_offset(usize::MAX, 200, 2));
+    // The attribute overrides the mode of `mul`
+    assert_eq!((199, 0), policy_hash(100, 200));
+    assert_eq!(u32::MAX - 29, Ledger(u32::MAX).mix(1));
+    assert_eq!(u32::MAX - 30, Ledger(0).seeded());
+    let scale = closure!(policy = "hashes", |x: u8| x * 200);
+    assert_eq!(144, scale(2));
+}
@@ -0,0 +1,76 @@
+#![no_std]
+
+use arithmetic_mode::Checked;
+use core::ops::{Add, Mul};
+
+/// A generic computation, which the wrapper checks with plain operators.
+fn weighted<T: Copy + Add<Output = T> + Mul<Output = T>>(values: [T; 3], weight: T) -> T {
+    values[0] * weight + values[1] * weight + values[2]
+}
+
+#[test]
+fn test_operators() {
+    let a = Checked::new(200_u8);
+    assert_eq!(Checked::new(250), a + 50);
+    assert_eq!(Checked(None), a + a);
+    assert_eq!(Checked::new(100), a / 2);
+    assert_eq!(Checked(None), a / 0);
+    assert_eq!(Checked(None), a % Checked::new(0));
+    assert_eq!(Checked::new(100), a >> 1);
+    assert_eq!(Checked(None), a << 8);
+    assert_eq!(Checked::new(-5), -Checked::new(5_i32));
+    assert_eq!(Checked(None), -Checked::new(i32::MIN));
+    // An overflow stays through the operations that follow
+    assert_eq!(None, ((a * 2) / 4).get());
+}
+
+#[test]
+fn test_assign() {
+    let mut total = Checked::new(250_u8);
+    total -= 50;
+    total *= Checked::new(1);
+    assert_eq!(Some(200), total.get());
+    total += 100;
+    assert_eq!(None, total.get());
+    total -= 100;
+    assert_eq!(None, total.get());
+}
+
+#[test]
+fn test_generic() {
+    let values = [10_u8, 20, 30].map(Checked::new);
+    assert_eq!(Some(120), weighted(values, Checked::new(3)).get());
+    assert_eq!(None, weighted(values, Checked::new(10)).get());
+}
+
+#[test]
+fn test_fold() {
+    assert_eq!(
+        Some(60),
+        [10_u8, 20, 30].into_iter().sum::<Checked<u8>>().get()
+    );
+    assert_eq!(
+        None,
+        [100_u8, 100, 100].into_iter().sum::<Checked<u8>>().get()
+    );
+    let factors = [2_i16, 3, 4].map(Checked::new);
+    assert_eq!(
+        Some(24),
+        factors.into_iter().product::<Checked<i16>>().get()
+    );
+    assert_eq!(Checked::new(0_u32), Checked::default());
+    assert_eq!(Some(7), Option::from(Checked::from(7_u64)));
+}
+
+#[test]
+fn test_builder() {
+    assert_eq!(Some(126), Checked::from(20_u32).add(22).mul(3).finish());
+    assert_eq!(Some(1), Checked::from(7_u8).rem(3).finish());
+    assert_eq!(Some(-4), Checked::from(8_i64).neg().div(2).finish());
+    assert_eq!(Some(32), Checked::from(1_u16).shl(6).shr(1).finish());
+    assert_eq!(Some(243), Checked::from(3_u8).pow(5).finish());
+    assert_eq!(None, Checked::from(3_u8).pow(6).finish());
+    // An overflow stays through the operations that follow
+    assert_eq!(None, Checked::from(0_u8).sub(1).add(1).finish());
+    assert_eq!(None, Checked::from(1_i32).div(0).mul(0).finish());
+}
@@ -0,0 +1,30 @@
+#![cfg(feature = "subtle")]
+
+use arithmetic_mode::ct_checked;
+use subtle::CtOption;
+
+fn option<T>(value: CtOption<T>) -> Option<T> {
+    value.into()
+}
+
+#[test]
+fn test_ct_checked() {
+    let (a, b) = (200_u8, 2_u8);
+    assert_eq!(Some(202), option(ct_checked! { a + b }));
+    assert_eq!(None, option(ct_checked! { a * b + 1 }));
+    assert_eq!(Some(99), option(ct_checked! { a / b - 1 }));
+    assert_eq!(None, option(ct_checked! { a / (b - 2) }));
+    assert_eq!(None, option(ct_checked! { a % (b - 2) }));
+    assert_eq!(None, option(ct_checked! { b - a + 1 }));
+    assert_eq!(Some(8), option(ct_checked! { b << 2 }));
+    assert_eq!(None, option(ct_checked! { b << 8 }));
+}
+
+#[test]
+fn test_ct_checked_signed() {
+    let (min, x) = (i32::MIN, -1_i32);
+    assert_eq!(None, option(ct_checked! { min / x }));
+    assert_eq!(None, option(ct_checked! { -min + 0 }));
+    assert_eq!(Some(i32::MAX), option(ct_checked! { -(min + 1) }));
+    assert_eq!(Some(true), option(ct_checked! { x * 2 < x }));
+}
@@ -0,0 +1,60 @@
+//! Checks the `defmt` feature, with `cargo test --features defmt --test defmt`.
+//! The binaries using `defmt` have to provide its global logger and panic
+//! handler, which only this one does, so the other tests are only compiled
+//! with `cargo check --features defmt`.
+#![cfg(feature = "defmt")]
+
+use arithmetic_mode::{panicking, ArithmeticError, ErrorKind, Location, Operation};
+use std::hint::black_box;
+use std::panic;
+use std::sync::Mutex;
+
+/// The bytes written by `defmt` in all the tests.
+static WRITTEN: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+#[defmt::global_logger]
+struct Logger;
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {}
+
+    unsafe fn flush() {}
+
+    unsafe fn release() {}
+
+    unsafe fn write(bytes: &[u8]) {
+        WRITTEN.lock().unwrap().extend_from_slice(bytes);
+    }
+}
+
+defmt::timestamp!("");
+
+#[defmt::panic_handler]
+fn defmt_panic() -> ! {
+    panic!("defmt panic")
+}
+
+/// The number of bytes written by `defmt` while formatting `value`.
+fn formatted_len(value: impl defmt::Format) -> usize {
+    let before = WRITTEN.lock().unwrap().len();
+    defmt::println!("{}", value);
+    WRITTEN.lock().unwrap().len() - before
+}
+
+#[test]
+fn test_format() {
+    let location = Location::__new(file!(), line!(), column!());
+    let error = ArithmeticError::new(Operation::Div).with_kind(ErrorKind::DivisionByZero);
+    assert_ne!(0, formatted_len(error));
+    assert_ne!(0, formatted_len(Operation::Mul));
+    assert_ne!(0, formatted_len(ErrorKind::Overflow));
+    assert_ne!(0, formatted_len(location));
+}
+
+#[test]
+fn test_panic() {
+    let x = black_box(255_u8);
+    let payload = panic::catch_unwind(|| panicking! { x + 1_u8 }).unwrap_err();
+    // The panic handler of `defmt` is called instead of the one of `core`
+    assert_eq!(Some(&"defmt panic"), payload.downcast_ref::<&str>());
+}
@@ -0,0 +1,29 @@
+#![no_std]
+
+use arithmetic_mode::{differential_test, overflow_matrix};
+
+differential_test! {
+    fn mul_add(a: u8 in 0..=255, b: u8 in 0..=16) -> u8 {
+        a * b + 7
+    }
+}
+
+differential_test! {
+    fn signed_average(a: i8 in -128..=127, b: i8 in -128..=127) -> i8 {
+        (a + b) / 2
+    }
+}
+
+differential_test! {
+    fn ratio(a: u16 in 0..=1000, b: u16 in 0..=3) -> u16 {
+        a / b - 1
+    }
+}
+
+differential_test! {
+    fn negated_remainder(a: i16 in -300..=300, b: i16 in -3..=3) -> i16 {
+        -(a % b) * 100
+    }
+}
+
+overflow_matrix! { u8, i8, u16 in 0..=300, i16 in -300..=300 }
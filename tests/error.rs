@@ -0,0 +1,67 @@
+//! With the `msrv` feature, `ArithmeticError` implements `std::error::Error`
+//! instead of `core::error::Error`, so only with the `std` feature.
+#![cfg(any(feature = "std", not(feature = "msrv")))]
+
+use arithmetic_mode::{try_checked, ArithmeticError, Operation};
+
+fn parse_scaled(s: &str, scale: u32) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+    let value: u32 = s.parse()?;
+    Ok(try_checked! { value * scale + 1 }?)
+}
+
+#[test]
+fn test_boxed_error() {
+    assert_eq!(421, parse_scaled("42", 10).unwrap());
+    let error = parse_scaled("4294967295", 10).unwrap_err();
+    let error = error.downcast_ref::<ArithmeticError>().unwrap();
+    assert_eq!(Operation::Mul, error.operation());
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ParseError {
+    #[error("invalid number")]
+    Invalid(#[from] core::num::ParseIntError),
+    #[error("number too large")]
+    Overflow(#[from] ArithmeticError),
+}
+
+fn parse_sum(a: &str, b: &str) -> Result<u8, ParseError> {
+    let (a, b): (u8, u8) = (a.parse()?, b.parse()?);
+    Ok(try_checked! { a + b }?)
+}
+
+#[test]
+fn test_thiserror() {
+    use std::error::Error;
+
+    assert_eq!(3, parse_sum("1", "2").unwrap());
+    assert!(matches!(parse_sum("x", "2"), Err(ParseError::Invalid(_))));
+    let error = parse_sum("200", "100").unwrap_err();
+    assert!(matches!(error, ParseError::Overflow(_)));
+    let source = error.source().unwrap().to_string();
+    assert!(source.starts_with("attempt to add with overflow: `a + b` with operands 200 and 100"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_io_error() {
+    fn read_len(len: u16) -> std::io::Result<u16> {
+        Ok(try_checked! { len + 2 }?)
+    }
+
+    assert_eq!(3, read_len(1).unwrap());
+    let error = read_len(u16::MAX).unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+    assert_eq!(
+        "attempt to add with overflow: `len + 2` with operands 65535 and 2",
+        error.to_string().split(", at ").next().unwrap()
+    );
+
+    fn block_count(len: u64, block_size: u64) -> std::io::Result<u64> {
+        Ok(try_checked! { len / block_size }?)
+    }
+
+    assert_eq!(4, block_count(4096, 1024).unwrap());
+    let error = block_count(4096, 0).unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidInput, error.kind());
+}
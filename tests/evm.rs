@@ -0,0 +1,58 @@
+#![cfg(feature = "evm")]
+
+use arithmetic_mode::evm;
+use arithmetic_mode::evm::U256;
+
+#[test]
+fn wraps_modulo_2_256() {
+    let (a, b) = (U256::MAX, U256::from(2));
+    assert_eq!(U256::from(1), evm! { a + b });
+    assert_eq!(U256::MAX, evm! { 0 - 1 });
+    assert_eq!(U256::MAX - U256::from(1), evm! { a * b });
+    assert_eq!(U256::from(2), evm! { -a + 1 });
+}
+
+#[test]
+fn division_by_zero_is_zero() {
+    let (a, zero) = (U256::from(7), 0_u64);
+    assert_eq!(U256::ZERO, evm! { a / zero });
+    assert_eq!(U256::ZERO, evm! { a % zero });
+    assert_eq!(U256::from(3), evm! { a / 2 });
+    assert_eq!(U256::from(1), evm! { a % 3 });
+}
+
+#[test]
+fn exponentiation() {
+    let (x, e) = (U256::from(3), 4_u8);
+    assert_eq!(U256::from(81), evm! { x ** e });
+    assert_eq!(U256::from(18), evm! { 2 * x ** 2 });
+    assert_eq!(U256::from(2).pow(U256::from(81)), evm! { 2 ** x ** e });
+    assert_eq!(U256::ZERO, evm! { 2 ** 256 });
+    assert_eq!(U256::MAX, evm! { -(x ** 0) });
+}
+
+#[test]
+fn shifts() {
+    let a = U256::from(1);
+    assert_eq!(U256::from(1) << 255, evm! { a << 255 });
+    assert_eq!(U256::ZERO, evm! { a << 256 });
+    assert_eq!(U256::ZERO, evm! { (a << 255) >> 256 });
+    assert_eq!(U256::ZERO, evm! { a << (2 ** 64) });
+}
+
+#[test]
+fn large_literals() {
+    let max =
+        evm! { 115792089237316195423570985008687907853269984665640564039457584007913129639935 + 0 };
+    assert_eq!(U256::MAX, max);
+    assert_eq!(
+        U256::from(1_u128 << 127),
+        evm! { 0x8000_0000_0000_0000_0000_0000_0000_0000 + 0 }
+    );
+}
+
+#[test]
+fn comparisons() {
+    let (a, b) = (U256::from(5), 7_u32);
+    assert!(evm! { a * 2 > b && a + 2 == b });
+}
@@ -0,0 +1,90 @@
+//! Keeps the compile-time footprint of the crate in check. The macros are
+//! built before any code that uses them, so their dependencies are on the
+//! critical path of every build, and adding one should be deliberate.
+
+use std::process::Command;
+
+/// The crates that are built for a dependent of `arithmetic-mode`, besides
+/// the crates of the workspace, including the dependencies of build scripts.
+const BUDGET: &[&str] = &["proc-macro2", "quote", "syn", "unicode-ident"];
+
+/// The crates that the `deep-stack` feature adds to [`BUDGET`], whose build
+/// script compiles C and assembly with `cc`, which is why it is opt-in.
+const DEEP_STACK_BUDGET: &[&str] = &[
+    "ar_archive_writer",
+    "cc",
+    "cfg-if",
+    "find-msvc-tools",
+    "libc",
+    "memchr",
+    "object",
+    "psm",
+    "shlex",
+    "stacker",
+    "windows-link",
+    "windows-sys",
+];
+
+/// The `syn` features that the macros need, without its default features.
+const SYN_FEATURES: &[&str] = &[
+    "clone-impls",
+    "full",
+    "parsing",
+    "printing",
+    "proc-macro",
+    "visit-mut",
+];
+
+/// The packages that a dependent enabling `features` builds, with their
+/// features, like `syn v2.0.37 full,parsing`.
+fn dependency_tree(features: &str) -> String {
+    let output = Command::new(env!("CARGO"))
+        .args([
+            "tree",
+            "-p",
+            "arithmetic-mode",
+            "-e",
+            "normal,build",
+            "--features",
+            features,
+            "--target",
+            "all",
+        ])
+        .args(["--prefix", "none", "--format", "{p} {f}"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Checks that the crates built with `features` are in `budget`.
+fn assert_budget(features: &str, budget: &[&str]) {
+    let tree = dependency_tree(features);
+    for line in tree.lines() {
+        let name = line.split(' ').next().unwrap();
+        assert!(
+            name.starts_with("arithmetic-mode") || budget.contains(&name),
+            "`{name}` is not in the dependency budget"
+        );
+        if name == "syn" {
+            let features = line.split(' ').nth(2).unwrap_or_default();
+            for feature in features.split(',').filter(|f| !f.is_empty()) {
+                assert!(
+                    SYN_FEATURES.contains(&feature),
+                    "The `syn` feature `{feature}` is not in the dependency budget"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_dependency_budget() {
+    assert_budget("", BUDGET);
+    assert_budget("deep-stack", &[BUDGET, DEEP_STACK_BUDGET].concat());
+}
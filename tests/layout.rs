@@ -0,0 +1,49 @@
+//! Checks the sizes and layouts of `arithmetic_mode::layout`.
+
+use std::alloc::Layout;
+
+use arithmetic_mode::{layout, Operation};
+
+#[repr(C)]
+struct Header {
+    len: u16,
+    kind: u8,
+}
+
+#[test]
+fn test_size() {
+    assert_eq!(Ok(16 + 5 * 4), layout::size::<u32>(5, 16));
+    assert_eq!(Ok(16), layout::size::<()>(usize::MAX, 16));
+    let error = layout::size::<u32>(usize::MAX / 2, 0).unwrap_err();
+    assert_eq!(Operation::Mul, error.operation());
+    let error = layout::size::<u8>(usize::MAX, 1).unwrap_err();
+    assert_eq!(Operation::Add, error.operation());
+    const SIZE: usize = match layout::size::<u16>(8, 2) {
+        Ok(size) => size,
+        Err(_) => panic!(),
+    };
+    assert_eq!(18, SIZE);
+}
+
+#[test]
+fn test_array() {
+    assert_eq!(Layout::array::<u64>(7).ok(), layout::array::<u64>(7).ok());
+    let error = layout::array::<u64>(usize::MAX / 8 + 1).unwrap_err();
+    assert_eq!(Operation::Mul, error.operation());
+    // The size fits in `usize`, but not in `isize`
+    let error = layout::array::<u16>(usize::MAX / 2).unwrap_err();
+    assert_eq!(Operation::Conversion, error.operation());
+}
+
+#[test]
+fn test_with_header() {
+    let (layout, offset) = layout::with_header::<Header, u8>(3).unwrap();
+    assert_eq!((4, 8, 2), (offset, layout.size(), layout.align()));
+    let expected = Layout::new::<Header>().extend(Layout::array::<u32>(3).unwrap());
+    let (layout, offset) = layout::with_header::<Header, u32>(3).unwrap();
+    assert_eq!(expected.unwrap(), (layout, offset));
+    let (layout, offset) = layout::with_header::<Header, u32>(0).unwrap();
+    assert_eq!((4, 4, 4), (offset, layout.size(), layout.align()));
+    let error = layout::with_header::<Header, u8>(isize::MAX as usize).unwrap_err();
+    assert_eq!(Operation::Conversion, error.operation());
+}
@@ -0,0 +1,39 @@
+//! Checks that the panics of the expansions are reported at the operator that
+//! failed. The panic hook is global, so these tests are in their own binary.
+//! With the `abort` feature, the expansions abort instead.
+#![cfg(not(feature = "abort"))]
+
+use arithmetic_mode::{arithmetic_mode, panicking, saturating, wrapping};
+use std::fmt::Debug;
+use std::panic::{self, UnwindSafe};
+use std::sync::Mutex;
+
+static LOCATION: Mutex<Option<(u32, u32)>> = Mutex::new(None);
+
+/// The character of this file where `f` panics.
+fn panic_location<T: Debug>(f: impl FnOnce() -> T + UnwindSafe) -> char {
+    panic::set_hook(Box::new(|info| {
+        let location = info.location().unwrap();
+        *LOCATION.lock().unwrap() = Some((location.line(), location.column()));
+    }));
+    panic::catch_unwind(f).unwrap_err();
+    let _ = panic::take_hook();
+    let (line, column) = LOCATION.lock().unwrap().take().unwrap();
+    let line = include_str!("location.rs").lines().nth(line as usize - 1);
+    line.unwrap().chars().nth(column as usize - 1).unwrap()
+}
+
+#[arithmetic_mode(panicking)]
+fn area(width: u8, height: u8) -> u8 {
+    width * height
+}
+
+#[test]
+fn test_panic_location() {
+    let (x, zero) = (200_u8, 0_u8);
+    assert_eq!('*', panic_location(|| panicking! { 1_u8 + x * 2 }));
+    assert_eq!('+', panic_location(|| panicking! { "message": x + x }));
+    assert_eq!('/', panic_location(|| wrapping! { x / zero }));
+    assert_eq!('%', panic_location(|| saturating! { x % zero }));
+    assert_eq!('*', panic_location(|| area(x, x)));
+}
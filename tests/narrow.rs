@@ -0,0 +1,59 @@
+#![cfg(feature = "ux")]
+
+use arithmetic_mode::{checked, panicking, saturating, try_checked, wrapping, Operation};
+use ux::{i5, u24, u48, u5};
+
+#[test]
+fn test_ux_wrapping() {
+    let (a, b) = (u5::new(30), u5::new(3));
+    assert_eq!(u5::new(1), wrapping! { a + b });
+    assert_eq!(u5::new(26), wrapping! { a * b });
+    assert_eq!(u5::new(16), wrapping! { b << 4_u32 });
+    assert_eq!(u5::new(24), wrapping! { b << 33_u32 });
+    assert_eq!(u5::new(1), wrapping! { b - u5::MAX - b });
+    let (c, d) = (i5::MIN, i5::new(-1));
+    assert_eq!(i5::MIN, wrapping! { c / d });
+    assert_eq!(i5::new(0), wrapping! { c % d });
+    assert_eq!(i5::new(-8), wrapping! { c >> 1_u32 });
+}
+
+#[test]
+fn test_ux_checked() {
+    let (a, b) = (u24::new(0xFF_FFF0), u24::new(0x10));
+    assert_eq!(None, checked! { a + b });
+    assert_eq!(Some(u24::MAX), checked! { a + (b - b / b) });
+    assert_eq!(None, checked! { b << 24_u32 });
+    let error = try_checked! { b * b * b * b * b * b }.unwrap_err();
+    assert_eq!(Operation::Mul, error.operation());
+    let (c, d) = (i5::MIN, i5::new(-1));
+    assert_eq!(None, checked! { c / d });
+    assert_eq!(None, checked! { c % d });
+    let e = i5::new(-16);
+    assert_eq!(Some(i5::new(2)), checked! { c / e - d });
+    assert_eq!(None, checked! { c - d * c / e });
+}
+
+#[test]
+fn test_ux_saturating() {
+    let (a, b) = (u48::MAX, u48::new(2));
+    assert_eq!(u48::MAX, saturating! { a * b });
+    assert_eq!(u48::new(0), saturating! { b - a });
+    let (c, d) = (i5::new(-9), i5::new(2));
+    assert_eq!(i5::MIN, saturating! { c * d });
+    assert_eq!(i5::new(-13), saturating! { c - d - d });
+    assert_eq!(i5::MAX, saturating! { i5::MIN / (c / c - d) });
+}
+
+#[test]
+fn test_ux_panicking() {
+    let (a, b, c) = (u5::new(15), u5::new(2), u5::new(1));
+    assert_eq!(u5::new(31), panicking! { a * b + c });
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic = "attempt to multiply with overflow"]
+fn test_ux_panicking_overflow() {
+    let (a, b) = (u5::new(16), u5::new(2));
+    panicking! { a * b };
+}
@@ -0,0 +1,72 @@
+//! Checks that the `wrapping!` and `saturating!` expansions have no hidden
+//! panic paths, which fails to link otherwise. Like the `no-panic` crate, each
+//! function drops a guard referring to an undefined symbol while unwinding,
+//! which the optimizer only removes if nothing in the function can panic, so
+//! it only works with optimizations:
+//! `cargo test --release --features no-panic --test no_panic`.
+#![cfg(all(feature = "no-panic", not(debug_assertions)))]
+
+use arithmetic_mode::{saturating, wrapping};
+use core::hint::black_box;
+use core::num::NonZero;
+
+macro_rules! no_panic {
+    ($(fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty $body:block)*) => {
+        $(
+            #[inline(never)]
+            fn $name($($arg: $ty),*) -> $ret {
+                struct Guard;
+
+                impl Drop for Guard {
+                    fn drop(&mut self) {
+                        extern "C" {
+                            #[link_name = concat!("\n\n`", stringify!($name), "` may panic\n\n")]
+                            fn may_panic() -> !;
+                        }
+                        unsafe { may_panic() }
+                    }
+                }
+
+                let guard = Guard;
+                let value = $body;
+                core::mem::forget(guard);
+                value
+            }
+        )*
+    };
+}
+
+// Dividing by zero panics in all the modes, so the divisors are non-zero
+macro_rules! no_panic_modes {
+    ($($ty:ident)*) => {
+        ::paste::paste! {
+            no_panic! {
+                $(
+                    fn [<wrapping_ $ty>](a: $ty, b: $ty, c: NonZero<$ty>, s: u32) -> $ty {
+                        let c = c.get();
+                        wrapping! { (a + b) * a - b / c + a % c + (a << s) + (b >> s) }
+                    }
+
+                    fn [<saturating_ $ty>](a: $ty, b: $ty, c: NonZero<$ty>) -> $ty {
+                        let c = c.get();
+                        saturating! { (a + b) * a - b / c }
+                    }
+                )*
+            }
+
+            // The arguments are opaque, so that the checks are not optimized
+            // for them
+            #[test]
+            fn test_no_panic() {
+                $(
+                    let one = black_box(NonZero::new(1).unwrap());
+                    assert_eq!(0, [<wrapping_ $ty>](black_box(0), black_box(0), one, black_box(100)));
+                    let max = black_box(NonZero::new($ty::MAX).unwrap());
+                    assert_eq!($ty::MAX, [<saturating_ $ty>](black_box($ty::MAX), black_box(1), max));
+                )*
+            }
+        }
+    };
+}
+
+no_panic_modes!(i8 u8 i16 u16 i32 u32 i64 u64 i128 u128 isize usize);
@@ -0,0 +1,84 @@
+#![no_std]
+
+use arithmetic_mode::{Panicking, Strict};
+use core::ops::{Add, Mul};
+
+/// A generic computation, which the wrappers check with plain operators.
+fn weighted<T: Copy + Add<Output = T> + Mul<Output = T>>(values: [T; 3], weight: T) -> T {
+    values[0] * weight + values[1] * weight + values[2]
+}
+
+#[test]
+fn test_operators() {
+    let a = Panicking(200_u8);
+    assert_eq!(Panicking(250), a + 50);
+    assert_eq!(Panicking(100), a / Panicking(2));
+    assert_eq!(Panicking(0), a % 2);
+    assert_eq!(Panicking(100), a >> 1);
+    assert_eq!(Panicking(144), a << 1);
+    assert_eq!(Strict(100), Strict(200_u8) >> 1);
+    assert_eq!(Strict(-128), Strict(-64_i8) << 1);
+    assert_eq!(Panicking(-5), -Panicking(5_i32));
+    let values = [10_u8, 20, 30].map(Panicking);
+    assert_eq!(Panicking(120), weighted(values, Panicking(3)));
+}
+
+#[test]
+fn test_assign() {
+    let mut total = Strict(250_u8);
+    total -= 50;
+    total *= Strict(1);
+    total <<= 0;
+    assert_eq!(Strict(200), total);
+}
+
+#[test]
+fn test_fold() {
+    assert_eq!(Panicking(60), [10_u8, 20, 30].into_iter().sum());
+    let factors = [2_i16, 3, 4].map(Strict);
+    assert_eq!(Strict(24), factors.into_iter().product());
+    assert_eq!(Panicking(0_u32), Panicking::default());
+    assert_eq!(Strict(7_u64), Strict::from(7));
+}
+
+#[test]
+#[should_panic = "attempt to multiply with overflow: 200 * 2"]
+fn test_overflow() {
+    let _ = Panicking(200_u8) * 2;
+}
+
+#[test]
+#[should_panic = "attempt to divide by zero: 200 / 0"]
+fn test_division_by_zero() {
+    let _ = Strict(200_u8) / 0;
+}
+
+#[test]
+#[should_panic = "attempt to subtract with overflow: 0 - -128"]
+fn test_neg() {
+    let _ = -Panicking(i8::MIN);
+}
+
+#[test]
+#[should_panic = "attempt to add with overflow: 200 + 100"]
+fn test_sum() {
+    let _: Panicking<u8> = [100_u8, 100, 100].into_iter().sum();
+}
+
+#[test]
+#[should_panic = "attempt to shift left with overflow: 192 << 1"]
+fn test_strict_shl() {
+    let _ = Strict(0b1100_0000_u8) << 1;
+}
+
+#[test]
+#[should_panic = "attempt to shift left with overflow: 64 << 1"]
+fn test_strict_shl_sign() {
+    let _ = Strict(64_i8) << 1;
+}
+
+#[test]
+#[should_panic = "attempt to shift right with overflow: 200 >> 8"]
+fn test_shr() {
+    let _ = Panicking(200_u8) >> 8;
+}
@@ -0,0 +1,50 @@
+//! Checks the overflow counts of the `profile` feature, with
+//! `cargo test --features profile --test profile`. The registry is global, so
+//! these tests are in their own binary.
+#![cfg(feature = "profile")]
+
+use arithmetic_mode::{arithmetic_mode, checked, profile, saturating, try_checked};
+
+#[arithmetic_mode(wrapping)]
+fn hash(a: u32, b: u32) -> u32 {
+    a * 31 + b
+}
+
+/// The number of overflows of the site of `expression`, which is not
+/// registered until it overflows.
+fn overflows(expression: &str) -> Option<usize> {
+    profile::sites()
+        .find(|site| site.expression() == expression)
+        .map(|site| site.overflows())
+}
+
+#[test]
+fn test_profile() {
+    let (x, zero) = (200_u8, 0_u8);
+    for _ in 0..3 {
+        hash(u32::MAX, 31);
+    }
+    assert_eq!(Some(3), overflows("a * 31"));
+    assert_eq!(Some(3), overflows("a * 31 + b"));
+    assert_eq!(None, checked! { x + 1_u8 * zero + x });
+    assert_eq!(Some(1), overflows("x + 1_u8 * zero + x"));
+    assert_eq!(None, overflows("1_u8 * zero"));
+    try_checked! { x % zero }.unwrap_err();
+    assert_eq!(Some(1), overflows("x % zero"));
+    assert_eq!(255, saturating! { x * 2_u8 });
+    assert_eq!(Some(1), overflows("x * 2_u8"));
+    // The overflow aborts the tests with the `abort` feature
+    #[cfg(not(feature = "abort"))]
+    {
+        std::panic::catch_unwind(|| arithmetic_mode::panicking! { x << 8_u32 }).unwrap_err();
+        assert_eq!(Some(1), overflows("x << 8_u32"));
+    }
+    let site = profile::sites().find(|site| site.expression() == "a * 31");
+    let site = site.unwrap();
+    assert_eq!(
+        "profile.rs",
+        site.location().file().rsplit('/').next().unwrap()
+    );
+    assert_eq!((10, 7), (site.location().line(), site.location().column()));
+    assert!(site.to_string().ends_with(": `a * 31` overflowed 3 times"));
+}
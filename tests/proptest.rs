@@ -0,0 +1,23 @@
+#![cfg(feature = "proptest")]
+
+use arithmetic_mode::proptest_modes;
+
+#[test]
+fn mul_add() {
+    proptest_modes!(|a: u8, b: u8| a * b + 7);
+}
+
+#[test]
+fn signed_sub() {
+    proptest_modes!(|a: i16, b: i16| a - b);
+}
+
+#[test]
+fn signed_div() {
+    proptest_modes!(|a: i8, b: i8| a / b);
+}
+
+#[test]
+fn negated_remainder() {
+    proptest_modes!(|a: i32, b: i32| -(a % b));
+}
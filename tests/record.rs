@@ -0,0 +1,54 @@
+//! Checks the steps that `record!` records.
+
+use arithmetic_mode::record::{Recorder, Step};
+use arithmetic_mode::{record, Operation};
+
+/// The steps of `record!`, in order.
+#[derive(Default)]
+struct Log(Vec<Step>);
+
+impl Recorder for Log {
+    fn record(&mut self, step: Step) {
+        self.0.push(step);
+    }
+}
+
+#[test]
+fn test_record() {
+    let (a, b, shift) = (250_u8, 10_u8, 9_u32);
+    let mut log = Log::default();
+    assert_eq!(8, record! { &mut log; [wrapping] (a + b) << shift });
+    let steps: Vec<_> = log
+        .0
+        .iter()
+        .map(|step| (step.operation(), step.result()))
+        .collect();
+    assert_eq!(vec![(Operation::Add, "4"), (Operation::Shl, "8")], steps);
+    assert!(log.0.iter().all(Step::overflowed));
+    assert_eq!(("4", "9"), log.0[1].operands());
+    assert_eq!("(a + b) << shift", log.0[1].expression());
+
+    let mut log = Log::default();
+    assert_eq!(Some(21), record! { &mut log; [checked] a / b - 4 });
+    assert_eq!(2, log.0.len());
+    assert!(log.0.iter().all(|step| !step.overflowed()));
+    assert_eq!("Some(21)", log.0[1].result());
+
+    let mut log = Log::default();
+    assert_eq!(None, record! { &mut log; [checked] b - a + 1 });
+    assert_eq!(1, log.0.len());
+    assert_eq!(("10", "250"), log.0[0].operands());
+    assert_eq!("None", log.0[0].result());
+}
+
+#[test]
+fn test_record_display() {
+    let b = 10_u8;
+    let mut log = Log::default();
+    assert_eq!(130, record! { &mut log; b * 13 });
+    let step = log.0[0].to_string();
+    assert!(
+        step.ends_with(": `b * 13` with operands 10 and 13 = 130"),
+        "{step}"
+    );
+}
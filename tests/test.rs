@@ -1,7 +1,29 @@
 #![no_std]
 #![allow(clippy::precedence)]
 
-use arithmetic_mode::{checked, panicking, saturating, wrapping};
+use arithmetic_mode::{
+    assert_no_overflow, bench, blend_add, bps_of, checked, checked_product, checked_sum,
+    const_checked, control_flow, default, dot_product, elementwise, fast_checked, forbid, hashing,
+    lerp, mac, mixed, modes, modfield, mul_div, panicking, percent_of, qformat, saturating,
+    scale_u8, try_checked, verified, wrapping, ArithmeticError, ErrorKind, Operation,
+};
+use core::ops::ControlFlow;
+
+// The tests are not compiled with `--cfg arithmetic_mode_default`, so the
+// default mode is `panicking`
+#[test]
+fn test_default() {
+    let (width, height) = (30_u8, 5_u8);
+    assert_eq!(151, default! { width * height + 1 });
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic = "attempt to multiply with overflow"]
+fn test_default_overflow() {
+    let (width, height) = (30_u8, 10_u8);
+    default! { width * height + 1 };
+}
 
 #[test]
 fn test_panicking() {
@@ -9,16 +31,20 @@ fn test_panicking() {
     assert_eq!(11, panicking! { 1_u8 + 2_u8 * 3_u8 + 4_u8 });
 }
 
+#[cfg(not(feature = "abort"))]
 #[test]
 #[should_panic]
 fn test_u8_add_u8() {
-    panicking! { 5_u8+255_u8 };
+    let x = 5_u8;
+    panicking! { x+255_u8 };
 }
 
+#[cfg(not(feature = "abort"))]
 #[test]
 #[should_panic]
 fn test_u8_add_u8_2() {
-    panicking! { 200_u8+30_u8+30_u8 };
+    let x = 200_u8;
+    panicking! { x+30_u8+30_u8 };
 }
 
 #[test]
@@ -39,6 +65,170 @@ fn test_wrapping_add_overflow() {
     assert_eq!(u32::MAX, wrapping! { 0_u32 - 1 });
 }
 
+#[test]
+fn test_hashing() {
+    // FNV-1a
+    let mut h = 0xcbf2_9ce4_8422_2325_u64;
+    for &b in b"a" {
+        let b = u64::from(b);
+        h = hashing! { (h ^ b) * 0x100_0000_01b3 };
+    }
+    assert_eq!(0xaf63_dc4c_8601_ec8c, h);
+    let k = 0x9e37_79b9_u32;
+    assert_eq!(0x79b9_9e37, hashing! { k << 16 | k >> 16 });
+    assert_eq!(0x79b9_9e37, hashing! { u32: k << 48 | k >> 48 });
+    assert_eq!(u64::MAX, hashing! { 0 - 1 });
+    assert_eq!(u8::MAX, hashing! { 0_u8 - 1 });
+}
+
+#[test]
+fn test_division_flavors() {
+    // The quotients rounded in `f64`, which is exact for the 8-bit types, and
+    // the matching remainders
+    fn expected(a: i32, b: i32) -> [(i32, i32); 4] {
+        let quotient = a as f64 / b as f64;
+        let half_even = match quotient.round() {
+            // Halfway and rounded away from zero to odd
+            q if (q - quotient).abs() == 0.5 && q % 2.0 != 0.0 => q - q.signum(),
+            q => q,
+        };
+        [
+            quotient.floor(),
+            quotient.ceil(),
+            a.div_euclid(b) as f64,
+            half_even,
+        ]
+        .map(|q| (q as i32, a - b * q as i32))
+    }
+    for a in i8::MIN..=i8::MAX {
+        for b in (i8::MIN..=i8::MAX).filter(|&b| b != 0) {
+            // `MIN % -1` overflows like `MIN / -1`, as in `checked_rem`
+            let fits = |(q, r): (i32, i32)| {
+                let q = i8::try_from(q).ok();
+                (q, q.and(i8::try_from(r).ok()))
+            };
+            let [floor, ceil, euclid, half_even] = expected(a.into(), b.into()).map(fits);
+            assert_eq!(
+                floor,
+                (
+                    checked! { div = floor; a / b },
+                    checked! { div = floor; a % b }
+                )
+            );
+            assert_eq!(
+                ceil,
+                (
+                    checked! { div = ceil; a / b },
+                    checked! { div = ceil; a % b }
+                )
+            );
+            assert_eq!(
+                euclid,
+                (
+                    checked! { div = euclid; a / b },
+                    checked! { div = euclid; a % b }
+                )
+            );
+            assert_eq!(
+                half_even,
+                (
+                    checked! { div = half_even; a / b },
+                    checked! { div = half_even; a % b }
+                )
+            );
+        }
+    }
+    for a in u8::MIN..=u8::MAX {
+        for b in 1..=u8::MAX {
+            let fits = |(q, r): (i32, i32)| (u8::try_from(q).ok(), u8::try_from(r).ok());
+            let [floor, ceil, euclid, half_even] = expected(a.into(), b.into()).map(fits);
+            assert_eq!(
+                floor,
+                (
+                    checked! { div = floor; a / b },
+                    checked! { div = floor; a % b }
+                )
+            );
+            assert_eq!(
+                ceil,
+                (
+                    checked! { div = ceil; a / b },
+                    checked! { div = ceil; a % b }
+                )
+            );
+            assert_eq!(
+                euclid,
+                (
+                    checked! { div = euclid; a / b },
+                    checked! { div = euclid; a % b }
+                )
+            );
+            assert_eq!(
+                half_even,
+                (
+                    checked! { div = half_even; a / b },
+                    checked! { div = half_even; a % b }
+                )
+            );
+        }
+    }
+}
+
+#[test]
+fn test_division_flavor_modes() {
+    let (a, b) = (-7_i64, 2_i64);
+    assert_eq!(-4, panicking! { div = floor; a / b });
+    assert_eq!(-4, verified! { div = floor; a / b });
+    assert_eq!(1, panicking! { div = floor; "remainder": a % b });
+    assert_eq!(-3, wrapping! { div = ceil; a / b });
+    assert_eq!(-1, wrapping! { div = ceil; a % b });
+    assert_eq!(Ok(-4), try_checked! { div = euclid; a / b });
+    assert_eq!(
+        Err(()),
+        try_checked! { err = (); div = euclid; i64::MIN / -1 }
+    );
+    let min = i64::MIN;
+    assert_eq!(min, wrapping! { div = floor; min / -1 });
+    assert_eq!(0, wrapping! { div = ceil; min % -1 });
+    assert_eq!(i64::MAX, saturating! { div = ceil; min / -1 });
+    assert_eq!(0, saturating! { div = floor; min % -1 });
+    let error = try_checked! { div = floor; a / (b - 2) }.unwrap_err();
+    assert_eq!(
+        (Operation::Div, ErrorKind::DivisionByZero),
+        (error.operation(), error.kind())
+    );
+    // The remainder of the unsigned ceiling division is negative
+    let (len, page) = (10_u32, 4_u32);
+    assert_eq!(3, wrapping! { div = ceil; len / page });
+    assert_eq!(u32::MAX - 1, wrapping! { div = ceil; len % page });
+    assert_eq!(None, checked! { div = ceil; len % page });
+    assert_eq!(2, hashing! { div = ceil; 3 / 2 });
+    // Rounded to even when halfway, also at the bounds
+    let (max, two) = (u64::MAX, 2_u64);
+    assert_eq!(1 << 63, panicking! { div = half_even; max / two });
+    assert_eq!(max, wrapping! { div = half_even; max % two });
+    assert_eq!(-2, saturating! { div = half_even; -5_i8 / 2_i8 });
+    assert_eq!(2, wrapping! { div = half_even; 5_i8 / 2_i8 });
+    assert_eq!(1, wrapping! { div = half_even; 5_i8 % 2_i8 });
+    assert_eq!(-1, wrapping! { div = half_even; 7_i8 % 2_i8 });
+    assert_eq!(i128::MAX, saturating! { div = half_even; i128::MIN / -1 });
+}
+
+#[test]
+#[should_panic = "attempt to divide by zero"]
+fn test_division_flavor_by_zero() {
+    let zero = 0_u8;
+    wrapping! { div = ceil; 1_u8 / zero };
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic = "attempt to divide with overflow"]
+fn test_division_flavor_overflow() {
+    let min = i8::MIN;
+    panicking! { div = floor; min / -1 };
+}
+
 #[test]
 fn test_saturating_add() {
     assert_eq!(15, saturating! { 5_u8 + 10_u8 });
@@ -51,6 +241,13 @@ fn test_saturating_add_overflow() {
     assert_eq!(0, saturating! { 0_u32 - 1 });
 }
 
+#[test]
+fn test_saturating_rem() {
+    assert_eq!(2, saturating! { 17_u8 % 5 });
+    assert_eq!(0, saturating! { i32::MIN % -1 });
+    assert_eq!(-1, saturating! { -7_i8 % 3 });
+}
+
 #[test]
 fn test_saturating_operator_precedence() {
     assert_eq!(11, saturating! { 1_u8 + 2_u8 * 3_u8 + 4_u8 });
@@ -63,9 +260,10 @@ fn test_checked_add() {
 
 #[test]
 fn test_checked_add_overflow() {
-    assert_eq!(None, checked! { 5_u8 + 255_u8 });
-    assert_eq!(None, checked! { 200_u8 + 30_u8 + 30_u8 });
-    assert_eq!(None, checked! { 0_u32 - 1 });
+    let (x, y, z) = (5_u8, 200_u8, 0_u32);
+    assert_eq!(None, checked! { x + 255_u8 });
+    assert_eq!(None, checked! { y + 30_u8 + 30_u8 });
+    assert_eq!(None, checked! { z - 1 });
 }
 
 #[test]
@@ -73,25 +271,30 @@ fn test_checked_operator_precedence() {
     assert_eq!(Some(11), checked! { 1_u8 + 2_u8 * 3_u8 + 4_u8 });
 }
 
+// Expressions without arithmetic warn that there is nothing to transform
 macro_rules! test_unchanging {
     ($ident:ident, $expr:expr) => {
         ::paste::paste! {
             #[test]
+            #[allow(deprecated)]
             fn [<test_unchanging_panicking_ $ident>]() {
                 assert_eq!(($expr), ($crate::panicking! { $expr }));
             }
 
             #[test]
+            #[allow(deprecated)]
             fn [<test_unchanging_wrapped_ $ident>]() {
                 assert_eq!($expr, $crate::wrapping!{ $expr });
             }
 
             #[test]
+            #[allow(deprecated)]
             fn [<test_unchanging_saturating_ $ident>]() {
                 assert_eq!($expr, $crate::saturating!{ $expr });
             }
 
             #[test]
+            #[allow(deprecated)]
             fn [<test_unchanging_checked_ $ident>]() {
                 assert_eq!(Some($expr), $crate::checked!{ $expr });
             }
@@ -105,3 +308,776 @@ test_unchanging!(or, 1 | 2);
 test_unchanging!(and, -10 & 2);
 test_unchanging!(xor, 1 ^ 2);
 test_unchanging!(complex, (1 ^ 2) | 3_i32 + -4_i32 * 5_i32);
+
+#[test]
+fn test_reference_operands() {
+    let values = [200_u8, 30_u8, 30_u8];
+    assert_eq!(
+        Some(60),
+        values.iter().map(|v| panicking! { v + v }).next_back()
+    );
+    assert_eq!(4, values.iter().fold(0_u8, |acc, v| wrapping! { acc + v }));
+    assert_eq!(
+        255,
+        values.iter().fold(0_u8, |acc, v| saturating! { acc + v })
+    );
+    let (a, b) = (&5_u8, &10_u8);
+    assert_eq!(15, panicking! { &5_u8 + b });
+    assert_eq!(15, wrapping! { a + b });
+    assert_eq!(50, saturating! { a * b });
+}
+
+#[test]
+fn test_checked_option_operands() {
+    let x = 200_u8;
+    assert_eq!(Some(230), checked! { x + 30_u8 });
+    assert_eq!(None, checked! { x + opt(x.checked_div(0)) });
+    assert_eq!(Some(210), checked! { opt(x.checked_div(20)) + x });
+    assert_eq!(None, checked! { opt(x.checked_div(2)) * 3_u8 });
+}
+
+#[test]
+fn test_checked_short_circuit() {
+    let mut calls = 0;
+    let mut next = || {
+        calls += 1;
+        Some(1_u8)
+    };
+    let max = u8::MAX;
+    assert_eq!(None, checked! { max + 1 + opt(next()) });
+    assert_eq!(Some(3), checked! { 1_u8 + 1 + opt(next()) });
+    assert_eq!(1, calls);
+}
+
+#[test]
+fn test_operand_names() {
+    let (l, r) = (1_u8, 2_u8);
+    assert_eq!(5, wrapping! { 2_u8 + l + r });
+    assert_eq!(Some(1), checked! { r - l });
+    assert_eq!(Ok(2), try_checked! { r * l });
+}
+
+#[test]
+fn test_try_checked() {
+    assert_eq!(Ok(15), try_checked! { 5_u8 + 10_u8 });
+    assert_eq!(Ok(11), try_checked! { 1_u8 + 2_u8 * 3_u8 + 4_u8 });
+    let (x, y) = (200_u8, 0_u32);
+    assert_eq!(
+        Err(Operation::Add),
+        try_checked! { x + 30_u8 + 30_u8 }.map_err(|e| e.operation())
+    );
+    assert_eq!(
+        Err(Operation::Sub),
+        try_checked! { y - 1 }.map_err(|e| e.operation())
+    );
+}
+
+#[test]
+fn test_control_flow() {
+    assert_eq!(
+        ControlFlow::Continue(11),
+        control_flow! { 1_u8 + 2_u8 * 3_u8 + 4_u8 }
+    );
+    let (x, y) = (200_u8, 0_u32);
+    let ControlFlow::Break(error) = control_flow!(x * 2) else {
+        panic!("the multiplication overflows");
+    };
+    assert_eq!(Operation::Mul, error.operation());
+    let ControlFlow::Break(error) = control_flow!(1_u32 / y) else {
+        panic!("the division divides by zero");
+    };
+    assert_eq!(ErrorKind::DivisionByZero, error.kind());
+    // The iteration stops at the first overflow
+    let mut visited = 0;
+    let sum = [100_u8, 100, 100, 100].iter().try_fold(0_u8, |acc, x| {
+        visited += 1;
+        control_flow! { acc + x }
+    });
+    assert!(sum.is_break());
+    assert_eq!(3, visited);
+}
+
+#[test]
+fn test_try_checked_result_operands() {
+    #[derive(Debug)]
+    struct ParseError;
+
+    impl From<ParseError> for ArithmeticError {
+        fn from(_: ParseError) -> Self {
+            ArithmeticError::new(Operation::Conversion)
+        }
+    }
+
+    fn parse(s: &str) -> Result<u32, ParseError> {
+        s.parse().map_err(|_| ParseError)
+    }
+
+    let (scale, base) = (10_u32, 7_u32);
+    assert_eq!(Ok(427), try_checked! { parse("42")? * scale + base });
+    assert_eq!(
+        Err(ArithmeticError::new(Operation::Conversion)),
+        try_checked! { parse("x")? * scale + base }
+    );
+    assert_eq!(
+        Err(Operation::Mul),
+        try_checked! { parse("4294967295")? * scale + base }.map_err(|e| e.operation())
+    );
+    assert_eq!(Ok(255), try_checked! { u8::try_from(base * 36)? + 3_u8 });
+}
+
+#[derive(Debug, PartialEq)]
+enum ContractError {
+    MathOverflow,
+    Paused(u32),
+}
+
+#[test]
+fn test_try_checked_custom_error() {
+    let (balance, amount) = (100_u64, 30_u64);
+    assert_eq!(
+        Ok(70),
+        try_checked! { err = ContractError::MathOverflow; balance - amount }
+    );
+    assert_eq!(
+        Err(ContractError::MathOverflow),
+        try_checked! { err = ContractError::MathOverflow; amount - balance }
+    );
+    let code = 7;
+    assert_eq!(
+        Err(ContractError::Paused(code)),
+        try_checked! { err = ContractError::Paused(code); u64: amount / (balance - 100) }
+    );
+}
+
+const fn const_custom_error(a: u8, b: u8) -> Result<u8, ContractError> {
+    try_checked! { err = ContractError::MathOverflow; const a * b }
+}
+
+#[test]
+fn test_const_custom_error() {
+    const PRODUCT: Result<u8, ContractError> = const_custom_error(16, 16);
+    assert_eq!(Err(ContractError::MathOverflow), PRODUCT);
+    assert_eq!(Ok(12), const_custom_error(3, 4));
+}
+
+#[test]
+fn test_error_details() {
+    let (x, y) = (200_u8, 100_u8);
+    let error = try_checked! { 1_u8 + x * 2 - y }.unwrap_err();
+    assert_eq!(Operation::Mul, error.operation());
+    assert_eq!(Some("x * 2u8"), error.expression());
+    assert_eq!(Some(("200", "2")), error.operands());
+    let location = error.location().unwrap();
+    assert_eq!(file!(), location.file());
+    assert_eq!(line!() - 6, location.line());
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic(expected = "attempt to subtract with overflow: `x - y` with operands 1 and 2, at")]
+fn test_panicking_message() {
+    let (x, y) = (1_u8, 2_u8);
+    panicking! { x - y };
+}
+
+#[test]
+fn test_division_by_zero() {
+    let (x, zero) = (i8::MIN, 0_i8);
+    let error = try_checked! { x / zero }.unwrap_err();
+    assert_eq!(
+        (Operation::Div, ErrorKind::DivisionByZero),
+        (error.operation(), error.kind())
+    );
+    let error = try_checked! { x % zero }.unwrap_err();
+    assert_eq!(
+        (Operation::Rem, ErrorKind::DivisionByZero),
+        (error.operation(), error.kind())
+    );
+    let error = try_checked! { x / -1 }.unwrap_err();
+    assert_eq!(
+        (Operation::Div, ErrorKind::Overflow),
+        (error.operation(), error.kind())
+    );
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic(expected = "attempt to calculate the remainder with a divisor of zero: `x % y`")]
+fn test_panicking_division_by_zero_message() {
+    let (x, y) = (1_u8, 0_u8);
+    panicking! { x % y };
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic(
+    expected = "frame budget overflow: attempt to add with overflow: `elapsed + frame_cost`"
+)]
+fn test_panicking_custom_message() {
+    let (elapsed, frame_cost) = (u32::MAX, 16_u32);
+    panicking! { "frame budget overflow": elapsed + frame_cost };
+}
+
+#[test]
+fn test_panicking_custom_message_literal_type() {
+    assert_eq!(7, panicking! { "unreachable": u8: 1 + 2 * 3 });
+}
+
+const fn const_panicking(a: u8, b: u8) -> u8 {
+    panicking! { const a * b + 1 }
+}
+
+const fn const_wrapping(a: u8, b: u8) -> u8 {
+    wrapping! { const a * b + 1 }
+}
+
+const fn const_saturating(a: u8, b: u8) -> u8 {
+    saturating! { const a * b + 1 }
+}
+
+const fn const_checked(a: u8, b: u8) -> Option<u8> {
+    checked! { const a * b + 1 }
+}
+
+const fn const_try_checked(a: u8, b: u8) -> Result<u8, ArithmeticError> {
+    try_checked! { const a * b + 1 }
+}
+
+#[test]
+fn test_const_fn() {
+    const PANICKING: u8 = const_panicking(3, 4);
+    const WRAPPING: u8 = const_wrapping(16, 16);
+    const SATURATING: u8 = const_saturating(16, 16);
+    const CHECKED: Option<u8> = const_checked(16, 16);
+    const TRY_CHECKED: Result<u8, ArithmeticError> = const_try_checked(16, 16);
+    assert_eq!(13, PANICKING);
+    assert_eq!(1, WRAPPING);
+    assert_eq!(255, SATURATING);
+    assert_eq!(None, CHECKED);
+    assert_eq!(Err(ArithmeticError::new(Operation::Mul)), TRY_CHECKED);
+    assert_eq!(Ok(13), const_try_checked(3, 4));
+}
+
+#[test]
+#[should_panic(expected = "attempt to multiply with overflow: `a * b`")]
+fn test_const_fn_panicking_message() {
+    const_panicking(16, 16);
+}
+
+const HEADER: usize = 4;
+
+struct Packet<const N: usize>([u8; N]);
+
+fn header() -> [u8; panicking!(const HEADER * 2 + 1)] {
+    [0; 9]
+}
+
+#[test]
+fn test_const_position() {
+    assert_eq!(9, header().len());
+    let packet = Packet::<{ const_checked!(HEADER * 3) }>([0; 12]);
+    assert_eq!(12, packet.0.len());
+    let table: [u8; wrapping!(const HEADER * 16 - 1)] = [0; 63];
+    assert_eq!(63, table.len());
+}
+
+#[test]
+fn test_const_checked() {
+    const WIDTH: u16 = 300;
+    assert_eq!(90_000, const_checked! { WIDTH as u32 * 300 });
+    assert_eq!(7, const_checked! { u8: 1 + 2 * 3 });
+    assert_eq!(255, const_checked! { "unreachable": u8::MAX / 1 });
+}
+
+#[test]
+fn test_assert_no_overflow() {
+    let (x, y) = (200_u8, 50_u8);
+    assert_eq!(250, assert_no_overflow! { x + y });
+    assert_eq!(4, assert_no_overflow! { u8: (x + y) / 50 - 1 });
+}
+
+#[test]
+#[should_panic(expected = "assertion failed: no overflow in `x + y * 2`: \
+    attempt to add with overflow: `x + y * 2` with operands 200 and 100")]
+fn test_assert_no_overflow_failure() {
+    let (x, y) = (200_u8, 50_u8);
+    assert_no_overflow! { x + y * 2 };
+}
+
+#[test]
+fn test_verified() {
+    let (width, height) = (15_u8, 17_u8);
+    assert_eq!(255, verified! { width * height });
+    assert_eq!(7, verified! { u8: height / 2 - 1 });
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic(expected = "attempt to add with overflow: `x + 1` with operands 255 and 1")]
+fn test_verified_overflow() {
+    let x = u8::MAX;
+    verified! { x + 1 };
+}
+
+#[test]
+fn test_mixed() {
+    let (a, b) = (250_u8, 10_u8);
+    assert_eq!(4, mixed! { a +% b });
+    assert_eq!(255, mixed! { a +| b });
+    assert_eq!(0, mixed! { b -| a });
+    assert_eq!(16, mixed! { b -% a / 2 * 2 });
+    assert_eq!(254, mixed! { (a +% b) *| a - 1 });
+    assert_eq!(Some(4), [a].iter().map(|v| mixed! { v +% b }).next());
+    assert_eq!(None, mixed! { [checked] a + b *[wrapping] 2 });
+    assert_eq!(Some(20), mixed! { [checked] (a +[wrapping] b) * 5 });
+    assert_eq!(255, mixed! { a *[saturating] 2 -% 0 });
+}
+
+#[test]
+fn test_modes() {
+    let (pixel, gain, offset) = (200_u8, 2_u8, 1000_usize);
+    assert_eq!(
+        Some(1255),
+        modes! { u8 = saturating, usize = checked; offset + (pixel * gain) as usize }
+    );
+    assert_eq!(
+        None,
+        modes! { u8 = saturating, usize = checked; usize::MAX - 10 + (pixel + 1) as usize }
+    );
+    // The types not given panic by default
+    assert_eq!(144, modes! { u8 = wrapping; pixel * gain });
+    assert_eq!(
+        2440,
+        modes! { u8 = wrapping; offset + (pixel * gain) as usize * 10 }
+    );
+    assert_eq!(
+        Some(84),
+        modes! { _ = checked, u8 = wrapping; -((pixel * gain) as i8) + 200 as i8 / 2 }
+    );
+    let error = modes! { usize = try_checked; offset * usize::MAX }.unwrap_err();
+    assert_eq!(Operation::Mul, error.operation());
+    let error = modes! { _ = try_checked; offset / (pixel - 200) as usize }.unwrap_err();
+    assert_eq!(ErrorKind::DivisionByZero, error.kind());
+    assert_eq!(
+        Some(80),
+        modes! { usize = checked; (pixel >> 1) as usize - 20 }
+    );
+}
+
+#[test]
+fn test_bench() {
+    let (a, b) = (250_u8, 10_u8);
+    assert_eq!(4, bench! { [wrapping] a + b });
+    assert_eq!(255, bench! { [saturating] a + b * 2 });
+    assert_eq!(None, bench! { [checked] a + b });
+    assert_eq!(Some(25), bench! { [checked] a / (b: u8) });
+    assert_eq!(Some(26), [a].iter().map(|v| bench! { v / b + 1 }).next());
+}
+
+#[test]
+fn test_checked_sum() {
+    let (a, b, c) = (100_u8, 50_u8, 3_u8);
+    assert_eq!(Some(153), checked_sum!(a, b, c));
+    assert_eq!(Some(150), checked_sum!(a, b,));
+    assert_eq!(None, checked_sum!(a, b, a, b));
+    assert_eq!(Some(150), checked_sum!(u8: a - b, 50, b));
+    assert_eq!(Some(255), checked_sum!(a * 2, b + 5));
+    const TOTAL: Option<u16> = checked_sum!(const u16: 60000, 5535);
+    assert_eq!(Some(65535), TOTAL);
+    let error = checked_sum!([try_checked] a, a, a, b).unwrap_err();
+    assert_eq!(Some("a + a + a"), error.expression());
+    assert_eq!(Some(("200", "100")), error.operands());
+}
+
+#[test]
+fn test_checked_product() {
+    let (a, b, c) = (10_u8, 5_u8, 3_u8);
+    assert_eq!(Some(150), checked_product!(a, b, c));
+    assert_eq!(None, checked_product!(a, b, c, 2));
+    assert_eq!(Some(225), checked_product!(a + b, c * 5));
+    assert_eq!(Ok(50), checked_product!([try_checked] a, b));
+}
+
+#[test]
+fn test_elementwise() {
+    struct Frame {
+        pixels: [u8; 3],
+    }
+    let (a, b, gain) = ([250_u8, 10, 0], [10_u8, 20, 30], 3_u8);
+    assert_eq!([4, 30, 30], elementwise! { [wrapping] a + b });
+    assert_eq!([255, 70, 90], elementwise! { [saturating] a + b * gain });
+    assert_eq!(
+        [None, Some(70), Some(90)],
+        elementwise! { [checked] a + b * 3 }
+    );
+    assert_eq!([260, 30, 30], elementwise! { a as u16 + b as u16 });
+    let frame = Frame { pixels: b };
+    assert_eq!([20, 40, 60], elementwise! { frame.pixels << 1 });
+    let error = elementwise! { [try_checked] b - a }[0].unwrap_err();
+    assert_eq!(Some(("10", "250")), error.operands());
+}
+
+#[test]
+fn test_dot_product() {
+    let (a, b) = ([200_u8, 100, 50], [2_u8, 1, 1]);
+    assert_eq!(None, dot_product! { [checked] a, b });
+    assert_eq!(255, dot_product! { [saturating] a, b });
+    assert_eq!(38, dot_product! { [wrapping] a, b });
+    assert_eq!(Some(150), dot_product! { [checked] &a[1..], &b[1..] });
+    let error = dot_product! { [try_checked] a, b }.unwrap_err();
+    assert_eq!(Operation::Conversion, error.operation());
+    let (c, d) = ([i64::MAX, i64::MAX, -1], [i64::MAX, -i64::MAX, 1]);
+    assert_eq!(-1, dot_product! { c, d });
+    let empty: [u32; 0] = [];
+    assert_eq!(0, dot_product! { empty, empty });
+}
+
+#[test]
+#[should_panic = "different lengths 3 and 2"]
+fn test_dot_product_lengths() {
+    let (a, b) = ([1_u8, 2, 3], [1_u8, 2]);
+    dot_product! { a, &b[..] };
+}
+
+#[test]
+fn test_mac() {
+    let (a, b) = (100_i8, -2_i8);
+    assert_eq!(-100, mac! { 100_i8, a, b });
+    assert_eq!(None, mac! { [checked] -100_i8, a, b });
+    assert_eq!(i8::MIN, mac! { [saturating] -100_i8, a, b });
+    assert_eq!(-44, mac! { [wrapping] -100_i8, a, b });
+    let error = mac! { [try_checked] -100_i8, a, b }.unwrap_err();
+    assert_eq!(Operation::Add, error.operation());
+    assert_eq!(Some(u128::MAX), mac! { [checked] 1_u128, u128::MAX / 2, 2 });
+    assert_eq!(u128::MAX, mac! { [saturating] 0_u128, u128::MAX, 2 });
+}
+
+#[test]
+#[should_panic = "attempt to multiply and accumulate with overflow"]
+fn test_mac_overflow() {
+    let (a, b) = (u64::MAX, 2_u64);
+    mac! { 0, a, b };
+}
+
+#[test]
+fn test_mul_div() {
+    let (a, b, c) = (200_u8, 100_u8, 50_u8);
+    assert_eq!(Some(200), mul_div! { [checked] a, b, 100 });
+    assert_eq!(None, mul_div! { [checked] a, b, c });
+    assert_eq!(None, mul_div! { [checked] a, b, 0 });
+    assert_eq!(255, mul_div! { [saturating] a, b, c });
+    assert_eq!(144, mul_div! { [wrapping] a, b, c });
+    let error = mul_div! { [try_checked] a, b, 0 }.unwrap_err();
+    assert_eq!(ErrorKind::DivisionByZero, error.kind());
+    let error = mul_div! { [try_checked] a, b, c }.unwrap_err();
+    assert_eq!(
+        (Operation::Div, ErrorKind::Overflow),
+        (error.operation(), error.kind())
+    );
+    // 7 * 5 / 2 = 17.5, and 7 * 5 / 3 = 11.67
+    let (x, y) = (7_i64, 5_i64);
+    for (divisor, roundings) in [(2, [17, 18, 17, 18, 18]), (3, [11, 12, 11, 12, 12])] {
+        assert_eq!(
+            roundings,
+            [
+                mul_div! { x, y, divisor },
+                mul_div! { x, y, divisor, rounding = up },
+                mul_div! { x, y, divisor, rounding = floor },
+                mul_div! { x, y, divisor, rounding = ceil },
+                mul_div! { x, y, divisor, rounding = nearest },
+            ]
+        );
+        assert_eq!(
+            roundings.map(|rounded| -rounded),
+            [
+                mul_div! { -x, y, divisor },
+                mul_div! { x, -y, divisor, rounding = up },
+                mul_div! { x, y, -divisor, rounding = ceil },
+                mul_div! { -x, -y, -divisor, rounding = floor },
+                mul_div! { x, y, -divisor, rounding = nearest },
+            ]
+        );
+    }
+    assert_eq!(i64::MIN, mul_div! { [saturating] i64::MAX, i64::MAX, -1 });
+    assert_eq!(u64::MAX, mul_div! { u64::MAX, u64::MAX, u64::MAX });
+    assert_eq!(215, mul_div! { u8::MAX, 42_u8, 50, rounding = up });
+    assert_eq!(255, mul_div! { u8::MAX, 254_u8, 254, rounding = nearest });
+}
+
+#[test]
+fn test_percent_of() {
+    let (x, p) = (250_u8, 50_u8);
+    assert_eq!(125, percent_of! { x, p });
+    assert_eq!(Some(255), percent_of! { [checked] x, 102 });
+    assert_eq!(None, percent_of! { [checked] x, 103 });
+    assert_eq!(255, percent_of! { [saturating] x, 200 });
+    assert_eq!(244, percent_of! { [wrapping] x, 200 });
+    assert_eq!(-4, percent_of! { -7_i16, 50, rounding = floor });
+    let error = percent_of! { [try_checked] x, 200 }.unwrap_err();
+    assert_eq!(Operation::Div, error.operation());
+}
+
+#[test]
+fn test_bps_of() {
+    let (amount, fee_bps) = (12_345_u32, 25_u32);
+    assert_eq!(30, bps_of! { amount, fee_bps });
+    assert_eq!(31, bps_of! { amount, fee_bps, rounding = nearest });
+    assert_eq!(Some(u32::MAX), bps_of! { [checked] u32::MAX, 10_000 });
+    assert_eq!(None, bps_of! { [checked] u32::MAX, 10_001 });
+    assert_eq!(i16::MIN, bps_of! { [saturating] i16::MIN, 20_000 });
+}
+
+#[test]
+fn test_lerp() {
+    let (a, b) = (200_u8, 100_u8);
+    assert_eq!(200, lerp! { a, b, 0, 4 });
+    assert_eq!(175, lerp! { a, b, 1, 4 });
+    assert_eq!(100, lerp! { a, b, 4, 4 });
+    assert_eq!(Some(0), lerp! { [checked] a, b, 8, 4 });
+    assert_eq!(None, lerp! { [checked] a, b, 9, 4 });
+    assert_eq!(None, lerp! { [checked] b, a, 7, 4 });
+    assert_eq!(0, lerp! { [saturating] a, b, 9, 4 });
+    assert_eq!(255, lerp! { [saturating] b, a, 7, 4 });
+    // 200 - 225 and 100 + 175 wrap around
+    assert_eq!(231, lerp! { [wrapping] a, b, 9, 4 });
+    assert_eq!(19, lerp! { [wrapping] b, a, 7, 4 });
+    // 200 - 100 / 3 = 166.67 and 100 + 100 / 3 = 133.33
+    let roundings = |a, b| {
+        [
+            lerp! { a, b, 1_u8, 3 },
+            lerp! { a, b, 1, 3, rounding = up },
+            lerp! { a, b, 1, 3, rounding = floor },
+            lerp! { a, b, 1, 3, rounding = ceil },
+            lerp! { a, b, 1, 3, rounding = nearest },
+        ]
+    };
+    assert_eq!([167, 166, 166, 167, 167], roundings(a, b));
+    assert_eq!([133, 134, 133, 134, 133], roundings(b, a));
+    let (min, max) = (i64::MIN, i64::MAX);
+    assert_eq!(max, lerp! { min, max, min, min });
+    assert_eq!(-1, lerp! { min, max, 1, 2 });
+    assert_eq!(0, lerp! { min, max, 1, 2, rounding = nearest });
+    assert_eq!(None, lerp! { [checked] max, min, -1, 1 });
+    let error = lerp! { [try_checked] a, b, 9, 4 }.unwrap_err();
+    assert_eq!(Operation::Add, error.operation());
+    let error = lerp! { [try_checked] a, b, 1, 0 }.unwrap_err();
+    assert_eq!(ErrorKind::DivisionByZero, error.kind());
+}
+
+#[test]
+#[should_panic = "attempt to interpolate with overflow"]
+fn test_lerp_overflow() {
+    let (a, b) = (1_u32, 0_u32);
+    lerp! { a, b, 2, 1 };
+}
+
+#[test]
+fn test_blend_add() {
+    let (a, b) = (100_u8, 27_u8);
+    assert_eq!(127, blend_add! { a, b });
+    assert_eq!(255, blend_add! { a, b, a + b, 1 });
+    let (rgba, glow) = ([250_u8, 0, 128, 255], [10_u8, 20, 127, 0]);
+    assert_eq!([255, 20, 255, 255], blend_add!(rgba, glow));
+}
+
+#[test]
+fn test_scale_u8() {
+    let (x, alpha) = (255_u8, 128_u8);
+    assert_eq!(128, scale_u8!(x, alpha, 255));
+    assert_eq!(0, scale_u8! { x, 0, 255 });
+    assert_eq!(255, scale_u8! { x, 255, 1 });
+    // 3 * 128 / 255 = 1.51
+    assert_eq!(2, scale_u8! { 3, alpha, 255 });
+    assert_eq!(1, scale_u8! { 3, alpha, 255, rounding = down });
+    let rgb = [255_u8, 10, 1];
+    assert_eq!([128, 5, 1], scale_u8! { rgb, alpha, 255 });
+}
+
+#[test]
+#[should_panic = "attempt to divide by zero"]
+fn test_scale_u8_by_zero() {
+    let pixel = [1_u8; 4];
+    scale_u8! { pixel, 1, 0 };
+}
+
+#[test]
+#[should_panic = "attempt to divide by zero"]
+fn test_mul_div_by_zero() {
+    let zero = 0_u32;
+    mul_div! { [saturating] 1, 2, zero };
+}
+
+#[test]
+fn test_modfield() {
+    const P: u64 = 0xffff_ffff_0000_0001;
+    let values = [0, 1, 2, 0xffff_ffff, P - 2, P - 1, u64::MAX];
+    for a in values {
+        for b in values {
+            let (wide_a, wide_b, p) = (a as u128, b as u128, P as u128);
+            assert_eq!(((wide_a * wide_b) % p) as u64, modfield!(P; a * b));
+            assert_eq!(((wide_a + wide_b) % p) as u64, modfield!(P; a + b));
+            assert_eq!(
+                ((wide_a % p + p - wide_b % p) % p) as u64,
+                modfield!(P; a - b)
+            );
+        }
+    }
+    let (x, y) = (12_u32, 5_u32);
+    assert_eq!(12, modfield!(13_u32; x * y + 4));
+    assert_eq!(x, modfield!(13_u32; x / y * y));
+    assert_eq!(1, modfield!(13_u32; -x));
+    assert_eq!(6, modfield!(7; u128::MAX * 2));
+}
+
+#[test]
+#[should_panic = "attempt to divide by 3, which has no inverse modulo 12"]
+fn test_modfield_without_inverse() {
+    let x = 15_u8;
+    modfield!(12_u8; 1 / x);
+}
+
+#[test]
+fn test_qformat() {
+    for a in (i16::MIN..=i16::MAX).step_by(97) {
+        for b in [i16::MIN, -16384, -3, 0, 1, 12345, i16::MAX] {
+            let by_hand = (a as i32 * b as i32 + (1 << 14)) >> 15;
+            let expected = by_hand.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+            assert_eq!(expected, qformat!(Q15; a * b));
+            assert_eq!(a.saturating_add(b), qformat!(Q15; a + b));
+        }
+    }
+    assert_eq!(i16::MAX, qformat!(Q15; i16::MIN * i16::MIN));
+    assert_eq!(i16::MAX, qformat!(Q15; -i16::MIN + 0));
+    let (x, y) = (1_i32 << 30, -(3_i32 << 29));
+    assert_eq!(-(3 << 28), qformat!(Q31; x * y));
+    assert_eq!(i32::MIN, qformat!(Q31; y / x));
+    assert_eq!(1 << 30, qformat!(Q31; x * 1.0));
+    assert_eq!(-3, qformat!(Q2; -0.75 + 0_i8));
+}
+
+#[test]
+#[should_panic = "attempt to divide by zero"]
+fn test_qformat_division_by_zero() {
+    let (x, zero) = (1_i16, 0_i16);
+    qformat!(Q15; x / zero);
+}
+
+#[test]
+fn test_fast_checked() {
+    let (a, b) = (250_u8, 10_u8);
+    assert_eq!(Some(240), fast_checked! { u8: a - b });
+    assert_eq!(None, fast_checked! { u8: a + b - b });
+    assert_eq!(Some(-128), fast_checked! { i8: -64 * 2 });
+    assert_eq!(None, fast_checked! { i8: -(64 * 2) });
+    let min = i8::MIN;
+    assert_eq!(None, fast_checked! { i8: -min + 0 });
+    assert_eq!(
+        Some(20),
+        [b].iter()
+            .map(|v| fast_checked! { u8: v * 2 })
+            .next()
+            .unwrap()
+    );
+    const PAGES: Option<u16> = fast_checked! { const u16: 4096 * 15 + 8 };
+    assert_eq!(Some(61448), PAGES);
+}
+
+#[test]
+fn test_fast_checked_matches_checked() {
+    for a in u8::MIN..=u8::MAX {
+        for b in u8::MIN..=u8::MAX {
+            for c in [0_u8, 1, 2, 3, 127, 128, 255] {
+                assert_eq!(
+                    checked! { a + b * c - b },
+                    fast_checked! { u8: a + b * c - b }
+                );
+                assert_eq!(
+                    checked! { a * c - b + c },
+                    fast_checked! { u8: a * c - b + c }
+                );
+            }
+        }
+    }
+    for a in i8::MIN..=i8::MAX {
+        for b in i8::MIN..=i8::MAX {
+            for c in [i8::MIN, -2, -1, 0, 1, 2, i8::MAX] {
+                assert_eq!(checked! { a * b - c }, fast_checked! { i8: a * b - c });
+                assert_eq!(
+                    checked! { a - b * c + a },
+                    fast_checked! { i8: a - b * c + a }
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_forbid() {
+    let (price, quantity) = (250_u8, 2_u8);
+    assert_eq!(None, forbid! { div, rem; [checked] price * quantity });
+    assert_eq!(255, forbid! { div; price +| price });
+    assert_eq!(252, forbid! { mul, shl; price + quantity });
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic(expected = "attempt to multiply with overflow: `a * 2` with operands 250 and 2")]
+fn test_mixed_overflow() {
+    let a = 250_u8;
+    mixed! { a * 2 + (a +| 1) };
+}
+
+#[cfg(not(feature = "abort"))]
+#[test]
+#[should_panic(expected = "attempt to multiply with overflow: `offset * 2usize` with operands")]
+fn test_modes_overflow() {
+    let (pixel, offset) = (200_u8, usize::MAX);
+    modes! { u8 = saturating; offset * 2 + (pixel * 2) as usize };
+}
+
+#[test]
+fn test_literal_type_propagation() {
+    let x = 5_i16;
+    assert_eq!(11, panicking! { 2 * 3 + x as i32 });
+    assert_eq!(-128, wrapping! { 127 + 1_i8 });
+    assert_eq!(i16::MIN, saturating! { -2 * 3 + (i8::MIN as i16) * 300 });
+    assert_eq!(None, checked! { 200 + 30 + x as u8 * 6 });
+    assert_eq!(Ok(8), try_checked! { 1 + 1_u16 << 2 });
+}
+
+#[test]
+fn test_literal_type_prefix() {
+    assert_eq!(11, panicking! { u8: 1 + 2 * 3 + 4 });
+    assert_eq!(0, wrapping! { u8: 255 + 1 });
+    assert_eq!(i16::MAX, saturating! { i16: 200 * 200 });
+    let (x, y) = (2_u64, 2_i8);
+    assert_eq!(None, checked! { u64: x - 3 });
+    assert_eq!(
+        Err(Operation::Mul),
+        try_checked! { i8: 64 * y }.map_err(|e| e.operation())
+    );
+}
+
+#[test]
+fn test_constant_folding() {
+    let x = 1_u32;
+    assert_eq!(3601, panicking! { 60_u32 * 60_u32 + x });
+    assert_eq!(0, wrapping! { u8: 255 + 1 });
+    assert_eq!(-128, saturating! { i8: -100 - 100 });
+    assert_eq!(Some(-128), checked! { i8: -100 - 28 });
+    assert_eq!(Ok(2), try_checked! { u64: 1 << 3 >> 2 });
+    assert_eq!(2, mixed! { 255_u8 +% 3_u8 });
+}
+
+#[test]
+fn test_type_hint() {
+    let (x, y) = (200_u8, 100_u8);
+    assert_eq!(44, wrapping! { (x: u8) + y });
+    assert_eq!(255, saturating! { (1 + 1: u8) * x });
+    assert_eq!(Some(-128), checked! { (x as i16: i16) - 328 });
+    assert_eq!(
+        Err(Operation::Sub),
+        try_checked! { 1 - (y: u8) }.map_err(|e| e.operation())
+    );
+    assert_eq!(-1, panicking! { (255_u8 as i8: i8) * 1 });
+}
@@ -0,0 +1,53 @@
+//! Checks the operations that `trace!` prints. The hook is global, so these
+//! tests are in their own binary.
+
+use arithmetic_mode::trace;
+use std::fmt;
+use std::sync::Mutex;
+
+static TRACED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn record(operation: fmt::Arguments<'_>) {
+    TRACED.lock().unwrap().push(operation.to_string());
+}
+
+/// The operations printed by `f`.
+fn traced<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    trace::set_hook(record);
+    let mut traced = TRACED.lock().unwrap();
+    traced.clear();
+    drop(traced);
+    let result = f();
+    (result, TRACED.lock().unwrap().drain(..).collect())
+}
+
+#[test]
+fn test_trace() {
+    let (a, b, c) = (200_u8, 2_u8, 7_u8);
+    assert_eq!(
+        (
+            151,
+            vec!["200 * 2 = 144".to_owned(), "144 + 7 = 151".to_owned()]
+        ),
+        traced(|| trace! { [wrapping] a * b + c })
+    );
+    assert_eq!(
+        (
+            255,
+            vec!["200 * 2 = 255".to_owned(), "255 + 7 = 255".to_owned()]
+        ),
+        traced(|| trace! { [saturating] a * b + c })
+    );
+    // The operations after the failed one are not evaluated
+    assert_eq!(
+        (None, vec!["200 * 2 = None".to_owned()]),
+        traced(|| trace! { [checked] a * b + c })
+    );
+    let (result, operations) = traced(|| trace! { [try_checked] c * 2 - b });
+    assert_eq!(Ok(12), result);
+    assert_eq!(vec!["7 * 2 = Ok(14)", "14 - 2 = Ok(12)"], operations);
+    assert_eq!(
+        (23, vec!["7 * 3 = 21".to_owned(), "21 + 2 = 23".to_owned()]),
+        traced(|| trace! { c * 3 + b })
+    );
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "glam")]
+
+use arithmetic_mode::{checked, saturating, try_checked, wrapping, Operation};
+use glam::{I16Vec2, IVec3, U8Vec4, UVec2};
+
+#[test]
+fn test_glam_elementwise() {
+    let (pos, vel, ticks) = (
+        IVec3::new(10, -20, 0),
+        IVec3::new(3, -4, 5),
+        IVec3::splat(2),
+    );
+    assert_eq!(
+        IVec3::new(16, -28, 10),
+        checked! { pos + vel * ticks }.unwrap()
+    );
+    let (six, offset) = (IVec3::splat(6), IVec3::new(2, 0, 0));
+    assert_eq!(IVec3::new(5, 5, 0), wrapping! { pos / vel % six + offset });
+    assert_eq!(IVec3::new(40, -80, 0), wrapping! { pos << 2_u32 });
+}
+
+#[test]
+fn test_glam_saturating() {
+    let (pos, vel) = (I16Vec2::new(32_000, -32_000), I16Vec2::new(1_000, 1_000));
+    assert_eq!(I16Vec2::new(i16::MAX, -31_000), saturating! { pos + vel });
+    assert_eq!(I16Vec2::new(31_000, i16::MIN), saturating! { pos - vel });
+    let color = U8Vec4::new(200, 100, 50, 255);
+    let two = U8Vec4::splat(2);
+    assert_eq!(U8Vec4::new(255, 200, 100, 255), saturating! { color * two });
+}
+
+#[test]
+fn test_glam_checked() {
+    let (a, b) = (UVec2::new(1, u32::MAX), UVec2::new(1, 1));
+    assert_eq!(None, checked! { a + b });
+    assert_eq!(
+        Some(UVec2::new(0, u32::MAX - 1)),
+        checked! { a - b + b - b }
+    );
+    assert_eq!(None, checked! { a / (b - b) });
+    assert_eq!(None, checked! { a << 32_u32 });
+    let error = try_checked! { b - a }.unwrap_err();
+    assert_eq!(Operation::Sub, error.operation());
+}